@@ -0,0 +1,375 @@
+//! Checks attributes against the ones the compiler knows about.
+//!
+//! Built-in attributes are listed in a registry along with the arguments they take and what they can be attached to.
+//! Attributes the compiler doesn't know about are only warned about, so that newer code still builds with older compilers.
+//! Statements can have attributes too, which get checked the same way.
+//! The conditions of `@cfg` get checked first, on their own, since everything else is only checked in code they leave in.
+
+//--> Imports <--
+
+use crate::{
+	Error,
+	ErrorKind,
+	ErrorList,
+	compiler::{
+		InterpretError,
+		line_of,
+		parser::astgen::{
+			AbstractSyntaxTree,
+			Attribute,
+			Block,
+			Condition,
+			Expr,
+			ExprKind,
+			Item,
+			ItemKind,
+			Meta,
+			MetaKind,
+			Stmt,
+			StmtKind,
+		},
+	},
+};
+
+use logos::Span;
+
+use std::{
+	fmt,
+	path::Path,
+};
+
+//--> Structs <--
+
+/// Everything the compiler knows about a built-in attribute.
+pub(crate) struct BuiltinAttribute {
+	pub name: &'static str,
+	pub args: ArgShape,
+	pub targets: &'static [Target],
+}
+
+struct Checker<'a> {
+	file: Option<&'a Path>,
+	source: &'a str,
+	errors: ErrorList,
+	/// Whether it's only `@cfg` being checked, or everything but it.
+	cfg: bool,
+}
+
+//--> Enums <--
+
+/// What arguments an attribute takes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ArgShape {
+	/// `@test`
+	None,
+	/// One or more plain names, like `@allow(unused, needless_mut)`.
+	Words,
+	/// A single condition, like `@cfg(feature = "x")` or `@cfg(all(unix, not(debug)))`.
+	Predicate,
+}
+
+/// The kinds of things an attribute can be attached to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Target {
+	Use,
+	Func,
+	Type,
+	Trait,
+	Effect,
+	Impl,
+	Const,
+	Stmt,
+}
+
+#[derive(Clone, Debug)]
+pub enum AttributeError {
+	/// Nobody has heard of this attribute. This is only ever a warning.
+	Unknown(String),
+	/// The attribute was given arguments it doesn't take.
+	UnexpectedArgs(&'static str),
+	/// The attribute needs arguments of a particular shape.
+	ExpectedArgs(&'static str, &'static str),
+	/// The attribute can't be attached to this kind of thing.
+	WrongTarget(&'static str, &'static str),
+}
+
+//--> Functions <--
+
+/// The attributes built into the compiler.
+pub(crate) const BUILTIN_ATTRIBUTES: &[BuiltinAttribute] = &[
+	// Marks something which is implemented by the runtime rather than in Rouge.
+	BuiltinAttribute { name: "builtin", args: ArgShape::None, targets: &[Target::Type, Target::Func, Target::Trait] },
+	// Turns lints off for the item it's attached to.
+	BuiltinAttribute { name: "allow", args: ArgShape::Words, targets: &[Target::Use, Target::Func, Target::Type, Target::Trait, Target::Effect, Target::Impl, Target::Const] },
+	// Marks a function as a test.
+	BuiltinAttribute { name: "test", args: ArgShape::None, targets: &[Target::Func] },
+	// Only compiles the item or statement it's attached to if the condition holds.
+	BuiltinAttribute { name: "cfg", args: ArgShape::Predicate, targets: &[Target::Use, Target::Func, Target::Type, Target::Trait, Target::Effect, Target::Impl, Target::Const, Target::Stmt] },
+];
+
+/// Looks up a built-in attribute by name.
+pub(crate) fn lookup(name: &str) -> Option<&'static BuiltinAttribute> {
+	BUILTIN_ATTRIBUTES.iter().find(|attr| attr.name == name)
+}
+
+/// Checks every `@cfg` in a file, which has to make sense before anything it turns off can be removed.
+pub(crate) fn check_cfg(path: Option<&Path>, source: &str, tree: &AbstractSyntaxTree) -> ErrorList {
+	Checker { file: path, source, errors: ErrorList::new(), cfg: true }.check(tree)
+}
+
+/// Checks every other attribute in a file, once whatever `@cfg` turned off is gone, giving back any errors and warnings.
+pub(crate) fn check(path: Option<&Path>, source: &str, tree: &AbstractSyntaxTree) -> ErrorList {
+	Checker { file: path, source, errors: ErrorList::new(), cfg: false }.check(tree)
+}
+
+impl Target {
+	fn of(kind: &ItemKind) -> Target {
+		match kind {
+			ItemKind::Use(_) => Target::Use,
+			ItemKind::Func(_) => Target::Func,
+			ItemKind::Type(_) => Target::Type,
+			ItemKind::Trait(_) => Target::Trait,
+			ItemKind::Effect(_) => Target::Effect,
+			ItemKind::Impl(_) => Target::Impl,
+			ItemKind::Const(_) => Target::Const,
+		}
+	}
+
+	fn as_str(&self) -> &'static str {
+		match self {
+			Target::Use => "imports",
+			Target::Func => "functions",
+			Target::Type => "types",
+			Target::Trait => "traits",
+			Target::Effect => "effects",
+			Target::Impl => "implementations",
+			Target::Const => "constants",
+			Target::Stmt => "statements",
+		}
+	}
+}
+
+impl Checker<'_> {
+	fn check(mut self, tree: &AbstractSyntaxTree) -> ErrorList {
+		for item in tree.items.iter() {
+			self.item(item);
+		}
+
+		self.errors
+	}
+
+	fn report(&mut self, is_warning: bool, span: Span, err: AttributeError) {
+		self.errors.push(Error::new(is_warning, self.file, Some(line_of(self.source, span.start)), Some(span.clone()), self.source.get(span), ErrorKind::Interpret(InterpretError::Attribute(err))));
+	}
+
+	fn item(&mut self, item: &Item) {
+		let target = Target::of(&item.kind);
+
+		for attr in item.attrs.iter() {
+			self.attribute(attr, target);
+		}
+
+		let items = match &item.kind {
+			ItemKind::Func(func) => {
+				if let Some(body) = &func.body { self.block(body); }
+				return;
+			},
+			ItemKind::Const(decl) => {
+				if let Some(value) = &decl.value { self.expr(value); }
+				return;
+			},
+			ItemKind::Type(decl) => &decl.items,
+			ItemKind::Trait(decl) => &decl.items,
+			ItemKind::Effect(decl) => &decl.items,
+			ItemKind::Impl(decl) => &decl.items,
+			ItemKind::Use(_) => return,
+		};
+
+		for item in items.iter() {
+			self.item(item);
+		}
+	}
+
+	fn block(&mut self, block: &Block) {
+		for stmt in block.stmts.iter() {
+			self.stmt(stmt);
+		}
+	}
+
+	fn stmt(&mut self, stmt: &Stmt) {
+		for attr in stmt.attrs.iter() {
+			self.attribute(attr, Target::Stmt);
+		}
+
+		match &stmt.kind {
+			StmtKind::Let { value, .. } => self.expr(value),
+			StmtKind::Assign { target, value, .. } => {
+				self.expr(target);
+				self.expr(value);
+			},
+			StmtKind::Expr(expr) => self.expr(expr),
+			StmtKind::Handler(handler) => self.block(&handler.body),
+		}
+	}
+
+	/// Looks through an expression for blocks, whose statements might have attributes.
+	fn expr(&mut self, expr: &Expr) {
+		match &expr.kind {
+			ExprKind::Lit(_) | ExprKind::Path(_) | ExprKind::Continue(_) => {},
+			ExprKind::Call { callee: first, args } | ExprKind::MethodCall { receiver: first, args, .. } => {
+				self.expr(first);
+				for arg in args.iter() { self.expr(arg); }
+			},
+			ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } => self.expr(base),
+			ExprKind::Index { base: lhs, index: rhs } | ExprKind::Binary { lhs, rhs, .. } => {
+				self.expr(lhs);
+				self.expr(rhs);
+			},
+			ExprKind::Tuple(elems) | ExprKind::List(elems) | ExprKind::Interp(elems) => {
+				for elem in elems.iter() { self.expr(elem); }
+			},
+			ExprKind::Record(fields) => {
+				for (_, value) in fields.iter() { self.expr(value); }
+			},
+			ExprKind::Map(entries) => {
+				for (key, value) in entries.iter() {
+					self.expr(key);
+					self.expr(value);
+				}
+			},
+			ExprKind::Closure { body, .. } | ExprKind::Loop { body, .. } | ExprKind::Block { body, .. } => self.block(body),
+			ExprKind::Unary { expr: inner, .. } | ExprKind::Try(inner) => self.expr(inner),
+			ExprKind::If { branches, else_ } => {
+				for branch in branches.iter() {
+					match &branch.cond {
+						Condition::Expr(cond) | Condition::Matches(cond, _) => self.expr(cond),
+					}
+					self.block(&branch.body);
+				}
+
+				if let Some(else_) = else_ { self.block(else_); }
+			},
+			ExprKind::Match { scrutinee, arms } => {
+				self.expr(scrutinee);
+				for arm in arms.iter() { self.expr(&arm.body); }
+			},
+			ExprKind::While { cond: head, body, .. } | ExprKind::Until { cond: head, body, .. } | ExprKind::For { iter: head, body, .. } => {
+				self.expr(head);
+				self.block(body);
+			},
+			ExprKind::Handle { handler, body } => {
+				self.block(&handler.body);
+				self.block(body);
+			},
+			ExprKind::Break { value, .. } | ExprKind::Return(value) => {
+				if let Some(value) = value { self.expr(value); }
+			},
+		}
+	}
+
+	fn attribute(&mut self, attr: &Attribute, target: Target) {
+		if (attr.name.name == "cfg") != self.cfg { return }
+
+		let Some(builtin) = lookup(&attr.name.name) else {
+			self.report(true, attr.span.clone(), AttributeError::Unknown(attr.name.name.clone()));
+			return;
+		};
+
+		if !builtin.targets.contains(&target) {
+			self.report(false, attr.span.clone(), AttributeError::WrongTarget(builtin.name, target.as_str()));
+		}
+
+		match builtin.args {
+			ArgShape::None if !attr.args.is_empty() => {
+				self.report(false, attr.span.clone(), AttributeError::UnexpectedArgs(builtin.name));
+			},
+			ArgShape::Words if attr.args.is_empty() || attr.args.iter().any(|arg| arg.kind != MetaKind::Word) => {
+				self.report(false, attr.span.clone(), AttributeError::ExpectedArgs(builtin.name, "one or more names"));
+			},
+			ArgShape::Predicate if attr.args.len() != 1 || !is_predicate(&attr.args[0]) => {
+				self.report(false, attr.span.clone(), AttributeError::ExpectedArgs(builtin.name, "a single condition"));
+			},
+			_ => {},
+		}
+	}
+}
+
+/// Whether an argument makes sense as a condition: a flag, a `key = "value"` pair, or `all`, `any` or `not` of other conditions.
+fn is_predicate(meta: &Meta) -> bool {
+	match &meta.kind {
+		MetaKind::Word | MetaKind::Value(_) => true,
+		MetaKind::List(args) => match meta.name.name.as_str() {
+			"all" | "any" => args.iter().all(is_predicate),
+			"not" => args.len() == 1 && is_predicate(&args[0]),
+			_ => false,
+		},
+	}
+}
+
+impl fmt::Display for AttributeError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			AttributeError::Unknown(name) => write!(f, "unknown attribute `@{}`", name),
+			AttributeError::UnexpectedArgs(name) => write!(f, "`@{}` doesn't take any arguments", name),
+			AttributeError::ExpectedArgs(name, what) => write!(f, "`@{}` expects {}", name, what),
+			AttributeError::WrongTarget(name, target) => write!(f, "`@{}` can't be used on {}", name, target),
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compiler::{
+		Options,
+		parse_source,
+	};
+
+	fn check_source(source: &str) -> ErrorList {
+		match parse_source(None, source, &Options::default()) {
+			Ok((_, warnings)) => warnings,
+			Err(errors) => errors,
+		}
+	}
+
+	#[test]
+	fn builtin_primitives_are_fine() {
+		assert!(check_source("@builtin\npub type nat\n\n@builtin\npub type str\n").is_empty());
+	}
+
+	#[test]
+	fn unknown_attributes_are_warnings() {
+		let errors = check_source("@frobnicate(hard)\nfunc f() do\n\t1\nend\n");
+
+		assert_eq!(errors.len(), 1);
+		assert!(errors[0].is_warning());
+		assert!(matches!(errors[0].kind(), ErrorKind::Interpret(InterpretError::Attribute(AttributeError::Unknown(name))) if name == "frobnicate"));
+	}
+
+	#[test]
+	fn arguments_are_checked() {
+		let errors = check_source("@test(now)\nfunc a() do\n\t1\nend\n\n@allow\nfunc b() do\n\t1\nend\n");
+		assert_eq!(errors.len(), 2);
+		assert!(errors.iter().all(|e| !e.is_warning()));
+
+		// A condition which doesn't make sense stops everything else from being checked, since there's no knowing what it turns off.
+		let errors = check_source("@cfg(feature = \"x\", other)\nfunc c() do\n\t1\nend\n\n@test(now)\nfunc d() do\n\t1\nend\n");
+		assert_eq!(errors.len(), 1);
+		assert!(matches!(errors[0].kind(), ErrorKind::Interpret(InterpretError::Attribute(AttributeError::ExpectedArgs("cfg", _)))));
+	}
+
+	#[test]
+	fn targets_are_checked() {
+		let errors = check_source("@test\ntype Foo is\n\tbar: nat\nend\n");
+
+		assert_eq!(errors.len(), 1);
+		assert!(matches!(errors[0].kind(), ErrorKind::Interpret(InterpretError::Attribute(AttributeError::WrongTarget("test", "types")))));
+	}
+
+	#[test]
+	fn nested_conditions() {
+		assert!(check_source("@cfg(all(feature = \"x\", not(any(windows, target = \"wasm\"))))\n@allow(unused)\nfunc f() do\n\t1\nend\n").is_empty());
+	}
+}
@@ -0,0 +1,273 @@
+//! Conditional compilation, which removes the items and statements whose `@cfg(...)` condition doesn't hold.
+//!
+//! Conditions are checked against the flags in the options, which a program embedding the runtime or the `--cfg` flag sets.
+//! A plain name like `gpu` holds if the flag was given at all, `os = "linux"` holds if it was given with that value,
+//! and `all`, `any` and `not` put conditions together.
+//! This happens straight after parsing, so nothing later on (the other attributes, labels and name resolution included) ever sees what was removed.
+
+//--> Imports <--
+
+use super::parser::astgen::{
+	AbstractSyntaxTree,
+	Attribute,
+	Block,
+	Condition,
+	Expr,
+	ExprKind,
+	Item,
+	ItemKind,
+	Literal,
+	Meta,
+	MetaKind,
+	Stmt,
+	StmtKind,
+};
+
+//--> Structs <--
+
+struct Stripper<'a> {
+	flags: &'a [(String, Option<String>)],
+}
+
+//--> Functions <--
+
+/// Removes everything in a file which is turned off for the flags given.
+/// The conditions have already been checked by this point, so any which doesn't make sense just doesn't hold.
+pub(crate) fn strip(tree: &mut AbstractSyntaxTree, flags: &[(String, Option<String>)]) {
+	Stripper { flags }.items(&mut tree.items);
+}
+
+impl Stripper<'_> {
+	/// Whether every `@cfg(...)` in a list of attributes holds.
+	fn enabled(&self, attrs: &[Attribute]) -> bool {
+		attrs.iter()
+			.filter(|attr| attr.name.name == "cfg")
+			.all(|attr| attr.args.first().is_some_and(|cond| self.holds(cond)))
+	}
+
+	fn holds(&self, cond: &Meta) -> bool {
+		let name = cond.name.name.as_str();
+
+		match &cond.kind {
+			MetaKind::Word => self.flags.iter().any(|(flag, _)| flag == name),
+			MetaKind::Value(lit) => {
+				// Flags only ever have text for values, so `threads = 4` is the same as `threads = "4"`.
+				let value = match lit {
+					Literal::Str(s) => s.clone(),
+					Literal::Num(_) | Literal::Bool(_) => lit.to_string(),
+					_ => return false,
+				};
+
+				self.flags.iter().any(|(flag, given)| flag == name && given.as_ref() == Some(&value))
+			},
+			MetaKind::List(conds) => match name {
+				"all" => conds.iter().all(|cond| self.holds(cond)),
+				"any" => conds.iter().any(|cond| self.holds(cond)),
+				"not" => conds.len() == 1 && !self.holds(&conds[0]),
+				_ => false,
+			},
+		}
+	}
+
+	fn items(&self, items: &mut Vec<Item>) {
+		items.retain(|item| self.enabled(&item.attrs));
+
+		for item in items.iter_mut() {
+			self.item(item);
+		}
+	}
+
+	fn item(&self, item: &mut Item) {
+		match &mut item.kind {
+			ItemKind::Func(func) => if let Some(body) = &mut func.body { self.block(body) },
+			ItemKind::Const(decl) => if let Some(value) = &mut decl.value { self.expr(value) },
+			ItemKind::Type(decl) => self.items(&mut decl.items),
+			ItemKind::Trait(decl) => self.items(&mut decl.items),
+			ItemKind::Effect(decl) => self.items(&mut decl.items),
+			ItemKind::Impl(decl) => self.items(&mut decl.items),
+			ItemKind::Use(_) => {},
+		}
+	}
+
+	fn block(&self, block: &mut Block) {
+		block.stmts.retain(|stmt| self.enabled(&stmt.attrs));
+
+		for stmt in block.stmts.iter_mut() {
+			self.stmt(stmt);
+		}
+	}
+
+	fn stmt(&self, stmt: &mut Stmt) {
+		match &mut stmt.kind {
+			StmtKind::Let { value, .. } => self.expr(value),
+			StmtKind::Assign { target, value, .. } => {
+				self.expr(target);
+				self.expr(value);
+			},
+			StmtKind::Expr(expr) => self.expr(expr),
+			StmtKind::Handler(handler) => self.block(&mut handler.body),
+		}
+	}
+
+	fn expr(&self, expr: &mut Expr) {
+		match &mut expr.kind {
+			ExprKind::Lit(_) | ExprKind::Path(_) | ExprKind::Continue(_) => {},
+			ExprKind::Call { callee: first, args } | ExprKind::MethodCall { receiver: first, args, .. } => {
+				self.expr(first);
+				for arg in args.iter_mut() { self.expr(arg); }
+			},
+			ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } => self.expr(base),
+			ExprKind::Index { base: lhs, index: rhs } | ExprKind::Binary { lhs, rhs, .. } => {
+				self.expr(lhs);
+				self.expr(rhs);
+			},
+			ExprKind::Tuple(elems) | ExprKind::List(elems) | ExprKind::Interp(elems) => {
+				for elem in elems.iter_mut() { self.expr(elem); }
+			},
+			ExprKind::Record(fields) => {
+				for (_, value) in fields.iter_mut() { self.expr(value); }
+			},
+			ExprKind::Map(entries) => {
+				for (key, value) in entries.iter_mut() {
+					self.expr(key);
+					self.expr(value);
+				}
+			},
+			ExprKind::Closure { body, .. } | ExprKind::Loop { body, .. } | ExprKind::Block { body, .. } => self.block(body),
+			ExprKind::Unary { expr: inner, .. } | ExprKind::Try(inner) => self.expr(inner),
+			ExprKind::If { branches, else_ } => {
+				for branch in branches.iter_mut() {
+					match &mut branch.cond {
+						Condition::Expr(cond) | Condition::Matches(cond, _) => self.expr(cond),
+					}
+					self.block(&mut branch.body);
+				}
+
+				if let Some(else_) = else_ { self.block(else_); }
+			},
+			ExprKind::Match { scrutinee, arms } => {
+				self.expr(scrutinee);
+				for arm in arms.iter_mut() { self.expr(&mut arm.body); }
+			},
+			ExprKind::While { cond: head, body, .. } | ExprKind::Until { cond: head, body, .. } | ExprKind::For { iter: head, body, .. } => {
+				self.expr(head);
+				self.block(body);
+			},
+			ExprKind::Handle { handler, body } => {
+				self.block(&mut handler.body);
+				self.block(body);
+			},
+			ExprKind::Break { value, .. } | ExprKind::Return(value) => {
+				if let Some(value) = value { self.expr(value); }
+			},
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		ErrorKind,
+		ErrorList,
+		compiler::{
+			InterpretError,
+			Options,
+			TestPackage,
+			parse_source,
+		},
+	};
+
+	fn options(flags: &[(&str, Option<&str>)]) -> Options {
+		Options {
+			cfg: flags.iter().map(|(name, value)| (name.to_string(), value.map(str::to_string))).collect(),
+			..Options::default()
+		}
+	}
+
+	/// The names of the functions left in a file once it's been parsed with the flags given.
+	fn funcs(source: &str, flags: &[(&str, Option<&str>)]) -> Vec<String> {
+		let (ast, _) = parse_source(None, source, &options(flags)).unwrap();
+
+		ast.items.iter()
+			.filter_map(|item| match &item.kind {
+				ItemKind::Func(func) => Some(func.name.name.clone()),
+				_ => None,
+			})
+			.collect()
+	}
+
+	/// Checks a package with the flags given, giving back its errors and warnings.
+	fn check(name: &str, files: &[(&str, &str)], flags: &[(&str, Option<&str>)]) -> ErrorList {
+		match TestPackage::new(name, files).check(&options(flags)) {
+			Ok((_, warnings)) => warnings,
+			Err(errors) => errors,
+		}
+	}
+
+	#[test]
+	fn conditions() {
+		let source = "@cfg(gpu)\nfunc a() do\nend\n\n@cfg(os = \"linux\")\nfunc b() do\nend\n\n@cfg(all(gpu, not(os = \"linux\")))\nfunc c() do\nend\n\n@cfg(any(threads = 4, debug))\nfunc d() do\nend\n\nfunc e() do\nend\n";
+
+		assert_eq!(funcs(source, &[]), vec!["e"]);
+		assert_eq!(funcs(source, &[("gpu", Some("cuda")), ("os", Some("linux"))]), vec!["a", "b", "e"]);
+		assert_eq!(funcs(source, &[("gpu", None), ("os", Some("macos")), ("threads", Some("4"))]), vec!["a", "c", "d", "e"]);
+	}
+
+	#[test]
+	fn statements_can_be_turned_off() {
+		let source = "func f() -> nat do\n\tmut n := 1\n\t@cfg(gpu)\n\tn += 10\n\n\t@cfg(not(gpu))\n\t@cfg(debug)\n\tn += 100\n\tn\nend\n";
+		let stmts = |flags: &[(&str, Option<&str>)]| {
+			let (ast, _) = parse_source(None, source, &options(flags)).unwrap();
+			let ItemKind::Func(func) = &ast.items[0].kind else { unreachable!() };
+			func.body.as_ref().unwrap().stmts.len()
+		};
+
+		assert_eq!(stmts(&[]), 2);
+		assert_eq!(stmts(&[("gpu", None)]), 3);
+		assert_eq!(stmts(&[("debug", None)]), 3);
+	}
+
+	#[test]
+	fn turned_off_code_is_never_resolved() {
+		let files = [
+			("main.ro", "@cfg(gpu)\nuse self::shader::compile\n\n@cfg(gpu)\nfunc render() -> nat do\n\tcompile(draw_calls())\nend\n\n@cfg(not(gpu))\nfunc render() -> nat do\n\t0\nend\n\npub func main() -> nat do\n\t@cfg(os = \"nowhere\")\n\tmissing()\n\trender()\nend\n"),
+			("shader.ro", "pub func compile(calls: nat) -> nat do\n\tcalls\nend\n"),
+		];
+
+		assert!(check("off", &files, &[]).is_empty());
+
+		let errors = check("on", &files, &[("gpu", None)]);
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].line(), Some(6));
+		assert!(matches!(errors[0].kind(), ErrorKind::Interpret(InterpretError::Resolve(_))));
+	}
+
+	#[test]
+	fn attributes_on_statements_are_checked() {
+		let messages = |source: &str, flags: &[(&str, Option<&str>)]| -> Vec<String> {
+			let errors = match parse_source(None, source, &options(flags)) {
+				Ok((_, warnings)) => warnings,
+				Err(errors) => errors,
+			};
+
+			errors.iter().map(|e| e.kind().to_string()).collect()
+		};
+
+		assert_eq!(messages("func f() do\n\t@test\n\tg()\n\t@cfg(a, b)\n\th()\nend\n", &[]), vec!["`@cfg` expects a single condition"]);
+		assert_eq!(messages("func f() do\n\t@test\n\tg()\n\t@cfg(a)\n\th()\nend\n", &[]), vec!["`@test` can't be used on statements"]);
+	}
+
+	#[test]
+	fn turned_off_code_is_not_checked() {
+		let source = "@cfg(gpu)\nfunc f() do\n\tbreak `nope\nend\n\n@cfg(gpu)\n@shiny\nfunc g() do\nend\n";
+		assert!(parse_source(None, source, &Options::default()).unwrap().1.is_empty());
+
+		let errors = parse_source(None, source, &options(&[("gpu", None)])).err().unwrap();
+		assert_eq!(errors.len(), 2);
+		assert!(errors[0].is_warning() && errors[0].line() == Some(7));
+		assert!(!errors[1].is_warning() && errors[1].line() == Some(3));
+	}
+}
@@ -0,0 +1,1304 @@
+//! Works out the values of constants before the program runs, by interpreting their syntax trees.
+//!
+//! Every `const` item and `const` variable gets evaluated, calling any `const func`s their values need along the way.
+//! Their results go into a pool of constants, which is where the compiled program gets them from instead of working them out again.
+//! Checking what a constant is allowed to use happens earlier, along with mutability, so anything that makes it here is something
+//! the evaluator knows how to do. What can still go wrong is what would go wrong while the program runs, like dividing by zero.
+//!
+//! A `const func` can loop and call itself, so evaluation is capped at a number of steps (one for each expression evaluated),
+//! and at how many calls deep it can go. Both are reported as errors rather than letting the compiler hang or run out of stack.
+
+//--> Imports <--
+
+use crate::{
+	Error,
+	ErrorKind,
+	ErrorList,
+	compiler::{
+		InterpretError,
+		Options,
+		line_of,
+		modules::{
+			ModuleId,
+			ModuleTree,
+		},
+		parser::astgen::{
+			AssignOp,
+			BinaryOp,
+			Block,
+			Condition,
+			ConstDecl,
+			Expr,
+			ExprKind,
+			Func,
+			Ident,
+			Item,
+			ItemKind,
+			Literal,
+			Mutability,
+			Pattern,
+			PatternKind,
+			StmtKind,
+			UnaryOp,
+		},
+		resolve::{
+			DefId,
+			DefKind,
+			LocalId,
+			Res,
+			Resolutions,
+		},
+		typeck::{
+			Prim,
+			Types,
+		},
+	},
+};
+
+use logos::Span;
+
+use std::{
+	cmp::Ordering,
+	collections::{
+		HashMap,
+		HashSet,
+	},
+	fmt,
+	path::PathBuf,
+};
+
+//--> Structs <--
+
+/// The value worked out for a constant, for showing in editors when it gets hovered over.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstHint {
+	pub file: Option<PathBuf>,
+	pub line: usize,
+	/// Where the constant's name is.
+	pub span: Span,
+	pub name: String,
+	/// The value, written out as source code.
+	pub value: String,
+}
+
+/// The values of every constant in a package, each stored once however many constants have it.
+#[derive(Debug, Default)]
+pub(crate) struct ConstPool {
+	pub values: Vec<Value>,
+	/// Where the value of each `const` item is in the pool.
+	pub items: HashMap<DefId, usize>,
+	/// Where the value of each `const` variable is in the pool, by where its value was written.
+	pub exprs: HashMap<(ModuleId, Span), usize>,
+	/// Hints for every constant in the package being compiled, leaving out the standard library.
+	pub hints: Vec<ConstHint>,
+}
+
+struct Evaluator<'a> {
+	tree: &'a ModuleTree,
+	res: &'a Resolutions,
+	types: &'a Types,
+	errors: ErrorList,
+	module: ModuleId,
+	pool: ConstPool,
+
+	consts: HashMap<DefId, (ModuleId, &'a ConstDecl)>,
+	funcs: HashMap<DefId, (ModuleId, &'a Func)>,
+	/// Constants being evaluated, so that ones whose values need themselves get caught.
+	evaluating: Vec<DefId>,
+	/// Constants which couldn't be evaluated, whose errors have already been reported.
+	failed: HashSet<DefId>,
+
+	/// The variables of the call being evaluated.
+	locals: HashMap<LocalId, Value>,
+	/// The values of `const` variables which have been evaluated, which later constants in the same function can use.
+	const_locals: HashMap<LocalId, Value>,
+	steps: usize,
+	max_steps: usize,
+	calls: usize,
+	max_calls: usize,
+}
+
+//--> Enums <--
+
+/// A value worked out before the program runs.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+	Bool(bool),
+	Char(char),
+	Byte(u8),
+	Nat(u64),
+	Int(i64),
+	Flo(f64),
+	Str(String),
+	/// `(a, b)`, where `()` is the unit value.
+	Tuple(Vec<Value>),
+	/// `(name: a, other: b)`, with the fields in the order they were written.
+	Record(Vec<(String, Value)>),
+	List(Vec<Value>),
+	/// `[key: value]`, with the entries in the order they were first added.
+	Map(Vec<(Value, Value)>),
+	/// A variant of a union, or a value of a record or tuple type. A record type's fields are all in one record.
+	Variant { def: DefId, name: String, fields: Vec<Value> },
+	/// A trait object, along with the functions in its vtable.
+	Object { vtable: Vec<DefId>, value: Box<Value> },
+}
+
+#[derive(Clone, Debug)]
+pub enum ConstError {
+	/// Working out a constant took more steps than it's allowed.
+	StepLimit(usize),
+	/// Working out a constant went more calls deep than it's allowed.
+	CallLimit(usize),
+	/// A constant's value depends on itself.
+	Cycle(String),
+	/// Some arithmetic went past what its type can hold.
+	Overflow { op: &'static str, ty: &'static str },
+	DivideByZero,
+	OutOfBounds { index: i128, len: usize },
+	MissingKey(String),
+	/// None of the arms of a match matched the value.
+	NoArm(String),
+	/// Something which can be checked but not yet worked out before the program runs.
+	Unsupported(&'static str),
+}
+
+/// Why evaluation stopped partway through an expression.
+enum Unwind {
+	Break(Option<String>, Value),
+	Continue(Option<String>),
+	Return(Value),
+	/// Something went wrong, which has already been reported.
+	Failed,
+}
+
+/// A step along the way from a variable to the part of it being assigned to.
+enum Proj {
+	Field(String),
+	Tuple(usize),
+	Index(Value),
+}
+
+//--> Type Aliases <--
+
+type Eval = Result<Value, Unwind>;
+
+//--> Functions <--
+
+/// Evaluates every constant in a module tree which has had its types checked.
+pub(crate) fn evaluate(tree: &ModuleTree, res: &Resolutions, types: &Types, options: &Options) -> Result<ConstPool, ErrorList> {
+	let mut evaluator = Evaluator {
+		tree,
+		res,
+		types,
+		errors: ErrorList::new(),
+		module: 0,
+		pool: ConstPool::default(),
+		consts: HashMap::new(),
+		funcs: HashMap::new(),
+		evaluating: Vec::new(),
+		failed: HashSet::new(),
+		locals: HashMap::new(),
+		const_locals: HashMap::new(),
+		steps: 0,
+		max_steps: options.max_const_steps,
+		calls: 0,
+		max_calls: options.max_const_calls,
+	};
+
+	for (module, m) in tree.modules.iter().enumerate() {
+		for item in m.ast.items.iter() {
+			evaluator.collect(module, item);
+		}
+	}
+
+	let mut consts: Vec<DefId> = evaluator.consts.keys().copied().collect();
+	consts.sort_unstable();
+
+	// The default of a constant in a trait can depend on which impl it's for, so it's only worked out when something uses it.
+	for def in consts.into_iter().filter(|def| res.defs[*def].parent.is_none_or(|parent| res.defs[parent].kind != DefKind::Trait)) {
+		let _ = evaluator.const_item(def);
+	}
+
+	for (module, m) in tree.modules.iter().enumerate() {
+		evaluator.module = module;
+
+		for item in m.ast.items.iter() {
+			evaluator.item(item);
+		}
+	}
+
+	if evaluator.errors.is_empty() {
+		evaluator.pool.hints.sort_by(|a, b| (&a.file, a.span.start).cmp(&(&b.file, b.span.start)));
+		Ok(evaluator.pool)
+	} else {
+		Err(evaluator.errors)
+	}
+}
+
+impl ConstPool {
+	/// Adds a value to the pool, unless it's already there, and gives back where it is.
+	fn add(&mut self, value: Value) -> usize {
+		match self.values.iter().position(|other| *other == value) {
+			Some(index) => index,
+			None => {
+				self.values.push(value);
+				self.values.len() - 1
+			},
+		}
+	}
+}
+
+impl<'a> Evaluator<'a> {
+	fn report(&mut self, span: Span, err: ConstError) -> &mut Error {
+		let module = &self.tree.modules[self.module];
+		let line = line_of(&module.source, span.start);
+		let slice = module.source.get(span.clone());
+
+		self.errors.push(Error::new(false, module.file.as_deref(), Some(line), Some(span), slice, ErrorKind::Interpret(InterpretError::Const(err))));
+		self.errors.last_mut().unwrap()
+	}
+
+	/// Leaves a hint for a constant's value, if it's in the package being compiled.
+	fn hint(&mut self, module: ModuleId, name: &Ident, value: &Value) {
+		let module = &self.tree.modules[module];
+		if module.path[0] != "pkg" || name.name.contains('$') { return }
+
+		self.pool.hints.push(ConstHint {
+			file: module.file.clone(),
+			line: line_of(&module.source, name.span.start),
+			span: name.span.clone(),
+			name: name.name.clone(),
+			value: value.to_string(),
+		});
+	}
+
+	/// Reports an error, and stops evaluating.
+	fn fail(&mut self, span: &Span, err: ConstError) -> Unwind {
+		self.report(span.clone(), err);
+		Unwind::Failed
+	}
+
+	/// Reports going past one of the limits on evaluation. That can happen a long way from the constant being worked out,
+	/// like deep inside of a function it calls, so the constant gets pointed out as well.
+	fn limit(&mut self, span: &Span, err: ConstError) -> Unwind {
+		let note = self.evaluating.last().and_then(|def| {
+			let (module, decl) = self.consts[def];
+			let is_inside = module == self.module && decl.value.as_ref().is_some_and(|value| value.span.contains(&span.start));
+			if is_inside { return None }
+
+			let at = &self.tree.modules[module];
+			let mut note = format!("this happened while working out `{}`, on line {}", decl.name.name, line_of(&at.source, decl.name.span.start));
+			if module != self.module { note += &format!(" of `{}`", at.path.join("::")) }
+
+			Some(note)
+		});
+
+		let err = self.report(span.clone(), err);
+		if let Some(note) = note { err.add_note(note); }
+
+		Unwind::Failed
+	}
+
+	fn collect(&mut self, module: ModuleId, item: &'a Item) {
+		let items = match &item.kind {
+			ItemKind::Const(decl) => {
+				if let Some(def) = item.def { self.consts.insert(def, (module, decl)); }
+				return;
+			},
+			ItemKind::Func(func) => {
+				if let Some(def) = item.def.filter(|_| func.is_const) { self.funcs.insert(def, (module, func)); }
+				return;
+			},
+			ItemKind::Type(decl) => &decl.items,
+			ItemKind::Trait(decl) => &decl.items,
+			ItemKind::Impl(decl) => &decl.items,
+			_ => return,
+		};
+
+		for item in items.iter() {
+			self.collect(module, item);
+		}
+	}
+
+	/// Evaluates a `const` item, if it hasn't been already.
+	fn const_item(&mut self, def: DefId) -> Eval {
+		if let Some(index) = self.pool.items.get(&def) { return Ok(self.pool.values[*index].clone()) }
+		if self.failed.contains(&def) { return Err(Unwind::Failed) }
+
+		let (module, decl) = self.consts[&def];
+		// A constant without a value was either reported already, or is in a trait, where only impls are used.
+		let Some(value) = &decl.value else { return Err(Unwind::Failed) };
+
+		if let Some(pos) = self.evaluating.iter().position(|other| *other == def) {
+			let names: Vec<String> = self.evaluating[pos..].iter().chain([&def]).map(|def| format!("`{}`", self.res.defs[*def].name.name)).collect();
+			let (outer, span) = (self.module, decl.name.span.clone());
+
+			self.module = module;
+			self.report(span, ConstError::Cycle(decl.name.name.clone()))
+				.add_note(format!("its value goes {}", names.join(" -> ")));
+			self.module = outer;
+
+			self.failed.insert(def);
+			return Err(Unwind::Failed);
+		}
+
+		// A constant starts from scratch, whatever it was needed by.
+		let outer = (self.module, std::mem::take(&mut self.locals), self.steps, self.calls);
+		self.module = module;
+		(self.steps, self.calls) = (0, 0);
+		self.evaluating.push(def);
+
+		let result = self.expr(value);
+
+		self.evaluating.pop();
+		(self.module, self.locals, self.steps, self.calls) = outer;
+
+		match result {
+			Ok(value) => {
+				let index = self.pool.add(value.clone());
+				self.pool.items.insert(def, index);
+				self.hint(module, &decl.name, &value);
+				Ok(value)
+			},
+			Err(_) => {
+				self.failed.insert(def);
+				Err(Unwind::Failed)
+			},
+		}
+	}
+
+	//--> Finding `const` Variables <--
+
+	fn item(&mut self, item: &Item) {
+		let items = match &item.kind {
+			ItemKind::Func(func) => {
+				if let Some(body) = &func.body { self.find_block(body); }
+				return;
+			},
+			ItemKind::Type(decl) => &decl.items,
+			ItemKind::Trait(decl) => &decl.items,
+			ItemKind::Effect(decl) => &decl.items,
+			ItemKind::Impl(decl) => &decl.items,
+			ItemKind::Use(_) | ItemKind::Const(_) => return,
+		};
+
+		for item in items.iter() {
+			self.item(item);
+		}
+	}
+
+	/// Evaluates every `const` variable in a block, in the order they're written.
+	fn find_block(&mut self, block: &Block) {
+		for stmt in block.stmts.iter() {
+			match &stmt.kind {
+				StmtKind::Let { mutability: Mutability::Const, pat, value, .. } => {
+					self.find(value);
+					(self.steps, self.calls) = (0, 0);
+
+					let Ok(result) = self.expr(value) else { continue };
+					let index = self.pool.add(result.clone());
+					self.pool.exprs.insert((self.module, value.span.clone()), index);
+
+					let mut bound = HashMap::new();
+					if !bind(pat, &result, &mut bound, &mut |_| None) { continue }
+
+					let mut locals: Vec<(LocalId, Value)> = bound.into_iter().collect();
+					locals.sort_unstable_by_key(|(local, _)| *local);
+
+					for (local, value) in locals {
+						self.hint(self.module, &self.res.locals[local].name, &value);
+						self.const_locals.insert(local, value);
+					}
+				},
+				StmtKind::Let { value, .. } | StmtKind::Expr(value) => self.find(value),
+				StmtKind::Assign { target, value, .. } => {
+					self.find(target);
+					self.find(value);
+				},
+				StmtKind::Handler(handler) => self.find_block(&handler.body),
+			}
+		}
+	}
+
+	fn find(&mut self, expr: &Expr) {
+		match &expr.kind {
+			ExprKind::Lit(_) | ExprKind::Path(_) | ExprKind::Continue(_) => {},
+			ExprKind::Call { callee: first, args: rest } | ExprKind::MethodCall { receiver: first, args: rest, .. } => {
+				self.find(first);
+				for arg in rest.iter() { self.find(arg); }
+			},
+			ExprKind::Field { base: inner, .. } | ExprKind::TupleField { base: inner, .. } | ExprKind::Unary { expr: inner, .. } | ExprKind::Try(inner) => self.find(inner),
+			ExprKind::Index { base: lhs, index: rhs } | ExprKind::Binary { lhs, rhs, .. } => {
+				self.find(lhs);
+				self.find(rhs);
+			},
+			ExprKind::Tuple(elems) | ExprKind::List(elems) | ExprKind::Interp(elems) => {
+				for elem in elems.iter() { self.find(elem); }
+			},
+			ExprKind::Record(fields) => {
+				for (_, value) in fields.iter() { self.find(value); }
+			},
+			ExprKind::Map(entries) => {
+				for (key, value) in entries.iter() {
+					self.find(key);
+					self.find(value);
+				}
+			},
+			ExprKind::Closure { body, .. } | ExprKind::Loop { body, .. } | ExprKind::Block { body, .. } => self.find_block(body),
+			ExprKind::If { branches, else_ } => {
+				for branch in branches.iter() {
+					match &branch.cond {
+						Condition::Expr(cond) | Condition::Matches(cond, _) => self.find(cond),
+					}
+					self.find_block(&branch.body);
+				}
+
+				if let Some(else_) = else_ { self.find_block(else_); }
+			},
+			ExprKind::Match { scrutinee, arms } => {
+				self.find(scrutinee);
+				for arm in arms.iter() { self.find(&arm.body); }
+			},
+			ExprKind::While { cond, body, .. } | ExprKind::Until { cond, body, .. } | ExprKind::For { iter: cond, body, .. } => {
+				self.find(cond);
+				self.find_block(body);
+			},
+			ExprKind::Handle { handler, body } => {
+				self.find_block(&handler.body);
+				self.find_block(body);
+			},
+			ExprKind::Break { value, .. } | ExprKind::Return(value) => {
+				if let Some(value) = value { self.find(value); }
+			},
+		}
+	}
+
+	//--> Evaluation <--
+
+	fn block(&mut self, block: &Block) -> Eval {
+		let mut value = Value::unit();
+
+		for (i, stmt) in block.stmts.iter().enumerate() {
+			match &stmt.kind {
+				StmtKind::Let { pat, value: init, .. } => {
+					let init = self.expr(init)?;
+					let mut bound = HashMap::new();
+
+					// Patterns in declarations always match, since they can only be made of names and tuples.
+					if bind(pat, &init, &mut bound, &mut |_| None) { self.locals.extend(bound); }
+				},
+				StmtKind::Assign { op, target, value } => self.assign(*op, target, value)?,
+				StmtKind::Expr(expr) => {
+					let found = self.expr(expr)?;
+					if i + 1 == block.stmts.len() { value = found }
+				},
+				StmtKind::Handler(_) => return Err(self.fail(&stmt.span, ConstError::Unsupported("handlers"))),
+			}
+		}
+
+		Ok(value)
+	}
+
+	fn expr(&mut self, expr: &Expr) -> Eval {
+		let value = self.eval(expr)?;
+
+		// A value turned into a trait object takes its vtable along with it.
+		Ok(match self.types.vtables.get(&(self.module, expr.span.clone())) {
+			Some(vtable) => Value::Object { vtable: vtable.clone(), value: Box::new(value) },
+			None => value,
+		})
+	}
+
+	fn eval(&mut self, expr: &Expr) -> Eval {
+		self.steps += 1;
+		if self.steps > self.max_steps { return Err(self.limit(&expr.span, ConstError::StepLimit(self.max_steps))) }
+
+		match &expr.kind {
+			ExprKind::Lit(lit) => Ok(self.literal(lit, false, &expr.span)),
+			ExprKind::Unary { op: UnaryOp::Neg, expr: operand } if matches!(operand.kind, ExprKind::Lit(Literal::Num(_))) => {
+				let ExprKind::Lit(lit) = &operand.kind else { unreachable!() };
+				Ok(self.literal(lit, true, &expr.span))
+			},
+			ExprKind::Path(path) => match path.res {
+				// Type checking found which impl's constant a path like `Counter::MAX` is, if it could.
+				_ if path.resolved < path.segments.len() => match self.types.assoc_consts.get(&(self.module, path.span.clone())) {
+					Some(def) => self.const_item(*def),
+					None => Err(self.fail(&path.span, ConstError::Unsupported("paths through a type's impls"))),
+				},
+				Res::Local(local) => match self.locals.get(&local).or_else(|| self.const_locals.get(&local)) {
+					Some(value) => Ok(value.clone()),
+					// Only a `const` variable which couldn't be evaluated gets here, which was already reported.
+					None => Err(Unwind::Failed),
+				},
+				Res::Def(def) => match self.res.defs[def].kind {
+					DefKind::Const => self.const_item(def),
+					DefKind::Variant => Ok(Value::Variant { def, name: self.res.defs[def].name.name.clone(), fields: Vec::new() }),
+					_ => Err(self.fail(&path.span, ConstError::Unsupported("functions as values"))),
+				},
+				_ => Err(Unwind::Failed),
+			},
+			ExprKind::Call { callee, args } => {
+				let ExprKind::Path(path) = &callee.kind else { return Err(self.fail(&callee.span, ConstError::Unsupported("calls to anything but a function"))) };
+
+				let def = match path.res {
+					Res::Def(def) if path.resolved == path.segments.len() => def,
+					Res::SelfType(def) if path.resolved == path.segments.len() && self.res.defs[def].kind == DefKind::Type => def,
+					_ => return Err(self.fail(&path.span, ConstError::Unsupported("calls through a type's impls"))),
+				};
+
+				let mut values = Vec::new();
+				for arg in args.iter() { values.push(self.expr(arg)?); }
+
+				match self.res.defs[def].kind {
+					DefKind::Variant | DefKind::Type => Ok(Value::Variant { def, name: self.res.defs[def].name.name.clone(), fields: values }),
+					_ => self.call(def, values, &expr.span),
+				}
+			},
+			ExprKind::MethodCall { method, .. } => Err(self.fail(&method.span, ConstError::Unsupported("method calls"))),
+			ExprKind::Field { base, field } => {
+				let fields = match self.expr(base)? {
+					Value::Record(fields) => fields,
+					// A record type's fields are all in the one record inside of it.
+					Value::Variant { mut fields, .. } if matches!(fields[..], [Value::Record(_)]) => {
+						let Some(Value::Record(fields)) = fields.pop() else { unreachable!() };
+						fields
+					},
+					_ => return Err(self.fail(&field.span, ConstError::Unsupported("fields of anything but a record"))),
+				};
+
+				Ok(fields.into_iter().find(|(name, _)| *name == field.name).map(|(_, value)| value).unwrap_or(Value::unit()))
+			},
+			ExprKind::TupleField { base, index } => match self.expr(base)? {
+				Value::Tuple(mut elems) | Value::Variant { fields: mut elems, .. } if *index < elems.len() => Ok(elems.swap_remove(*index)),
+				_ => Err(Unwind::Failed),
+			},
+			ExprKind::Index { base, index } => {
+				let base = self.expr(base)?;
+				let index_value = self.expr(index)?;
+				self.index(base, index_value, &index.span)
+			},
+			ExprKind::Tuple(elems) => Ok(Value::Tuple(self.exprs(elems)?)),
+			ExprKind::List(elems) => Ok(Value::List(self.exprs(elems)?)),
+			// Strings and characters go in as they are, and everything else the way it's shown in hints.
+			ExprKind::Interp(parts) => {
+				let mut text = String::new();
+
+				for value in self.exprs(parts)? {
+					match value {
+						Value::Str(s) => text.push_str(&s),
+						Value::Char(c) => text.push(c),
+						value => text.push_str(&value.to_string()),
+					}
+				}
+
+				Ok(Value::Str(text))
+			},
+			ExprKind::Record(fields) => {
+				let mut values = Vec::new();
+				for (name, value) in fields.iter() { values.push((name.name.clone(), self.expr(value)?)); }
+				Ok(Value::Record(values))
+			},
+			ExprKind::Map(entries) => {
+				let mut map = Vec::new();
+
+				for (key, value) in entries.iter() {
+					let (key, value) = (self.expr(key)?, self.expr(value)?);
+					insert(&mut map, key, value);
+				}
+
+				Ok(Value::Map(map))
+			},
+			ExprKind::Unary { op, expr: operand } => {
+				let value = self.expr(operand)?;
+
+				match self.types.operators.get(&(self.module, expr.span.clone())) {
+					Some(def) => self.call(*def, vec![value], &expr.span),
+					None => self.unary(*op, value, &expr.span),
+				}
+			},
+			// `and` and `or` only evaluate their right-hand side if they need to.
+			ExprKind::Binary { op: op @ (BinaryOp::And | BinaryOp::Or), lhs, rhs } => match self.expr(lhs)? {
+				Value::Bool(value) if value == (*op == BinaryOp::Or) => Ok(Value::Bool(value)),
+				_ => self.expr(rhs),
+			},
+			ExprKind::Binary { op, lhs, rhs } => {
+				let (lhs, rhs) = (self.expr(lhs)?, self.expr(rhs)?);
+
+				match self.types.operators.get(&(self.module, expr.span.clone())) {
+					Some(def) => self.call(*def, vec![lhs, rhs], &expr.span),
+					None => self.binary(*op, lhs, rhs, &expr.span),
+				}
+			},
+			ExprKind::If { branches, else_ } => {
+				for branch in branches.iter() {
+					let taken = match &branch.cond {
+						Condition::Expr(cond) => self.expr(cond)? == Value::Bool(true),
+						Condition::Matches(value, pat) => {
+							let value = self.expr(value)?;
+							self.matches(pat, &value)?
+						},
+					};
+
+					if taken {
+						let value = self.block(&branch.body)?;
+						return Ok(if else_.is_some() { value } else { Value::unit() });
+					}
+				}
+
+				match else_ {
+					Some(else_) => self.block(else_),
+					None => Ok(Value::unit()),
+				}
+			},
+			ExprKind::Match { scrutinee, arms } => {
+				let value = self.expr(scrutinee)?;
+
+				for arm in arms.iter() {
+					if self.matches(&arm.pat, &value)? { return self.expr(&arm.body) }
+				}
+
+				Err(self.fail(&expr.span, ConstError::NoArm(value.to_string())))
+			},
+			ExprKind::Loop { label, body } => {
+				let label = label.as_ref().map(|label| &label.name);
+
+				loop {
+					match self.block(body) {
+						Ok(_) => {},
+						Err(Unwind::Break(target, value)) if target.is_none() || target.as_ref() == label => return Ok(value),
+						Err(Unwind::Continue(target)) if target.is_none() || target.as_ref() == label => {},
+						Err(unwind) => return Err(unwind),
+					}
+				}
+			},
+			ExprKind::Block { label, body } => match self.block(body) {
+				Err(Unwind::Break(Some(target), value)) if target == label.name => Ok(value),
+				result => result,
+			},
+			ExprKind::Break { label, value } => {
+				let value = match value {
+					Some(value) => self.expr(value)?,
+					None => Value::unit(),
+				};
+
+				Err(Unwind::Break(label.as_ref().map(|label| label.name.clone()), value))
+			},
+			ExprKind::Continue(label) => Err(Unwind::Continue(label.as_ref().map(|label| label.name.clone()))),
+			ExprKind::Return(value) => {
+				let value = match value {
+					Some(value) => self.expr(value)?,
+					None => Value::unit(),
+				};
+
+				Err(Unwind::Return(value))
+			},
+			ExprKind::Closure { .. } => Err(self.fail(&expr.span, ConstError::Unsupported("closures"))),
+			ExprKind::Handle { .. } | ExprKind::Try(_) => Err(self.fail(&expr.span, ConstError::Unsupported("handlers"))),
+			// Desugaring has already turned these into `always` loops.
+			ExprKind::While { .. } | ExprKind::Until { .. } | ExprKind::For { .. } => Err(self.fail(&expr.span, ConstError::Unsupported("loops"))),
+		}
+	}
+
+	fn exprs(&mut self, exprs: &[Expr]) -> Result<Vec<Value>, Unwind> {
+		let mut values = Vec::new();
+		for expr in exprs.iter() { values.push(self.expr(expr)?); }
+		Ok(values)
+	}
+
+	/// Calls a `const func`, with a fresh set of variables.
+	fn call(&mut self, def: DefId, args: Vec<Value>, span: &Span) -> Eval {
+		self.invoke(def, args, span).map(|(value, _)| value)
+	}
+
+	/// Calls a `const func`, giving back what its first parameter ended up as along with what it returned,
+	/// for methods which change `self`.
+	fn invoke(&mut self, def: DefId, args: Vec<Value>, span: &Span) -> Result<(Value, Option<Value>), Unwind> {
+		let Some((module, func)) = self.funcs.get(&def).copied() else {
+			return Err(self.fail(span, ConstError::Unsupported("functions which aren't `const`")));
+		};
+
+		let Some(body) = &func.body else {
+			return Err(self.fail(span, ConstError::Unsupported("builtin functions")));
+		};
+
+		if self.calls == self.max_calls { return Err(self.limit(span, ConstError::CallLimit(self.max_calls))) }
+
+		let locals = func.params.iter().zip(args).filter_map(|(param, arg)| Some((param.local?, arg))).collect();
+		let outer = (std::mem::replace(&mut self.module, module), std::mem::replace(&mut self.locals, locals));
+		self.calls += 1;
+
+		let result = self.block(body);
+		let first = func.params.first().and_then(|param| self.locals.remove(&param.local?));
+
+		self.calls -= 1;
+		(self.module, self.locals) = outer;
+
+		match result {
+			Ok(value) | Err(Unwind::Return(value)) => Ok((value, first)),
+			Err(unwind) => Err(unwind),
+		}
+	}
+
+	fn literal(&self, lit: &Literal, negated: bool, span: &Span) -> Value {
+		match lit {
+			Literal::Num(n) => match self.types.numbers.get(&(self.module, span.clone())) {
+				Some(Prim::Byte) => Value::Byte(*n as u8),
+				Some(Prim::Int) => Value::Int(if negated { (*n as i64).wrapping_neg() } else { *n as i64 }),
+				Some(Prim::Flo) => Value::Flo(if negated { -(*n as f64) } else { *n as f64 }),
+				_ => Value::Nat(*n),
+			},
+			Literal::Float(f) => Value::Flo(if negated { -f } else { *f }),
+			Literal::Char(c) => Value::Char(*c),
+			Literal::Str(s) => Value::Str(s.clone()),
+			Literal::Byte(b) => Value::Byte(*b),
+			Literal::ByteStr(bytes) => Value::List(bytes.iter().map(|b| Value::Byte(*b)).collect()),
+			Literal::Bool(b) => Value::Bool(*b),
+		}
+	}
+
+	fn unary(&mut self, op: UnaryOp, value: Value, span: &Span) -> Eval {
+		Ok(match (op, value) {
+			(UnaryOp::Plus, value) => value,
+			(UnaryOp::Neg, Value::Int(n)) => Value::Int(n.checked_neg().ok_or_else(|| self.fail(span, ConstError::Overflow { op: "-", ty: "int" }))?),
+			(UnaryOp::Neg, Value::Flo(n)) => Value::Flo(-n),
+			(UnaryOp::Not, Value::Bool(b)) => Value::Bool(!b),
+			(UnaryOp::Not, Value::Byte(n)) => Value::Byte(!n),
+			(UnaryOp::Not, Value::Nat(n)) => Value::Nat(!n),
+			(UnaryOp::Not, Value::Int(n)) => Value::Int(!n),
+			_ => return Err(Unwind::Failed),
+		})
+	}
+
+	fn binary(&mut self, op: BinaryOp, lhs: Value, rhs: Value, span: &Span) -> Eval {
+		let name = op.as_str();
+
+		// Every kind of whole number works the same way, apart from what it can hold.
+		macro_rules! integral {
+			($a:expr, $b:expr, $ty:literal, $wrap:path) => {{
+				let (a, b) = ($a, $b);
+				let overflow = ConstError::Overflow { op: name, ty: $ty };
+
+				let result = match op {
+					BinaryOp::Add => a.checked_add(b),
+					BinaryOp::Sub => a.checked_sub(b),
+					BinaryOp::Mul => a.checked_mul(b),
+					BinaryOp::Div | BinaryOp::Rem | BinaryOp::DivisibleBy if b == 0 => return Err(self.fail(span, ConstError::DivideByZero)),
+					BinaryOp::Div => a.checked_div(b),
+					BinaryOp::Rem => a.checked_rem(b),
+					// The only remainder which doesn't fit is `int`'s lowest value by -1, which divides it fine.
+					BinaryOp::DivisibleBy => return Ok(Value::Bool(a.checked_rem(b).is_none_or(|rem| rem == 0))),
+					BinaryOp::BitAnd => Some(a & b),
+					BinaryOp::BitOr => Some(a | b),
+					BinaryOp::BitXor => Some(a ^ b),
+					_ => return Err(Unwind::Failed),
+				};
+
+				match result {
+					Some(value) => Ok($wrap(value)),
+					None => Err(self.fail(span, overflow)),
+				}
+			}};
+		}
+
+		match (op, lhs, rhs) {
+			(BinaryOp::Shl | BinaryOp::Shr, lhs, rhs) => self.shift(op, lhs, rhs, span),
+			(BinaryOp::Eq, lhs, rhs) => Ok(Value::Bool(lhs == rhs)),
+			(BinaryOp::Ne, lhs, rhs) => Ok(Value::Bool(lhs != rhs)),
+			(BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge, lhs, rhs) => self.compare(op, &lhs, &rhs, span),
+			(BinaryOp::Range | BinaryOp::RangeInclusive | BinaryOp::Bind, ..) => Err(self.fail(span, ConstError::Unsupported("ranges and `>=>`"))),
+			(BinaryOp::Xor, Value::Bool(a), Value::Bool(b)) => Ok(Value::Bool(a != b)),
+			(BinaryOp::BitAnd, Value::Bool(a), Value::Bool(b)) => Ok(Value::Bool(a & b)),
+			(BinaryOp::BitOr, Value::Bool(a), Value::Bool(b)) => Ok(Value::Bool(a | b)),
+			(BinaryOp::BitXor, Value::Bool(a), Value::Bool(b)) => Ok(Value::Bool(a ^ b)),
+			(BinaryOp::Add, Value::Str(a), Value::Str(b)) => Ok(Value::Str(a + &b)),
+			(BinaryOp::Add, Value::List(mut a), Value::List(b)) => {
+				a.extend(b);
+				Ok(Value::List(a))
+			},
+			(BinaryOp::Add, Value::List(mut a), b) => {
+				a.push(b);
+				Ok(Value::List(a))
+			},
+			(_, Value::Byte(a), Value::Byte(b)) => integral!(a, b, "byte", Value::Byte),
+			(_, Value::Nat(a), Value::Nat(b)) => integral!(a, b, "nat", Value::Nat),
+			(_, Value::Int(a), Value::Int(b)) => integral!(a, b, "int", Value::Int),
+			(_, Value::Flo(a), Value::Flo(b)) => Ok(Value::Flo(match op {
+				BinaryOp::Add => a + b,
+				BinaryOp::Sub => a - b,
+				BinaryOp::Mul => a * b,
+				BinaryOp::Div => a / b,
+				BinaryOp::Rem => a % b,
+				_ => return Err(Unwind::Failed),
+			})),
+			_ => Err(Unwind::Failed),
+		}
+	}
+
+	fn shift(&mut self, op: BinaryOp, lhs: Value, rhs: Value, span: &Span) -> Eval {
+		let by = match rhs {
+			Value::Byte(n) => n as u64,
+			Value::Nat(n) => n,
+			Value::Int(n) if n >= 0 => n as u64,
+			_ => u64::MAX,
+		};
+
+		let (ty, bits): (&'static str, u64) = match lhs {
+			Value::Byte(_) => ("byte", 8),
+			Value::Nat(_) => ("nat", 64),
+			_ => ("int", 64),
+		};
+
+		if by >= bits { return Err(self.fail(span, ConstError::Overflow { op: op.as_str(), ty })) }
+		let by = by as u32;
+
+		Ok(match (op, lhs) {
+			(BinaryOp::Shl, Value::Byte(n)) => Value::Byte(n << by),
+			(BinaryOp::Shl, Value::Nat(n)) => Value::Nat(n << by),
+			(BinaryOp::Shl, Value::Int(n)) => Value::Int(n << by),
+			(_, Value::Byte(n)) => Value::Byte(n >> by),
+			(_, Value::Nat(n)) => Value::Nat(n >> by),
+			(_, Value::Int(n)) => Value::Int(n >> by),
+			_ => return Err(Unwind::Failed),
+		})
+	}
+
+	fn compare(&mut self, op: BinaryOp, lhs: &Value, rhs: &Value, span: &Span) -> Eval {
+		let Some(ordering) = lhs.compare(rhs) else {
+			return Err(self.fail(span, ConstError::Unsupported("comparisons of anything but numbers, characters, strings and lists of them")));
+		};
+
+		Ok(Value::Bool(match op {
+			BinaryOp::Lt => ordering.is_lt(),
+			BinaryOp::Le => ordering.is_le(),
+			BinaryOp::Gt => ordering.is_gt(),
+			_ => ordering.is_ge(),
+		}))
+	}
+
+	fn index(&mut self, base: Value, index: Value, span: &Span) -> Eval {
+		match base {
+			Value::List(mut elems) => {
+				let at = self.position(&index, elems.len(), span)?;
+				Ok(elems.swap_remove(at))
+			},
+			Value::Map(entries) => match entries.into_iter().find(|(key, _)| *key == index) {
+				Some((_, value)) => Ok(value),
+				None => Err(self.fail(span, ConstError::MissingKey(index.to_string()))),
+			},
+			_ => Err(self.fail(span, ConstError::Unsupported("indexing anything but lists and maps"))),
+		}
+	}
+
+	/// Works out which element of a list an index refers to, where negative indices count from the end.
+	fn position(&mut self, index: &Value, len: usize, span: &Span) -> Result<usize, Unwind> {
+		let index = match index {
+			Value::Byte(n) => *n as i128,
+			Value::Nat(n) => *n as i128,
+			Value::Int(n) => *n as i128,
+			_ => return Err(Unwind::Failed),
+		};
+
+		let at = if index < 0 { len as i128 + index } else { index };
+		if (0..len as i128).contains(&at) { Ok(at as usize) } else { Err(self.fail(span, ConstError::OutOfBounds { index, len })) }
+	}
+
+	fn assign(&mut self, op: AssignOp, target: &Expr, value: &Expr) -> Result<(), Unwind> {
+		let value = self.expr(value)?;
+
+		// Indices get evaluated up front, so the variable only needs to be borrowed once they're all known.
+		let mut projs = Vec::new();
+		let mut place = target;
+
+		let local = loop {
+			match &place.kind {
+				ExprKind::Field { base, field } => {
+					projs.push((Proj::Field(field.name.clone()), field.span.clone()));
+					place = base;
+				},
+				ExprKind::TupleField { base, index } => {
+					projs.push((Proj::Tuple(*index), place.span.clone()));
+					place = base;
+				},
+				ExprKind::Index { base, index } => {
+					let at = self.expr(index)?;
+					projs.push((Proj::Index(at), index.span.clone()));
+					place = base;
+				},
+				ExprKind::Path(path) => match path.res {
+					Res::Local(local) => break local,
+					_ => return Err(Unwind::Failed),
+				},
+				_ => return Err(Unwind::Failed),
+			}
+		};
+
+		let value = match op {
+			AssignOp::Compound(op) => {
+				let current = self.expr(target)?;
+
+				match self.types.operators.get(&(self.module, target.span.clone())).copied() {
+					Some(def) => self.invoke(def, vec![current, value], &target.span)?.1.ok_or(Unwind::Failed)?,
+					None => self.binary(op, current, value, &target.span)?,
+				}
+			},
+			_ => value,
+		};
+
+		let Some(mut slot) = self.locals.remove(&local) else { return Err(Unwind::Failed) };
+		let result = self.project(&mut slot, &projs, value);
+		self.locals.insert(local, slot);
+		result
+	}
+
+	/// Follows the steps from a variable to whatever's being assigned to, outermost last, and assigns to it.
+	fn project(&mut self, slot: &mut Value, projs: &[(Proj, Span)], value: Value) -> Result<(), Unwind> {
+		let Some(((proj, span), rest)) = projs.split_last() else {
+			*slot = value;
+			return Ok(());
+		};
+
+		let inner = match (proj, slot) {
+			(Proj::Field(name), Value::Record(fields)) => match fields.iter_mut().find(|(field, _)| field == name) {
+				Some((_, inner)) => inner,
+				None => return Err(Unwind::Failed),
+			},
+			// The record inside of a record type is what actually has the field.
+			(Proj::Field(_), Value::Variant { fields, .. }) if matches!(fields[..], [Value::Record(_)]) => return self.project(&mut fields[0], projs, value),
+			(Proj::Tuple(index), Value::Tuple(elems) | Value::Variant { fields: elems, .. }) => match elems.get_mut(*index) {
+				Some(inner) => inner,
+				None => return Err(Unwind::Failed),
+			},
+			(Proj::Index(index), Value::List(elems)) => {
+				let at = self.position(index, elems.len(), span)?;
+				&mut elems[at]
+			},
+			// Assigning to a key which isn't in a map yet adds it.
+			(Proj::Index(key), Value::Map(entries)) => {
+				if !entries.iter().any(|(other, _)| other == key) { entries.push((key.clone(), Value::unit())) }
+				&mut entries.iter_mut().find(|(other, _)| other == key).unwrap().1
+			},
+			_ => return Err(Unwind::Failed),
+		};
+
+		self.project(inner, rest, value)
+	}
+
+	/// Checks whether a pattern matches a value, binding its names if it does.
+	fn matches(&mut self, pat: &Pattern, value: &Value) -> Result<bool, Unwind> {
+		let mut bound = HashMap::new();
+		let mut failed = false;
+
+		let matched = bind(pat, value, &mut bound, &mut |def| match self.const_item(def) {
+			Ok(value) => Some(value),
+			Err(_) => {
+				failed = true;
+				None
+			},
+		});
+
+		if failed { return Err(Unwind::Failed) }
+		if matched { self.locals.extend(bound); }
+		Ok(matched)
+	}
+}
+
+/// Matches a pattern against a value, putting whatever it binds into `bound`. `consts` gives the values of constants used as patterns.
+fn bind(pat: &Pattern, value: &Value, bound: &mut HashMap<LocalId, Value>, consts: &mut impl FnMut(DefId) -> Option<Value>) -> bool {
+	match (&pat.kind, value) {
+		(PatternKind::Wildcard, _) => true,
+		(PatternKind::Binding { local, .. }, _) => {
+			if let Some(local) = local { bound.insert(*local, value.clone()); }
+			true
+		},
+		(PatternKind::Lit { negated, lit }, _) => lit_matches(lit, *negated, value) == Some(Ordering::Equal),
+		(PatternKind::Range { start, end, inclusive }, _) => {
+			// An end which is left off doesn't rule anything out on its side.
+			let within = |end: &Option<Box<Pattern>>, allowed: &dyn Fn(Ordering) -> bool| match end.as_deref().map(|end| &end.kind) {
+				None => true,
+				Some(PatternKind::Lit { negated, lit }) => lit_matches(lit, *negated, value).is_some_and(allowed),
+				Some(_) => false,
+			};
+
+			within(start, &|ordering| ordering.is_ge()) && within(end, &|ordering| if *inclusive { ordering.is_le() } else { ordering.is_lt() })
+		},
+		(PatternKind::Path(path), _) => match path.res {
+			Res::Def(def) => match value {
+				Value::Variant { def: other, .. } if *other == def => true,
+				_ => consts(def).is_some_and(|constant| constant == *value),
+			},
+			_ => false,
+		},
+		(PatternKind::TupleStruct { path, fields }, Value::Variant { def, fields: values, .. }) => {
+			matches!(path.res, Res::Def(found) | Res::SelfType(found) if found == *def) && fields.len() == values.len()
+				&& fields.iter().zip(values.iter()).all(|(field, value)| bind(field, value, bound, consts))
+		},
+		(PatternKind::Tuple(pats), Value::Tuple(values)) => {
+			pats.len() == values.len() && pats.iter().zip(values.iter()).all(|(pat, value)| bind(pat, value, bound, consts))
+		},
+		_ => false,
+	}
+}
+
+/// Compares a value against a literal in a pattern, giving back how the value is ordered next to it.
+fn lit_matches(lit: &Literal, negated: bool, value: &Value) -> Option<Ordering> {
+	let sign = if negated { -1 } else { 1 };
+
+	match (lit, value) {
+		(Literal::Num(n), Value::Byte(v)) => (*v as i128).partial_cmp(&(sign * *n as i128)),
+		(Literal::Num(n), Value::Nat(v)) => (*v as i128).partial_cmp(&(sign * *n as i128)),
+		(Literal::Num(n), Value::Int(v)) => (*v as i128).partial_cmp(&(sign * *n as i128)),
+		(Literal::Num(n), Value::Flo(v)) => v.partial_cmp(&(sign as f64 * *n as f64)),
+		(Literal::Float(f), Value::Flo(v)) => v.partial_cmp(&(sign as f64 * f)),
+		(Literal::Byte(b), Value::Byte(v)) => v.partial_cmp(b),
+		(Literal::Char(c), Value::Char(v)) => v.partial_cmp(c),
+		(Literal::Str(s), Value::Str(v)) => v.partial_cmp(s),
+		(Literal::Bool(b), Value::Bool(v)) => v.partial_cmp(b),
+		_ => None,
+	}
+}
+
+/// Sets a key in a map, keeping it where it was if it's already there.
+fn insert(map: &mut Vec<(Value, Value)>, key: Value, value: Value) {
+	match map.iter_mut().find(|(other, _)| *other == key) {
+		Some((_, slot)) => *slot = value,
+		None => map.push((key, value)),
+	}
+}
+
+impl Value {
+	fn unit() -> Value {
+		Value::Tuple(Vec::new())
+	}
+
+	/// How two values are ordered, if they're something which can be ordered.
+	fn compare(&self, other: &Value) -> Option<Ordering> {
+		match (self, other) {
+			(Value::Bool(a), Value::Bool(b)) => a.partial_cmp(b),
+			(Value::Char(a), Value::Char(b)) => a.partial_cmp(b),
+			(Value::Byte(a), Value::Byte(b)) => a.partial_cmp(b),
+			(Value::Nat(a), Value::Nat(b)) => a.partial_cmp(b),
+			(Value::Int(a), Value::Int(b)) => a.partial_cmp(b),
+			(Value::Flo(a), Value::Flo(b)) => a.partial_cmp(b),
+			(Value::Str(a), Value::Str(b)) => a.partial_cmp(b),
+			(Value::Tuple(a), Value::Tuple(b)) | (Value::List(a), Value::List(b)) => {
+				for (a, b) in a.iter().zip(b.iter()) {
+					match a.compare(b)? {
+						Ordering::Equal => {},
+						ordering => return Some(ordering),
+					}
+				}
+
+				Some(a.len().cmp(&b.len()))
+			},
+			_ => None,
+		}
+	}
+}
+
+impl fmt::Display for Value {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let list = |f: &mut fmt::Formatter<'_>, values: &[Value]| -> fmt::Result {
+			for (i, value) in values.iter().enumerate() {
+				if i > 0 { write!(f, ", ")?; }
+				write!(f, "{}", value)?;
+			}
+			Ok(())
+		};
+
+		match self {
+			Value::Bool(b) => write!(f, "{}", b),
+			Value::Char(c) => write!(f, "{:?}", c),
+			Value::Byte(n) => write!(f, "{}", n),
+			Value::Nat(n) => write!(f, "{}", n),
+			Value::Int(n) => write!(f, "{}", n),
+			Value::Flo(n) => write!(f, "{:?}", n),
+			Value::Str(s) => write!(f, "{:?}", s),
+			Value::Tuple(elems) => {
+				write!(f, "(")?;
+				list(f, elems)?;
+				write!(f, ")")
+			},
+			Value::Record(fields) => {
+				write!(f, "(")?;
+				for (i, (name, value)) in fields.iter().enumerate() {
+					if i > 0 { write!(f, ", ")?; }
+					write!(f, "{}: {}", name, value)?;
+				}
+				write!(f, ")")
+			},
+			Value::List(elems) => {
+				write!(f, "[")?;
+				list(f, elems)?;
+				write!(f, "]")
+			},
+			// Turning a value into a trait object happens without being written, so only the value itself gets shown.
+			Value::Object { value, .. } => write!(f, "{}", value),
+			Value::Map(entries) if entries.is_empty() => write!(f, "[:]"),
+			Value::Map(entries) => {
+				write!(f, "[")?;
+				for (i, (key, value)) in entries.iter().enumerate() {
+					if i > 0 { write!(f, ", ")?; }
+					write!(f, "{}: {}", key, value)?;
+				}
+				write!(f, "]")
+			},
+			Value::Variant { name, fields, .. } if fields.is_empty() => write!(f, "{}", name),
+			Value::Variant { name, fields, .. } if matches!(fields[..], [Value::Record(_)]) => write!(f, "{}{}", name, fields[0]),
+			Value::Variant { name, fields, .. } => {
+				write!(f, "{}(", name)?;
+				list(f, fields)?;
+				write!(f, ")")
+			},
+		}
+	}
+}
+
+impl fmt::Display for ConstError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ConstError::StepLimit(max) => write!(f, "working out this constant took too long; the limit is {} steps", max),
+			ConstError::CallLimit(max) => write!(f, "working out this constant went too many calls deep; the limit is {}", max),
+			ConstError::Cycle(name) => write!(f, "the value of `{}` depends on itself", name),
+			ConstError::Overflow { op, ty } => write!(f, "`{}` went past what `{}` can hold while working out a constant", op, ty),
+			ConstError::DivideByZero => write!(f, "this divides by zero while working out a constant"),
+			ConstError::OutOfBounds { index, len } => write!(f, "index {} is out of bounds for a list of length {}", index, len),
+			ConstError::MissingKey(key) => write!(f, "the map doesn't have the key `{}`", key),
+			ConstError::NoArm(value) => write!(f, "none of the arms match `{}`", value),
+			ConstError::Unsupported(what) => write!(f, "{} can't be worked out before the program runs yet", what),
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compiler::{
+		Options,
+		TestPackage,
+	};
+
+	/// Checks a package with a single file.
+	fn check_source(name: &str, source: &str, options: &Options) -> Result<ConstPool, ErrorList> {
+		TestPackage::new(name, &[("main.ro", source)]).check(options).map(|(package, _)| package.consts)
+	}
+
+	fn values(name: &str, source: &str) -> Vec<(String, String)> {
+		check_source(name, source, &Options::default()).unwrap().hints.into_iter().map(|hint| (hint.name, hint.value)).collect()
+	}
+
+	fn const_errors(name: &str, source: &str, options: &Options) -> Vec<(Option<usize>, ConstError)> {
+		check_source(name, source, options).err().unwrap().into_iter()
+			.filter(|e| !e.is_warning())
+			.map(|e| match e.kind() {
+				ErrorKind::Interpret(InterpretError::Const(err)) => (e.line(), err),
+				kind => panic!("expected a const error, got {}", kind),
+			})
+			.collect()
+	}
+
+	#[test]
+	fn constants_are_worked_out() {
+		let values = values("values", "const func factorial(n: nat) -> nat do\n\tmut total := 1\n\tmut i := n\n\n\twhile i > 1 do\n\t\ttotal *= i\n\t\ti -= 1\n\tend\n\n\ttotal\nend\n\nconst func fib(n: nat) -> nat do\n\tif n < 2 then return n\n\tfib(n - 1) + fib(n - 2)\nend\n\nconst func describe(o: Option<int>) -> str do\n\tif o matches\n\t\tSome(-1) then \"minus one\"\n\t\tSome(n) then \"some\"\n\t\tNone then \"none\"\n\tend\nend\n\nconst SIX := factorial(3)\nconst FIBS := [fib(10), fib(SIX)]\nconst NAMES := [1: \"one\", 2: \"two\"]\nconst WHICH := (a: describe(Some(-1)), b: describe(None))\n\npub func main() do\n\tconst twice := SIX * 2\n\tconst (left, right) := (twice / 4, NAMES[2])\nend\n");
+
+		assert_eq!(values, [
+			("SIX".to_string(), "6".to_string()),
+			("FIBS".to_string(), "[55, 8]".to_string()),
+			("NAMES".to_string(), "[1: \"one\", 2: \"two\"]".to_string()),
+			("WHICH".to_string(), "(a: \"minus one\", b: \"none\")".to_string()),
+			("twice".to_string(), "12".to_string()),
+			("left".to_string(), "3".to_string()),
+			("right".to_string(), "\"two\"".to_string()),
+		]);
+	}
+
+	#[test]
+	fn records_and_tuple_types_are_values() {
+		let values = values("records", "type Point is\n\tpub x: int\n\tpub y: int\nend\n\ntype Pair is\n\t(nat, str)\nend\n\nconst func moved(p: Point) -> Point do\n\tmut q := p\n\tq.x += 10\n\tq\nend\n\nconst func second(p: Pair) -> str do\n\tif p matches Pair(_, name) then name else \"none\"\nend\n\nconst POINT := moved(Point(x: 1, y: -1))\nconst X := POINT.x\nconst PAIR := Pair(2, \"two\")\nconst NAME := second(PAIR)\n\npub func main() do\nend\n");
+
+		assert_eq!(values, [
+			("POINT".to_string(), "Point(x: 11, y: -1)".to_string()),
+			("X".to_string(), "11".to_string()),
+			("PAIR".to_string(), "Pair(2, \"two\")".to_string()),
+			("NAME".to_string(), "\"two\"".to_string()),
+		]);
+	}
+
+	#[test]
+	fn operators_call_const_impls() {
+		let values = values("operators", "use std::ops::*\n\ntype Money is\n\t(nat)\nend\n\nimpl Add for Money is\n\tconst func add(self, rhs: Money) -> Money do\n\t\tMoney(self.0 + rhs.0)\n\tend\nend\n\nimpl AddAssign for Money is\n\tconst func add_assign(mut self, rhs: Money) do\n\t\tself.0 += rhs.0\n\tend\nend\n\nconst func doubled(m: Money) -> Money do\n\tmut total := m\n\ttotal += m\n\ttotal\nend\n\nconst SUM := Money(1) + Money(2)\nconst DOUBLED := doubled(SUM)\n\npub func main() do\nend\n");
+
+		assert_eq!(values, [
+			("SUM".to_string(), "Money(3)".to_string()),
+			("DOUBLED".to_string(), "Money(6)".to_string()),
+		]);
+	}
+
+	#[test]
+	fn associated_constants_come_from_impls() {
+		let values = values("assoc", "trait Sized is\n\tconst SIZE: nat\n\tconst DOUBLE: nat = 0\nend\n\ntype Small is\n\t(nat)\nend\n\ntype Big is\n\t(nat)\nend\n\nimpl Sized for Small is\n\tconst SIZE := 2\nend\n\nimpl Sized for Big is\n\tconst SIZE: nat = Small::SIZE * 10\n\tconst DOUBLE: nat = Self::SIZE * 2\nend\n\nconst TOTAL := Small::SIZE + Big::DOUBLE + Small::DOUBLE\n\npub func main() do\nend\n");
+
+		assert_eq!(values, [
+			("DOUBLE".to_string(), "0".to_string()),
+			("SIZE".to_string(), "2".to_string()),
+			("SIZE".to_string(), "20".to_string()),
+			("DOUBLE".to_string(), "40".to_string()),
+			("TOTAL".to_string(), "42".to_string()),
+		]);
+	}
+
+	#[test]
+	fn the_pool_stores_each_value_once() {
+		let pool = check_source("pool", "const A := 5\nconst B := 2 + 3\nconst C := \"five\"\n\npub func main() do\nend\n", &Options::default()).unwrap();
+
+		let mut indices: Vec<usize> = pool.items.values().copied().collect();
+		indices.sort_unstable();
+		assert_eq!(indices, [0, 0, 1]);
+		assert_eq!(pool.values, [Value::Nat(5), Value::Str("five".to_string())]);
+	}
+
+	#[test]
+	fn mistakes_are_caught_before_running() {
+		let errors = const_errors("mistakes", "const func half(n: int) -> int do\n\t10 / n\nend\n\nconst func at(i: int) -> nat do\n\t[1, 2, 3][i]\nend\n\nconst A := half(0)\nconst B := at(-4)\nconst C := 0 - 1\nconst D := E + 1\nconst E := D\n\npub func main() do\nend\n", &Options::default());
+
+		assert!(matches!(&errors[..], [
+			(Some(2), ConstError::DivideByZero),
+			(Some(6), ConstError::OutOfBounds { index: -4, len: 3 }),
+			(Some(11), ConstError::Overflow { op: "-", ty: "nat" }),
+			(Some(12), ConstError::Cycle(name)),
+		] if name == "D"), "{:?}", errors);
+	}
+
+	#[test]
+	fn trait_objects_keep_their_vtables() {
+		let pool = check_source("objects", "trait Widget is\n\tfunc width(self) -> nat\nend\n\ntype Button is\n\t(nat)\nend\n\nimpl Widget for Button is\n\tfunc width(self) -> nat do\n\t\tself.0\n\tend\nend\n\nimpl Widget for str is\n\tfunc width(self) -> nat do\n\t\t1\n\tend\nend\n\nconst WIDGETS: [dyn Widget] = [Button(2), \"label\"]\n\npub func main() do\nend\n", &Options::default()).unwrap();
+
+		assert_eq!(pool.hints[0].value, "[Button(2), \"label\"]");
+
+		let [Value::List(widgets)] = &pool.values[..] else { panic!("expected a list, got {:?}", pool.values) };
+		let [Value::Object { vtable: button, .. }, Value::Object { vtable: label, value }] = &widgets[..] else { panic!("expected trait objects, got {:?}", widgets) };
+		assert!(button.len() == 1 && label.len() == 1 && button != label);
+		assert_eq!(**value, Value::Str("label".to_string()));
+	}
+
+	#[test]
+	fn evaluation_is_limited() {
+		let options = Options { max_const_steps: 1000, max_const_calls: 10, ..Options::default() };
+		let source = format!("const func spin() -> nat do\n\tmut n := 0\n\talways\n\t\tn += 1\n\tend\nend\n\nconst func deep(n: nat) -> nat do\n\tdeep(n + 1)\nend\n\nconst SPIN := spin()\nconst DEEP := deep(0)\nconst BIG := [{}]\n\npub func main() do\nend\n", ["0"; 1000].join(", "));
+		let errors = const_errors("limits", &source, &options);
+
+		assert!(matches!(&errors[..], [
+			(Some(4), ConstError::StepLimit(1000)),
+			(Some(9), ConstError::CallLimit(10)),
+			(Some(14), ConstError::StepLimit(1000)),
+		]), "{:?}", errors);
+
+		// Whichever constant was being worked out gets pointed at, unless that's where it happened anyway.
+		let errors = check_source("limits-notes", &source, &options).err().unwrap();
+		let notes: Vec<&[String]> = errors.iter().filter(|e| !e.is_warning()).map(|e| e.notes()).collect();
+		assert_eq!(notes[0], ["this happened while working out `SPIN`, on line 12"]);
+		assert_eq!(notes[1], ["this happened while working out `DEEP`, on line 13"]);
+		assert!(notes[2].is_empty());
+	}
+}
@@ -0,0 +1,429 @@
+//! Rewrites the surface language into a smaller core language, so that later stages have less to deal with.
+//!
+//! After desugaring, the only loops left are `always` loops, `for` loops have become handlers for `Yield::yield`,
+//! and `?` has become a pattern match which calls `Exception::throw` on failure.
+
+//--> Imports <--
+
+use super::parser::astgen::{
+	AbstractSyntaxTree,
+	AssignOp,
+	Block,
+	Condition,
+	Expr,
+	ExprKind,
+	Handler,
+	Ident,
+	IfBranch,
+	Item,
+	ItemKind,
+	MatchArm,
+	Path,
+	Pattern,
+	PatternKind,
+	Stmt,
+	StmtKind,
+};
+
+use logos::Span;
+
+//--> Structs <--
+
+struct Desugarer {
+	/// Used to come up with fresh names. Generated names start with `$`, which can't appear in source code, so they never clash.
+	next_name: usize,
+	/// The loops we're currently inside of, innermost last, as they were written in the source.
+	loops: Vec<Loop>,
+}
+
+#[derive(Clone)]
+struct Loop {
+	label: Option<String>,
+	kind: LoopKind,
+}
+
+//--> Enums <--
+
+/// What a loop looked like before desugaring, which decides what `continue` turns into.
+#[derive(Clone)]
+enum LoopKind {
+	/// `always`, `loop` and `while` loops, where `continue` stays as it is.
+	Plain,
+	/// `until` loops, where `continue` needs to check the condition first.
+	Until(Expr),
+	/// `for` loops, whose bodies get wrapped in a block with this label. `continue` breaks out of it,
+	/// which finishes handling the value the loop was given, so the iterator goes on to the next one.
+	For(Ident),
+}
+
+//--> Functions <--
+
+/// Desugars every function body and constant in a file.
+pub(crate) fn desugar(tree: &mut AbstractSyntaxTree) {
+	let mut desugarer = Desugarer { next_name: 0, loops: Vec::new() };
+
+	for item in tree.items.iter_mut() {
+		desugarer.item(item);
+	}
+}
+
+impl Desugarer {
+	fn fresh_name(&mut self, span: &Span) -> Ident {
+		let ident = Ident::new(&format!("${}", self.next_name), span.clone());
+		self.next_name += 1;
+		ident
+	}
+
+	fn item(&mut self, item: &mut Item) {
+		let items = match &mut item.kind {
+			ItemKind::Func(func) => {
+				if let Some(body) = &mut func.body { self.block(body); }
+				return;
+			},
+			ItemKind::Const(decl) => {
+				if let Some(value) = &mut decl.value { self.expr(value); }
+				return;
+			},
+			ItemKind::Type(decl) => &mut decl.items,
+			ItemKind::Trait(decl) => &mut decl.items,
+			ItemKind::Effect(decl) => &mut decl.items,
+			ItemKind::Impl(decl) => &mut decl.items,
+			ItemKind::Use(_) => return,
+		};
+
+		for item in items.iter_mut() {
+			self.item(item);
+		}
+	}
+
+	fn block(&mut self, block: &mut Block) {
+		// A handler without a body of its own handles everything after it in the block.
+		if let Some(pos) = block.stmts.iter().position(|stmt| matches!(stmt.kind, StmtKind::Handler(_))) {
+			let rest: Vec<Stmt> = block.stmts.drain(pos + 1..).collect();
+			let stmt = block.stmts.pop().unwrap();
+
+			let StmtKind::Handler(handler) = stmt.kind else { unreachable!() };
+
+			let body_span = rest.first().map_or(stmt.span.end..stmt.span.end, |first| first.span.start..rest.last().unwrap().span.end);
+
+			block.stmts.push(Stmt::expr(Expr::new(
+				ExprKind::Handle { handler: Box::new(handler), body: Block::new(rest, body_span) },
+				stmt.span.start..block.span.end,
+			)));
+		}
+
+		for stmt in block.stmts.iter_mut() {
+			self.stmt(stmt);
+		}
+	}
+
+	fn stmt(&mut self, stmt: &mut Stmt) {
+		match &mut stmt.kind {
+			StmtKind::Let { value, .. } => self.expr(value),
+			StmtKind::Assign { op, target, value } => {
+				self.expr(target);
+				self.expr(value);
+
+				// `target .= method(args)` is just `target = target.method(args)`.
+				if *op == AssignOp::Method {
+					if let ExprKind::Call { callee, args } = &mut value.kind {
+						if let ExprKind::Path(path) = &callee.kind {
+							let method = path.segments[0].name.clone();
+							let args = std::mem::take(args);
+
+							*value = Expr::new(
+								ExprKind::MethodCall { receiver: Box::new(target.clone()), method, args },
+								value.span.clone(),
+							);
+							*op = AssignOp::Assign;
+						}
+					}
+				}
+			},
+			StmtKind::Expr(expr) => self.expr(expr),
+			StmtKind::Handler(handler) => self.block(&mut handler.body),
+		}
+	}
+
+	fn expr(&mut self, expr: &mut Expr) {
+		let span = expr.span.clone();
+
+		match &mut expr.kind {
+			ExprKind::Lit(_) | ExprKind::Path(_) => {},
+			ExprKind::Call { callee, args } => {
+				self.expr(callee);
+				for arg in args.iter_mut() { self.expr(arg); }
+			},
+			ExprKind::MethodCall { receiver, args, .. } => {
+				self.expr(receiver);
+				for arg in args.iter_mut() { self.expr(arg); }
+			},
+			ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } => self.expr(base),
+			ExprKind::Index { base, index } => {
+				self.expr(base);
+				self.expr(index);
+			},
+			ExprKind::Tuple(elems) | ExprKind::List(elems) | ExprKind::Interp(elems) => {
+				for elem in elems.iter_mut() { self.expr(elem); }
+			},
+			ExprKind::Record(fields) => {
+				for (_, value) in fields.iter_mut() { self.expr(value); }
+			},
+			ExprKind::Map(entries) => {
+				for (key, value) in entries.iter_mut() {
+					self.expr(key);
+					self.expr(value);
+				}
+			},
+			// A closure's body is a world of its own, so `continue` in there can't refer to a loop outside of it.
+			ExprKind::Closure { body, .. } => {
+				let loops = std::mem::take(&mut self.loops);
+				self.block(body);
+				self.loops = loops;
+			},
+			ExprKind::Unary { expr, .. } => self.expr(expr),
+			ExprKind::Binary { lhs, rhs, .. } => {
+				self.expr(lhs);
+				self.expr(rhs);
+			},
+			ExprKind::If { branches, else_ } => {
+				for branch in branches.iter_mut() {
+					match &mut branch.cond {
+						Condition::Expr(cond) | Condition::Matches(cond, _) => self.expr(cond),
+					}
+					self.block(&mut branch.body);
+				}
+
+				if let Some(else_) = else_ { self.block(else_); }
+			},
+			ExprKind::Match { scrutinee, arms } => {
+				self.expr(scrutinee);
+				for arm in arms.iter_mut() { self.expr(&mut arm.body); }
+			},
+			ExprKind::Loop { label, body } => {
+				self.loops.push(Loop { label: label.as_ref().map(|l| l.name.clone()), kind: LoopKind::Plain });
+				self.block(body);
+				self.loops.pop();
+			},
+			ExprKind::Block { body, .. } => self.block(body),
+			ExprKind::Handle { handler, body } => {
+				self.block(&mut handler.body);
+				self.block(body);
+			},
+			ExprKind::Break { value, .. } | ExprKind::Return(value) => {
+				if let Some(value) = value { self.expr(value); }
+			},
+			ExprKind::Continue(label) => {
+				let target = match label {
+					Some(label) => self.loops.iter().rev().find(|l| l.label.as_ref() == Some(&label.name)),
+					None => self.loops.last(),
+				};
+
+				match target.map(|l| l.kind.clone()) {
+					// `continue` in an `until` loop still has to check whether it's time to stop.
+					Some(LoopKind::Until(cond)) => {
+						let label = label.clone();
+
+						*expr = Expr::new(ExprKind::If {
+							branches: vec![IfBranch {
+								cond: Condition::Expr(cond),
+								body: Block::new(vec![Stmt::expr(Expr::new(ExprKind::Break { label: label.clone(), value: None }, span.clone()))], span.clone()),
+							}],
+							else_: Some(Block::new(vec![Stmt::expr(Expr::new(ExprKind::Continue(label), span.clone()))], span.clone())),
+						}, span);
+					},
+					Some(LoopKind::For(block)) => expr.kind = ExprKind::Break { label: Some(block), value: None },
+					_ => {},
+				}
+			},
+			// `while cond do body end` becomes `always do if cond then body else break end end`.
+			ExprKind::While { label, cond, body } => {
+				self.expr(cond);
+				self.loops.push(Loop { label: label.as_ref().map(|l| l.name.clone()), kind: LoopKind::Plain });
+				self.block(body);
+				self.loops.pop();
+
+				let cond = std::mem::replace(cond.as_mut(), Expr::new(ExprKind::Continue(None), span.clone()));
+				let body = std::mem::replace(body, Block::new(Vec::new(), span.clone()));
+
+				expr.kind = ExprKind::Loop {
+					label: label.take(),
+					body: Block::new(vec![Stmt::expr(Expr::new(ExprKind::If {
+						branches: vec![IfBranch { cond: Condition::Expr(cond), body }],
+						else_: Some(Block::new(vec![Stmt::expr(Expr::new(ExprKind::Break { label: None, value: None }, span.clone()))], span.clone())),
+					}, span.clone()))], span),
+				};
+			},
+			// `until cond do body end` becomes `always do body; if cond then break end end`.
+			ExprKind::Until { label, cond, body } => {
+				self.expr(cond);
+				self.loops.push(Loop { label: label.as_ref().map(|l| l.name.clone()), kind: LoopKind::Until(cond.as_ref().clone()) });
+				self.block(body);
+				self.loops.pop();
+
+				let cond = std::mem::replace(cond.as_mut(), Expr::new(ExprKind::Continue(None), span.clone()));
+				let mut body = std::mem::replace(body, Block::new(Vec::new(), span.clone()));
+
+				body.stmts.push(Stmt::expr(Expr::new(ExprKind::If {
+					branches: vec![IfBranch {
+						cond: Condition::Expr(cond),
+						body: Block::new(vec![Stmt::expr(Expr::new(ExprKind::Break { label: None, value: None }, span.clone()))], span.clone()),
+					}],
+					else_: None,
+				}, span.clone())));
+
+				expr.kind = ExprKind::Loop { label: label.take(), body };
+			},
+			// `for pat in iter do body end` becomes a handler for `Yield::yield(pat)` wrapped around `iter.each()`,
+			// which performs it with each value. It sits inside an `always` loop which runs once, so that `break` has something to break out of.
+			ExprKind::For { label, pat, iter, body } => {
+				self.expr(iter);
+
+				let block = self.fresh_name(&span);
+				self.loops.push(Loop { label: label.as_ref().map(|l| l.name.clone()), kind: LoopKind::For(block.clone()) });
+				self.block(body);
+				self.loops.pop();
+
+				let pat = pat.clone();
+				let iter = std::mem::replace(iter.as_mut(), Expr::new(ExprKind::Continue(None), span.clone()));
+				let body = std::mem::replace(body, Block::new(Vec::new(), span.clone()));
+				let body = Block::new(vec![Stmt::expr(Expr::new(ExprKind::Block { label: block, body }, span.clone()))], span.clone());
+
+				let each = Expr::new(ExprKind::MethodCall {
+					receiver: Box::new(iter.clone()),
+					method: Ident::new("each", iter.span.clone()),
+					args: Vec::new(),
+				}, iter.span.clone());
+
+				let handle = Expr::new(ExprKind::Handle {
+					handler: Box::new(Handler {
+						operation: Path::from_names(&["Yield", "yield"], span.clone()),
+						params: vec![pat],
+						body,
+						span: span.clone(),
+					}),
+					body: Block::new(vec![Stmt::expr(each)], span.clone()),
+				}, span.clone());
+
+				expr.kind = ExprKind::Loop {
+					label: label.take(),
+					body: Block::new(vec![
+						Stmt::expr(handle),
+						Stmt::expr(Expr::new(ExprKind::Break { label: None, value: None }, span.clone())),
+					], span),
+				};
+			},
+			// `expr?` becomes `if expr matches Ok(value) then value; Err(err) then Exception::throw(err) end`.
+			ExprKind::Try(inner) => {
+				self.expr(inner);
+
+				let inner = std::mem::replace(inner.as_mut(), Expr::new(ExprKind::Continue(None), span.clone()));
+				let value = self.fresh_name(&span);
+				let err = self.fresh_name(&span);
+
+				expr.kind = ExprKind::Match {
+					scrutinee: Box::new(inner),
+					arms: vec![
+						MatchArm {
+							pat: Pattern::new(PatternKind::TupleStruct {
+								path: Path::from_names(&["Ok"], span.clone()),
+								fields: vec![Pattern::new(PatternKind::Binding { is_mut: false, name: value.clone(), local: None }, span.clone())],
+							}, span.clone()),
+							body: Expr::new(ExprKind::Path(Path::from_names(&[&value.name], span.clone())), span.clone()),
+							span: span.clone(),
+						},
+						MatchArm {
+							pat: Pattern::new(PatternKind::TupleStruct {
+								path: Path::from_names(&["Err"], span.clone()),
+								fields: vec![Pattern::new(PatternKind::Binding { is_mut: false, name: err.clone(), local: None }, span.clone())],
+							}, span.clone()),
+							body: Expr::new(ExprKind::Call {
+								callee: Box::new(Expr::new(ExprKind::Path(Path::from_names(&["Exception", "throw"], span.clone())), span.clone())),
+								args: vec![Expr::new(ExprKind::Path(Path::from_names(&[&err.name], span.clone())), span.clone())],
+							}, span.clone()),
+							span: span.clone(),
+						},
+					],
+				};
+			},
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use crate::compiler::{
+		Options,
+		parse_source,
+	};
+
+	fn core(source: &str) -> String {
+		let (ast, _) = parse_source(None, source, &Options::default()).unwrap();
+		ast.to_string()
+	}
+
+	#[test]
+	fn while_becomes_always() {
+		assert_eq!(
+			core("func f() do\n\twhile i > 0 do\n\t\ti -= 1\n\tend\nend\n"),
+			"func f() do\n\talways do\n\t\tif i > 0 then\n\t\t\ti -= 1\n\t\telse\n\t\t\tbreak\n\t\tend\n\tend\nend\n"
+		);
+	}
+
+	#[test]
+	fn until_checks_after_body() {
+		assert_eq!(
+			core("func f() do\n\tuntil i >= 10 do\n\t\ti += 1\n\t\tcontinue\n\tend\nend\n"),
+			"func f() do\n\talways do\n\t\ti += 1\n\t\tif i >= 10 then\n\t\t\tbreak\n\t\telse\n\t\t\tcontinue\n\t\tend\n\t\tif i >= 10 then\n\t\t\tbreak\n\t\tend\n\tend\nend\n"
+		);
+	}
+
+	#[test]
+	fn for_becomes_yield_handler() {
+		assert_eq!(
+			core("func f() do\n\tfor friend in friends do\n\t\tif friend == \"John\" then continue\n\t\toutl(friend)\n\tend\nend\n"),
+			"func f() do\n\talways do\n\t\twhen Yield::yield(friend) do\n\t\t\t`$0 do\n\t\t\t\tif friend == \"John\" then\n\t\t\t\t\tbreak `$0\n\t\t\t\tend\n\t\t\t\toutl(friend)\n\t\t\tend\n\t\tin\n\t\t\tfriends.each()\n\t\tend\n\t\tbreak\n\tend\nend\n"
+		);
+	}
+
+	#[test]
+	fn try_becomes_throw() {
+		assert_eq!(
+			core("func f() do\n\tx := open(path)?\nend\n"),
+			"func f() do\n\tx := if open(path) matches\n\t\tOk($0) then $0\n\t\tErr($1) then Exception::throw($1)\n\tend\nend\n"
+		);
+	}
+
+	#[test]
+	fn handler_covers_rest_of_block() {
+		assert_eq!(
+			core("func f() do\n\ta()\n\twhen Exception::throw(e) do\n\t\tlog(e)\n\tend\n\tb()\nend\n"),
+			"func f() do\n\ta()\n\twhen Exception::throw(e) do\n\t\tlog(e)\n\tin\n\t\tb()\n\tend\nend\n"
+		);
+	}
+
+	#[test]
+	fn method_assign_becomes_call() {
+		assert_eq!(
+			core("func f() do\n\tname .= trim()\nend\n"),
+			"func f() do\n\tname = name.trim()\nend\n"
+		);
+	}
+
+	#[test]
+	fn nested_loops_keep_their_own_continue() {
+		let dumped = core("func f() do\n\tfor x in xs do\n\t\twhile x > 0 do continue\n\tend\nend\n");
+		assert!(dumped.contains("\t\t\t\t\tcontinue\n"));
+		assert!(!dumped.contains("break `$0"));
+	}
+
+	#[test]
+	fn labelled_continue_leaves_inner_loops() {
+		let dumped = core("func f() do\n\t`outer for x in xs do\n\t\tfor y in ys do\n\t\t\tif y == x then continue `outer\n\t\tend\n\tend\nend\n");
+
+		assert!(dumped.contains("\t`outer always do\n\t\twhen Yield::yield(x) do\n\t\t\t`$0 do\n"));
+		assert!(dumped.contains("\t\t\t\t\t\t\tbreak `$0\n"));
+	}
+}
@@ -0,0 +1,379 @@
+//! Formats source code in the one canonical style, working from the concrete syntax tree so that comments are kept.
+//!
+//! Line breaks are left where they were written, apart from runs of blank lines, which are squashed down to one.
+//! Everything else is decided by the formatter:
+//!
+//!  - Each line is indented with tabs, one more than the line the thing it's inside of started on.
+//!    Lines which close something (`end`, `else`, `)` and friends) line up with the line which opened it.
+//!  - Tokens on a line are separated by a single space, except around brackets, `.`, `::`, ranges, generics, unary operators
+//!    and the expressions interpolated into strings.
+//!  - Constructors applied without parentheses, like `Some n`, get them added: `Some(n)`.
+//!  - `loop` is written as `always`.
+//!
+//! Macro definitions and arguments only have a meaning once they've been expanded, so the spacing inside of them is left alone,
+//! and the lines inside of them keep their indentation relative to each other.
+
+//--> Imports <--
+
+use crate::{
+	ErrorList,
+	compiler::{
+		lexer::{
+			Token,
+			TokenInner,
+			TokenStream,
+		},
+		parser::{
+			ParseLimits,
+			cstgen::{
+				ConcreteSyntaxElement,
+				ConcreteSyntaxNode,
+				ConcreteSyntaxTree,
+				NodeKind,
+			},
+		},
+	},
+};
+
+use std::{
+	collections::HashMap,
+	path::Path,
+};
+
+//--> Structs <--
+
+struct Formatter<'a> {
+	source: &'a str,
+	/// Where each line of the source starts.
+	line_starts: Vec<usize>,
+	nodes: Vec<NodeInfo>,
+	leaves: Vec<Leaf>,
+	/// The nodes we're currently inside of while flattening the tree, innermost last.
+	stack: Vec<usize>,
+	/// How far each source line gets indented, worked out as it's needed.
+	depths: HashMap<usize, usize>,
+}
+
+struct NodeInfo {
+	kind: NodeKind,
+	/// The line the node opens on, which is what the lines inside of it are indented relative to.
+	anchor: usize,
+}
+
+/// A token, along with where it sits in the tree.
+struct Leaf {
+	token: Token,
+	line: usize,
+	/// The nodes this token is inside of, innermost last.
+	ancestors: Vec<usize>,
+}
+
+//--> Functions <--
+
+/// Formats some source code, giving back any errors which stopped it from being parsed.
+pub(crate) fn format_source(path: Option<&Path>, source: &str, limits: &ParseLimits) -> Result<String, ErrorList> {
+	if source.trim().is_empty() { return Ok(String::new()) }
+
+	let tokens = TokenStream::lex(path, source, limits)?;
+	let (tree, _) = ConcreteSyntaxTree::new(path, source, tokens, limits)?;
+
+	let mut formatter = Formatter {
+		source,
+		line_starts: std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1)).collect(),
+		nodes: Vec::new(),
+		leaves: Vec::new(),
+		stack: Vec::new(),
+		depths: HashMap::new(),
+	};
+
+	// The root isn't inside of anything, so it doesn't count towards indentation.
+	for child in tree.root.children.iter() {
+		formatter.element(child);
+	}
+
+	Ok(formatter.write())
+}
+
+impl Formatter<'_> {
+	fn line_of(&self, offset: usize) -> usize {
+		self.line_starts.partition_point(|&start| start <= offset) - 1
+	}
+
+	/// How many tabs a line of the source was indented with, counting four spaces as a tab.
+	fn original_indent(&self, line: usize) -> usize {
+		let indent: String = self.source[self.line_starts[line]..].chars().take_while(|c| *c == '\t' || *c == ' ').collect();
+		indent.matches('\t').count() + indent.matches("    ").count()
+	}
+
+	//--> Flattening <--
+
+	fn element(&mut self, element: &ConcreteSyntaxElement) {
+		match element {
+			ConcreteSyntaxElement::Node(node) => self.node(node),
+			ConcreteSyntaxElement::Token(token) => self.leaf(token.clone()),
+		}
+	}
+
+	fn leaf(&mut self, mut token: Token) {
+		if token.inner == TokenInner::WordLoop {
+			token.slice = "always".into();
+		}
+
+		self.leaves.push(Leaf {
+			line: self.line_of(token.span.start),
+			token,
+			ancestors: self.stack.clone(),
+		});
+	}
+
+	fn node(&mut self, node: &ConcreteSyntaxNode) {
+		let index = self.nodes.len();
+		self.nodes.push(NodeInfo { kind: node.kind, anchor: usize::MAX });
+
+		let first = self.leaves.len();
+		let before = self.leaves.iter().rev().find(|leaf| !leaf.token.inner.is_trivia()).map(|leaf| leaf.line);
+
+		self.stack.push(index);
+
+		// `Some n` becomes `Some(n)`.
+		let bare_args = node.kind == NodeKind::ArgList && !node.has_token(&TokenInner::SymOParen);
+		let span = node.span();
+
+		if bare_args { self.leaf(Token { inner: TokenInner::SymOParen, span: span.start..span.start, slice: "(".into() }); }
+
+		for child in node.children.iter() {
+			self.element(child);
+		}
+
+		if bare_args { self.leaf(Token { inner: TokenInner::SymCParen, span: span.end..span.end, slice: ")".into() }); }
+
+		self.stack.pop();
+
+		// Attributes sit on their own lines above what they're attached to, so they don't count towards where it starts.
+		let nodes = &self.nodes;
+		let attribute = |leaf: &Leaf| leaf.ancestors.iter().any(|&node| node > index && nodes[node].kind == NodeKind::Attribute);
+
+		// A block which doesn't start with `do` opens wherever its header (like `if cond then`) finished.
+		let anchor = match self.leaves[first..].iter().find(|leaf| !leaf.token.inner.is_trivia() && !attribute(leaf)) {
+			Some(leaf) if node.kind == NodeKind::Block && leaf.token.inner != TokenInner::WordDo => before.unwrap_or(leaf.line),
+			Some(leaf) => leaf.line,
+			None => usize::MAX,
+		};
+
+		self.nodes[index].anchor = anchor;
+	}
+
+	fn parent(&self, leaf: &Leaf) -> Option<NodeKind> {
+		leaf.ancestors.last().map(|&node| self.nodes[node].kind)
+	}
+
+	//--> Indentation <--
+
+	/// Works out how far the line a leaf starts should be indented.
+	fn depth(&mut self, leaf: usize) -> usize {
+		let line = self.leaves[leaf].line;
+		if let Some(&depth) = self.depths.get(&line) { return depth }
+
+		let opener = self.leaves[leaf].ancestors.iter()
+			.rev()
+			.find(|&&node| self.nodes[node].anchor < line)
+			.copied();
+
+		let depth = match opener {
+			None => 0,
+			Some(opener) => {
+				let anchor = self.nodes[opener].anchor;
+				let anchor_leaf = self.leaves.iter().position(|leaf| leaf.line == anchor && !leaf.token.inner.is_trivia()).unwrap();
+				let base = self.depth(anchor_leaf);
+
+				let inner = &self.leaves[leaf].token.inner;
+				let closes = matches!(inner, TokenInner::SymCParen | TokenInner::SymCBracket | TokenInner::SymCBrace);
+
+				if self.nodes[opener].kind == NodeKind::TokenTree {
+					// Nothing inside of a token tree has any structure yet, so lines keep their indentation relative to the line it opens on.
+					if closes { base } else { base + self.original_indent(line).saturating_sub(self.original_indent(anchor)).max(1) }
+				} else if closes || matches!(inner, TokenInner::WordEnd | TokenInner::WordElif | TokenInner::WordElse | TokenInner::WordIn | TokenInner::WordDo) {
+					base
+				} else {
+					base + 1
+				}
+			},
+		};
+
+		self.depths.insert(line, depth);
+		depth
+	}
+
+	//--> Spacing <--
+
+	/// Whether there should be a space between two tokens on the same line.
+	fn space_between(&self, a: &Leaf, b: &Leaf) -> bool {
+		use TokenInner::*;
+
+		if b.token.inner == Comment { return true }
+
+		let a_parent = self.parent(a);
+		let b_parent = self.parent(b);
+
+		// Spacing inside of macros is left as it was.
+		if a_parent == Some(NodeKind::TokenTree) || b_parent == Some(NodeKind::TokenTree) {
+			return a.token.span.end != b.token.span.start;
+		}
+
+		let generic = |parent: Option<NodeKind>| matches!(parent, Some(NodeKind::GenericArgs | NodeKind::GenericParams));
+
+		match (&a.token.inner, &b.token.inner) {
+			(_, SymCParen | SymCBracket | SymCBrace | SymComma | SymSemicolon | SymColon | SymDot | SymQuad | SymTry | SymTryChain) => false,
+			(SymOParen | SymOBracket | SymOBrace | SymDot | SymQuad | SymTryChain | SymDecorator | SymBacktick | SymDollar, _) => false,
+			(SymRange | SymRangeEqual, _) | (_, SymRange | SymRangeEqual) => false,
+			(LitStrHead(_) | LitStrMid(_), _) | (_, LitStrMid(_) | LitStrTail(_)) => false,
+			(SymOAngle, _) if generic(a_parent) => false,
+			(_, SymOAngle | SymCAngle) if generic(b_parent) => false,
+			(_, SymBang) if b_parent == Some(NodeKind::MacroCallExpr) => false,
+			(SymDash | SymPlus | SymBang, _) if matches!(a_parent, Some(NodeKind::UnaryExpr | NodeKind::LiteralPat | NodeKind::RangePat)) => false,
+			(SymCAngle, SymOParen) if generic(a_parent) => false,
+			(WordIdentifier(_) | WordSelf | WordSelfType | SymCParen | SymCBracket, SymOParen) => false,
+			// Keywords can be used as the names of functions and methods, like `or`.
+			(_, SymOParen) if b_parent == Some(NodeKind::ArgList) || (a_parent == Some(NodeKind::Func) && b_parent == Some(NodeKind::ParamList)) => false,
+			(_, SymOBracket) => b_parent != Some(NodeKind::IndexExpr),
+			_ => true,
+		}
+	}
+
+	//--> Output <--
+
+	fn write(&mut self) -> String {
+		let mut lines: Vec<String> = Vec::new();
+		let mut current: Option<(String, usize)> = None;
+
+		for i in 0..self.leaves.len() {
+			if self.leaves[i].token.inner == TokenInner::SymNewline {
+				lines.push(current.take().map(|(line, _)| line).unwrap_or_default());
+				continue;
+			}
+
+			match &mut current {
+				None => {
+					let depth = self.depth(i);
+					current = Some(("\t".repeat(depth) + &self.leaves[i].token.slice, i));
+				},
+				Some((line, prev)) => {
+					if self.space_between(&self.leaves[*prev], &self.leaves[i]) { line.push(' '); }
+					line.push_str(&self.leaves[i].token.slice);
+					*prev = i;
+				},
+			}
+		}
+
+		if let Some((line, _)) = current { lines.push(line); }
+
+		// No blank lines at the start or end, and never more than one in a row.
+		let mut out = String::new();
+		let mut blank = false;
+
+		for line in lines.iter().map(|line| line.trim_end()) {
+			if line.is_empty() {
+				blank = !out.is_empty();
+				continue;
+			}
+
+			if blank { out.push('\n'); }
+			blank = false;
+
+			out.push_str(line);
+			out.push('\n');
+		}
+
+		out
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compiler::{
+		Options,
+		parse_source,
+	};
+
+	use std::{
+		fs,
+		path::PathBuf,
+	};
+
+	fn format(source: &str) -> String {
+		format_source(None, source, &ParseLimits::default()).unwrap()
+	}
+
+	fn ro_files(dir: PathBuf, files: &mut Vec<PathBuf>) {
+		for entry in fs::read_dir(dir).unwrap() {
+			let path = entry.unwrap().path();
+
+			if path.is_dir() {
+				ro_files(path, files);
+			} else if path.extension().is_some_and(|ext| ext == "ro") {
+				files.push(path);
+			}
+		}
+	}
+
+	#[test]
+	fn spacing_is_canonical() {
+		assert_eq!(
+			format("pub   func f( x:nat,y :[ nat ] )->nat do\n\tz:=x+ y [ -1 ]\n\tg (z).h( 0..=3 , -z )?\nend\n"),
+			"pub func f(x: nat, y: [nat]) -> nat do\n\tz := x + y[-1]\n\tg(z).h(0..=3, -z)?\nend\n"
+		);
+	}
+
+	#[test]
+	fn indentation_follows_structure() {
+		assert_eq!(
+			format("func f() -> Option<nat> where\nT: Bound\n     do\nif a then\n        b\n  else\nc\n end\nend\n"),
+			"func f() -> Option<nat> where\n\tT: Bound\ndo\n\tif a then\n\t\tb\n\telse\n\t\tc\n\tend\nend\n"
+		);
+	}
+
+	#[test]
+	fn comments_and_blank_lines() {
+		assert_eq!(
+			format("\n\n# A comment.\nfunc f() do   # trailing\n\n\n\ta()\n\t\t# before end\nend\n\n\n"),
+			"# A comment.\nfunc f() do # trailing\n\n\ta()\n\t# before end\nend\n"
+		);
+	}
+
+	#[test]
+	fn constructors_get_parentheses() {
+		assert_eq!(format("func f() do\n\tloop\n\t\tSome n\n\tend\nend\n"), "func f() do\n\talways\n\t\tSome(n)\n\tend\nend\n");
+	}
+
+	#[test]
+	fn macros_keep_their_insides() {
+		let source = "macro twice is\n    ($e:expr) then {\n        $e\n            $e\n    }\nend\n\nfunc f() do\n\tassert!(x>1,   \"no\")\nend\n";
+		assert_eq!(
+			format(source),
+			"macro twice is\n\t($e:expr) then {\n\t\t$e\n\t\t\t$e\n\t}\nend\n\nfunc f() do\n\tassert!(x>1, \"no\")\nend\n"
+		);
+	}
+
+	#[test]
+	fn std_and_examples_are_stable() {
+		let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+		let mut files = Vec::new();
+		ro_files(root.join("std"), &mut files);
+		ro_files(root.join("examples"), &mut files);
+
+		for file in files {
+			let source = fs::read_to_string(&file).unwrap();
+			let once = format_source(Some(&file), &source, &ParseLimits::default()).unwrap_or_else(|e| panic!("couldn't format {}: {:?}", file.display(), e));
+			let twice = format(&once);
+
+			assert_eq!(once, twice, "formatting {} twice gave different results", file.display());
+
+			if !once.trim().is_empty() {
+				assert!(parse_source(Some(&file), &once, &Options::default()).is_ok(), "{} doesn't parse after formatting", file.display());
+			}
+		}
+	}
+}
@@ -0,0 +1,348 @@
+//! Checks that every `break` and `continue` has something to break out of or continue.
+//!
+//! Loops and blocks can be given a label like `` `outer ``, which `break` and `continue` can then refer to.
+//! A label is only in scope inside the thing it labels, and never reaches into closures.
+
+//--> Imports <--
+
+use crate::{
+	Error,
+	ErrorKind,
+	ErrorList,
+	compiler::{
+		InterpretError,
+		line_of,
+		parser::astgen::{
+			AbstractSyntaxTree,
+			Block,
+			Condition,
+			Expr,
+			ExprKind,
+			Ident,
+			Item,
+			ItemKind,
+			Stmt,
+			StmtKind,
+		},
+	},
+};
+
+use logos::Span;
+
+use std::{
+	collections::HashSet,
+	fmt,
+	path::Path,
+};
+
+//--> Structs <--
+
+struct Checker<'a> {
+	file: Option<&'a Path>,
+	source: &'a str,
+	errors: ErrorList,
+	/// The loops and blocks we're currently inside of, innermost last.
+	targets: Vec<Target>,
+	/// Every label seen so far in the current function, so that we can tell an unknown label from one which is out of scope.
+	defined: HashSet<String>,
+	/// Labels which weren't in scope where they were used, which get reported at the end of the function.
+	unresolved: Vec<Ident>,
+}
+
+struct Target {
+	label: Option<String>,
+	kind: TargetKind,
+}
+
+//--> Enums <--
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TargetKind {
+	/// `always` loops, which are the only loops that can give back a value.
+	Always,
+	/// `while`, `until` and `for` loops.
+	Loop,
+	/// Labelled blocks, which can be broken out of but not continued.
+	Block,
+}
+
+#[derive(Clone, Debug)]
+pub enum LabelError {
+	/// Nothing in the function has this label.
+	Unknown(String),
+	/// Something has this label, but we're not inside of it.
+	OutOfScope(String),
+	/// `continue` was used with the label of a block.
+	ContinueBlock(String),
+	/// `break` or `continue` was used outside of any loop.
+	OutsideLoop(&'static str),
+	/// `break` tried to give back a value from a loop which can't have one.
+	BreakValue,
+}
+
+//--> Functions <--
+
+/// Checks every `break` and `continue` in a file, giving back any errors.
+pub(crate) fn check(path: Option<&Path>, source: &str, tree: &AbstractSyntaxTree) -> ErrorList {
+	let mut checker = Checker {
+		file: path,
+		source,
+		errors: ErrorList::new(),
+		targets: Vec::new(),
+		defined: HashSet::new(),
+		unresolved: Vec::new(),
+	};
+
+	for item in tree.items.iter() {
+		checker.item(item);
+	}
+
+	checker.errors
+}
+
+impl Checker<'_> {
+	fn report(&mut self, span: Span, err: LabelError) {
+		self.errors.push(Error::new(false, self.file, Some(line_of(self.source, span.start)), Some(span.clone()), self.source.get(span), ErrorKind::Interpret(InterpretError::Label(err))));
+	}
+
+	fn item(&mut self, item: &Item) {
+		let items = match &item.kind {
+			ItemKind::Func(func) => {
+				if let Some(body) = &func.body { self.body(|checker| checker.block(body)); }
+				return;
+			},
+			ItemKind::Const(decl) => {
+				if let Some(value) = &decl.value { self.body(|checker| checker.expr(value)); }
+				return;
+			},
+			ItemKind::Type(decl) => &decl.items,
+			ItemKind::Trait(decl) => &decl.items,
+			ItemKind::Effect(decl) => &decl.items,
+			ItemKind::Impl(decl) => &decl.items,
+			ItemKind::Use(_) => return,
+		};
+
+		for item in items.iter() {
+			self.item(item);
+		}
+	}
+
+	/// Checks the body of a function or constant, and then reports any labels it couldn't find.
+	fn body(&mut self, check: impl FnOnce(&mut Self)) {
+		check(self);
+
+		for label in std::mem::take(&mut self.unresolved) {
+			let err = if self.defined.contains(&label.name) {
+				LabelError::OutOfScope(label.name)
+			} else {
+				LabelError::Unknown(label.name)
+			};
+
+			self.report(label.span, err);
+		}
+
+		self.defined.clear();
+	}
+
+	fn block(&mut self, block: &Block) {
+		for stmt in block.stmts.iter() {
+			self.stmt(stmt);
+		}
+	}
+
+	fn stmt(&mut self, stmt: &Stmt) {
+		match &stmt.kind {
+			StmtKind::Let { value, .. } => self.expr(value),
+			StmtKind::Assign { target, value, .. } => {
+				self.expr(target);
+				self.expr(value);
+			},
+			StmtKind::Expr(expr) => self.expr(expr),
+			StmtKind::Handler(handler) => self.block(&handler.body),
+		}
+	}
+
+	/// Checks a block from inside a loop or labelled block.
+	fn target(&mut self, label: Option<&Ident>, kind: TargetKind, body: &Block) {
+		if let Some(label) = label {
+			self.defined.insert(label.name.clone());
+		}
+
+		self.targets.push(Target { label: label.map(|l| l.name.clone()), kind });
+		self.block(body);
+		self.targets.pop();
+	}
+
+	/// Works out what a `break` or `continue` refers to. Without a label, that's the innermost loop.
+	fn resolve(&mut self, label: Option<&Ident>, keyword: &'static str, span: &Span) -> Option<TargetKind> {
+		let found = match label {
+			Some(label) => self.targets.iter().rev().find(|target| target.label.as_ref() == Some(&label.name)),
+			None => self.targets.iter().rev().find(|target| target.kind != TargetKind::Block),
+		};
+
+		match (found, label) {
+			(Some(target), _) => Some(target.kind),
+			(None, Some(label)) => {
+				self.unresolved.push(label.clone());
+				None
+			},
+			(None, None) => {
+				self.report(span.clone(), LabelError::OutsideLoop(keyword));
+				None
+			},
+		}
+	}
+
+	fn expr(&mut self, expr: &Expr) {
+		match &expr.kind {
+			ExprKind::Lit(_) | ExprKind::Path(_) => {},
+			ExprKind::Call { callee, args } => {
+				self.expr(callee);
+				for arg in args.iter() { self.expr(arg); }
+			},
+			ExprKind::MethodCall { receiver, args, .. } => {
+				self.expr(receiver);
+				for arg in args.iter() { self.expr(arg); }
+			},
+			ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } => self.expr(base),
+			ExprKind::Index { base, index } => {
+				self.expr(base);
+				self.expr(index);
+			},
+			ExprKind::Tuple(elems) | ExprKind::List(elems) | ExprKind::Interp(elems) => {
+				for elem in elems.iter() { self.expr(elem); }
+			},
+			ExprKind::Record(fields) => {
+				for (_, value) in fields.iter() { self.expr(value); }
+			},
+			ExprKind::Map(entries) => {
+				for (key, value) in entries.iter() {
+					self.expr(key);
+					self.expr(value);
+				}
+			},
+			// Nothing outside of a closure can be broken out of from inside it.
+			ExprKind::Closure { body, .. } => {
+				let targets = std::mem::take(&mut self.targets);
+				self.block(body);
+				self.targets = targets;
+			},
+			ExprKind::Unary { expr, .. } => self.expr(expr),
+			ExprKind::Binary { lhs, rhs, .. } => {
+				self.expr(lhs);
+				self.expr(rhs);
+			},
+			ExprKind::If { branches, else_ } => {
+				for branch in branches.iter() {
+					match &branch.cond {
+						Condition::Expr(cond) | Condition::Matches(cond, _) => self.expr(cond),
+					}
+					self.block(&branch.body);
+				}
+
+				if let Some(else_) = else_ { self.block(else_); }
+			},
+			ExprKind::Match { scrutinee, arms } => {
+				self.expr(scrutinee);
+				for arm in arms.iter() { self.expr(&arm.body); }
+			},
+			ExprKind::Loop { label, body } => self.target(label.as_ref(), TargetKind::Always, body),
+			ExprKind::While { label, cond, body } | ExprKind::Until { label, cond, body } => {
+				self.expr(cond);
+				self.target(label.as_ref(), TargetKind::Loop, body);
+			},
+			ExprKind::For { label, iter, body, .. } => {
+				self.expr(iter);
+				self.target(label.as_ref(), TargetKind::Loop, body);
+			},
+			ExprKind::Block { label, body } => self.target(Some(label), TargetKind::Block, body),
+			ExprKind::Handle { handler, body } => {
+				self.block(&handler.body);
+				self.block(body);
+			},
+			ExprKind::Break { label, value } => {
+				if let Some(value) = value { self.expr(value); }
+
+				if self.resolve(label.as_ref(), "break", &expr.span) == Some(TargetKind::Loop) && value.is_some() {
+					self.report(expr.span.clone(), LabelError::BreakValue);
+				}
+			},
+			ExprKind::Continue(label) => {
+				if self.resolve(label.as_ref(), "continue", &expr.span) == Some(TargetKind::Block) {
+					let name = label.as_ref().map(|l| l.name.clone()).unwrap_or_default();
+					self.report(expr.span.clone(), LabelError::ContinueBlock(name));
+				}
+			},
+			ExprKind::Return(value) => {
+				if let Some(value) = value { self.expr(value); }
+			},
+			ExprKind::Try(inner) => self.expr(inner),
+		}
+	}
+}
+
+impl fmt::Display for LabelError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			LabelError::Unknown(name) => write!(f, "there's no loop or block labelled `` `{} ``", name),
+			LabelError::OutOfScope(name) => write!(f, "`` `{} `` can only be used inside the loop or block it labels", name),
+			LabelError::ContinueBlock(name) => write!(f, "`` `{} `` labels a block, which can't be continued", name),
+			LabelError::OutsideLoop(keyword) => write!(f, "`{}` can only be used inside a loop", keyword),
+			LabelError::BreakValue => write!(f, "only `always` loops and labelled blocks can give back a value with `break`"),
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compiler::{
+		Options,
+		parse_source,
+	};
+
+	fn check_source(source: &str) -> Vec<LabelError> {
+		let errors = match parse_source(None, source, &Options::default()) {
+			Ok((_, warnings)) => warnings,
+			Err(errors) => errors,
+		};
+
+		errors.into_iter()
+			.map(|e| match e.kind() {
+				ErrorKind::Interpret(InterpretError::Label(err)) => err,
+				kind => panic!("expected a label error, got {}", kind),
+			})
+			.collect()
+	}
+
+	#[test]
+	fn labels_in_scope_are_fine() {
+		let source = "func f() do\n\t`outer for x in xs do\n\t\t`inner while x > 0 do\n\t\t\tif x == 2 then continue `outer\n\t\t\tif x == 3 then break `inner\n\t\t\tbreak `outer\n\t\tend\n\tend\n\tfound := `search do\n\t\tif a then break `search 1\n\t\tbreak `search 2\n\tend\nend\n";
+		assert!(check_source(source).is_empty());
+	}
+
+	#[test]
+	fn unknown_and_out_of_scope_labels() {
+		let errors = check_source("func f() do\n\t`first always do break\n\talways do\n\t\tbreak `first\n\t\tcontinue `second\n\tend\nend\n");
+
+		assert!(matches!(&errors[0], LabelError::OutOfScope(name) if name == "first"));
+		assert!(matches!(&errors[1], LabelError::Unknown(name) if name == "second"));
+	}
+
+	#[test]
+	fn labels_stop_at_closures() {
+		let errors = check_source("func f() do\n\t`outer always do\n\t\tg((x) do\n\t\t\tbreak `outer\n\t\tend)\n\tend\nend\n");
+		assert!(matches!(&errors[..], [LabelError::OutOfScope(_)]));
+	}
+
+	#[test]
+	fn blocks_are_not_loops() {
+		let errors = check_source("func f() do\n\t`b do\n\t\tcontinue `b\n\t\tbreak\n\tend\n\twhile a do break 5\nend\n");
+
+		assert!(matches!(&errors[0], LabelError::ContinueBlock(name) if name == "b"));
+		assert!(matches!(&errors[1], LabelError::OutsideLoop("break")));
+		assert!(matches!(&errors[2], LabelError::BreakValue));
+	}
+}
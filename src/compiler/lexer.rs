@@ -7,12 +7,17 @@ use super::{
 		ErrorList,
 	},
 	InterpretError,
+	line_of,
+	parser::{
+		Limit,
+		ParseError,
+		ParseLimits,
+	},
 };
 
 use std::{
+	fmt,
 	path::Path,
-	fs::File,
-	io::Read,
 	str::FromStr,
 	iter::Enumerate,
 	vec::IntoIter,
@@ -45,6 +50,19 @@ pub(crate) struct Token {
 #[derive(Debug, PartialEq)]
 pub(crate) struct TokenStream(pub Vec<Token>);
 
+/// What the lexer keeps track of while going through a file, since interpolated strings have it lex pieces of the file on their own.
+struct Lexing<'a> {
+	path: Option<&'a Path>,
+	source: &'a str,
+	limits: &'a ParseLimits,
+	tokens: Vec<Token>,
+	errors: ErrorList,
+	/// How many tokens which aren't trivia have been made so far.
+	count: usize,
+	/// How many interpolated strings deep the lexer is, since each one gets lexed by calling back into `range`.
+	depth: usize,
+}
+
 //--> Enums <--
 
 /// Tokens!
@@ -55,9 +73,16 @@ pub(crate) enum TokenInner {
 	LitChar(char),
 	/// A string literal is surrounded in double quotes and evaluates to a, well, string of Unicode characters.
 	/// _Raw_ string literals, which do not process escape sequences, are also surrounded with `r#`.
-	#[regex(r#""(?:[^"]|\\")*""#, TokenInner::lit_char_str)]
+	#[token("\"", TokenInner::lit_char_str)]
 	#[regex(r##"r#"?:[^("#r)]*"#r"##, TokenInner::lit_char_str_raw)]
 	LitCharStr(String),
+	/// The start of a string with expressions interpolated into it, up to the first `\{`, like `"Hello, \{`.
+	/// The lexer splits those strings up itself, so these never come straight out of Logos.
+	LitStrHead(String),
+	/// The text between two interpolated expressions, like `} and \{`.
+	LitStrMid(String),
+	/// The end of a string with expressions interpolated into it, like `}!"`.
+	LitStrTail(String),
 	/// A byte literal is surrounded in single quotes and `b` and evaluates to a single byte value.
 	#[regex(r"b'(?:[^']|\\')'b", TokenInner::lit_byte)]
 	LitByte(u8),
@@ -73,7 +98,7 @@ pub(crate) enum TokenInner {
 	#[regex(r"0[xX][0-9a-fA-F][_0-9a-fA-F]*", TokenInner::hex)]
 	LitNum(u64),
 	/// A floating point number literal. Must be in decimal.
-	#[regex(r"[0-9][_0-9]*(.[0-9][_0-9]*|[eE][+-][0-9][_0-9]*|.[0-9][_0-9]*[eE][+-][0-9][_0-9]*)", TokenInner::float)]
+	#[regex(r"[0-9][_0-9]*(\.[0-9][_0-9]*|[eE][+-][0-9][_0-9]*|\.[0-9][_0-9]*[eE][+-][0-9][_0-9]*)", TokenInner::float)]
 	LitFloat(f64),
 	/// Used for mutable re-assignment (binary) and variable declaration with explicit type
 	#[token("=")]
@@ -126,6 +151,9 @@ pub(crate) enum TokenInner {
 	/// Used for attributes/decorators (unary)
 	#[token("@")]
 	SymDecorator,
+	/// Marks metavariables and repetitions in macro definitions
+	#[token("$")]
+	SymDollar,
 	/// Used for bitwise exclusive or (binary)
 	#[token("^")]
 	SymCaret,
@@ -174,6 +202,12 @@ pub(crate) enum TokenInner {
 	/// Used for method access and reassignment
 	#[token(".=")]
 	SymDotEqual,
+	/// Used for exclusive ranges (`0..10`)
+	#[token("..")]
+	SymRange,
+	/// Used for inclusive ranges (`0..=10`)
+	#[token("..=")]
+	SymRangeEqual,
 	/// Used for monadic error propagation
 	#[token("?")]
 	SymTry,
@@ -234,9 +268,10 @@ pub(crate) enum TokenInner {
 	/// Sometimes means the same thing as ; or ,
 	#[token("\n")]
 	SymNewline,
-	/// Used for arbitrary operators (still considering whether I actually want this capability in Rouge)
-	#[regex(r"\p{Punctuation}*", |l| l.slice().to_string())]
-	SymUser(String),
+	/// Comments start with `#` (or `##` for documentation) and run until the end of the line.
+	/// They're kept as tokens so that tools which care about them, like a formatter, can see them.
+	#[regex(r"#[^\n]*")]
+	Comment,
 	/// Type representing boolean logic values
 	#[token("bool")]
 	WordBoolType,
@@ -317,6 +352,7 @@ pub(crate) enum TokenInner {
 	WordElse,
 	/// Indicates a simple loop block.
 	#[token("loop")]
+	#[token("always")]
 	WordLoop,
 	/// Indicates a conditional loop block, which loops while a condition is true.
 	/// The condition is checked at the beginning of each iteration, meaning it might not run at all.
@@ -338,6 +374,15 @@ pub(crate) enum TokenInner {
 	/// Indicates an effect handler.
 	#[token("when")]
 	WordWhen,
+	/// Exits a loop early.
+	#[token("break")]
+	WordBreak,
+	/// Skips to the next iteration of a loop.
+	#[token("continue")]
+	WordContinue,
+	/// Exits a function early.
+	#[token("return")]
+	WordReturn,
 	/// Used in various places related to code blocks, such as:
 	///  - at the end of function signatures `func func_name(arg: ArgType) -> ReturnType -< EffectType do`
 	///  - at the end of while/until/for loop signatures `while/until condition do` or `for item in iter do`
@@ -373,7 +418,7 @@ pub(crate) enum TokenInner {
 	#[token("xor")]
 	WordXor,
 	/// Any legal identifier for a type, function, effect, trait, whatever
-	#[regex(r"\p{XID_Start}\p{XID_Continue}*", |l| l.slice().to_string())]
+	#[regex(r"[\p{XID_Start}_]\p{XID_Continue}*", |l| l.slice().to_string())]
 	WordIdentifier(String),
 	/// The obligatory error variant.
 	#[error]
@@ -386,11 +431,10 @@ pub(crate) enum TokenInner {
 pub enum LexError {
 	/// The token is invalid, plain and simple.
 	/// 
-	/// NOTE: Due to limitations in Logos, this is the only variant that will see any use for the time being.
-	/// Logos, the crate being used to implement the lexer, doesn't really let you put data in the Error variant of your Token enum.
+	/// NOTE: Logos, the crate being used to implement the lexer, doesn't really let you put data in the Error variant of your Token enum.
 	/// This limits how good the error reporting can be, and I would like to look into getting this situation improved.
 	InvalidToken,
-	/// The lexer tried to parse this text as a number literal, but failed.
+	/// The lexer tried to parse this text as a number literal, but it's too big to fit in 64 bits.
 	NumberParseFail,
 	/// The lexer tried to parse this text as a character, byte, string, or byte string literal, but it encountered an escape sequence it didn't recognize.
 	UnknownEscapeSequence,
@@ -419,42 +463,230 @@ impl InputIter for TokenStream {
 		self.iter_indices().find(|(_, token)| predicate(token.clone())).map(|(pos, _)| pos)
 	}
 
-	fn slice_index(&self, count: usize) -> std::result::Result<usize, nom::Needed> {
+	fn slice_index(&self, _count: usize) -> std::result::Result<usize, nom::Needed> {
 		todo!()
 	}
 }
 
 impl TokenStream {
-	/// Given a path to a file of source code, converts it into a stream of tokens.
+	/// Given some source code (and the file it came from, if any), converts it into a stream of tokens.
 	/// This can fail, and the lexer will provide errors if so.
-	pub(crate) fn lex_file(p: &Path) -> Result {
-		match File::open(p) {
-			Ok(mut f) => {
-				let mut source = String::new();
-				match f.read_to_string(&mut source) {
-					Ok(_) => {
-						let mut toks = Vec::new();
-
-						for (token, span) in TokenInner::lexer(&source).spanned() {
-							toks.push(Token {
-								slice: (&source[span.clone()]).into(),
-								span: span,
-								inner: token,
-							})
-						}
-
-						Ok(TokenStream(toks))
-					},
-					Err(e) => Err(vec![
-						Error::new(false, Some(p), None, None, None, ErrorKind::IO(e.kind()))
-					])
+	/// It gives up as soon as the source goes past the size or token limits, so that huge files don't get lexed in full first.
+	pub(crate) fn lex(path: Option<&Path>, source: &str, limits: &ParseLimits) -> Result {
+		let mut lexing = Lexing {
+			path,
+			source,
+			limits,
+			tokens: Vec::new(),
+			errors: ErrorList::new(),
+			count: 0,
+			depth: 0,
+		};
+
+		if source.len() > limits.max_source_bytes {
+			return Err(lexing.limit_error(None, Limit::SourceBytes(limits.max_source_bytes)));
+		}
+
+		lexing.range(0..source.len())?;
+
+		if lexing.errors.is_empty() {
+			Ok(TokenStream(lexing.tokens))
+		} else {
+			Err(lexing.errors)
+		}
+	}
+}
+
+impl Lexing<'_> {
+	/// Lexes a piece of the source. Only going past the token limit stops it early, since that's all that can't be carried on from.
+	fn range(&mut self, range: Span) -> std::result::Result<(), ErrorList> {
+		for (token, span) in TokenInner::lexer(&self.source[range.clone()]).spanned() {
+			let span = range.start + span.start..range.start + span.end;
+
+			if token == TokenInner::Error {
+				// Only number literals start with a digit, and the only way one can fail to lex is by being too big.
+				let err = if self.source[span.clone()].starts_with(|c: char| c.is_ascii_digit()) { LexError::NumberParseFail } else { LexError::InvalidToken };
+				self.error(span, err);
+				continue;
+			}
+
+			if matches!(token, TokenInner::LitCharStr(_)) && self.source[span.clone()].starts_with('"') {
+				let (_, holes) = scan_str(&self.source[span.start + 1..]).expect("the string was already lexed");
+
+				if !holes.is_empty() {
+					let holes = holes.into_iter().map(|hole| span.start + 1 + hole.start..span.start + 1 + hole.end).collect();
+					self.interpolated(span, holes)?;
+					continue;
 				}
+			}
+
+			self.push(token, span)?;
+		}
+
+		Ok(())
+	}
+
+	/// Splits a string with expressions interpolated into it into its pieces of text, and lexes the expressions in between them.
+	fn interpolated(&mut self, span: Span, holes: Vec<Span>) -> std::result::Result<(), ErrorList> {
+		// Just the start of the string gets pointed at, since the rest of it could be huge.
+		if self.depth == self.limits.max_depth { return Err(self.limit_error(Some(span.start..holes[0].start), Limit::Depth(self.limits.max_depth))) }
+		self.depth += 1;
+
+		// The first piece starts with the opening quote, and the rest start with the `}` which closed the expression before them.
+		let mut start = span.start;
+
+		for (i, hole) in holes.iter().enumerate() {
+			let make = if i == 0 { TokenInner::LitStrHead } else { TokenInner::LitStrMid };
+			self.piece(start..hole.start, start + 1..hole.start - 2, make)?;
+			self.range(hole.clone())?;
+			start = hole.end;
+		}
+
+		self.piece(start..span.end, start + 1..span.end - 1, TokenInner::LitStrTail)?;
+		self.depth -= 1;
+
+		Ok(())
+	}
+
+	/// Makes a token for a piece of an interpolated string, out of the text between its delimiters.
+	fn piece(&mut self, span: Span, text: Span, make: fn(String) -> TokenInner) -> std::result::Result<(), ErrorList> {
+		match unescape(&self.source[text]) {
+			Some(text) => self.push(make(text), span),
+			None => {
+				self.error(span, LexError::InvalidToken);
+				Ok(())
 			},
-			Err(e) => Err(vec![
-				Error::new(false, Some(p), None, None, None, ErrorKind::IO(e.kind()))
-			])
 		}
 	}
+
+	fn push(&mut self, inner: TokenInner, span: Span) -> std::result::Result<(), ErrorList> {
+		if !inner.is_trivia() {
+			self.count += 1;
+			if self.count > self.limits.max_tokens { return Err(self.limit_error(Some(span), Limit::Tokens(self.limits.max_tokens))) }
+		}
+
+		self.tokens.push(Token {
+			slice: self.source[span.clone()].into(),
+			span,
+			inner,
+		});
+
+		Ok(())
+	}
+
+	fn error(&mut self, span: Span, err: LexError) {
+		self.errors.push(Error::new(false, self.path, Some(line_of(self.source, span.start)), Some(span.clone()), Some(&self.source[span]), ErrorKind::Interpret(InterpretError::Lex(err))));
+	}
+
+	fn limit_error(&self, span: Option<Span>, limit: Limit) -> ErrorList {
+		let line = span.as_ref().map(|span| line_of(self.source, span.start));
+		vec![Error::new(false, self.path, line, span.clone(), span.and_then(|span| self.source.get(span)), ErrorKind::Interpret(InterpretError::Parse(ParseError::LimitExceeded(limit))))]
+	}
+}
+
+/// Finds where a string literal ends, given the text straight after its opening quote, along with where each expression interpolated into it is.
+/// Interpolated expressions can have brackets and strings of their own in them, so those are kept track of too.
+/// Strings can go inside of each other as deep as anyone likes, so this keeps its own stack of them rather than calling itself.
+fn scan_str(text: &str) -> Option<(usize, Vec<Span>)> {
+	let bytes = text.as_bytes();
+	let mut holes = Vec::new();
+	// Everything open at this point: `None` for a string, or how many brackets are open inside of an interpolated expression.
+	let mut open: Vec<Option<usize>> = vec![None];
+	let mut start = 0;
+	let mut pos = 0;
+
+	while let Some(inner) = open.last_mut() {
+		match (inner, *bytes.get(pos)?) {
+			(None, b'"') => {
+				open.pop();
+				pos += 1;
+			},
+			(None, b'\\') if bytes.get(pos + 1) == Some(&b'{') => {
+				open.push(Some(0));
+				pos += 2;
+				if open.len() == 2 { start = pos }
+			},
+			(None, b'\\') => pos += 2,
+			(Some(depth), b'{') => {
+				*depth += 1;
+				pos += 1;
+			},
+			(Some(0), b'}') => {
+				open.pop();
+				// Only the expressions interpolated into the outermost string get lexed from here.
+				if open.len() == 1 { holes.push(start..pos) }
+				pos += 1;
+			},
+			(Some(depth), b'}') => {
+				*depth -= 1;
+				pos += 1;
+			},
+			(Some(_), b'"') => {
+				open.push(None);
+				pos += 1;
+			},
+			(Some(_), b'\'') => {
+				// A character literal, which could be a bracket or a quote.
+				pos += 1;
+				while *bytes.get(pos)? != b'\'' { pos += if bytes[pos] == b'\\' { 2 } else { 1 } }
+				pos += 1;
+			},
+			_ => pos += 1,
+		}
+	}
+
+	Some((pos, holes))
+}
+
+/// Processes the escape sequences in the text of a string literal.
+fn unescape(text: &str) -> Option<String> {
+	// Reversed so that popping characters off the end walks the literal from front to back.
+	let mut chars: Vec<char> = text.chars().rev().collect();
+
+	let mut result = String::new();
+
+	while let Some(c0) = chars.pop() {
+
+		if c0 == '\\' {
+			match chars.pop()? {
+				'0' => result.push('\0'),
+				'a' => result.push(char::from_u32(0x07)?),
+				'b' => result.push(char::from_u32(0x08)?),
+				't' => result.push('\t'),
+				'n' => result.push('\n'),
+				'v' => result.push(char::from_u32(0x0B)?),
+				'f' => result.push(char::from_u32(0x0C)?),
+				'r' => result.push('\r'),
+				'e' => result.push(char::from_u32(0x1B)?),
+				'x' => {
+					// ASCII escape sequence '\xFF' where FF is two hex digits
+					let mut escape = String::new();
+					escape.push(chars.pop()?);
+					escape.push(chars.pop()?);
+
+					let val = u8::from_str_radix(&escape, 16).ok()?;
+
+					if val.is_ascii() { result.push(char::from_u32(val as u32)?); } else { return None }
+				},
+				'u' => if chars.pop()? == '{' {
+					// Unicode escape sequence '\u{F*}' where F is a hex digit
+					let mut escape = String::new();
+
+					loop {
+						let cx = chars.pop()?;
+						if cx == '}' { break; } else { escape.push(cx); }
+					}
+
+					result.push(char::from_u32(u32::from_str_radix(&escape, 16).ok()?)?);
+				} else { return None },
+				c => result.push(c)
+			}
+		} else {
+			result.push(c0);
+		}
+	}
+
+	Some(result)
 }
 
 impl TokenInner {
@@ -481,7 +713,7 @@ impl TokenInner {
 				'e' => if slice.len() == 2 { char::from_u32(0x1B) } else { None },
 				'x' => if slice.len() == 4 {
 					// ASCII escape sequence '\xFF' where FF is two hex digits
-					let escape: String = (&slice[2..4]).iter().collect();
+					let escape: String = slice[2..4].iter().collect();
 					
 					let val = u8::from_str_radix(&escape, 16).ok()?;
 
@@ -489,7 +721,7 @@ impl TokenInner {
 				} else { None },
 				'u' => if slice.len() > 2 && slice[2] == '{' && slice[slice.len() - 1] == '}' {
 					// Unicode escape sequence '\u{F*}' where F is a hex digit
-					let escape: String = (&slice[3..slice.len() - 2]).iter().collect();
+					let escape: String = slice[3..slice.len() - 1].iter().collect();
 
 					char::from_u32(u32::from_str_radix(&escape, 16).ok()?)
 				} else { None },
@@ -501,56 +733,15 @@ impl TokenInner {
 	}
 
 	/// Processes a string literal into an actual string.
+	/// Where it ends has to be found by hand, since strings interpolated into it can have quotes of their own.
 	fn lit_char_str(l: &mut Lexer<TokenInner>) -> Option<String> {
-		let mut chars: Vec<char> = l.slice().strip_prefix('"')?.strip_suffix('"')?.chars().collect();
-
-		let mut result = String::new();
-
-		loop {
-			let c0 = chars.pop()?;
-
-			if c0 == '\\' {
-				match chars.pop()? {
-					'0' => result.push('\0'),
-					'a' => result.push(char::from_u32(0x07)?),
-					'b' => result.push(char::from_u32(0x08)?),
-					't' => result.push('\t'),
-					'n' => result.push('\n'),
-					'v' => result.push(char::from_u32(0x0B)?),
-					'f' => result.push(char::from_u32(0x0C)?),
-					'r' => result.push('\r'),
-					'e' => result.push(char::from_u32(0x1B)?),
-					'x' => {
-						// ASCII escape sequence '\xFF' where FF is two hex digits
-						let mut escape = String::new();
-						escape.push(chars.pop()?);
-						escape.push(chars.pop()?);
-
-						let val = u8::from_str_radix(&escape, 16).ok()?;
-
-						if val.is_ascii() { result.push(char::from_u32(val as u32)?); } else { return None }
-					},
-					'u' => if chars.pop()? == '{' {
-						// Unicode escape sequence '\u{F*}' where F is a hex digit
-						let mut escape = String::new();
-
-						loop {
-							let cx = chars.pop()?;
-							if cx == '}' { break; } else { escape.push(cx); }
-						}
+		let (len, holes) = scan_str(l.remainder())?;
+		l.bump(len);
 
-						result.push(char::from_u32(u32::from_str_radix(&escape, 16).ok()?)?);
-					} else { return None },
-					c => result.push(c)
-				}
-			} else {
-				result.push(c0);
-			}
-
-			if chars.is_empty() { break }
-		}
+		// Strings with expressions interpolated into them get split up into pieces afterwards, so there's no one string to give back.
+		if !holes.is_empty() { return Some(String::new()) }
 
-		Some(result)
+		unescape(l.slice().strip_prefix('"')?.strip_suffix('"')?)
 	}
 
 	/// Processes a raw string literal into an actual string.
@@ -589,7 +780,7 @@ impl TokenInner {
 				'e' => if slice.len() == 2 { Some(0x1B) } else { None },
 				'x' => if slice.len() == 4 {
 					// ASCII escape sequence '\xFF' where FF is two hex digits
-					let escape: String = (&slice[2..4]).iter().collect();
+					let escape: String = slice[2..4].iter().collect();
 					
 					u8::from_str_radix(&escape, 16).ok()
 				} else { None },
@@ -608,7 +799,8 @@ impl TokenInner {
 
 	/// Processes a byte string literal into an actual byte string.
 	fn lit_byte_str(l: &mut Lexer<TokenInner>) -> Option<Vec<u8>> {
-		let mut chars: Vec<char> = l.slice().strip_prefix("b\"")?.strip_suffix("\"b")?.chars().collect();
+		// Reversed so that popping characters off the end walks the literal from front to back.
+		let mut chars: Vec<char> = l.slice().strip_prefix("b\"")?.strip_suffix("\"b")?.chars().rev().collect();
 
 		// Byte string literals cannot contain Unicode.
 		if !chars.iter().all(|ch| ch.is_ascii()) { return None }
@@ -616,9 +808,7 @@ impl TokenInner {
 		let mut result = Vec::new();
 		let mut buf: [u8; 1] = [0];
 
-		loop {
-			let c0 = chars.pop()?;
-
+		while let Some(c0) = chars.pop() {
 			if c0 == '\\' {
 				match chars.pop()? {
 					'0' => result.push(0x00),
@@ -647,8 +837,6 @@ impl TokenInner {
 				c0.encode_utf8(&mut buf);
 				result.push(buf[0]);
 			}
-
-			if chars.is_empty() { break }
 		}
 
 		Some(result)
@@ -677,6 +865,7 @@ impl TokenInner {
 		u64::from_str_radix(
 			l.slice()
 				.to_lowercase()
+				.replace('_', "")
 				.strip_prefix("0b")?,
 				2
 		).ok()
@@ -686,23 +875,36 @@ impl TokenInner {
 		u64::from_str_radix(
 			l.slice()
 				.to_lowercase()
+				.replace('_', "")
 				.strip_prefix("0o")?,
 				8
 		).ok()
 	}
 
-	fn dec(l: &mut Lexer<TokenInner>) -> Option<u64> { u64::from_str(l.slice()).ok() }
+	fn dec(l: &mut Lexer<TokenInner>) -> Option<u64> { u64::from_str(&l.slice().replace('_', "")).ok() }
 
 	fn hex(l: &mut Lexer<TokenInner>) -> Option<u64> {
 		u64::from_str_radix(
 			l.slice()
 				.to_lowercase()
+				.replace('_', "")
 				.strip_prefix("0x")?,
 				16
 		).ok()
 	}
 
-	fn float(l: &mut Lexer<TokenInner>) -> Option<f64> { f64::from_str(l.slice()).ok() }
+	fn float(l: &mut Lexer<TokenInner>) -> Option<f64> { f64::from_str(&l.slice().replace('_', "")).ok() }
+}
+
+impl fmt::Display for LexError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			LexError::InvalidToken => write!(f, "invalid token"),
+			LexError::NumberParseFail => write!(f, "this number literal is too big; the biggest there can be is {}", u64::MAX),
+			LexError::UnknownEscapeSequence => write!(f, "unknown escape sequence"),
+			LexError::InvalidEscapeSequence => write!(f, "invalid escape sequence"),
+		}
+	}
 }
 
 //--> Unit Testing <--
@@ -710,4 +912,76 @@ impl TokenInner {
 #[cfg(test)]
 mod tests {
 	use super::*;
+
+	fn inners(source: &str) -> Vec<TokenInner> {
+		TokenStream::lex(None, source, &ParseLimits::default()).unwrap().0.into_iter().map(|t| t.inner).collect()
+	}
+
+	#[test]
+	fn string_escapes_keep_order() {
+		assert_eq!(inners(r#""a\tb""#), vec![TokenInner::LitCharStr("a\tb".into())]);
+		assert_eq!(inners(r#""hi\n""#), vec![TokenInner::LitCharStr("hi\n".into())]);
+		assert_eq!(inners(r#""""#), vec![TokenInner::LitCharStr(String::new())]);
+	}
+
+	#[test]
+	fn interpolated_strings_are_split_up() {
+		assert_eq!(inners(r#""\{n}! = \{f(n, "\{m}")}""#), vec![
+			TokenInner::LitStrHead(String::new()),
+			TokenInner::WordIdentifier("n".into()),
+			TokenInner::LitStrMid("! = ".into()),
+			TokenInner::WordIdentifier("f".into()),
+			TokenInner::SymOParen,
+			TokenInner::WordIdentifier("n".into()),
+			TokenInner::SymComma,
+			TokenInner::LitStrHead(String::new()),
+			TokenInner::WordIdentifier("m".into()),
+			TokenInner::LitStrTail(String::new()),
+			TokenInner::SymCParen,
+			TokenInner::LitStrTail(String::new()),
+		]);
+
+		assert_eq!(inners(r#""a\t\{'}'}\u{62}""#), vec![TokenInner::LitStrHead("a\t".into()), TokenInner::LitChar('}'), TokenInner::LitStrTail("b".into())]);
+	}
+
+	#[test]
+	fn interpolated_strings_only_go_so_deep() {
+		let nested = |depth: usize| format!("{}x{}", "\"\\{".repeat(depth), "}\"".repeat(depth));
+		let limits = ParseLimits { max_depth: 16, ..ParseLimits::default() };
+
+		assert!(TokenStream::lex(None, &nested(16), &limits).is_ok());
+
+		let errors = TokenStream::lex(None, &nested(17), &limits).err().unwrap();
+		assert!(matches!(errors[..], [ref e] if matches!(e.kind(), ErrorKind::Interpret(InterpretError::Parse(ParseError::LimitExceeded(Limit::Depth(16)))))));
+
+		// Far past where calling back into the lexer for each one would have run out of stack.
+		assert!(TokenStream::lex(None, &nested(20_000), &ParseLimits::default()).is_err());
+	}
+
+	#[test]
+	fn numbers_and_ranges() {
+		assert_eq!(inners("1_000..=2.5"), vec![TokenInner::LitNum(1000), TokenInner::SymRangeEqual, TokenInner::LitFloat(2.5)]);
+	}
+
+	#[test]
+	fn numbers_which_are_too_big_say_so() {
+		for source in ["18446744073709551615", "0xffff_ffff_ffff_ffff"] {
+			assert_eq!(inners(source), vec![TokenInner::LitNum(u64::MAX)]);
+		}
+
+		for source in ["18446744073709551616".to_string(), "0x1_0000_0000_0000_0000".to_string(), format!("0b1{}", "0".repeat(64)), format!("0o{}", "7".repeat(23))] {
+			let errors = TokenStream::lex(None, &source, &ParseLimits::default()).err().unwrap();
+			assert!(matches!(errors[..], [ref e] if matches!(e.kind(), ErrorKind::Interpret(InterpretError::Lex(LexError::NumberParseFail)))), "{}", source);
+		}
+	}
+
+	#[test]
+	fn parens_and_comments_are_separate_tokens() {
+		assert_eq!(inners("f() # call"), vec![
+			TokenInner::WordIdentifier("f".into()),
+			TokenInner::SymOParen,
+			TokenInner::SymCParen,
+			TokenInner::Comment,
+		]);
+	}
 }
\ No newline at end of file
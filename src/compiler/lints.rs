@@ -0,0 +1,574 @@
+//! Lints, which warn about code that works but probably isn't what was meant, like a variable which never gets used.
+//!
+//! Every lint is a warning to begin with. `@allow(...)` turns lints off for an item and everything inside of it,
+//! and the `-W` and `-D` flags set them for a whole package, with `-D` turning them into errors.
+//! Lints can be named one at a time, or as a group: `unused` is every lint about something which never gets used,
+//! and `warnings` is every warning there is. Anything allowed in the source code stays allowed whatever the flags say.
+//!
+//! Names starting with `_` are left out of the unused lints, so something can be left unused on purpose.
+
+//--> Imports <--
+
+use crate::{
+	Error,
+	ErrorKind,
+	ErrorList,
+	compiler::{
+		InterpretError,
+		Options,
+		line_of,
+		modules::{
+			ModuleId,
+			ModuleTree,
+		},
+		parser::astgen::{
+			Block,
+			Condition,
+			Expr,
+			ExprKind,
+			Ident,
+			Item,
+			ItemKind,
+			Mutability,
+			Param,
+			Pattern,
+			PatternKind,
+			Stmt,
+			StmtKind,
+			Visibility,
+		},
+		resolve::{
+			LocalId,
+			Res,
+			Resolutions,
+		},
+		typeck::{
+			TypeError,
+			Types,
+		},
+	},
+};
+
+use logos::Span;
+
+use std::{
+	collections::HashSet,
+	fmt,
+};
+
+//--> Structs <--
+
+struct Linter<'a> {
+	tree: &'a ModuleTree,
+	types: &'a Types,
+	errors: ErrorList,
+	module: ModuleId,
+	/// Every variable declared in the package, along with the module it's in and whether it was declared `mut`.
+	bindings: Vec<(ModuleId, LocalId, bool)>,
+	/// Variables whose values get read somewhere.
+	read: HashSet<LocalId>,
+	/// Variables which get changed somewhere, either by being assigned to or by a method which takes `mut self`.
+	changed: HashSet<LocalId>,
+}
+
+//--> Enums <--
+
+/// How seriously a lint gets taken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LintLevel {
+	Allow,
+	Warn,
+	Deny,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Lint {
+	UnusedVariables,
+	UnusedImports,
+	DeadCode,
+	Unreachable,
+	NeedlessMut,
+}
+
+#[derive(Clone, Debug)]
+pub enum LintError {
+	/// A variable or parameter is never read.
+	UnusedVariable(String),
+	/// An import never gets used by anything.
+	UnusedImport(String),
+	/// A private function never gets called.
+	UnusedFunction(String),
+	/// A variable is declared `mut`, but never changed.
+	NeedlessMut(String),
+	/// `@allow(...)` or a command-line flag named a lint that doesn't exist.
+	Unknown(String),
+}
+
+//--> Functions <--
+
+/// Looks for code in the package which works, but probably isn't what was meant.
+pub(crate) fn check(tree: &ModuleTree, res: &Resolutions, types: &Types) -> ErrorList {
+	let mut linter = Linter {
+		tree,
+		types,
+		errors: ErrorList::new(),
+		module: 0,
+		bindings: Vec::new(),
+		read: HashSet::new(),
+		changed: HashSet::new(),
+	};
+
+	for (module, m) in tree.modules.iter().enumerate() {
+		// The standard library is already how it's meant to be.
+		if m.path[0] != "pkg" { continue }
+		linter.module = module;
+
+		for item in m.ast.items.iter() {
+			linter.item(item, true);
+		}
+	}
+
+	for (module, span) in res.unused_imports.iter() {
+		linter.module = *module;
+		let name = tree.modules[*module].source.get(span.clone()).unwrap_or_default().to_string();
+		linter.warn(span.clone(), LintError::UnusedImport(name));
+	}
+
+	for (module, local, is_mut) in std::mem::take(&mut linter.bindings) {
+		let name = res.locals[local].name.clone();
+		if name.name.starts_with('_') || name.name.contains('$') || name.name == "self" { continue }
+		linter.module = module;
+
+		if !linter.read.contains(&local) {
+			let note = match linter.changed.contains(&local) {
+				true => format!("it gets changed, but nothing reads it afterwards, so if that's on purpose, call it `_{}`", name.name),
+				false => format!("if that's on purpose, call it `_{}`", name.name),
+			};
+
+			linter.warn(name.span.clone(), LintError::UnusedVariable(name.name.clone())).add_note(note);
+		} else if is_mut && !linter.changed.contains(&local) {
+			linter.warn(name.span.clone(), LintError::NeedlessMut(name.name.clone()));
+		}
+	}
+
+	linter.errors.sort_by_key(|err| (err.file.clone(), err.span.as_ref().map(|span| span.start)));
+	linter.errors
+}
+
+/// Applies lint levels to the warnings and errors from checking a package, from `@allow(...)` on the items they're in
+/// and from the levels set for the whole package. Warnings which are allowed get left out, and ones which are denied become errors.
+pub(crate) fn apply_levels(tree: &ModuleTree, errors: ErrorList, options: &Options) -> ErrorList {
+	let mut out = ErrorList::new();
+
+	for (name, _) in options.lints.iter() {
+		if !is_lint(name) {
+			out.push(Error::new(false, None, None, None, None, ErrorKind::Interpret(InterpretError::Lint(LintError::Unknown(name.clone())))));
+		}
+	}
+
+	// Each `@allow(...)`, as the module it's in, where the item it's on is, and the names it was given.
+	let mut allows: Vec<(ModuleId, Span, Vec<Ident>)> = Vec::new();
+
+	for (module, m) in tree.modules.iter().enumerate() {
+		if m.path[0] != "pkg" { continue }
+
+		for item in m.ast.items.iter() {
+			collect_allows(module, item, &mut allows);
+		}
+	}
+
+	for (module, _, names) in allows.iter() {
+		let m = &tree.modules[*module];
+
+		for name in names.iter().filter(|name| !is_lint(&name.name)) {
+			let (line, span) = (line_of(&m.source, name.span.start), name.span.clone());
+			out.push(Error::new(true, m.file.as_deref(), Some(line), Some(span.clone()), m.source.get(span), ErrorKind::Interpret(InterpretError::Lint(LintError::Unknown(name.name.clone())))));
+		}
+	}
+
+	for mut err in errors {
+		if !err.is_warning { out.push(err); continue }
+		let lint = Lint::of(&err.kind);
+
+		let allowed = err.span.as_ref().is_some_and(|at| allows.iter().any(|(module, span, names)| {
+			tree.modules[*module].file == err.file && span.start <= at.start && at.end <= span.end
+				&& names.iter().any(|name| covers(&name.name, lint))
+		}));
+		if allowed { continue }
+
+		// Later flags win over earlier ones, like `-D unused -W unused_variables`.
+		let level = options.lints.iter().rev().find(|(name, _)| covers(name, lint));
+
+		match level {
+			Some((_, LintLevel::Allow)) => continue,
+			Some((name, LintLevel::Deny)) => {
+				err.is_warning = false;
+				err.add_note(format!("it's an error because of `-D {}`", name));
+			},
+			_ => {},
+		}
+
+		out.push(err);
+	}
+
+	out
+}
+
+/// Finds every `@allow(...)` on an item or anything inside of it.
+fn collect_allows(module: ModuleId, item: &Item, out: &mut Vec<(ModuleId, Span, Vec<Ident>)>) {
+	for attr in item.attrs.iter().filter(|attr| attr.name.name == "allow") {
+		out.push((module, item.span.clone(), attr.args.iter().map(|arg| arg.name.clone()).collect()));
+	}
+
+	let items = match &item.kind {
+		ItemKind::Type(decl) => &decl.items,
+		ItemKind::Trait(decl) => &decl.items,
+		ItemKind::Effect(decl) => &decl.items,
+		ItemKind::Impl(decl) => &decl.items,
+		_ => return,
+	};
+
+	for item in items.iter() {
+		collect_allows(module, item, out);
+	}
+}
+
+/// Whether a name is a lint, or a group of them.
+fn is_lint(name: &str) -> bool {
+	matches!(name, "unused" | "warnings") || Lint::ALL.iter().any(|lint| lint.name() == name)
+}
+
+/// Whether a name given to `@allow(...)` or a flag covers a warning, which is only a lint if it has one.
+fn covers(name: &str, lint: Option<Lint>) -> bool {
+	match (name, lint) {
+		("warnings", _) => true,
+		("unused", Some(Lint::UnusedVariables | Lint::UnusedImports | Lint::DeadCode)) => true,
+		(name, Some(lint)) => lint.name() == name,
+		_ => false,
+	}
+}
+
+impl Lint {
+	const ALL: [Lint; 5] = [Lint::UnusedVariables, Lint::UnusedImports, Lint::DeadCode, Lint::Unreachable, Lint::NeedlessMut];
+
+	fn name(self) -> &'static str {
+		match self {
+			Lint::UnusedVariables => "unused_variables",
+			Lint::UnusedImports => "unused_imports",
+			Lint::DeadCode => "dead_code",
+			Lint::Unreachable => "unreachable",
+			Lint::NeedlessMut => "needless_mut",
+		}
+	}
+
+	/// The lint a warning comes from. Unreachable code and match arms are found by type checking, and everything else here.
+	fn of(kind: &ErrorKind) -> Option<Lint> {
+		match kind {
+			ErrorKind::Interpret(InterpretError::Lint(err)) => match err {
+				LintError::UnusedVariable(_) => Some(Lint::UnusedVariables),
+				LintError::UnusedImport(_) => Some(Lint::UnusedImports),
+				LintError::UnusedFunction(_) => Some(Lint::DeadCode),
+				LintError::NeedlessMut(_) => Some(Lint::NeedlessMut),
+				LintError::Unknown(_) => None,
+			},
+			ErrorKind::Interpret(InterpretError::Type(TypeError::Unreachable | TypeError::UnreachableCode)) => Some(Lint::Unreachable),
+			_ => None,
+		}
+	}
+}
+
+impl Linter<'_> {
+	fn warn(&mut self, span: Span, err: LintError) -> &mut Error {
+		let module = &self.tree.modules[self.module];
+		let line = line_of(&module.source, span.start);
+		let slice = module.source.get(span.clone());
+
+		self.errors.push(Error::new(true, module.file.as_deref(), Some(line), Some(span), slice, ErrorKind::Interpret(InterpretError::Lint(err))));
+		self.errors.last_mut().unwrap()
+	}
+
+	/// Looks through an item. `direct` is whether a function there could only ever be called directly,
+	/// rather than through a trait, which can't be followed.
+	fn item(&mut self, item: &Item, direct: bool) {
+		let (items, direct) = match &item.kind {
+			ItemKind::Func(func) => {
+				let is_test = item.attrs.iter().any(|attr| matches!(attr.name.name.as_str(), "test" | "builtin"));
+				let unused = item.def.is_some_and(|def| !self.types.used.contains(&def));
+
+				if direct && unused && item.vis == Visibility::Private && !is_test && func.name.name != "main" && !func.name.name.starts_with('_') {
+					self.warn(func.name.span.clone(), LintError::UnusedFunction(func.name.name.clone()));
+				}
+
+				// Parameters of functions without bodies don't have anything which could use them.
+				if let Some(body) = &func.body {
+					self.params(&func.params);
+					self.block(body);
+				}
+				return;
+			},
+			ItemKind::Const(decl) => {
+				if let Some(value) = &decl.value { self.expr(value); }
+				return;
+			},
+			ItemKind::Type(decl) => (&decl.items, direct),
+			ItemKind::Trait(decl) => (&decl.items, false),
+			ItemKind::Effect(decl) => (&decl.items, false),
+			ItemKind::Impl(decl) => (&decl.items, decl.trait_.is_none()),
+			ItemKind::Use(_) => return,
+		};
+
+		for item in items.iter() {
+			self.item(item, direct);
+		}
+	}
+
+	fn params(&mut self, params: &[Param]) {
+		for param in params.iter() {
+			if let Some(local) = param.local { self.bindings.push((self.module, local, param.is_mut)); }
+		}
+	}
+
+	/// Records every name a pattern binds, where `is_mut` is whether the whole pattern was declared `mut`.
+	fn pattern(&mut self, pat: &Pattern, is_mut: bool) {
+		match &pat.kind {
+			PatternKind::Binding { is_mut: binding_mut, local: Some(local), .. } => self.bindings.push((self.module, *local, is_mut || *binding_mut)),
+			PatternKind::Range { start, end, .. } => {
+				for end in [start, end].into_iter().flatten() { self.pattern(end, is_mut); }
+			},
+			PatternKind::TupleStruct { fields, .. } | PatternKind::Tuple(fields) => {
+				for field in fields.iter() { self.pattern(field, is_mut); }
+			},
+			_ => {},
+		}
+	}
+
+	fn block(&mut self, block: &Block) {
+		for stmt in block.stmts.iter() {
+			self.stmt(stmt);
+		}
+	}
+
+	fn stmt(&mut self, stmt: &Stmt) {
+		match &stmt.kind {
+			StmtKind::Let { mutability, pat, value, .. } => {
+				self.expr(value);
+				self.pattern(pat, *mutability == Mutability::Mutable);
+			},
+			StmtKind::Assign { target, value, .. } => {
+				self.place(target);
+				self.expr(value);
+			},
+			StmtKind::Expr(expr) => self.expr(expr),
+			StmtKind::Handler(handler) => {
+				for param in handler.params.iter() { self.pattern(param, false); }
+				self.block(&handler.body);
+			},
+		}
+	}
+
+	/// Looks at something being assigned to, which changes the variable it's in without reading it.
+	fn place(&mut self, target: &Expr) {
+		match &target.kind {
+			ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } => self.place(base),
+			ExprKind::Index { base, index } => {
+				self.place(base);
+				self.expr(index);
+			},
+			ExprKind::Path(path) => if let Res::Local(local) = path.res { self.changed.insert(local); },
+			_ => self.expr(target),
+		}
+	}
+
+	fn expr(&mut self, expr: &Expr) {
+		match &expr.kind {
+			ExprKind::Lit(_) | ExprKind::Continue(_) => {},
+			ExprKind::Path(path) => if let Res::Local(local) = path.res { self.read.insert(local); },
+			ExprKind::Call { callee, args } => {
+				self.expr(callee);
+				for arg in args.iter() { self.expr(arg); }
+			},
+			ExprKind::MethodCall { receiver, args, .. } => {
+				self.expr(receiver);
+				for arg in args.iter() { self.expr(arg); }
+
+				// A method called before the receiver's type was known might change it, so it gets the benefit of the doubt.
+				let at = (self.module, receiver.span.clone());
+				if self.types.mutating.contains(&at) || self.types.unknown_receivers.contains(&at) {
+					if let Some(local) = root(receiver) { self.changed.insert(local); }
+				}
+			},
+			ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } => self.expr(base),
+			ExprKind::Index { base, index } => {
+				self.expr(base);
+				self.expr(index);
+			},
+			ExprKind::Tuple(elems) | ExprKind::List(elems) | ExprKind::Interp(elems) => {
+				for elem in elems.iter() { self.expr(elem); }
+			},
+			ExprKind::Record(fields) => {
+				for (_, value) in fields.iter() { self.expr(value); }
+			},
+			ExprKind::Map(entries) => {
+				for (key, value) in entries.iter() {
+					self.expr(key);
+					self.expr(value);
+				}
+			},
+			ExprKind::Closure { params, body, .. } => {
+				self.params(params);
+				self.block(body);
+			},
+			ExprKind::Unary { expr, .. } | ExprKind::Try(expr) => self.expr(expr),
+			ExprKind::Binary { lhs, rhs, .. } => {
+				self.expr(lhs);
+				self.expr(rhs);
+			},
+			ExprKind::If { branches, else_ } => {
+				for branch in branches.iter() {
+					match &branch.cond {
+						Condition::Expr(cond) => self.expr(cond),
+						Condition::Matches(value, pat) => {
+							self.expr(value);
+							self.pattern(pat, false);
+						},
+					}
+					self.block(&branch.body);
+				}
+
+				if let Some(else_) = else_ { self.block(else_); }
+			},
+			ExprKind::Match { scrutinee, arms } => {
+				self.expr(scrutinee);
+
+				for arm in arms.iter() {
+					self.pattern(&arm.pat, false);
+					self.expr(&arm.body);
+				}
+			},
+			ExprKind::Loop { body, .. } | ExprKind::Block { body, .. } => self.block(body),
+			ExprKind::While { cond, body, .. } | ExprKind::Until { cond, body, .. } => {
+				self.expr(cond);
+				self.block(body);
+			},
+			ExprKind::For { pat, iter, body, .. } => {
+				self.expr(iter);
+				self.pattern(pat, false);
+				self.block(body);
+			},
+			ExprKind::Handle { handler, body } => {
+				for param in handler.params.iter() { self.pattern(param, false); }
+				self.block(&handler.body);
+				self.block(body);
+			},
+			ExprKind::Break { value, .. } | ExprKind::Return(value) => {
+				if let Some(value) = value { self.expr(value); }
+			},
+		}
+	}
+}
+
+/// The variable something is a field or element of, however deep.
+fn root(expr: &Expr) -> Option<LocalId> {
+	match &expr.kind {
+		ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } | ExprKind::Index { base, .. } => root(base),
+		ExprKind::Path(path) => match path.res {
+			Res::Local(local) => Some(local),
+			_ => None,
+		},
+		_ => None,
+	}
+}
+
+impl fmt::Display for LintError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			LintError::UnusedVariable(name) => write!(f, "`{}` is never used", name),
+			LintError::UnusedImport(name) => write!(f, "`{}` is imported, but never used", name),
+			LintError::UnusedFunction(name) => write!(f, "the function `{}` is never called", name),
+			LintError::NeedlessMut(name) => write!(f, "`{}` is declared `mut`, but never changed", name),
+			LintError::Unknown(name) => write!(f, "there's no lint called `{}`", name),
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compiler::TestPackage;
+
+	/// Checks a package with the lint levels given.
+	/// Gives back each warning or error as its line, whether it's only a warning, and its message.
+	fn lint(name: &str, files: &[(&str, &str)], lints: &[(&str, LintLevel)]) -> Vec<(Option<usize>, bool, String)> {
+		let options = Options {
+			lints: lints.iter().map(|(name, level)| (name.to_string(), *level)).collect(),
+			..Options::default()
+		};
+
+		let errors = match TestPackage::new(name, files).check(&options) {
+			Ok((_, warnings)) => warnings,
+			Err(errors) => errors,
+		};
+
+		errors.into_iter().map(|e| (e.line(), e.is_warning(), e.kind().to_string())).collect()
+	}
+
+	#[test]
+	fn unused_things_get_warned_about() {
+		let errors = lint("unused", &[
+			("main.ro", "use self::util::[double, triple]\n\nfunc helper() -> nat do\n\t1\nend\n\nfunc _spare() do\nend\n\npub func main() do\n\tx := double(2)\n\ty := 3\n\t_z := 4\n\tmut total := 0\n\ttotal += x\n\tmut same := 1\n\tlimit := same\nend\n"),
+			("util.ro", "pub func double(n: nat) -> nat do\n\tn * 2\nend\n\npub func triple(n: nat) -> nat do\n\tn * 3\nend\n"),
+		], &[]);
+
+		assert_eq!(errors, vec![
+			(Some(1), true, "`triple` is imported, but never used".to_string()),
+			(Some(3), true, "the function `helper` is never called".to_string()),
+			(Some(12), true, "`y` is never used".to_string()),
+			(Some(14), true, "`total` is never used".to_string()),
+			(Some(16), true, "`same` is declared `mut`, but never changed".to_string()),
+			(Some(17), true, "`limit` is never used".to_string()),
+		]);
+	}
+
+	#[test]
+	fn interpolating_counts_as_a_use() {
+		let errors = lint("interpolated", &[
+			("main.ro", "func shout(s: str) -> str do\n\ts\nend\n\npub func main() do\n\tname := \"Ashton\"\n\tage := 22\n\toutl(\"\\{shout(name)} is \\{age}\")\nend\n"),
+		], &[("warnings", LintLevel::Deny)]);
+
+		assert_eq!(errors, vec![]);
+	}
+
+	#[test]
+	fn recursion_doesnt_count_as_a_use() {
+		let errors = lint("recursion", &[
+			("main.ro", "func countdown(n: nat) do\n\tif n > 0 then countdown(n - 1)\nend\n\npub func main() do\nend\n"),
+		], &[]);
+
+		assert_eq!(errors, vec![(Some(1), true, "the function `countdown` is never called".to_string())]);
+	}
+
+	#[test]
+	fn allow_silences_lints_inside_of_it() {
+		let errors = lint("allow", &[
+			("main.ro", "@allow(dead_code)\nfunc helper() do\nend\n\n@allow(unused)\nfunc other() do\n\tx := 1\nend\n\n@allow(unused_variables, typos)\npub func main() do\n\tx := 1\n\tmut y := 2\n\ty\nend\n"),
+		], &[("warnings", LintLevel::Deny)]);
+
+		assert_eq!(errors, vec![
+			(Some(10), true, "there's no lint called `typos`".to_string()),
+			(Some(13), false, "`y` is declared `mut`, but never changed".to_string()),
+		]);
+	}
+
+	#[test]
+	fn later_flags_win() {
+		let source = [("main.ro", "func helper() do\nend\n\npub func main() do\n\tx := 1\nend\n")];
+
+		let errors = lint("deny", &source, &[("unused", LintLevel::Deny), ("dead_code", LintLevel::Allow)]);
+		assert_eq!(errors, vec![(Some(5), false, "`x` is never used".to_string())]);
+
+		let errors = lint("warn", &source, &[("unused", LintLevel::Deny), ("warnings", LintLevel::Warn), ("nonsense", LintLevel::Warn)]);
+		assert!(matches!(&errors[..], [(None, false, a), (Some(1), true, _), (Some(5), true, _)] if a == "there's no lint called `nonsense`"), "{:?}", errors);
+	}
+}
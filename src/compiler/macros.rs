@@ -0,0 +1,980 @@
+//! Declarative macros, which are expanded on the token stream before it gets parsed.
+//!
+//! A macro is a list of rules, each of which matches the tokens the macro is invoked with and gives back new ones:
+//!
+//! ```text
+//! macro assert is
+//!     ($cond:expr) then {
+//!         if !($cond) then ...
+//!     }
+//! end
+//! ```
+//!
+//! Metavariables are written `$name:fragment`, where the fragment is one of `expr`, `ty`, `ident`, `literal` or `tt`.
+//! `$( ... ) sep op` matches something repeatedly, where the separator is optional and the operator is `*`, `+` or `?`.
+//!
+//! A macro can only be used in the file it's defined in, after the ones in the prelude, so there's no such thing as a `pub` macro.
+//!
+//! Expansion is hygienic as far as local variables go: anything a macro body binds with `:=`, `mut` or `for` gets a new name
+//! for each expansion, so it can't capture or clobber variables at the call site.
+
+//--> Imports <--
+
+use crate::{
+	Error,
+	ErrorKind,
+	ErrorList,
+	compiler::{
+		InterpretError,
+		line_of,
+		lexer::{
+			Token,
+			TokenInner,
+			TokenStream,
+		},
+		parser::{
+			Limit,
+			ParseError,
+			ParseLimits,
+		},
+	},
+};
+
+use logos::Span;
+
+use std::{
+	collections::{
+		HashMap,
+		HashSet,
+	},
+	fmt,
+	path::Path,
+};
+
+//--> Type Aliases <--
+
+type Bindings = HashMap<String, Binding>;
+
+//--> Structs <--
+
+struct MacroDef {
+	rules: Vec<Rule>,
+}
+
+struct Rule {
+	matcher: Vec<Matcher>,
+	body: Vec<Transcriber>,
+}
+
+/// A place where a macro was expanded, kept around so that errors in the expanded code can say where they came from.
+#[derive(Clone, Debug)]
+pub(crate) struct Expansion {
+	pub name: String,
+	pub span: Span,
+}
+
+struct Expander<'a> {
+	file: Option<&'a Path>,
+	source: &'a str,
+	macros: HashMap<String, MacroDef>,
+	expansions: Vec<Expansion>,
+	errors: ErrorList,
+	/// Counts expansions, so that each one gets its own names for hygiene.
+	next_id: usize,
+	/// How many tokens expansions can make in total, so that macros which keep doubling their output can't run forever.
+	max_tokens: usize,
+	/// How many tokens expansions have made so far, including ones which were invocations themselves.
+	produced: usize,
+}
+
+//--> Enums <--
+
+#[derive(Clone, Debug)]
+enum TokenTree {
+	Token(Token),
+	/// Something in brackets, along with the brackets themselves.
+	Group(Token, Vec<TokenTree>, Token),
+}
+
+enum Matcher {
+	Token(TokenInner),
+	Var(String, Fragment),
+	Group(TokenInner, Vec<Matcher>),
+	Repeat(Vec<Matcher>, Option<TokenInner>, RepeatOp),
+}
+
+enum Transcriber {
+	Token(Token),
+	Var(String),
+	Group(Token, Vec<Transcriber>, Token),
+	Repeat(Vec<Transcriber>, Option<Token>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Fragment {
+	Expr,
+	Type,
+	Ident,
+	Literal,
+	TokenTree,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RepeatOp {
+	ZeroOrMore,
+	OneOrMore,
+	ZeroOrOne,
+}
+
+/// What a metavariable matched. Metavariables inside repetitions match once per repetition.
+#[derive(Clone, Debug)]
+enum Binding {
+	One(Vec<TokenTree>),
+	Many(Vec<Binding>),
+}
+
+#[derive(Clone, Debug)]
+pub enum MacroError {
+	/// The macro definition doesn't make sense.
+	BadDefinition(&'static str),
+	/// Nobody has defined a macro with this name.
+	Unknown(String),
+	/// None of the macro's rules matched what it was given.
+	NoMatchingRule(String),
+	/// A metavariable in a macro body wasn't bound by the rule, or was used at the wrong depth of repetition.
+	BadMetavariable(String),
+	/// The macro kept expanding into more macros until we gave up.
+	RecursionLimit(String),
+	/// The macro was marked `pub`, but macros can't be used outside of the file they're defined in.
+	Public(String),
+}
+
+//--> Functions <--
+
+/// Macros which are available everywhere, written in Rouge.
+const PRELUDE: &str = include_str!("../../std/macros/macros.ro");
+
+/// How many macros deep an expansion can go before we decide it's never going to stop.
+const RECURSION_LIMIT: usize = 64;
+
+/// Takes the macro definitions out of a token stream, and expands every macro invocation in it.
+/// Expansion stops as soon as it has made more tokens than the limits allow.
+pub(crate) fn expand(path: Option<&Path>, source: &str, tokens: TokenStream, limits: &ParseLimits) -> Result<(TokenStream, Vec<Expansion>), ErrorList> {
+	let mut prelude = Expander::new(None, PRELUDE, &ParseLimits::default());
+	prelude.collect_definitions(TokenStream::lex(None, PRELUDE, &ParseLimits::default()).expect("the macro prelude should lex").0);
+	debug_assert!(prelude.errors.is_empty(), "the macro prelude should be valid: {:?}", prelude.errors);
+
+	let mut expander = Expander::new(path, source, limits);
+	expander.macros = prelude.macros;
+
+	let tokens = expander.collect_definitions(tokens.0);
+	let tokens = expander.expand_tokens(tokens, 0);
+
+	if expander.errors.iter().all(|e| e.is_warning()) {
+		Ok((TokenStream(tokens), expander.expansions))
+	} else {
+		Err(expander.errors)
+	}
+}
+
+/// Notes down which expansions each error happened inside of, innermost first.
+pub(crate) fn trace(mut errors: ErrorList, source: &str, expansions: &[Expansion]) -> ErrorList {
+	for error in errors.iter_mut() {
+		let Some(span) = error.span() else { continue };
+
+		let mut containing: Vec<&Expansion> = expansions.iter()
+			.filter(|expansion| expansion.span.start <= span.start && span.end <= expansion.span.end)
+			.collect();
+
+		containing.sort_by_key(|expansion| expansion.span.len());
+
+		for expansion in containing {
+			error.add_note(format!("in expansion of `{}!` on line {}", expansion.name, line_of(source, expansion.span.start)));
+		}
+	}
+
+	errors
+}
+
+impl<'a> Expander<'a> {
+	fn new(file: Option<&'a Path>, source: &'a str, limits: &ParseLimits) -> Expander<'a> {
+		Expander {
+			file,
+			source,
+			macros: HashMap::new(),
+			expansions: Vec::new(),
+			errors: ErrorList::new(),
+			next_id: 0,
+			max_tokens: limits.max_tokens,
+			produced: 0,
+		}
+	}
+
+	fn report(&mut self, span: Span, err: MacroError) {
+		self.errors.push(Error::new(false, self.file, Some(line_of(self.source, span.start)), Some(span.clone()), self.source.get(span), ErrorKind::Interpret(InterpretError::Macro(err))));
+	}
+
+	//--> Definitions <--
+
+	/// Takes every `macro name is ... end` at the start of a line out of the tokens, and remembers it.
+	fn collect_definitions(&mut self, tokens: Vec<Token>) -> Vec<Token> {
+		let mut out: Vec<Token> = Vec::with_capacity(tokens.len());
+		let mut pos = 0;
+
+		while pos < tokens.len() {
+			let at_line_start = out.iter()
+				.rev()
+				.find(|token| token.inner != TokenInner::Comment)
+				.is_none_or(|token| token.inner == TokenInner::SymNewline);
+
+			if at_line_start {
+				if let Some(end) = self.definition(&tokens, pos) {
+					pos = end;
+					continue;
+				}
+			}
+
+			out.push(tokens[pos].clone());
+			pos += 1;
+		}
+
+		out
+	}
+
+	/// Parses a macro definition starting at the given position, if there is one, and gives back where it ends.
+	fn definition(&mut self, tokens: &[Token], start: usize) -> Option<usize> {
+		let mut pos = start;
+
+		let mut token = next_token(tokens, &mut pos)?;
+		let mut public = None;
+
+		if token.inner == TokenInner::WordPub {
+			public = Some(token.span.clone());
+			token = next_token(tokens, &mut pos)?;
+		}
+
+		if !matches!(&token.inner, TokenInner::WordIdentifier(word) if word == "macro") { return None }
+		let TokenInner::WordIdentifier(name) = &next_token(tokens, &mut pos)?.inner else { return None };
+		if next_token(tokens, &mut pos)?.inner != TokenInner::WordIs { return None }
+
+		let name = name.clone();
+
+		if let Some(span) = public {
+			self.report(span, MacroError::Public(name.clone()));
+		}
+		let mut rules = Vec::new();
+
+		loop {
+			let rule_start = pos;
+			let Some(tree) = parse_tree(tokens, &mut pos) else {
+				let span = tokens.get(rule_start).unwrap_or(&tokens[start]).span.clone();
+				self.report(span, MacroError::BadDefinition("`end` or a rule like `(...) then { ... }`"));
+				return Some(tokens.len());
+			};
+
+			match tree {
+				TokenTree::Token(token) if token.inner == TokenInner::SymNewline => continue,
+				TokenTree::Token(token) if token.inner == TokenInner::WordEnd => break,
+				TokenTree::Group(open, matcher, _) if open.inner == TokenInner::SymOParen => {
+					let then = parse_tree(tokens, &mut pos);
+					let body = parse_tree(tokens, &mut pos);
+
+					let (Some(TokenTree::Token(then)), Some(TokenTree::Group(body_open, body, _))) = (then, body) else {
+						self.report(open.span, MacroError::BadDefinition("`then` and a body in braces after a rule's pattern"));
+						continue;
+					};
+
+					if then.inner != TokenInner::WordThen || body_open.inner != TokenInner::SymOBrace {
+						self.report(then.span, MacroError::BadDefinition("`then` and a body in braces after a rule's pattern"));
+						continue;
+					}
+
+					let (Some(matcher), Some(body)) = (self.matchers(&matcher), self.transcribers(&body)) else { continue };
+					rules.push(Rule { matcher, body });
+				},
+				TokenTree::Token(token) | TokenTree::Group(token, ..) => {
+					self.report(token.span, MacroError::BadDefinition("a rule like `(...) then { ... }`"));
+				},
+			}
+		}
+
+		self.macros.insert(name, MacroDef { rules });
+		Some(pos)
+	}
+
+	fn matchers(&mut self, trees: &[TokenTree]) -> Option<Vec<Matcher>> {
+		let mut out = Vec::new();
+		let mut i = 0;
+
+		while i < trees.len() {
+			match &trees[i] {
+				TokenTree::Token(token) if token.inner == TokenInner::SymNewline => {},
+				TokenTree::Token(dollar) if dollar.inner == TokenInner::SymDollar => match trees.get(i + 1) {
+					Some(TokenTree::Token(Token { inner: TokenInner::WordIdentifier(name), .. })) => {
+						let fragment = match (trees.get(i + 2), trees.get(i + 3)) {
+							(Some(TokenTree::Token(colon)), Some(TokenTree::Token(kind))) if colon.inner == TokenInner::SymColon => fragment(&kind.slice),
+							_ => None,
+						};
+
+						let Some(fragment) = fragment else {
+							self.report(dollar.span.clone(), MacroError::BadDefinition("`:` and one of `expr`, `ty`, `ident`, `literal` or `tt` after a metavariable"));
+							return None;
+						};
+
+						out.push(Matcher::Var(name.clone(), fragment));
+						i += 3;
+					},
+					Some(TokenTree::Group(open, inner, _)) if open.inner == TokenInner::SymOParen => {
+						let inner = self.matchers(inner)?;
+						let (sep, op, used) = self.repetition(&trees[i + 2..], &dollar.span)?;
+
+						out.push(Matcher::Repeat(inner, sep.map(|sep| sep.inner), op));
+						i += 1 + used;
+					},
+					_ => {
+						self.report(dollar.span.clone(), MacroError::BadDefinition("a metavariable or repetition after `$`"));
+						return None;
+					},
+				},
+				TokenTree::Token(token) => out.push(Matcher::Token(token.inner.clone())),
+				TokenTree::Group(open, inner, _) => out.push(Matcher::Group(open.inner.clone(), self.matchers(inner)?)),
+			}
+
+			i += 1;
+		}
+
+		Some(out)
+	}
+
+	fn transcribers(&mut self, trees: &[TokenTree]) -> Option<Vec<Transcriber>> {
+		let mut out = Vec::new();
+		let mut i = 0;
+
+		while i < trees.len() {
+			match &trees[i] {
+				TokenTree::Token(dollar) if dollar.inner == TokenInner::SymDollar => match trees.get(i + 1) {
+					Some(TokenTree::Token(Token { inner: TokenInner::WordIdentifier(name), .. })) => {
+						out.push(Transcriber::Var(name.clone()));
+						i += 1;
+					},
+					Some(TokenTree::Group(open, inner, _)) if open.inner == TokenInner::SymOParen => {
+						let inner = self.transcribers(inner)?;
+						let (sep, _, used) = self.repetition(&trees[i + 2..], &dollar.span)?;
+
+						out.push(Transcriber::Repeat(inner, sep));
+						i += 1 + used;
+					},
+					_ => {
+						self.report(dollar.span.clone(), MacroError::BadDefinition("a metavariable or repetition after `$`"));
+						return None;
+					},
+				},
+				TokenTree::Token(token) => out.push(Transcriber::Token(token.clone())),
+				TokenTree::Group(open, inner, close) => out.push(Transcriber::Group(open.clone(), self.transcribers(inner)?, close.clone())),
+			}
+
+			i += 1;
+		}
+
+		Some(out)
+	}
+
+	/// Parses the optional separator and the operator after `$( ... )`, giving back how many token trees they took up.
+	fn repetition(&mut self, rest: &[TokenTree], span: &Span) -> Option<(Option<Token>, RepeatOp, usize)> {
+		let op = |tree: Option<&TokenTree>| match tree {
+			Some(TokenTree::Token(token)) => match token.inner {
+				TokenInner::SymStar => Some(RepeatOp::ZeroOrMore),
+				TokenInner::SymPlus => Some(RepeatOp::OneOrMore),
+				TokenInner::SymTry => Some(RepeatOp::ZeroOrOne),
+				_ => None,
+			},
+			_ => None,
+		};
+
+		if let Some(op) = op(rest.first()) {
+			return Some((None, op, 1));
+		}
+
+		match (rest.first(), op(rest.get(1))) {
+			(Some(TokenTree::Token(sep)), Some(op)) if sep.inner != TokenInner::SymNewline => Some((Some(sep.clone()), op, 2)),
+			_ => {
+				self.report(span.clone(), MacroError::BadDefinition("`*`, `+` or `?` after a repetition"));
+				None
+			},
+		}
+	}
+
+	//--> Expansion <--
+
+	fn expand_tokens(&mut self, tokens: Vec<Token>, depth: usize) -> Vec<Token> {
+		let mut out = Vec::with_capacity(tokens.len());
+		let mut pos = 0;
+
+		while pos < tokens.len() && self.produced <= self.max_tokens {
+			let Some(name) = invocation(&tokens, pos) else {
+				out.push(tokens[pos].clone());
+				pos += 1;
+				continue;
+			};
+
+			let start = tokens[pos].span.start;
+			let mut end = pos + 2;
+
+			let Some(TokenTree::Group(_, args, close)) = parse_tree(&tokens, &mut end) else {
+				self.report(tokens[pos].span.clone(), MacroError::BadDefinition("balanced brackets around a macro's arguments"));
+				break;
+			};
+
+			let span = start..close.span.end;
+			pos = end;
+
+			if depth >= RECURSION_LIMIT {
+				self.report(span, MacroError::RecursionLimit(name));
+				continue;
+			}
+
+			match self.expand_invocation(&name, &args, &span) {
+				Ok(expanded) => {
+					self.produced += expanded.iter().filter(|token| !token.inner.is_trivia()).count();
+
+					if self.produced > self.max_tokens {
+						let limit = ParseError::LimitExceeded(Limit::Tokens(self.max_tokens));
+						self.errors.push(Error::new(false, self.file, Some(line_of(self.source, span.start)), Some(span.clone()), self.source.get(span.clone()), ErrorKind::Interpret(InterpretError::Parse(limit))));
+						break;
+					}
+
+					self.expansions.push(Expansion { name, span });
+					let expanded = self.expand_tokens(expanded, depth + 1);
+					out.extend(expanded);
+				},
+				Err(err) => self.report(span, err),
+			}
+		}
+
+		out
+	}
+
+	fn expand_invocation(&mut self, name: &str, args: &[TokenTree], span: &Span) -> Result<Vec<Token>, MacroError> {
+		let def = self.macros.get(name).ok_or_else(|| MacroError::Unknown(name.to_string()))?;
+
+		for rule in def.rules.iter() {
+			let mut bindings = Bindings::new();
+			let mut pos = 0;
+
+			if !match_seq(&rule.matcher, args, &mut pos, None, &mut bindings) { continue }
+			skip_newlines(args, &mut pos);
+			if pos != args.len() { continue }
+
+			let mut out = Vec::new();
+			transcribe(&rule.body, &bindings, span, &mut out)?;
+
+			hygiene(&mut out, self.next_id);
+			self.next_id += 1;
+
+			// The body's braces usually put it on lines of its own, but those new lines aren't part of it.
+			let mut tokens: Vec<Token> = out.into_iter().map(|(token, _)| token).collect();
+			while tokens.last().is_some_and(|t| t.inner == TokenInner::SymNewline) { tokens.pop(); }
+			let leading = tokens.iter().take_while(|t| t.inner == TokenInner::SymNewline).count();
+
+			return Ok(tokens.split_off(leading));
+		}
+
+		Err(MacroError::NoMatchingRule(name.to_string()))
+	}
+}
+
+/// Gives the next token which isn't a comment, and moves past it.
+fn next_token<'t>(tokens: &'t [Token], pos: &mut usize) -> Option<&'t Token> {
+	while tokens.get(*pos)?.inner == TokenInner::Comment { *pos += 1; }
+	*pos += 1;
+	tokens.get(*pos - 1)
+}
+
+/// Parses a single token tree, leaving out comments. Gives back nothing at the end of the tokens, or if the brackets don't match up.
+fn parse_tree(tokens: &[Token], pos: &mut usize) -> Option<TokenTree> {
+	let open = next_token(tokens, pos)?.clone();
+
+	let Some(expected) = closing_bracket(&open.inner) else {
+		return if is_closing_bracket(&open.inner) { None } else { Some(TokenTree::Token(open)) };
+	};
+
+	let mut inner = Vec::new();
+
+	loop {
+		let mut peek = *pos;
+		let token = next_token(tokens, &mut peek)?;
+
+		if token.inner == expected {
+			let close = token.clone();
+			*pos = peek;
+			return Some(TokenTree::Group(open, inner, close));
+		}
+
+		inner.push(parse_tree(tokens, pos)?);
+	}
+}
+
+fn closing_bracket(token: &TokenInner) -> Option<TokenInner> {
+	match token {
+		TokenInner::SymOParen => Some(TokenInner::SymCParen),
+		TokenInner::SymOBracket => Some(TokenInner::SymCBracket),
+		TokenInner::SymOBrace => Some(TokenInner::SymCBrace),
+		_ => None,
+	}
+}
+
+fn is_closing_bracket(token: &TokenInner) -> bool {
+	matches!(token, TokenInner::SymCParen | TokenInner::SymCBracket | TokenInner::SymCBrace)
+}
+
+fn fragment(kind: &str) -> Option<Fragment> {
+	match kind {
+		"expr" => Some(Fragment::Expr),
+		"ty" => Some(Fragment::Type),
+		"ident" => Some(Fragment::Ident),
+		"literal" => Some(Fragment::Literal),
+		"tt" => Some(Fragment::TokenTree),
+		_ => None,
+	}
+}
+
+/// Whether there's a macro invocation like `name!(...)` here. The `!` has to come straight after the name.
+fn invocation(tokens: &[Token], pos: usize) -> Option<String> {
+	let TokenInner::WordIdentifier(name) = &tokens[pos].inner else { return None };
+	let bang = tokens.get(pos + 1)?;
+	let open = tokens.get(pos + 2)?;
+
+	// Tokens which came out of a macro body all share the span of the invocation, so they count as being next to each other.
+	let adjacent = bang.span.start == tokens[pos].span.end || bang.span == tokens[pos].span;
+
+	if bang.inner == TokenInner::SymBang && adjacent && closing_bracket(&open.inner).is_some() {
+		Some(name.clone())
+	} else {
+		None
+	}
+}
+
+fn skip_newlines(trees: &[TokenTree], pos: &mut usize) {
+	while matches!(trees.get(*pos), Some(TokenTree::Token(token)) if token.inner == TokenInner::SymNewline) {
+		*pos += 1;
+	}
+}
+
+/// Backs `pos` up over any new lines at the end of what's been matched since `start`.
+fn trim_newlines(trees: &[TokenTree], start: usize, pos: &mut usize) {
+	while *pos > start && matches!(&trees[*pos - 1], TokenTree::Token(token) if token.inner == TokenInner::SymNewline) {
+		*pos -= 1;
+	}
+}
+
+fn tree_span(tree: &TokenTree) -> Span {
+	match tree {
+		TokenTree::Token(token) => token.span.clone(),
+		TokenTree::Group(open, _, close) => open.span.start..close.span.end,
+	}
+}
+
+fn is_literal(token: &TokenInner) -> bool {
+	matches!(token,
+		TokenInner::LitChar(_) | TokenInner::LitCharStr(_) | TokenInner::LitByte(_) | TokenInner::LitByteStr(_) |
+		TokenInner::LitNum(_) | TokenInner::LitFloat(_) | TokenInner::WordTrue | TokenInner::WordFalse
+	)
+}
+
+/// Matches a sequence of matchers against some input, starting at `pos`.
+/// `stop` is the token which comes after the sequence, if there is one, so that fragments like `expr` know where to stop.
+fn match_seq(matchers: &[Matcher], input: &[TokenTree], pos: &mut usize, stop: Option<&TokenInner>, bindings: &mut Bindings) -> bool {
+	for (i, matcher) in matchers.iter().enumerate() {
+		skip_newlines(input, pos);
+
+		let next_stop = match matchers.get(i + 1) {
+			Some(Matcher::Token(token)) => Some(token),
+			Some(_) => None,
+			None => stop,
+		};
+
+		match matcher {
+			Matcher::Token(expected) => match input.get(*pos) {
+				Some(TokenTree::Token(token)) if token.inner == *expected => *pos += 1,
+				_ => return false,
+			},
+			Matcher::Group(open, inner) => match input.get(*pos) {
+				Some(TokenTree::Group(token, trees, _)) if token.inner == *open => {
+					let mut inner_pos = 0;
+					if !match_seq(inner, trees, &mut inner_pos, None, bindings) { return false }
+
+					skip_newlines(trees, &mut inner_pos);
+					if inner_pos != trees.len() { return false }
+
+					*pos += 1;
+				},
+				_ => return false,
+			},
+			Matcher::Var(name, fragment) => {
+				let start = *pos;
+
+				match fragment {
+					Fragment::TokenTree if *pos < input.len() => *pos += 1,
+					Fragment::Ident if matches!(input.get(*pos), Some(TokenTree::Token(Token { inner: TokenInner::WordIdentifier(_), .. }))) => *pos += 1,
+					Fragment::Literal => {
+						if matches!(input.get(*pos), Some(TokenTree::Token(token)) if token.inner == TokenInner::SymDash) { *pos += 1; }
+
+						match input.get(*pos) {
+							Some(TokenTree::Token(token)) if is_literal(&token.inner) => *pos += 1,
+							_ => return false,
+						}
+					},
+					Fragment::Expr => {
+						// Closing brackets end their group anyway, so only commas and semicolons need looking out for.
+						while let Some(tree) = input.get(*pos) {
+							if let TokenTree::Token(token) = tree {
+								if Some(&token.inner) == next_stop || matches!(token.inner, TokenInner::SymComma | TokenInner::SymSemicolon) { break }
+							}
+
+							*pos += 1;
+						}
+
+						trim_newlines(input, start, pos);
+						if *pos == start { return false }
+					},
+					Fragment::Type => {
+						// Angle brackets aren't grouped by the lexer, so a comma inside `map<K, V>` doesn't end the type.
+						let mut angles = 0usize;
+
+						while let Some(tree) = input.get(*pos) {
+							if let TokenTree::Token(token) = tree {
+								match token.inner {
+									TokenInner::SymOAngle => angles += 1,
+									TokenInner::SymDOAngle => angles += 2,
+									TokenInner::SymCAngle if angles > 0 => angles -= 1,
+									TokenInner::SymDCAngle if angles > 1 => angles -= 2,
+									_ if angles > 0 => {},
+									ref inner if Some(inner) == next_stop => break,
+									TokenInner::SymComma | TokenInner::SymSemicolon | TokenInner::SymEqual | TokenInner::SymPipe => break,
+									_ => {},
+								}
+							}
+
+							*pos += 1;
+						}
+
+						trim_newlines(input, start, pos);
+						if *pos == start { return false }
+					},
+					_ => return false,
+				}
+
+				let trees = &input[start..*pos];
+
+				// An expression gets put in brackets, so `$e * 2` given `1 < 2` still multiplies the whole thing.
+				if *fragment == Fragment::Expr && trees.len() > 1 {
+					let bracket = |inner, slice: &str, span: &Span| Token { inner, span: span.clone(), slice: slice.to_string() };
+					let (first, last) = (tree_span(&trees[0]), tree_span(&trees[trees.len() - 1]));
+
+					bindings.insert(name.clone(), Binding::One(vec![TokenTree::Group(
+						bracket(TokenInner::SymOParen, "(", &first),
+						trees.to_vec(),
+						bracket(TokenInner::SymCParen, ")", &last),
+					)]));
+					continue;
+				}
+
+				bindings.insert(name.clone(), Binding::One(trees.to_vec()));
+			},
+			Matcher::Repeat(inner, sep, op) => {
+				let inner_stop = sep.as_ref().or(next_stop);
+				let mut iterations: Vec<Bindings> = Vec::new();
+
+				loop {
+					if *op == RepeatOp::ZeroOrOne && iterations.len() == 1 { break }
+
+					let mut attempt = *pos;
+
+					if let (Some(sep), false) = (sep, iterations.is_empty()) {
+						skip_newlines(input, &mut attempt);
+
+						match input.get(attempt) {
+							Some(TokenTree::Token(token)) if token.inner == *sep => attempt += 1,
+							_ => break,
+						}
+					}
+
+					let mut iteration = Bindings::new();
+					if !match_seq(inner, input, &mut attempt, inner_stop, &mut iteration) || attempt == *pos { break }
+
+					*pos = attempt;
+					iterations.push(iteration);
+				}
+
+				if *op == RepeatOp::OneOrMore && iterations.is_empty() { return false }
+
+				let mut names = Vec::new();
+				matcher_vars(inner, &mut names);
+
+				for name in names {
+					let each = iterations.iter()
+						.map(|iteration| iteration.get(&name).cloned().unwrap_or(Binding::Many(Vec::new())))
+						.collect();
+
+					bindings.insert(name, Binding::Many(each));
+				}
+			},
+		}
+	}
+
+	true
+}
+
+fn matcher_vars(matchers: &[Matcher], names: &mut Vec<String>) {
+	for matcher in matchers {
+		match matcher {
+			Matcher::Var(name, _) => names.push(name.clone()),
+			Matcher::Group(_, inner) | Matcher::Repeat(inner, ..) => matcher_vars(inner, names),
+			Matcher::Token(_) => {},
+		}
+	}
+}
+
+fn transcriber_vars(transcribers: &[Transcriber], names: &mut Vec<String>) {
+	for transcriber in transcribers {
+		match transcriber {
+			Transcriber::Var(name) => names.push(name.clone()),
+			Transcriber::Group(_, inner, _) | Transcriber::Repeat(inner, _) => transcriber_vars(inner, names),
+			Transcriber::Token(_) => {},
+		}
+	}
+}
+
+/// Writes out a macro body with its metavariables filled in.
+/// Each token comes with whether it came from the body itself, rather than from what the macro was given.
+fn transcribe(body: &[Transcriber], bindings: &Bindings, span: &Span, out: &mut Vec<(Token, bool)>) -> Result<(), MacroError> {
+	let from_body = |token: &Token| (Token { span: span.clone(), ..token.clone() }, true);
+
+	for transcriber in body {
+		match transcriber {
+			Transcriber::Token(token) => out.push(from_body(token)),
+			Transcriber::Var(name) => match bindings.get(name) {
+				Some(Binding::One(trees)) => flatten(trees, out),
+				_ => return Err(MacroError::BadMetavariable(name.clone())),
+			},
+			Transcriber::Group(open, inner, close) => {
+				out.push(from_body(open));
+				transcribe(inner, bindings, span, out)?;
+				out.push(from_body(close));
+			},
+			Transcriber::Repeat(inner, sep) => {
+				let mut names = Vec::new();
+				transcriber_vars(inner, &mut names);
+
+				let repeated: Vec<(&String, &Vec<Binding>)> = names.iter()
+					.filter_map(|name| match bindings.get(name) {
+						Some(Binding::Many(each)) => Some((name, each)),
+						_ => None,
+					})
+					.collect();
+
+				let Some(count) = repeated.first().map(|(_, each)| each.len()) else {
+					return Err(MacroError::BadMetavariable(names.first().cloned().unwrap_or_default()));
+				};
+
+				if let Some((name, _)) = repeated.iter().find(|(_, each)| each.len() != count) {
+					return Err(MacroError::BadMetavariable(name.to_string()));
+				}
+
+				for i in 0..count {
+					let mut inner_bindings = bindings.clone();
+					for (name, each) in repeated.iter() {
+						inner_bindings.insert(name.to_string(), each[i].clone());
+					}
+
+					if i > 0 {
+						if let Some(sep) = sep { out.push(from_body(sep)); }
+					}
+
+					transcribe(inner, &inner_bindings, span, out)?;
+				}
+			},
+		}
+	}
+
+	Ok(())
+}
+
+fn flatten(trees: &[TokenTree], out: &mut Vec<(Token, bool)>) {
+	for tree in trees {
+		match tree {
+			TokenTree::Token(token) => out.push((token.clone(), false)),
+			TokenTree::Group(open, inner, close) => {
+				out.push((open.clone(), false));
+				flatten(inner, out);
+				out.push((close.clone(), false));
+			},
+		}
+	}
+}
+
+/// Renames the variables a macro body binds, so that they can't be mixed up with the ones at the call site.
+fn hygiene(tokens: &mut [(Token, bool)], id: usize) {
+	let mut bound = HashSet::new();
+
+	for (i, (token, from_body)) in tokens.iter().enumerate() {
+		let TokenInner::WordIdentifier(name) = &token.inner else { continue };
+		if !from_body { continue }
+
+		let prev = i.checked_sub(1).map(|i| &tokens[i].0.inner);
+		let next = tokens.get(i + 1).map(|(token, _)| &token.inner);
+
+		let binds = matches!(prev, Some(TokenInner::WordMut | TokenInner::WordFor))
+			|| next == Some(&TokenInner::SymWalrus)
+			|| (next == Some(&TokenInner::SymColon) && matches!(prev, None | Some(TokenInner::SymNewline | TokenInner::SymSemicolon)));
+
+		if binds { bound.insert(name.clone()); }
+	}
+
+	for i in 0..tokens.len() {
+		let after_dot = i.checked_sub(1).is_some_and(|i| tokens[i].0.inner == TokenInner::SymDot);
+		let (token, from_body) = &mut tokens[i];
+
+		let TokenInner::WordIdentifier(name) = &token.inner else { continue };
+		if !*from_body || after_dot || !bound.contains(name) { continue }
+
+		let renamed = format!("{}${}", name, id);
+		token.inner = TokenInner::WordIdentifier(renamed.clone());
+		token.slice = renamed;
+	}
+}
+
+impl fmt::Display for MacroError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MacroError::BadDefinition(what) => write!(f, "expected {}", what),
+			MacroError::Unknown(name) => write!(f, "there's no macro called `{}!`", name),
+			MacroError::NoMatchingRule(name) => write!(f, "no rule of `{}!` matches these arguments", name),
+			MacroError::BadMetavariable(name) => write!(f, "`${}` isn't bound here", name),
+			MacroError::RecursionLimit(name) => write!(f, "`{}!` expanded into itself too many times", name),
+			MacroError::Public(name) => write!(f, "`{}!` can't be `pub`, since macros can only be used in the file they're defined in", name),
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compiler::{
+		Options,
+		parse_source,
+	};
+
+	fn core(source: &str) -> String {
+		let (ast, _) = parse_source(None, source, &Options::default()).unwrap();
+		ast.to_string()
+	}
+
+	fn errors(source: &str) -> ErrorList {
+		parse_source(None, source, &Options::default()).err().unwrap()
+	}
+
+	#[test]
+	fn prelude_assert() {
+		assert_eq!(
+			core("func f(x: nat) do\n\tassert!(x > 0)\nend\n"),
+			"func f(x: nat) do\n\tif !(x > 0) then\n\t\tstd::error::Exception::throw(std::error::AssertionFailed::new(\"assertion failed\"))\n\tend\nend\n"
+		);
+	}
+
+	#[test]
+	fn prelude_assert_with_a_message() {
+		assert_eq!(
+			core("func f(n: nat) do\n\tassert!(n == 2, \"n isn't two\")\nend\n"),
+			"func f(n: nat) do\n\tif !(n == 2) then\n\t\tstd::error::Exception::throw(std::error::AssertionFailed::new(\"n isn't two\"))\n\tend\nend\n"
+		);
+	}
+
+	#[test]
+	fn expressions_keep_their_precedence() {
+		let source = "macro twice is\n\t($e:expr) then { $e * 2 }\nend\n\nfunc f() do\n\ttwice!(1 < 2)\nend\n";
+		assert_eq!(core(source), "func f() do\n\t(1 < 2) * 2\nend\n");
+	}
+
+	#[test]
+	fn types_can_have_commas_in_them() {
+		let source = "macro typed is\n\t($t:ty, $e:expr) then { x: $t = $e }\nend\n\nfunc f() do\n\ttyped!(map<str, nat>, other)\nend\n";
+		assert_eq!(core(source), "func f() do\n\tx$0: map<str, nat> = other\nend\n");
+	}
+
+	#[test]
+	fn rules_are_tried_in_order() {
+		let source = "macro pick is\n\t() then { 0 }\n\t($x:literal) then { 1 }\n\t($x:expr) then { 2 }\nend\n\nfunc f() do\n\tpick!()\n\tpick!(-5)\n\tpick!(a + b)\nend\n";
+		assert_eq!(core(source), "func f() do\n\t0\n\t1\n\t2\nend\n");
+	}
+
+	#[test]
+	fn repetitions() {
+		let source = "macro sum is\n\t($($x:expr),+) then { 0 $(+ $x)* }\nend\n\nfunc f() do\n\tsum!(a, b * 2, c)\nend\n";
+		assert_eq!(core(source), "func f() do\n\t((0 + a) + (b * 2)) + c\nend\n");
+	}
+
+	#[test]
+	fn bindings_are_hygienic() {
+		let source = "macro double is\n\t($e:expr) then {\n\t\ttmp := $e\n\t\ttmp + tmp\n\t}\nend\n\nfunc f(tmp: nat) do\n\tdouble!(tmp)\nend\n";
+		assert_eq!(core(source), "func f(tmp: nat) do\n\ttmp$0 := tmp\n\ttmp$0 + tmp$0\nend\n");
+	}
+
+	#[test]
+	fn errors_are_traced_through_expansions() {
+		let source = "macro broken is\n\t() then { 1 + * 2 }\nend\n\nfunc f() do\n\tbroken!()\nend\n";
+		let errors = errors(source);
+
+		assert_eq!(errors[0].notes(), ["in expansion of `broken!` on line 6"]);
+	}
+
+	#[test]
+	fn unknown_and_unmatched_macros() {
+		let errors = errors("macro one is\n\t($x:ident) then { $x }\nend\n\nfunc f() do\n\tone!(1)\n\ttwo!(x)\nend\n");
+
+		assert!(matches!(errors[0].kind(), ErrorKind::Interpret(InterpretError::Macro(MacroError::NoMatchingRule(name))) if name == "one"));
+		assert!(matches!(errors[1].kind(), ErrorKind::Interpret(InterpretError::Macro(MacroError::Unknown(name))) if name == "two"));
+	}
+
+	#[test]
+	fn macros_cant_be_pub() {
+		let errors = errors("pub macro twice is\n\t($e:expr) then { $e + $e }\nend\n");
+		assert!(matches!(&errors[..], [e] if matches!(e.kind(), ErrorKind::Interpret(InterpretError::Macro(MacroError::Public(name))) if name == "twice")));
+	}
+
+	#[test]
+	fn runaway_recursion_stops() {
+		let errors = errors("macro forever is\n\t() then { forever!() }\nend\n\nfunc f() do\n\tforever!()\nend\n");
+		assert!(matches!(errors[0].kind(), ErrorKind::Interpret(InterpretError::Macro(MacroError::RecursionLimit(_)))));
+	}
+
+	#[test]
+	fn doubling_macros_hit_the_token_limit() {
+		// Each of these makes twice as much code as the one before it, so expanding the last one in full would never finish.
+		let mut source = String::from("macro m0 is
+	($x:expr) then { $x }
+end
+
+");
+		for i in 1..=40 {
+			source += &format!("macro m{} is
+	($x:expr) then {{ m{}!($x) + m{}!($x) }}
+end
+
+", i, i - 1, i - 1);
+		}
+		source += "func f() do
+	m40!(1)
+end
+";
+
+		let options = Options { limits: ParseLimits { max_tokens: 10_000, ..Default::default() }, ..Default::default() };
+		let errors = parse_source(None, &source, &options).err().unwrap();
+
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].line(), Some(166));
+		assert!(matches!(errors[0].kind(), ErrorKind::Interpret(InterpretError::Parse(ParseError::LimitExceeded(Limit::Tokens(10_000))))));
+	}
+}
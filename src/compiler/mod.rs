@@ -1,56 +1,362 @@
 //--> Imports <--
 
+mod attributes;
+mod cfg;
+mod consteval;
+mod desugar;
+mod format;
+mod labels;
 mod lexer;
+mod lints;
+mod macros;
+mod modules;
+mod mutability;
 mod parser;
+mod resolve;
+mod typeck;
 
 use std::{
-	collections::HashMap,
-	path::Path,
+	fmt,
+	fs,
+	io::{
+		ErrorKind as IOError,
+		Read,
+	},
+	path::{
+		Path,
+		PathBuf,
+	},
 };
 
 use lexer::{
 	TokenStream,
 };
 
+use parser::astgen::AbstractSyntaxTree;
+
+pub use attributes::AttributeError;
+pub use consteval::{
+	ConstError,
+	ConstHint,
+};
+pub use labels::LabelError;
 pub use lexer::LexError;
-pub use parser::ParseError;
+pub use lints::{
+	LintError,
+	LintLevel,
+};
+pub use macros::MacroError;
+pub use modules::ModuleError;
+pub use mutability::MutabilityError;
+pub use parser::{
+	Limit,
+	ParseError,
+	ParseLimits,
+};
+pub use resolve::{
+	ExportKind,
+	ResolveError,
+};
+pub use typeck::{
+	TypeError,
+	TypeHint,
+};
+
+use crate::{
+	Error,
+	ErrorKind,
+	ErrorList,
+};
+
+//--> Structs <--
+
+/// Settings for how source code gets interpreted. Programs embedding the runtime can use these to tighten things up for code they don't trust.
+#[derive(Clone, Debug)]
+pub struct Options {
+	pub limits: ParseLimits,
+	/// Where to load the standard library's modules from, rather than using the copy built into the runtime.
+	pub std_path: Option<PathBuf>,
+	/// How many steps working out a single constant can take, counting one for each expression evaluated.
+	pub max_const_steps: usize,
+	/// How many calls deep working out a single constant can go. Each call takes up some of the compiler's own stack,
+	/// so this should be kept well short of running out of it.
+	pub max_const_calls: usize,
+	/// Lint levels for the whole package, like the `-W` and `-D` flags set, where later ones win over earlier ones.
+	/// Each name can be a lint, or a group of them like `unused`.
+	pub lints: Vec<(String, LintLevel)>,
+	/// The flags `@cfg(...)` conditions are checked against, like the `--cfg` flag sets.
+	/// A flag can be a plain name like `gpu`, or have a value like `os = "linux"`, and the same name can be given more than once.
+	/// By default, `os` and `family` are set to the ones Rust reports for the host, like `linux` and `unix`.
+	pub cfg: Vec<(String, Option<String>)>,
+}
 
-use crate::Error;
+/// Everything checking a package found out about it, which compiling it goes on to use.
+#[derive(Debug)]
+pub(crate) struct Package {
+	pub types: typeck::Types,
+	pub consts: consteval::ConstPool,
+	pub exports: Vec<(String, ExportKind)>,
+}
 
 //--> Enums <--
 
 #[derive(Clone, Debug)]
 pub enum InterpretError {
 	Lex(LexError),
+	Macro(MacroError),
 	Parse(ParseError),
+	Module(ModuleError),
+	Resolve(ResolveError),
+	Type(TypeError),
+	Attribute(AttributeError),
+	Label(LabelError),
+	Mutability(MutabilityError),
+	Const(ConstError),
+	Lint(LintError),
 }
 
 //--> Functions <--
 
-pub fn compile(paths: Vec<&Path>) {
+pub fn compile(paths: Vec<&Path>, options: &Options) -> Result<ErrorList, ErrorList> {
 	// We need at least one path!
-	if paths.is_empty() {}
-
-	// At least one path needs to be a file, so we don't go rooting around in directories until we absolutely need to.
-	if !paths.iter().any(|p| p.is_file()) {}
+	if paths.is_empty() {
+		return Err(vec![
+			Error::new(false, None, None, None, None, ErrorKind::IO(IOError::NotFound))
+		])
+	}
 
 	let mut errs: Vec<Error> = Vec::new();
 
-	let mut tok_files: HashMap<&Path, TokenStream> = HashMap::new();
-
-	for file_path in paths.iter().filter(|p| p.is_file()) {
-		match TokenStream::lex_file(file_path) {
-			Ok(t) => if !t.0.is_empty() {
-				tok_files.insert(file_path, t);
-			},
-			Err(e) => {
-				for err in e {
-					errs.push(err);
-				}
-			}
+	// Each path is the root file of a package, and the modules it needs get found from there.
+	for file_path in paths.iter() {
+		if !file_path.is_file() {
+			errs.push(Error::new(false, Some(file_path), None, None, None, ErrorKind::IO(IOError::NotFound)));
+			continue;
+		}
+
+		match check_package(file_path, options) {
+			Ok((_, warnings)) => errs.extend(warnings),
+			Err(e) => errs.extend(e),
+		}
+	}
+
+	if errs.iter().all(|e| e.is_warning()) {
+		Ok(errs)
+	} else {
+		Err(errs)
+	}
+}
+
+/// Works out the type of every variable in a package, for editors to show when they get hovered over.
+/// The standard library gets checked too, but doesn't get any hints.
+pub fn type_hints(path: &Path, options: &Options) -> Result<Vec<TypeHint>, ErrorList> {
+	check_package(path, options).map(|(package, _)| package.types.hints)
+}
+
+/// Works out the value of every constant in a package, for editors to show when they get hovered over.
+/// The standard library's constants get worked out too, but don't get any hints.
+pub fn const_values(path: &Path, options: &Options) -> Result<Vec<ConstHint>, ErrorList> {
+	check_package(path, options).map(|(package, _)| package.consts.hints)
+}
+
+/// Lists the full path of everything in a package marked `pub`, which is what programs embedding it are able to use.
+/// Anything only visible inside of the package, even with `prt`, gets left out.
+pub fn exports(path: &Path, options: &Options) -> Result<Vec<String>, ErrorList> {
+	check_package(path, options).map(|(package, _)| package.exports.into_iter().map(|(name, _)| name).collect())
+}
+
+/// Parses and desugars a file, and returns the resulting core syntax tree written out as source code.
+pub fn dump_core(path: &Path, options: &Options) -> Result<String, ErrorList> {
+	let source = read_source(path, &options.limits)?;
+
+	let (ast, _) = parse_source(Some(path), &source, options)?;
+
+	Ok(ast.to_string())
+}
+
+/// Formats a file in the canonical style, giving back the formatted source code.
+pub fn format(path: &Path, options: &Options) -> Result<String, ErrorList> {
+	let source = read_source(path, &options.limits)?;
+
+	format::format_source(Some(path), &source, &options.limits)
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Options {
+			limits: ParseLimits::default(),
+			std_path: None,
+			max_const_steps: 1_000_000,
+			max_const_calls: 64,
+			lints: Vec::new(),
+			cfg: vec![
+				("os".into(), Some(std::env::consts::OS.into())),
+				("family".into(), Some(std::env::consts::FAMILY.into())),
+			],
+		}
+	}
+}
+
+/// Loads a package from its root file, resolves the names in it, checks its types, checks what it changes, lints it and works out its constants.
+/// Each stage only goes ahead if the one before it didn't find any errors, so one mistake doesn't get reported over and over.
+pub(crate) fn check_package(path: &Path, options: &Options) -> Result<(Package, ErrorList), ErrorList> {
+	let (mut tree, warnings) = modules::load(path, options)?;
+
+	// Lint levels apply to the warnings from every stage, even when a later one found errors.
+	match check_tree(&mut tree, warnings, options) {
+		Ok((package, warnings)) => {
+			let warnings = lints::apply_levels(&tree, warnings, options);
+			if warnings.iter().all(|e| e.is_warning()) { Ok((package, warnings)) } else { Err(warnings) }
+		},
+		Err(errors) => Err(lints::apply_levels(&tree, errors, options)),
+	}
+}
+
+/// Checks a module tree which has been loaded, along with the warnings from loading it.
+fn check_tree(tree: &mut modules::ModuleTree, mut warnings: ErrorList, options: &Options) -> Result<(Package, ErrorList), ErrorList> {
+	let resolutions = match resolve::resolve(tree) {
+		Ok((resolutions, more)) => {
+			warnings.extend(more);
+			resolutions
+		},
+		Err(errors) => return Err(warnings.into_iter().chain(errors).collect()),
+	};
+
+	let types = match typeck::check(tree, &resolutions) {
+		Ok((types, more)) => {
+			warnings.extend(more);
+			types
+		},
+		Err(errors) => return Err(warnings.into_iter().chain(errors).collect()),
+	};
+
+	let errors = mutability::check(tree, &resolutions, &types.mutating);
+	if !errors.is_empty() {
+		return Err(warnings.into_iter().chain(errors).collect());
+	}
+
+	warnings.extend(lints::check(tree, &resolutions, &types));
+
+	match consteval::evaluate(tree, &resolutions, &types, options) {
+		Ok(consts) => Ok((Package { types, consts, exports: resolutions.exports }, warnings)),
+		Err(errors) => Err(warnings.into_iter().chain(errors).collect()),
+	}
+}
+
+/// Lexes, expands macros in, parses, checks attributes and labels in, removes whatever `@cfg(...)` turns off from, and desugars some source code.
+pub(crate) fn parse_source(path: Option<&Path>, source: &str, options: &Options) -> Result<(AbstractSyntaxTree, ErrorList), ErrorList> {
+	let tokens = TokenStream::lex(path, source, &options.limits)?;
+
+	// Macros work on raw tokens, so they need to be kept within the limits too. The parser checks them again after expansion.
+	parser::check_limits(path, source, &tokens, &options.limits)?;
+	let (tokens, expansions) = macros::expand(path, source, tokens, &options.limits)?;
+
+	let (mut ast, mut warnings) = parser::tokens_to_ast(path, source, tokens, &options.limits)
+		.map_err(|errors| macros::trace(errors, source, &expansions))?;
+
+	// Code which `@cfg` turns off only has to have conditions which make sense, and nothing else in it gets checked.
+	warnings.extend(attributes::check_cfg(path, source, &ast));
+
+	if warnings.iter().all(|e| e.is_warning()) {
+		cfg::strip(&mut ast, &options.cfg);
+		warnings.extend(attributes::check(path, source, &ast));
+		warnings.extend(labels::check(path, source, &ast));
+	}
+
+	let warnings = macros::trace(warnings, source, &expansions);
+
+	if !warnings.iter().all(|e| e.is_warning()) {
+		return Err(warnings);
+	}
+
+	desugar::desugar(&mut ast);
+
+	Ok((ast, warnings))
+}
+
+/// Reads a source file, without reading any more of it than the size limit allows.
+pub(crate) fn read_source(path: &Path, limits: &ParseLimits) -> Result<String, ErrorList> {
+	let io_error = |kind: IOError| vec![Error::new(false, Some(path), None, None, None, ErrorKind::IO(kind))];
+	let mut bytes = Vec::new();
+
+	// One byte more than the limit is enough to tell that it's been gone past.
+	fs::File::open(path)
+		.and_then(|file| file.take(limits.max_source_bytes as u64 + 1).read_to_end(&mut bytes))
+		.map_err(|e| io_error(e.kind()))?;
+
+	if bytes.len() > limits.max_source_bytes {
+		let limit = ParseError::LimitExceeded(Limit::SourceBytes(limits.max_source_bytes));
+		return Err(vec![Error::new(false, Some(path), None, None, None, ErrorKind::Interpret(InterpretError::Parse(limit)))]);
+	}
+
+	String::from_utf8(bytes).map_err(|_| io_error(IOError::InvalidData))
+}
+
+/// Works out which line a character offset into some source code is on, counting from 1.
+pub(crate) fn line_of(source: &str, offset: usize) -> usize {
+	source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+impl fmt::Display for InterpretError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			InterpretError::Lex(e) => write!(f, "{}", e),
+			InterpretError::Macro(e) => write!(f, "{}", e),
+			InterpretError::Parse(e) => write!(f, "{}", e),
+			InterpretError::Module(e) => write!(f, "{}", e),
+			InterpretError::Resolve(e) => write!(f, "{}", e),
+			InterpretError::Type(e) => write!(f, "{}", e),
+			InterpretError::Attribute(e) => write!(f, "{}", e),
+			InterpretError::Label(e) => write!(f, "{}", e),
+			InterpretError::Mutability(e) => write!(f, "{}", e),
+			InterpretError::Const(e) => write!(f, "{}", e),
+			InterpretError::Lint(e) => write!(f, "{}", e),
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+/// A package written out to a fresh temporary directory, for tests which need more than a single file.
+/// The directory gets removed again when the package is dropped.
+#[cfg(test)]
+pub(crate) struct TestPackage {
+	dir: PathBuf,
+	root: PathBuf,
+}
+
+#[cfg(test)]
+impl TestPackage {
+	/// Writes out each file, relative to the package's directory. The first one is the package's root.
+	/// `name` is only there to make it easier to tell which test a leftover directory came from.
+	pub(crate) fn new(name: &str, files: &[(&str, &str)]) -> TestPackage {
+		static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+		let id = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+		let dir = std::env::temp_dir().join(format!("rouge-{}-{}-{}", name, std::process::id(), id));
+		let _ = fs::remove_dir_all(&dir);
+
+		for (path, source) in files.iter() {
+			let path = dir.join(path);
+			fs::create_dir_all(path.parent().unwrap()).unwrap();
+			fs::write(path, source).unwrap();
 		}
+
+		TestPackage { root: dir.join(files[0].0), dir }
+	}
+
+	pub(crate) fn root(&self) -> &Path {
+		&self.root
 	}
 
-	// If we have any errors, return early.
-	if !errs.is_empty() {}
-}
\ No newline at end of file
+	/// Checks the package, the same way compiling it would.
+	pub(crate) fn check(&self, options: &Options) -> Result<(Package, ErrorList), ErrorList> {
+		check_package(&self.root, options)
+	}
+}
+
+#[cfg(test)]
+impl Drop for TestPackage {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.dir);
+	}
+}
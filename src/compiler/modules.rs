@@ -0,0 +1,778 @@
+//! Builds up the tree of modules a program is made of, starting from its root file.
+//!
+//! There are no module declarations: a module's submodules are whichever files its paths lead to.
+//! A module's submodules live in a directory named after it, so `pkg::error::option` can be either `error/option.ro` or `error/option/option.ro`.
+//! The root file is the exception, since its submodules sit right next to it.
+//!
+//! Paths can start from the current module (`self`), its parent (`super`), the root of its package (`pkg`), or the standard library (`std`).
+//! The standard library is built into the runtime, unless the options say to load it from somewhere else.
+//! Modules can import each other both ways. Imports which only ever lead back to themselves get caught when names are resolved.
+
+//--> Imports <--
+
+use crate::{
+	Error,
+	ErrorKind,
+	ErrorList,
+	compiler::{
+		InterpretError,
+		Options,
+		line_of,
+		parse_source,
+		read_source,
+		parser::astgen::{
+			AbstractSyntaxTree,
+			Block,
+			Condition,
+			Expr,
+			ExprKind,
+			GenericParam,
+			Ident,
+			Item,
+			ItemKind,
+			Path,
+			Pattern,
+			PatternKind,
+			Stmt,
+			StmtKind,
+			Type,
+			TypeBody,
+			TypeKind,
+			UseKind,
+			UseTree,
+			WherePredicate,
+		},
+	},
+};
+
+use logos::Span;
+
+use std::{
+	collections::HashMap,
+	fmt,
+	path::{
+		Path as FilePath,
+		PathBuf,
+	},
+};
+
+//--> Type Aliases <--
+
+pub(crate) type ModuleId = usize;
+
+//--> Structs <--
+
+/// Every module in a program, along with the standard library modules it uses.
+#[derive(Debug)]
+pub(crate) struct ModuleTree {
+	pub modules: Vec<Module>,
+	pub root: ModuleId,
+	/// The root of the standard library.
+	pub std: Option<ModuleId>,
+	/// `std::prelude`, whose names are in scope everywhere.
+	pub prelude: Option<ModuleId>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Module {
+	/// The full name of the module, like `["std", "error", "option"]`.
+	pub path: Vec<String>,
+	/// Where the module was loaded from. Only the root of the standard library doesn't have a file.
+	pub file: Option<PathBuf>,
+	pub source: String,
+	pub ast: AbstractSyntaxTree,
+	pub parent: Option<ModuleId>,
+	pub children: HashMap<String, ModuleId>,
+	/// Where its parent first names it. Submodules are found by their file rather than declared, so this stands in for a declaration.
+	pub named_at: Option<Span>,
+	/// The directory the module's submodules live in.
+	dir: PathBuf,
+}
+
+struct Loader<'a> {
+	options: &'a Options,
+	tree: ModuleTree,
+	errors: ErrorList,
+}
+
+/// Every path in a module which could lead to another one.
+#[derive(Default)]
+struct PathCollector {
+	paths: Vec<Path>,
+}
+
+//--> Enums <--
+
+#[derive(Clone, Debug)]
+pub enum ModuleError {
+	/// A path led to a module which doesn't have a file.
+	NotFound(String),
+	/// `super` was used in the root module of a package.
+	NoParent(String),
+}
+
+//--> Functions <--
+
+/// The files of the standard library, for when it isn't loaded from a directory. Each one is named by where it is inside of `std/`.
+const STD_FILES: &[(&str, &str)] = &[
+	("cmp/cmp.ro", include_str!("../../std/cmp/cmp.ro")),
+	("collections/collections.ro", include_str!("../../std/collections/collections.ro")),
+	("data/data.ro", include_str!("../../std/data/data.ro")),
+	("error/error.ro", include_str!("../../std/error/error.ro")),
+	("error/option.ro", include_str!("../../std/error/option.ro")),
+	("error/result.ro", include_str!("../../std/error/result.ro")),
+	("fs/fs.ro", include_str!("../../std/fs/fs.ro")),
+	("io/io.ro", include_str!("../../std/io/io.ro")),
+	("io/read.ro", include_str!("../../std/io/read.ro")),
+	("io/seek.ro", include_str!("../../std/io/seek.ro")),
+	("io/write.ro", include_str!("../../std/io/write.ro")),
+	("iter/iter.ro", include_str!("../../std/iter/iter.ro")),
+	("iter/mut_iter.ro", include_str!("../../std/iter/mut_iter.ro")),
+	("macros/macros.ro", include_str!("../../std/macros/macros.ro")),
+	("net/net.ro", include_str!("../../std/net/net.ro")),
+	("num/num.ro", include_str!("../../std/num/num.ro")),
+	("ops/ops.ro", include_str!("../../std/ops/ops.ro")),
+	("prelude/prelude.ro", include_str!("../../std/prelude/prelude.ro")),
+	("text/text.ro", include_str!("../../std/text/text.ro")),
+	("time/time.ro", include_str!("../../std/time/time.ro")),
+];
+
+/// Loads the module tree starting from a root file, giving back any warnings along with it.
+pub(crate) fn load(root: &FilePath, options: &Options) -> Result<(ModuleTree, ErrorList), ErrorList> {
+	let mut loader = Loader {
+		options,
+		tree: ModuleTree { modules: Vec::new(), root: 0, std: None, prelude: None },
+		errors: ErrorList::new(),
+	};
+
+	let dir = root.parent().map(|dir| dir.to_path_buf()).unwrap_or_default();
+	loader.tree.root = loader.add(vec!["pkg".into()], Some(root.to_path_buf()), dir, None);
+	loader.load_prelude();
+
+	// Loading a module can add more modules onto the end, which get looked at in turn.
+	let mut next = 0;
+	while next < loader.tree.modules.len() {
+		loader.resolve_paths(next);
+		next += 1;
+	}
+
+	if loader.errors.iter().all(|e| e.is_warning()) {
+		Ok((loader.tree, loader.errors))
+	} else {
+		Err(loader.errors)
+	}
+}
+
+impl Loader<'_> {
+	/// Reads and parses a module, adding it to the tree even if that fails so that it isn't tried again.
+	fn add(&mut self, path: Vec<String>, file: Option<PathBuf>, dir: PathBuf, parent: Option<ModuleId>) -> ModuleId {
+		let mut source = String::new();
+		let mut ast = AbstractSyntaxTree::default();
+
+		if let Some(file) = &file {
+			match self.built_in(&path, file) {
+				Some(text) => source = text.to_string(),
+				None => match read_source(file, &self.options.limits) {
+					Ok(text) => source = text,
+					Err(errors) => self.errors.extend(errors),
+				},
+			}
+
+			// Empty files are fine, they just don't have anything in them for us to look at.
+			if !source.trim().is_empty() {
+				match parse_source(Some(file), &source, self.options) {
+					Ok((parsed, warnings)) => {
+						ast = parsed;
+						self.errors.extend(warnings);
+					},
+					Err(errors) => self.errors.extend(errors),
+				}
+			}
+		}
+
+		let id = self.tree.modules.len();
+		let name = path.last().cloned();
+
+		self.tree.modules.push(Module { path, file, source, ast, parent, children: HashMap::new(), named_at: None, dir });
+
+		if let (Some(parent), Some(name)) = (parent, name) {
+			self.tree.modules[parent].children.insert(name, id);
+		}
+
+		id
+	}
+
+	fn report(&mut self, module: ModuleId, span: Span, err: ModuleError) -> &mut Error {
+		let module = &self.tree.modules[module];
+		let line = line_of(&module.source, span.start);
+		let slice = module.source.get(span.clone());
+
+		self.errors.push(Error::new(false, module.file.as_deref(), Some(line), Some(span), slice, ErrorKind::Interpret(InterpretError::Module(err))));
+		self.errors.last_mut().unwrap()
+	}
+
+	/// Follows every `use` in a module, and every path in its code which starts at a module.
+	fn resolve_paths(&mut self, module: ModuleId) {
+		let mut uses: Vec<UseTree> = Vec::new();
+		let mut collector = PathCollector::default();
+
+		for item in self.tree.modules[module].ast.items.iter() {
+			match &item.kind {
+				ItemKind::Use(tree) => uses.push(tree.clone()),
+				_ => collector.item(item),
+			}
+		}
+
+		for tree in uses.iter() {
+			self.resolve_use(module, module, tree, true);
+		}
+
+		for path in collector.paths.iter() {
+			let segments: Vec<Ident> = path.segments.iter().map(|segment| segment.name.clone()).collect();
+			self.walk(module, module, &segments, &path.span, true);
+		}
+	}
+
+	fn resolve_use(&mut self, module: ModuleId, from: ModuleId, tree: &UseTree, first: bool) {
+		let Some(found) = self.walk(module, from, &tree.prefix, &tree.span, first) else { return };
+
+		if let UseKind::List(trees) = &tree.kind {
+			for tree in trees.iter() {
+				self.resolve_use(module, found, tree, false);
+			}
+		}
+	}
+
+	/// Follows a path from a module for as long as it leads to other modules, loading them as it goes.
+	/// Gives back the last module the path reached, or nothing if it couldn't be followed.
+	fn walk(&mut self, module: ModuleId, from: ModuleId, segments: &[Ident], span: &Span, first: bool) -> Option<ModuleId> {
+		let mut current = from;
+
+		// Paths which don't start with one of these start from the current module.
+		if let Some(head) = segments.first().filter(|_| first) {
+			match head.name.as_str() {
+				"self" => {},
+				// `super::super` goes up two modules, and so on.
+				"super" => {
+					let supers = segments.iter().take_while(|segment| segment.name == "super").count();
+
+					for _ in 0..supers {
+						let Some(parent) = self.tree.modules[current].parent else {
+							let name = self.tree.modules[current].path.join("::");
+							self.report(module, span.clone(), ModuleError::NoParent(name));
+							return None;
+						};
+
+						current = parent;
+					}
+
+					return self.follow(module, current, &segments[supers..], span);
+				},
+				"pkg" => current = if self.tree.modules[current].path[0] == "std" { self.std() } else { self.tree.root },
+				"std" => current = self.std(),
+				_ => return self.follow(module, current, segments, span),
+			}
+
+			return self.follow(module, current, &segments[1..], span);
+		}
+
+		self.follow(module, current, segments, span)
+	}
+
+	/// Follows the rest of a path down through submodules.
+	fn follow(&mut self, module: ModuleId, from: ModuleId, segments: &[Ident], span: &Span) -> Option<ModuleId> {
+		let mut current = from;
+
+		for segment in segments.iter() {
+			let child = match self.tree.modules[current].children.get(&segment.name) {
+				Some(&child) => Some(child),
+				None => self.load_child(current, &segment.name),
+			};
+
+			if let Some(child) = child {
+				if current == module && self.tree.modules[child].named_at.is_none() {
+					self.tree.modules[child].named_at = Some(segment.span.clone());
+				}
+
+				current = child;
+				continue;
+			}
+
+			// Anything which isn't a module is an item, which name resolution takes care of.
+			if self.tree.modules[current].might_have(&segment.name) { break }
+
+			let parent = &self.tree.modules[current];
+			let name = parent.path.iter().chain(std::iter::once(&segment.name)).cloned().collect::<Vec<_>>().join("::");
+			let candidates = [parent.dir.join(format!("{}.ro", segment.name)), parent.dir.join(&segment.name).join(format!("{}.ro", segment.name))];
+
+			let err = self.report(module, span.clone(), ModuleError::NotFound(name));
+			err.add_note(format!("looked for `{}` and `{}`", candidates[0].display(), candidates[1].display()));
+			return None;
+		}
+
+		Some(current)
+	}
+
+	/// Looks for the file of a submodule, and loads it if it's there.
+	fn load_child(&mut self, parent: ModuleId, name: &str) -> Option<ModuleId> {
+		let mut path = self.tree.modules[parent].path.clone();
+		path.push(name.into());
+
+		let dir = self.tree.modules[parent].dir.join(name);
+		let file = [dir.join(format!("{}.ro", name)), self.tree.modules[parent].dir.join(format!("{}.ro", name))]
+			.into_iter()
+			.find(|file| self.built_in(&path, file).is_some() || file.is_file())?;
+
+		Some(self.add(path, Some(file), dir, Some(parent)))
+	}
+
+	/// The source of a standard library file, if it's the copy built into the runtime that's being used.
+	fn built_in(&self, path: &[String], file: &FilePath) -> Option<&'static str> {
+		if path[0] != "std" || self.options.std_path.is_some() { return None }
+
+		let inner = file.strip_prefix("std").ok()?;
+		STD_FILES.iter().find(|(name, _)| inner == FilePath::new(name)).map(|(_, source)| *source)
+	}
+
+	/// The root of the standard library, which is just a directory.
+	fn std(&mut self) -> ModuleId {
+		match self.tree.std {
+			Some(id) => id,
+			None => {
+				// The built in copy's files are named as though it were a directory called `std`, which is what errors in it show.
+				let dir = self.options.std_path.clone().unwrap_or_else(|| PathBuf::from("std"));
+				let id = self.add(vec!["std".into()], None, dir, None);
+				self.tree.std = Some(id);
+				id
+			},
+		}
+	}
+
+	/// Every program gets the prelude, so it's loaded up front.
+	/// So are `std::collections` and `std::ops`, which declare what the collection types and operators have, whether or not they get imported.
+	fn load_prelude(&mut self) {
+		let std = self.std();
+		self.tree.prelude = self.load_child(std, "prelude");
+		self.load_child(std, "collections");
+		self.load_child(std, "ops");
+
+		if self.tree.prelude.is_none() {
+			let file = self.tree.modules[std].dir.join("prelude").join("prelude.ro");
+			self.errors.push(Error::new(false, Some(&file), None, None, None, ErrorKind::IO(std::io::ErrorKind::NotFound)));
+		}
+	}
+}
+
+impl Module {
+	/// Whether the module declares or imports something with this name. Glob imports could bring in anything.
+	fn might_have(&self, name: &str) -> bool {
+		self.ast.items.iter().any(|item| match &item.kind {
+			ItemKind::Func(func) => func.name.name == name,
+			ItemKind::Type(decl) => decl.name.name == name,
+			ItemKind::Trait(decl) => decl.name.name == name,
+			ItemKind::Effect(decl) => decl.name.name == name,
+			ItemKind::Const(decl) => decl.name.name == name,
+			ItemKind::Use(tree) => tree.might_import(name),
+			ItemKind::Impl(_) => false,
+		})
+	}
+}
+
+impl UseTree {
+	fn might_import(&self, name: &str) -> bool {
+		match &self.kind {
+			UseKind::Single(Some(alias)) => alias.name == name,
+			UseKind::Single(None) => self.prefix.last().is_some_and(|last| last.name == name),
+			UseKind::Glob => true,
+			UseKind::List(trees) => trees.iter().any(|tree| tree.might_import(name)),
+		}
+	}
+}
+
+impl PathCollector {
+	/// Keeps hold of a path if it starts at a module, rather than at something in scope.
+	fn path(&mut self, path: &Path) {
+		let starts_at_module = match path.segments.first().map(|segment| segment.name.name.as_str()) {
+			Some("std" | "pkg" | "super") => true,
+			Some("self") => path.segments.len() > 1,
+			_ => false,
+		};
+
+		if starts_at_module { self.paths.push(path.clone()); }
+	}
+
+	fn item(&mut self, item: &Item) {
+		let items = match &item.kind {
+			ItemKind::Func(func) => {
+				self.generics(&func.generics, &func.where_clause);
+				for param in func.params.iter() { self.ty_opt(param.ty.as_ref()); }
+				self.ty_opt(func.ret.as_ref());
+				for effect in func.effects.iter() { self.ty(effect); }
+				if let Some(body) = &func.body { self.block(body); }
+				return;
+			},
+			ItemKind::Const(decl) => {
+				self.ty_opt(decl.ty.as_ref());
+				if let Some(value) = &decl.value { self.expr(value); }
+				return;
+			},
+			ItemKind::Type(decl) => {
+				self.generics(&decl.generics, &[]);
+
+				match &decl.body {
+					TypeBody::Opaque => {},
+					TypeBody::Alias(ty) => self.ty(ty),
+					TypeBody::Record(fields) => for field in fields.iter() { self.ty(&field.ty); },
+					TypeBody::Tuple(fields) => for field in fields.iter() { self.ty(field); },
+					TypeBody::Union(variants) => for variant in variants.iter() {
+						for field in variant.fields.iter() { self.ty(field); }
+					},
+				}
+
+				&decl.items
+			},
+			ItemKind::Trait(decl) => {
+				self.generics(&decl.generics, &[]);
+				for supertrait in decl.supertraits.iter() { self.ty(supertrait); }
+				&decl.items
+			},
+			ItemKind::Effect(decl) => {
+				self.generics(&decl.generics, &[]);
+				&decl.items
+			},
+			ItemKind::Impl(decl) => {
+				self.generics(&decl.generics, &decl.where_clause);
+				self.ty_opt(decl.trait_.as_ref());
+				self.ty(&decl.ty);
+				&decl.items
+			},
+			ItemKind::Use(_) => return,
+		};
+
+		for item in items.iter() {
+			self.item(item);
+		}
+	}
+
+	fn generics(&mut self, params: &[GenericParam], where_clause: &[WherePredicate]) {
+		for param in params.iter() {
+			for bound in param.bounds.iter() { self.ty(bound); }
+			self.ty_opt(param.default.as_ref());
+		}
+
+		for predicate in where_clause.iter() {
+			self.ty(&predicate.ty);
+			for bound in predicate.bounds.iter() { self.ty(bound); }
+		}
+	}
+
+	fn ty_opt(&mut self, ty: Option<&Type>) {
+		if let Some(ty) = ty { self.ty(ty); }
+	}
+
+	fn ty(&mut self, ty: &Type) {
+		match &ty.kind {
+			TypeKind::Path(path) => {
+				self.path(path);
+				for segment in path.segments.iter() {
+					for generic in segment.generics.iter() { self.ty(generic); }
+				}
+			},
+			TypeKind::Never => {},
+			TypeKind::Dyn(inner) | TypeKind::List(inner) => self.ty(inner),
+			TypeKind::Array(inner, len) => {
+				self.ty(inner);
+				self.expr(len);
+			},
+			TypeKind::Map(key, value) => {
+				self.ty(key);
+				self.ty(value);
+			},
+			TypeKind::Func { params, ret, effects } => {
+				for param in params.iter() { self.ty(param); }
+				if let Some(ret) = ret { self.ty(ret); }
+				for effect in effects.iter() { self.ty(effect); }
+			},
+			TypeKind::Tuple(elems) => {
+				for elem in elems.iter() { self.ty(elem); }
+			},
+			TypeKind::Record(fields) => {
+				for (_, ty) in fields.iter() { self.ty(ty); }
+			},
+		}
+	}
+
+	fn block(&mut self, block: &Block) {
+		for stmt in block.stmts.iter() {
+			self.stmt(stmt);
+		}
+	}
+
+	fn stmt(&mut self, stmt: &Stmt) {
+		match &stmt.kind {
+			StmtKind::Let { pat, ty, value, .. } => {
+				self.pattern(pat);
+				self.ty_opt(ty.as_ref());
+				self.expr(value);
+			},
+			StmtKind::Assign { target, value, .. } => {
+				self.expr(target);
+				self.expr(value);
+			},
+			StmtKind::Expr(expr) => self.expr(expr),
+			StmtKind::Handler(handler) => {
+				self.path(&handler.operation);
+				for param in handler.params.iter() { self.pattern(param); }
+				self.block(&handler.body);
+			},
+		}
+	}
+
+	fn pattern(&mut self, pat: &Pattern) {
+		match &pat.kind {
+			PatternKind::Wildcard | PatternKind::Binding { .. } | PatternKind::Lit { .. } => {},
+			PatternKind::Range { start, end, .. } => {
+				for end in [start, end].into_iter().flatten() { self.pattern(end); }
+			},
+			PatternKind::Path(path) => self.path(path),
+			PatternKind::TupleStruct { path, fields } => {
+				self.path(path);
+				for field in fields.iter() { self.pattern(field); }
+			},
+			PatternKind::Tuple(elems) => {
+				for elem in elems.iter() { self.pattern(elem); }
+			},
+		}
+	}
+
+	fn expr(&mut self, expr: &Expr) {
+		match &expr.kind {
+			ExprKind::Lit(_) | ExprKind::Continue(_) => {},
+			ExprKind::Path(path) => self.path(path),
+			ExprKind::Call { callee, args } => {
+				self.expr(callee);
+				for arg in args.iter() { self.expr(arg); }
+			},
+			ExprKind::MethodCall { receiver, args, .. } => {
+				self.expr(receiver);
+				for arg in args.iter() { self.expr(arg); }
+			},
+			ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } => self.expr(base),
+			ExprKind::Index { base, index } => {
+				self.expr(base);
+				self.expr(index);
+			},
+			ExprKind::Tuple(elems) | ExprKind::List(elems) | ExprKind::Interp(elems) => {
+				for elem in elems.iter() { self.expr(elem); }
+			},
+			ExprKind::Record(fields) => {
+				for (_, value) in fields.iter() { self.expr(value); }
+			},
+			ExprKind::Map(entries) => {
+				for (key, value) in entries.iter() {
+					self.expr(key);
+					self.expr(value);
+				}
+			},
+			ExprKind::Closure { params, ret, effects, body } => {
+				for param in params.iter() { self.ty_opt(param.ty.as_ref()); }
+				if let Some(ret) = ret { self.ty(ret); }
+				for effect in effects.iter() { self.ty(effect); }
+				self.block(body);
+			},
+			ExprKind::Unary { expr, .. } | ExprKind::Try(expr) => self.expr(expr),
+			ExprKind::Binary { lhs, rhs, .. } => {
+				self.expr(lhs);
+				self.expr(rhs);
+			},
+			ExprKind::If { branches, else_ } => {
+				for branch in branches.iter() {
+					match &branch.cond {
+						Condition::Expr(cond) => self.expr(cond),
+						Condition::Matches(cond, pat) => {
+							self.expr(cond);
+							self.pattern(pat);
+						},
+					}
+					self.block(&branch.body);
+				}
+
+				if let Some(else_) = else_ { self.block(else_); }
+			},
+			ExprKind::Match { scrutinee, arms } => {
+				self.expr(scrutinee);
+				for arm in arms.iter() {
+					self.pattern(&arm.pat);
+					self.expr(&arm.body);
+				}
+			},
+			ExprKind::Loop { body, .. } | ExprKind::Block { body, .. } => self.block(body),
+			ExprKind::While { cond, body, .. } | ExprKind::Until { cond, body, .. } => {
+				self.expr(cond);
+				self.block(body);
+			},
+			ExprKind::For { pat, iter, body, .. } => {
+				self.pattern(pat);
+				self.expr(iter);
+				self.block(body);
+			},
+			ExprKind::Handle { handler, body } => {
+				self.path(&handler.operation);
+				for param in handler.params.iter() { self.pattern(param); }
+				self.block(&handler.body);
+				self.block(body);
+			},
+			ExprKind::Break { value, .. } | ExprKind::Return(value) => {
+				if let Some(value) = value { self.expr(value); }
+			},
+		}
+	}
+}
+
+impl fmt::Display for ModuleError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ModuleError::NotFound(name) => write!(f, "couldn't find the module `{}`", name),
+			ModuleError::NoParent(name) => write!(f, "`{}` is the root of its package, so it has no `super`", name),
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compiler::{
+		Limit,
+		ParseError,
+		ParseLimits,
+		TestPackage,
+	};
+
+	fn module_errors(errors: ErrorList) -> Vec<(Option<usize>, ModuleError)> {
+		errors.into_iter()
+			.map(|e| match e.kind() {
+				ErrorKind::Interpret(InterpretError::Module(err)) => (e.line(), err),
+				kind => panic!("expected a module error, got {}", kind),
+			})
+			.collect()
+	}
+
+	/// The names of every module loaded from a package.
+	fn names(tree: &ModuleTree, package: &str) -> Vec<String> {
+		let mut names: Vec<String> = tree.modules.iter()
+			.filter(|module| module.path[0] == package)
+			.map(|module| module.path.join("::"))
+			.collect();
+
+		names.sort();
+		names
+	}
+
+	#[test]
+	fn modules_are_found_through_paths() {
+		let package = TestPackage::new("found", &[
+			("main.ro", "use pkg::util::helper\nuse self::shapes::[circle, square]::*\n\npub func main() do\n\thelper()\nend\n"),
+			("util/util.ro", "pub func helper() do\n\tsuper::shapes::circle::area()\nend\n"),
+			("shapes.ro", "pub type Shape\n"),
+			("shapes/circle.ro", "pub func area() do\nend\n"),
+			("shapes/square/square.ro", "pub func area() do\nend\n"),
+		]);
+
+		let (tree, _) = load(package.root(), &Options::default()).unwrap();
+		assert_eq!(names(&tree, "pkg"), ["pkg", "pkg::shapes", "pkg::shapes::circle", "pkg::shapes::square", "pkg::util"]);
+	}
+
+	#[test]
+	fn standard_library_modules_are_loaded() {
+		let package = TestPackage::new("std", &[
+			("main.ro", "use std::cmp::Ordering\n\npub func main() do\n\tassert!(true)\nend\n"),
+		]);
+
+		let (tree, _) = load(package.root(), &Options::default()).unwrap();
+		let names = names(&tree, "std");
+
+		for name in ["std", "std::cmp", "std::collections", "std::error", "std::error::option", "std::error::result", "std::ops", "std::prelude"] {
+			assert!(names.iter().any(|n| n == name), "{} wasn't loaded", name);
+		}
+	}
+
+	#[test]
+	fn every_standard_library_file_is_built_in() {
+		fn find(dir: &FilePath, inner: &FilePath, files: &mut Vec<String>) {
+			for entry in std::fs::read_dir(dir).unwrap() {
+				let path = entry.unwrap().path();
+				let name = inner.join(path.file_name().unwrap());
+
+				if path.is_dir() {
+					find(&path, &name, files);
+				} else if path.extension().is_some_and(|ext| ext == "ro") {
+					files.push(name.to_string_lossy().replace('\\', "/"));
+				}
+			}
+		}
+
+		let mut files = Vec::new();
+		find(&FilePath::new(env!("CARGO_MANIFEST_DIR")).join("std"), FilePath::new(""), &mut files);
+		files.sort();
+
+		let built_in: Vec<&str> = STD_FILES.iter().map(|(name, _)| *name).collect();
+		assert_eq!(files, built_in);
+	}
+
+	#[test]
+	fn standard_library_can_be_loaded_from_elsewhere() {
+		let package = TestPackage::new("other-std", &[
+			("main.ro", "pub func main() do
+	hello()
+end
+"),
+			("lib/prelude/prelude.ro", "pub func hello() do
+end
+"),
+		]);
+
+		let std_path = package.root().parent().unwrap().join("lib");
+		let options = Options { std_path: Some(std_path.clone()), ..Default::default() };
+		let (tree, _) = load(package.root(), &options).unwrap();
+		let prelude = &tree.modules[tree.prelude.unwrap()];
+		assert_eq!(prelude.file.as_deref(), Some(std_path.join("prelude").join("prelude.ro").as_path()));
+
+		// Somewhere without a standard library says which file it couldn't find.
+		let options = Options { std_path: Some(std_path.join("nowhere")), ..Default::default() };
+		let errors = load(package.root(), &options).err().unwrap();
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].file(), Some(std_path.join("nowhere").join("prelude").join("prelude.ro")));
+		assert!(matches!(errors[0].kind(), ErrorKind::IO(std::io::ErrorKind::NotFound)));
+	}
+
+	#[test]
+	fn files_past_the_size_limit_are_not_read() {
+		let big = format!("pub func big() do\n{}end\n", "\tx := 1\n".repeat(10_000));
+		let package = TestPackage::new("big", &[
+			("main.ro", "use self::big::*\n"),
+			("big.ro", &big),
+		]);
+
+		let options = Options { limits: ParseLimits { max_source_bytes: 1 << 16, ..Default::default() }, ..Default::default() };
+		let errors = load(package.root(), &options).err().unwrap();
+
+		assert_eq!(errors.len(), 1);
+		assert!(errors[0].file().is_some_and(|path| path.ends_with("big.ro")));
+		assert!(matches!(errors[0].kind(), ErrorKind::Interpret(InterpretError::Parse(ParseError::LimitExceeded(Limit::SourceBytes(65536))))));
+	}
+
+	#[test]
+	fn missing_modules_are_reported_at_the_use() {
+		let package = TestPackage::new("missing", &[
+			("main.ro", "use pkg::util\n\nuse self::nowhere::thing\n"),
+			("util.ro", "use super::super::thing\n"),
+		]);
+
+		let errors = module_errors(load(package.root(), &Options::default()).err().unwrap());
+
+		assert!(matches!(&errors[0], (Some(3), ModuleError::NotFound(name)) if name == "pkg::nowhere"));
+		assert!(matches!(&errors[1], (Some(1), ModuleError::NoParent(name)) if name == "pkg"));
+	}
+}
@@ -0,0 +1,558 @@
+//! Checks that only things declared `mut` get changed, and that constants only use what's known before the program runs.
+//!
+//! Rouge has mutable value semantics: every variable holds its own value, and nothing can be changed from somewhere else.
+//! Changing a field or an element changes the variable it's in, so that variable has to be `mut`.
+//! Closures get their own copy of each variable they capture, so changing one from inside of a closure isn't allowed,
+//! since the change would never be seen outside of it.
+//!
+//! The body of a `const func` gets run before the program does whenever a constant calls it, so it's held to the same rules as
+//! a constant's value, except that it can use its own variables and parameters, loop, and return early.
+
+//--> Imports <--
+
+use crate::{
+	Error,
+	ErrorKind,
+	ErrorList,
+	compiler::{
+		InterpretError,
+		line_of,
+		modules::{
+			ModuleId,
+			ModuleTree,
+		},
+		parser::astgen::{
+			Block,
+			Condition,
+			Expr,
+			ExprKind,
+			Item,
+			ItemKind,
+			Mutability,
+			Param,
+			Pattern,
+			PatternKind,
+			Stmt,
+			StmtKind,
+		},
+		resolve::{
+			DefId,
+			DefKind,
+			LocalId,
+			Res,
+			Resolutions,
+		},
+	},
+};
+
+use logos::Span;
+
+use std::{
+	collections::{
+		HashMap,
+		HashSet,
+	},
+	fmt,
+};
+
+//--> Structs <--
+
+struct Checker<'a> {
+	tree: &'a ModuleTree,
+	res: &'a Resolutions,
+	/// Receivers of methods which take `mut self`, found by type checking.
+	mutating: &'a HashSet<(ModuleId, Span)>,
+	errors: ErrorList,
+	module: ModuleId,
+	bindings: HashMap<LocalId, Binding>,
+	/// How many closures deep the checker is.
+	depth: usize,
+	const_funcs: HashSet<DefId>,
+	/// Whether the body of a `const func` is being checked, which can do more than a constant's value can.
+	in_const_func: bool,
+}
+
+/// How a local was declared.
+struct Binding {
+	mutability: Mutability,
+	is_param: bool,
+	/// How many closures deep it was declared.
+	depth: usize,
+}
+
+//--> Enums <--
+
+#[derive(Clone, Debug)]
+pub enum MutabilityError {
+	/// A variable, parameter or `self` which isn't `mut` was changed.
+	Immutable(String),
+	/// A `const` variable was changed.
+	Constant(String),
+	/// A variable captured by a closure was changed from inside of it.
+	Captured(String),
+	/// Something which isn't a variable, or a field or element of one, was assigned to.
+	NotAssignable,
+	/// A constant's value uses a variable which isn't `const`.
+	NotConst(String),
+	/// A constant's value calls a function which isn't a `const func`.
+	NotConstFunc(String),
+	/// A constant's value uses something which can only be done while the program runs, like a loop.
+	NotConstExpr(&'static str),
+}
+
+//--> Functions <--
+
+/// Checks every module in a tree which has had its names resolved and types checked.
+pub(crate) fn check(tree: &ModuleTree, res: &Resolutions, mutating: &HashSet<(ModuleId, Span)>) -> ErrorList {
+	let mut checker = Checker {
+		tree,
+		res,
+		mutating,
+		errors: ErrorList::new(),
+		module: 0,
+		bindings: HashMap::new(),
+		depth: 0,
+		const_funcs: HashSet::new(),
+		in_const_func: false,
+	};
+
+	for module in tree.modules.iter() {
+		for item in module.ast.items.iter() {
+			checker.collect_const_funcs(item);
+		}
+	}
+
+	for (module, m) in tree.modules.iter().enumerate() {
+		checker.module = module;
+
+		for item in m.ast.items.iter() {
+			checker.item(item);
+		}
+	}
+
+	checker.errors
+}
+
+impl Checker<'_> {
+	fn report(&mut self, span: Span, err: MutabilityError) -> &mut Error {
+		let module = &self.tree.modules[self.module];
+		let line = line_of(&module.source, span.start);
+		let slice = module.source.get(span.clone());
+
+		self.errors.push(Error::new(false, module.file.as_deref(), Some(line), Some(span), slice, ErrorKind::Interpret(InterpretError::Mutability(err))));
+		self.errors.last_mut().unwrap()
+	}
+
+	/// The line a local was declared on, for pointing back at.
+	fn declared_at(&self, local: LocalId) -> usize {
+		line_of(&self.tree.modules[self.module].source, self.res.locals[local].name.span.start)
+	}
+
+	fn collect_const_funcs(&mut self, item: &Item) {
+		let items = match &item.kind {
+			ItemKind::Func(func) => {
+				if let Some(def) = item.def.filter(|_| func.is_const) { self.const_funcs.insert(def); }
+				return;
+			},
+			ItemKind::Type(decl) => &decl.items,
+			ItemKind::Trait(decl) => &decl.items,
+			ItemKind::Impl(decl) => &decl.items,
+			_ => return,
+		};
+
+		for item in items.iter() {
+			self.collect_const_funcs(item);
+		}
+	}
+
+	fn item(&mut self, item: &Item) {
+		let items = match &item.kind {
+			ItemKind::Func(func) => {
+				self.params(&func.params);
+				if let Some(body) = &func.body {
+					self.block(body);
+
+					if func.is_const {
+						self.in_const_func = true;
+						self.const_block(body);
+						self.in_const_func = false;
+					}
+				}
+				return;
+			},
+			ItemKind::Const(decl) => {
+				if let Some(value) = &decl.value {
+					self.expr(value);
+					self.const_value(value);
+				}
+				return;
+			},
+			ItemKind::Type(decl) => &decl.items,
+			ItemKind::Trait(decl) => &decl.items,
+			ItemKind::Effect(decl) => &decl.items,
+			ItemKind::Impl(decl) => &decl.items,
+			ItemKind::Use(_) => return,
+		};
+
+		for item in items.iter() {
+			self.item(item);
+		}
+	}
+
+	fn params(&mut self, params: &[Param]) {
+		for param in params.iter() {
+			let mutability = if param.is_mut { Mutability::Mutable } else { Mutability::Immutable };
+			if let Some(local) = param.local {
+				self.bindings.insert(local, Binding { mutability, is_param: true, depth: self.depth });
+			}
+		}
+	}
+
+	/// Records every name a pattern binds. `mutability` is how the whole pattern was declared, which `mut` on a name can add to.
+	fn pattern(&mut self, pat: &Pattern, mutability: Mutability) {
+		match &pat.kind {
+			PatternKind::Binding { is_mut, local: Some(local), .. } => {
+				let mutability = if *is_mut { Mutability::Mutable } else { mutability };
+				self.bindings.insert(*local, Binding { mutability, is_param: false, depth: self.depth });
+			},
+			PatternKind::Range { start, end, .. } => {
+				for end in [start, end].into_iter().flatten() { self.pattern(end, mutability); }
+			},
+			PatternKind::TupleStruct { fields, .. } | PatternKind::Tuple(fields) => {
+				for field in fields.iter() { self.pattern(field, mutability); }
+			},
+			_ => {},
+		}
+	}
+
+	fn block(&mut self, block: &Block) {
+		for stmt in block.stmts.iter() {
+			self.stmt(stmt);
+		}
+	}
+
+	fn stmt(&mut self, stmt: &Stmt) {
+		match &stmt.kind {
+			StmtKind::Let { mutability, pat, value, .. } => {
+				self.expr(value);
+				if *mutability == Mutability::Const { self.const_value(value); }
+				self.pattern(pat, *mutability);
+			},
+			StmtKind::Assign { target, value, .. } => {
+				self.expr(target);
+				self.expr(value);
+				self.mutate(target, true);
+			},
+			StmtKind::Expr(expr) => self.expr(expr),
+			StmtKind::Handler(handler) => {
+				for param in handler.params.iter() { self.pattern(param, Mutability::Immutable); }
+				self.block(&handler.body);
+			},
+		}
+	}
+
+	fn expr(&mut self, expr: &Expr) {
+		match &expr.kind {
+			ExprKind::Lit(_) | ExprKind::Path(_) | ExprKind::Continue(_) => {},
+			ExprKind::Call { callee, args } => {
+				self.expr(callee);
+				for arg in args.iter() { self.expr(arg); }
+			},
+			ExprKind::MethodCall { receiver, args, .. } => {
+				self.expr(receiver);
+				for arg in args.iter() { self.expr(arg); }
+
+				if self.mutating.contains(&(self.module, receiver.span.clone())) { self.mutate(receiver, false); }
+			},
+			ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } => self.expr(base),
+			ExprKind::Index { base, index } => {
+				self.expr(base);
+				self.expr(index);
+			},
+			ExprKind::Tuple(elems) | ExprKind::List(elems) | ExprKind::Interp(elems) => {
+				for elem in elems.iter() { self.expr(elem); }
+			},
+			ExprKind::Record(fields) => {
+				for (_, value) in fields.iter() { self.expr(value); }
+			},
+			ExprKind::Map(entries) => {
+				for (key, value) in entries.iter() {
+					self.expr(key);
+					self.expr(value);
+				}
+			},
+			ExprKind::Closure { params, body, .. } => {
+				self.depth += 1;
+				self.params(params);
+				self.block(body);
+				self.depth -= 1;
+			},
+			ExprKind::Unary { expr, .. } | ExprKind::Try(expr) => self.expr(expr),
+			ExprKind::Binary { lhs, rhs, .. } => {
+				self.expr(lhs);
+				self.expr(rhs);
+			},
+			ExprKind::If { branches, else_ } => {
+				for branch in branches.iter() {
+					match &branch.cond {
+						Condition::Expr(cond) => self.expr(cond),
+						Condition::Matches(value, pat) => {
+							self.expr(value);
+							self.pattern(pat, Mutability::Immutable);
+						},
+					}
+					self.block(&branch.body);
+				}
+
+				if let Some(else_) = else_ { self.block(else_); }
+			},
+			ExprKind::Match { scrutinee, arms } => {
+				self.expr(scrutinee);
+
+				for arm in arms.iter() {
+					self.pattern(&arm.pat, Mutability::Immutable);
+					self.expr(&arm.body);
+				}
+			},
+			ExprKind::Loop { body, .. } | ExprKind::Block { body, .. } => self.block(body),
+			ExprKind::While { cond, body, .. } | ExprKind::Until { cond, body, .. } => {
+				self.expr(cond);
+				self.block(body);
+			},
+			ExprKind::For { pat, iter, body, .. } => {
+				self.expr(iter);
+				self.pattern(pat, Mutability::Immutable);
+				self.block(body);
+			},
+			ExprKind::Handle { handler, body } => {
+				for param in handler.params.iter() { self.pattern(param, Mutability::Immutable); }
+				self.block(&handler.body);
+				self.block(body);
+			},
+			ExprKind::Break { value, .. } | ExprKind::Return(value) => {
+				if let Some(value) = value { self.expr(value); }
+			},
+		}
+	}
+
+	/// Checks that something being changed can be. Assigning needs a variable to change, but a method taking `mut self` can change a temporary.
+	fn mutate(&mut self, target: &Expr, assigning: bool) {
+		let mut place = target;
+
+		// Changing a field or element changes whatever it's inside of.
+		let path = loop {
+			match &place.kind {
+				ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } | ExprKind::Index { base, .. } => place = base,
+				ExprKind::Path(path) => break Some(path),
+				_ => break None,
+			}
+		};
+
+		let local = match path.map(|path| path.res) {
+			Some(Res::Local(local)) => local,
+			Some(Res::Unresolved) => return,
+			_ => {
+				if assigning { self.report(target.span.clone(), MutabilityError::NotAssignable); }
+				return;
+			},
+		};
+
+		let Some(binding) = self.bindings.get(&local) else { return };
+		let name = self.res.locals[local].name.name.clone();
+		let line = self.declared_at(local);
+
+		if binding.depth < self.depth {
+			self.report(target.span.clone(), MutabilityError::Captured(name.clone()))
+				.add_note(format!("closures get their own copy of `{}`, which is declared on line {}, so the change would never be seen outside", name, line));
+			return;
+		}
+
+		match binding.mutability {
+			Mutability::Mutable => {},
+			Mutability::Const => {
+				self.report(target.span.clone(), MutabilityError::Constant(name.clone()))
+					.add_note(format!("`{}` is declared as `const` on line {}", name, line));
+			},
+			Mutability::Immutable => {
+				let is_param = binding.is_param;
+				let err = self.report(target.span.clone(), MutabilityError::Immutable(name.clone()));
+
+				match (is_param, name.as_str()) {
+					(true, "self") => err.add_note(format!("the method is declared on line {} with `self`, which would need to be `mut self`", line)),
+					(true, _) => err.add_note(format!("the parameter is declared on line {}, and would need to be `mut {}`", line, name)),
+					(false, _) => err.add_note(format!("`{}` is declared on line {}, and would need to be `mut {}`", name, line, name)),
+				}
+			},
+		}
+	}
+
+	/// Checks that a constant's value can be worked out before the program runs.
+	fn const_value(&mut self, expr: &Expr) -> bool {
+		let (what, span) = match &expr.kind {
+			ExprKind::Lit(_) => return true,
+			ExprKind::Path(path) => match path.res {
+				Res::Local(local) => {
+					if self.in_const_func || self.bindings.get(&local).is_some_and(|binding| binding.mutability == Mutability::Const) { return true }
+
+					let name = self.res.locals[local].name.name.clone();
+					let line = self.declared_at(local);
+					self.report(path.span.clone(), MutabilityError::NotConst(name.clone()))
+						.add_note(format!("`{}` is declared on line {}, and would need to be `const`", name, line));
+					return false;
+				},
+				_ => return true,
+			},
+			ExprKind::Call { callee, args } => {
+				if let ExprKind::Path(path) = &callee.kind {
+					if let Res::Def(def) = path.res {
+						let is_const = match self.res.defs[def].kind {
+							// Making a variant, record or tuple type is always fine.
+							DefKind::Variant | DefKind::Type => true,
+							DefKind::Func => self.const_funcs.contains(&def),
+							_ => false,
+						};
+
+						if !is_const {
+							self.report(path.span.clone(), MutabilityError::NotConstFunc(path.to_string()));
+							return false;
+						}
+					}
+				} else if !self.const_value(callee) {
+					return false;
+				}
+
+				return args.iter().all(|arg| self.const_value(arg));
+			},
+			ExprKind::Field { base: inner, .. } | ExprKind::TupleField { base: inner, .. } | ExprKind::Unary { expr: inner, .. } => return self.const_value(inner),
+			ExprKind::Index { base: lhs, index: rhs } | ExprKind::Binary { lhs, rhs, .. } => return self.const_value(lhs) && self.const_value(rhs),
+			ExprKind::Tuple(elems) | ExprKind::List(elems) | ExprKind::Interp(elems) => return elems.iter().all(|elem| self.const_value(elem)),
+			ExprKind::Record(fields) => return fields.iter().all(|(_, value)| self.const_value(value)),
+			ExprKind::Map(entries) => return entries.iter().all(|(key, value)| self.const_value(key) && self.const_value(value)),
+			// Each branch gets checked even once one has a problem, since they're written separately.
+			ExprKind::If { branches, else_ } => {
+				let mut ok = true;
+
+				for branch in branches.iter() {
+					let cond = match &branch.cond {
+						Condition::Expr(cond) | Condition::Matches(cond, _) => cond,
+					};
+
+					ok &= self.const_value(cond);
+					ok &= self.const_block(&branch.body);
+				}
+
+				if let Some(else_) = else_ { ok &= self.const_block(else_); }
+				return ok;
+			},
+			ExprKind::Match { scrutinee, arms } => return self.const_value(scrutinee) && arms.iter().all(|arm| self.const_value(&arm.body)),
+			ExprKind::Loop { body, .. } | ExprKind::Block { body, .. } if self.in_const_func => return self.const_block(body),
+			ExprKind::Break { value, .. } | ExprKind::Return(value) if self.in_const_func => return value.as_ref().is_none_or(|value| self.const_value(value)),
+			ExprKind::Continue(_) if self.in_const_func => return true,
+			ExprKind::MethodCall { method, .. } => ("method calls", method.span.clone()),
+			ExprKind::Closure { .. } => ("closures", expr.span.clone()),
+			// `for` loops have been desugared into handlers by now, and can't be run before the program is.
+			ExprKind::Handle { handler, .. } if self.in_const_func && handler.operation.to_string() == "Yield::yield" => ("`for` loops", expr.span.clone()),
+			ExprKind::Loop { .. } | ExprKind::While { .. } | ExprKind::Until { .. } | ExprKind::For { .. } => ("loops", expr.span.clone()),
+			ExprKind::Block { .. } | ExprKind::Break { .. } | ExprKind::Continue(_) => ("labelled blocks", expr.span.clone()),
+			ExprKind::Handle { .. } => ("handlers", expr.span.clone()),
+			ExprKind::Return(_) => ("`return`", expr.span.clone()),
+			ExprKind::Try(_) => ("`?`", expr.span.clone()),
+		};
+
+		self.report(span, MutabilityError::NotConstExpr(what));
+		false
+	}
+
+	/// Checks the block of a branch in a constant's value, which can only be made of expressions.
+	/// In a `const func`, it can declare and assign to variables as well.
+	fn const_block(&mut self, block: &Block) -> bool {
+		block.stmts.iter().fold(true, |ok, stmt| ok & match &stmt.kind {
+			StmtKind::Expr(expr) => self.const_value(expr),
+			StmtKind::Let { value, .. } if self.in_const_func => self.const_value(value),
+			StmtKind::Assign { target, value, .. } if self.in_const_func => self.const_value(target) && self.const_value(value),
+			_ => {
+				self.report(stmt.span.clone(), MutabilityError::NotConstExpr("statements"));
+				false
+			},
+		})
+	}
+}
+
+impl fmt::Display for MutabilityError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MutabilityError::Immutable(name) => write!(f, "`{}` can't be changed, since it isn't `mut`", name),
+			MutabilityError::Constant(name) => write!(f, "`{}` is a constant, so it can never be changed", name),
+			MutabilityError::Captured(name) => write!(f, "`{}` can't be changed from inside of a closure which captures it", name),
+			MutabilityError::NotAssignable => write!(f, "only variables, along with their fields and elements, can be assigned to"),
+			MutabilityError::NotConst(name) => write!(f, "`{}` isn't known until the program runs, so it can't be used in a constant", name),
+			MutabilityError::NotConstFunc(name) => write!(f, "`{}` isn't a `const func`, so it can't be called in a constant", name),
+			MutabilityError::NotConstExpr(what) => write!(f, "{} can't be used in a constant, since they only happen while the program runs", what),
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compiler::{
+		Options,
+		TestPackage,
+	};
+
+	/// Checks a package with a single file, and gives back the mutability errors in it.
+	fn mutability_errors(name: &str, source: &str) -> Vec<(Option<usize>, MutabilityError)> {
+		match TestPackage::new(name, &[("main.ro", source)]).check(&Options::default()) {
+			Ok(_) => Vec::new(),
+			Err(errors) => errors.into_iter()
+				.map(|e| match e.kind() {
+					ErrorKind::Interpret(InterpretError::Mutability(err)) => (e.line(), err),
+					kind => panic!("expected a mutability error, got {}", kind),
+				})
+				.collect(),
+		}
+	}
+
+	#[test]
+	fn only_mut_things_change() {
+		let errors = mutability_errors("mut", "type Counter is\n\t| Counter(nat)\n\n\tpub func bump(mut self) do\n\t\tself = Self::Counter(1)\n\tend\n\n\tpub func reset(self) do\n\t\tself = Self::Counter(0)\n\tend\nend\n\nfunc grow(list: [nat], mut other: [nat]) do\n\tlist[0] = 1\n\tother[0] = 1\nend\n\npub func main() do\n\tcounter := Counter::Counter(0)\n\tcounter.bump()\n\tmut pair := (1, 2)\n\tpair.0 = 3\n\tconst limit := 10\n\tlimit += 1\n\tCounter::Counter(0).bump()\nend\n");
+
+		assert!(matches!(&errors[..], [
+			(Some(9), MutabilityError::Immutable(a)),
+			(Some(14), MutabilityError::Immutable(b)),
+			(Some(20), MutabilityError::Immutable(c)),
+			(Some(24), MutabilityError::Constant(d)),
+		] if a == "self" && b == "list" && c == "counter" && d == "limit"), "{:?}", errors);
+	}
+
+	#[test]
+	fn closures_cant_change_what_they_capture() {
+		let errors = mutability_errors("captured", "pub func main() do\n\tmut total := 0\n\tadd := (n: nat) do\n\t\ttotal += n\n\t\tmut own := n\n\t\town += 1\n\tend\n\tadd(1)\n\ttotal += 1\nend\n");
+
+		assert!(matches!(&errors[..], [(Some(4), MutabilityError::Captured(name))] if name == "total"), "{:?}", errors);
+	}
+
+	#[test]
+	fn constants_are_known_before_running() {
+		let errors = mutability_errors("const", "const func double(n: nat) -> nat do\n\tn * 2\nend\n\nfunc triple(n: nat) -> nat do\n\tn * 3\nend\n\nconst TWO := double(1)\nconst THREE := triple(1)\nconst BOTH := [TWO, if TWO > 1 then 2 else 3]\n\npub func main() do\n\tn := 4\n\tconst a := n + 1\n\tconst b := TWO + 1\n\tconst c := [1].len()\nend\n");
+
+		assert!(matches!(&errors[..], [
+			(Some(10), MutabilityError::NotConstFunc(a)),
+			(Some(15), MutabilityError::NotConst(b)),
+			(Some(17), MutabilityError::NotConstExpr("method calls")),
+		] if a == "triple" && b == "n"), "{:?}", errors);
+	}
+
+	#[test]
+	fn const_funcs_only_do_const_things() {
+		let errors = mutability_errors("const-func", "func triple(n: nat) -> nat do\n\tn * 3\nend\n\nconst func sum(list: [nat]) -> nat do\n\tmut total := 0\n\tmut i := 0\n\n\twhile i < list.len() do\n\t\ttotal += triple(list[i])\n\t\ti += 1\n\tend\n\n\tfor n in list do total += n\n\ttotal\nend\n\npub func main() do\nend\n");
+
+		assert!(matches!(&errors[..], [
+			(Some(9), MutabilityError::NotConstExpr("method calls")),
+			(Some(10), MutabilityError::NotConstFunc(name)),
+			(Some(14), MutabilityError::NotConstExpr("`for` loops")),
+		] if name == "triple"), "{:?}", errors);
+	}
+}
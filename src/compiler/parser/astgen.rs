@@ -1,11 +1,2167 @@
 //--> Imports <--
 
+use crate::{
+	Error,
+	ErrorKind,
+	ErrorList,
+	compiler::{
+		InterpretError,
+		line_of,
+		lexer::{
+			Token,
+			TokenInner,
+		},
+		resolve::{
+			DefId,
+			LocalId,
+			Res,
+		},
+	},
+};
+
+use super::{
+	ParseError,
+	cstgen::{
+		ConcreteSyntaxElement,
+		ConcreteSyntaxNode,
+		ConcreteSyntaxTree,
+		NodeKind,
+	},
+};
+
+use logos::Span;
+
+use std::{
+	fmt,
+	path::Path as FilePath,
+};
+
 //--> Type Aliases <--
 
+pub(crate) type Result = std::result::Result<AbstractSyntaxTree, ErrorList>;
+
 //--> Structs <--
 
+/// The abstract syntax tree of a single file.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AbstractSyntaxTree {
+	pub items: Vec<Item>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Ident {
+	pub name: String,
+	pub span: Span,
+}
+
+/// A path like `std::error::Result<T, E>`. Special segments like `self`, `Self`, `super` and `pkg` are stored by name.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Path {
+	pub segments: Vec<PathSegment>,
+	pub span: Span,
+	/// What the path refers to, which is filled in by name resolution.
+	pub res: Res,
+	/// How many of the segments `res` covers. Any left over are for type checking to work out, like `default` in `T::default`.
+	pub resolved: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PathSegment {
+	pub name: Ident,
+	pub generics: Vec<Type>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Item {
+	pub attrs: Vec<Attribute>,
+	pub vis: Visibility,
+	pub kind: ItemKind,
+	/// Filled in by name resolution, for everything but `use`.
+	pub def: Option<DefId>,
+	/// Where the whole item is, attributes and all.
+	pub span: Span,
+}
+
+/// An attribute like `@allow(unused)`, attached to whatever comes after it.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Attribute {
+	pub name: Ident,
+	pub args: Vec<Meta>,
+	pub span: Span,
+}
+
+/// A single argument to an attribute, like `unused` or `feature = "x"`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Meta {
+	pub name: Ident,
+	pub kind: MetaKind,
+	pub span: Span,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Func {
+	pub name: Ident,
+	pub is_const: bool,
+	pub is_extern: bool,
+	pub generics: Vec<GenericParam>,
+	pub params: Vec<Param>,
+	pub ret: Option<Type>,
+	pub effects: Vec<Type>,
+	pub where_clause: Vec<WherePredicate>,
+	pub body: Option<Block>,
+}
+
+/// A function or closure parameter. Only `self` and closure parameters get away without a type.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Param {
+	pub is_mut: bool,
+	pub name: Ident,
+	pub ty: Option<Type>,
+	/// Filled in by name resolution.
+	pub local: Option<LocalId>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct GenericParam {
+	pub name: Ident,
+	pub bounds: Vec<Type>,
+	pub default: Option<Type>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct WherePredicate {
+	pub ty: Type,
+	pub bounds: Vec<Type>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TypeDecl {
+	pub name: Ident,
+	pub generics: Vec<GenericParam>,
+	pub body: TypeBody,
+	pub items: Vec<Item>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Field {
+	pub vis: Visibility,
+	pub name: Ident,
+	pub ty: Type,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Variant {
+	pub name: Ident,
+	pub fields: Vec<Type>,
+	/// Filled in by name resolution.
+	pub def: Option<DefId>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TraitDecl {
+	pub name: Ident,
+	pub generics: Vec<GenericParam>,
+	pub supertraits: Vec<Type>,
+	pub items: Vec<Item>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct EffectDecl {
+	pub name: Ident,
+	pub generics: Vec<GenericParam>,
+	pub items: Vec<Item>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ImplDecl {
+	pub generics: Vec<GenericParam>,
+	pub trait_: Option<Type>,
+	pub ty: Type,
+	pub where_clause: Vec<WherePredicate>,
+	pub items: Vec<Item>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ConstDecl {
+	pub name: Ident,
+	pub ty: Option<Type>,
+	/// Only a constant in a trait can go without one, leaving it to the impls.
+	pub value: Option<Expr>,
+}
+
+/// An import. `self::[option, result]::*` becomes a list of two glob imports.
+#[derive(Clone, Debug)]
+pub(crate) struct UseTree {
+	pub prefix: Vec<Ident>,
+	pub kind: UseKind,
+	pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Type {
+	pub kind: TypeKind,
+	pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Block {
+	pub stmts: Vec<Stmt>,
+	pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Stmt {
+	pub attrs: Vec<Attribute>,
+	pub kind: StmtKind,
+	pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Expr {
+	pub kind: ExprKind,
+	pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct IfBranch {
+	pub cond: Condition,
+	pub body: Block,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MatchArm {
+	pub pat: Pattern,
+	pub body: Expr,
+	pub span: Span,
+}
+
+/// A single clause of an effect handler, like `when Yield::yield(x) do ...`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Handler {
+	pub operation: Path,
+	pub params: Vec<Pattern>,
+	pub body: Block,
+	pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Pattern {
+	pub kind: PatternKind,
+	pub span: Span,
+}
+
+/// Writes out a syntax tree as Rouge source code.
+struct Printer<'f, 'a> {
+	f: &'f mut fmt::Formatter<'a>,
+	indent: usize,
+}
+
+/// Prints an expression on a single line, as far as that's possible.
+struct InlineExpr<'e>(&'e Expr);
+
+/// Walks a concrete syntax tree and turns it into an abstract one.
+struct Lowerer<'a> {
+	file: Option<&'a FilePath>,
+	source: &'a str,
+	errors: ErrorList,
+}
+
 //--> Enums <--
 
-//--> Traits <--
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Visibility {
+	/// Only accessible within the module it's declared in, and the modules inside of that one.
+	Private,
+	/// Marked with `prt`, accessible within the same package.
+	Protected,
+	/// Marked with `pub`, accessible from anywhere.
+	Public,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum MetaKind {
+	/// Just a name, like `unused`.
+	Word,
+	/// `name = "value"`
+	Value(Literal),
+	/// `name(more, args)`
+	List(Vec<Meta>),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum ItemKind {
+	Use(UseTree),
+	Func(Func),
+	Type(TypeDecl),
+	Trait(TraitDecl),
+	Effect(EffectDecl),
+	Impl(ImplDecl),
+	Const(ConstDecl),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum TypeBody {
+	/// No body at all, like the builtin types.
+	Opaque,
+	/// `type Name = Other`
+	Alias(Type),
+	/// Named fields.
+	Record(Vec<Field>),
+	/// Unnamed fields, like `(nat, nat)`.
+	Tuple(Vec<Type>),
+	/// A tagged union of variants.
+	Union(Vec<Variant>),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum UseKind {
+	/// Imports the last segment of the prefix, optionally under another name.
+	Single(Option<Ident>),
+	/// `::*`
+	Glob,
+	/// `::[a, b]`
+	List(Vec<UseTree>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum TypeKind {
+	Path(Path),
+	/// `!`
+	Never,
+	/// `dyn Trait`, where the trait is always a path.
+	Dyn(Box<Type>),
+	/// `[T]`
+	List(Box<Type>),
+	/// `[T; N]`
+	Array(Box<Type>, Box<Expr>),
+	/// `[K: V]`
+	Map(Box<Type>, Box<Type>),
+	/// `Func(A, B) -> R -< E`
+	Func { params: Vec<Type>, ret: Option<Box<Type>>, effects: Vec<Type> },
+	/// `(A, B)`, where `()` is the unit type.
+	Tuple(Vec<Type>),
+	/// `(name: A, other: B)`
+	Record(Vec<(Ident, Type)>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Mutability {
+	Immutable,
+	Mutable,
+	Const,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum StmtKind {
+	/// `name := value` and friends.
+	Let { mutability: Mutability, pat: Pattern, ty: Option<Type>, value: Expr },
+	/// `target = value`, `target += value`, `target .= method()`...
+	Assign { op: AssignOp, target: Expr, value: Expr },
+	Expr(Expr),
+	/// A handler without an `in` body, which handles the rest of the enclosing block.
+	Handler(Handler),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AssignOp {
+	Assign,
+	Compound(BinaryOp),
+	/// `.=`, which calls a method on the target and assigns the result back to it.
+	Method,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Literal {
+	Num(u64),
+	Float(f64),
+	Char(char),
+	Str(String),
+	Byte(u8),
+	ByteStr(Vec<u8>),
+	Bool(bool),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum UnaryOp {
+	Neg,
+	Plus,
+	Not,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BinaryOp {
+	Add,
+	Sub,
+	Mul,
+	Div,
+	Rem,
+	DivisibleBy,
+	BitAnd,
+	BitOr,
+	BitXor,
+	Shl,
+	Shr,
+	And,
+	Or,
+	Xor,
+	Eq,
+	Ne,
+	Lt,
+	Le,
+	Gt,
+	Ge,
+	Range,
+	RangeInclusive,
+	Bind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ExprKind {
+	Lit(Literal),
+	Path(Path),
+	Call { callee: Box<Expr>, args: Vec<Expr> },
+	MethodCall { receiver: Box<Expr>, method: Ident, args: Vec<Expr> },
+	Field { base: Box<Expr>, field: Ident },
+	/// `tuple.0`
+	TupleField { base: Box<Expr>, index: usize },
+	/// `list[index]`, where negative indices count from the end.
+	Index { base: Box<Expr>, index: Box<Expr> },
+	/// `(a, b)`, where `()` is the unit value.
+	Tuple(Vec<Expr>),
+	/// `(name: a, other: b)`
+	Record(Vec<(Ident, Expr)>),
+	/// `"\{a} and \{b}"`, where the text in between is made of string literals.
+	Interp(Vec<Expr>),
+	/// `[a, b, c]`
+	List(Vec<Expr>),
+	/// `[key: value, other: value]`
+	Map(Vec<(Expr, Expr)>),
+	/// `(a, b) do ... end`
+	Closure { params: Vec<Param>, ret: Option<Box<Type>>, effects: Vec<Type>, body: Block },
+	Unary { op: UnaryOp, expr: Box<Expr> },
+	Binary { op: BinaryOp, lhs: Box<Expr>, rhs: Box<Expr> },
+	If { branches: Vec<IfBranch>, else_: Option<Block> },
+	Match { scrutinee: Box<Expr>, arms: Vec<MatchArm> },
+	Loop { label: Option<Ident>, body: Block },
+	While { label: Option<Ident>, cond: Box<Expr>, body: Block },
+	Until { label: Option<Ident>, cond: Box<Expr>, body: Block },
+	For { label: Option<Ident>, pat: Pattern, iter: Box<Expr>, body: Block },
+	/// `` `name do ... end ``, which can be broken out of with a value.
+	Block { label: Ident, body: Block },
+	/// An effect handler wrapped around a block of code.
+	Handle { handler: Box<Handler>, body: Block },
+	Break { label: Option<Ident>, value: Option<Box<Expr>> },
+	Continue(Option<Ident>),
+	Return(Option<Box<Expr>>),
+	/// `expr?`
+	Try(Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Condition {
+	Expr(Expr),
+	/// `expr matches pattern`
+	Matches(Expr, Pattern),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum PatternKind {
+	/// `_`
+	Wildcard,
+	/// `name` or `mut name`. The local is filled in by name resolution.
+	Binding { is_mut: bool, name: Ident, local: Option<LocalId> },
+	Lit { negated: bool, lit: Literal },
+	/// `1..10` or `'a'..='z'`. Either end can be left off, like `1..` or `..=-1`, to go as far as the type does.
+	Range { start: Option<Box<Pattern>>, end: Option<Box<Pattern>>, inclusive: bool },
+	/// A unit variant or constant, like `None`.
+	Path(Path),
+	/// A variant with fields, like `Ok(value)`.
+	TupleStruct { path: Path, fields: Vec<Pattern> },
+	/// `(a, b)`
+	Tuple(Vec<Pattern>),
+}
+
+//--> Functions <--
+
+impl AbstractSyntaxTree {
+	pub(crate) fn new(cst: &ConcreteSyntaxTree, source: &str) -> Result {
+		let mut lowerer = Lowerer {
+			file: cst.file.as_deref(),
+			source,
+			errors: ErrorList::new(),
+		};
+
+		let items = cst.root.nodes()
+			.filter(|node| node.kind != NodeKind::Error)
+			.map(|node| lowerer.item(node))
+			.collect();
+
+		if lowerer.errors.is_empty() {
+			Ok(AbstractSyntaxTree { items })
+		} else {
+			Err(lowerer.errors)
+		}
+	}
+}
+
+impl Ident {
+	pub(crate) fn new(name: &str, span: Span) -> Ident {
+		Ident { name: name.to_string(), span }
+	}
+}
+
+impl Path {
+	/// Builds a path out of plain names, all pointing at the same span.
+	pub(crate) fn from_names(names: &[&str], span: Span) -> Path {
+		Path {
+			segments: names.iter()
+				.map(|name| PathSegment { name: Ident::new(name, span.clone()), generics: Vec::new() })
+				.collect(),
+			span,
+			res: Res::Unresolved,
+			resolved: 0,
+		}
+	}
+}
+
+impl Block {
+	pub(crate) fn new(stmts: Vec<Stmt>, span: Span) -> Block {
+		Block { stmts, span }
+	}
+}
+
+impl Stmt {
+	pub(crate) fn expr(expr: Expr) -> Stmt {
+		Stmt { attrs: Vec::new(), span: expr.span.clone(), kind: StmtKind::Expr(expr) }
+	}
+}
+
+impl Expr {
+	pub(crate) fn new(kind: ExprKind, span: Span) -> Expr {
+		Expr { kind, span }
+	}
+}
+
+impl Pattern {
+	pub(crate) fn new(kind: PatternKind, span: Span) -> Pattern {
+		Pattern { kind, span }
+	}
+}
+
+impl<'a> Lowerer<'a> {
+	fn error(&mut self, span: Span, err: ParseError) {
+		self.errors.push(Error::new(false, self.file, Some(line_of(self.source, span.start)), Some(span.clone()), self.source.get(span), ErrorKind::Interpret(InterpretError::Parse(err))));
+	}
+
+	fn ident(&self, token: &Token) -> Ident {
+		Ident::new(&token.slice, token.span.clone())
+	}
+
+	/// The first name-like token directly under a node.
+	fn name(&self, node: &ConcreteSyntaxNode) -> Ident {
+		let token = node.tokens()
+			.find(|token| matches!(token.inner, TokenInner::WordIdentifier(_) | TokenInner::WordAnd | TokenInner::WordOr | TokenInner::WordXor))
+			.unwrap();
+
+		self.ident(token)
+	}
+
+	//--> Items <--
+
+	fn item(&mut self, node: &ConcreteSyntaxNode) -> Item {
+		let vis = if node.has_token(&TokenInner::WordPub) {
+			Visibility::Public
+		} else if node.has_token(&TokenInner::WordPrt) {
+			Visibility::Protected
+		} else {
+			Visibility::Private
+		};
+
+		let kind = match node.kind {
+			NodeKind::Use => ItemKind::Use(self.use_tree(node.node(NodeKind::UseTree).unwrap())),
+			NodeKind::Func => ItemKind::Func(self.func(node)),
+			NodeKind::TypeDecl => ItemKind::Type(self.type_decl(node)),
+			NodeKind::Trait => ItemKind::Trait(TraitDecl {
+				name: self.name(node),
+				generics: self.generic_params(node),
+				supertraits: node.nodes().filter(|n| is_type(n.kind)).map(|n| self.ty(n)).collect(),
+				items: self.items(node),
+			}),
+			NodeKind::Effect => ItemKind::Effect(EffectDecl {
+				name: self.name(node),
+				generics: self.generic_params(node),
+				items: self.items(node),
+			}),
+			NodeKind::Impl => {
+				let mut types: Vec<Type> = node.nodes().filter(|n| is_type(n.kind)).map(|n| self.ty(n)).collect();
+				let ty = types.pop().unwrap();
+
+				ItemKind::Impl(ImplDecl {
+					generics: self.generic_params(node),
+					trait_: types.pop(),
+					ty,
+					where_clause: self.where_clause(node),
+					items: self.items(node),
+				})
+			},
+			NodeKind::Const => ItemKind::Const(ConstDecl {
+				name: self.name(node),
+				ty: node.nodes().find(|n| is_type(n.kind)).map(|n| self.ty(n)),
+				value: node.nodes().find(|n| is_expr(n.kind)).map(|n| self.expr(n)),
+			}),
+			kind => unreachable!("{:?} is not an item", kind),
+		};
+
+		Item {
+			attrs: node.nodes().filter(|n| n.kind == NodeKind::Attribute).map(|n| self.attribute(n)).collect(),
+			vis,
+			kind,
+			def: None,
+			span: node.span(),
+		}
+	}
+
+	fn attribute(&mut self, node: &ConcreteSyntaxNode) -> Attribute {
+		Attribute {
+			name: self.name(node),
+			args: node.node(NodeKind::AttrArgs).map(|args| self.metas(args)).unwrap_or_default(),
+			span: node.span(),
+		}
+	}
+
+	fn metas(&mut self, node: &ConcreteSyntaxNode) -> Vec<Meta> {
+		node.nodes()
+			.map(|meta| {
+				let kind = if let Some(lit) = meta.node(NodeKind::Literal) {
+					MetaKind::Value(literal(&lit.tokens().next().unwrap().inner))
+				} else if let Some(args) = meta.node(NodeKind::AttrArgs) {
+					MetaKind::List(self.metas(args))
+				} else {
+					MetaKind::Word
+				};
+
+				Meta { name: self.name(meta), kind, span: meta.span() }
+			})
+			.collect()
+	}
+
+	fn items(&mut self, node: &ConcreteSyntaxNode) -> Vec<Item> {
+		node.nodes()
+			.filter(|n| is_item(n.kind))
+			.map(|n| self.item(n))
+			.collect()
+	}
+
+	fn use_tree(&mut self, node: &ConcreteSyntaxNode) -> UseTree {
+		let prefix: Vec<Ident> = node.tokens()
+			.take_while(|token| token.inner != TokenInner::WordAs && token.inner != TokenInner::SymOBracket)
+			.filter(|token| token.inner != TokenInner::SymQuad && token.inner != TokenInner::SymStar)
+			.map(|token| self.ident(token))
+			.collect();
+
+		let kind = if node.has_token(&TokenInner::SymStar) {
+			UseKind::Glob
+		} else if node.has_token(&TokenInner::SymOBracket) {
+			let mut subtrees: Vec<&ConcreteSyntaxNode> = node.nodes().collect();
+
+			// A tree after the closing bracket gets tacked onto the end of every member of the list.
+			let suffix = if node.tokens().last().is_some_and(|t| t.inner == TokenInner::SymQuad) {
+				subtrees.pop().map(|n| self.use_tree(n))
+			} else {
+				None
+			};
+
+			UseKind::List(subtrees.into_iter()
+				.map(|n| {
+					let tree = self.use_tree(n);
+					match &suffix {
+						Some(suffix) => tree.append(suffix),
+						None => tree,
+					}
+				})
+				.collect())
+		} else {
+			let alias = node.tokens()
+				.skip_while(|token| token.inner != TokenInner::WordAs)
+				.nth(1)
+				.map(|token| self.ident(token));
+
+			UseKind::Single(alias)
+		};
+
+		UseTree { prefix, kind, span: node.span() }
+	}
+
+	fn func(&mut self, node: &ConcreteSyntaxNode) -> Func {
+		Func {
+			name: self.name(node),
+			is_const: node.has_token(&TokenInner::WordConst),
+			is_extern: node.has_token(&TokenInner::WordExtern),
+			generics: self.generic_params(node),
+			params: self.params(node),
+			ret: node.node(NodeKind::RetType).map(|ret| self.ty(ret.nodes().next().unwrap())),
+			effects: self.effects(node),
+			where_clause: self.where_clause(node),
+			body: node.node(NodeKind::Block).map(|block| self.block(block)),
+		}
+	}
+
+	fn params(&mut self, node: &ConcreteSyntaxNode) -> Vec<Param> {
+		node.node(NodeKind::ParamList)
+			.map(|list| list.nodes().map(|param| Param {
+				is_mut: param.has_token(&TokenInner::WordMut),
+				name: match param.tokens().find(|t| t.inner == TokenInner::WordSelf) {
+					Some(token) => self.ident(token),
+					None => self.name(param),
+				},
+				ty: param.nodes().next().map(|n| self.ty(n)),
+				local: None,
+			}).collect())
+			.unwrap_or_default()
+	}
+
+	fn effects(&mut self, node: &ConcreteSyntaxNode) -> Vec<Type> {
+		node.node(NodeKind::EffectClause)
+			.map(|effects| effects.nodes().map(|n| self.ty(n)).collect())
+			.unwrap_or_default()
+	}
+
+	fn generic_params(&mut self, node: &ConcreteSyntaxNode) -> Vec<GenericParam> {
+		let Some(params) = node.node(NodeKind::GenericParams).or_else(|| node.node(NodeKind::CollectionName)) else { return Vec::new() };
+
+		params.nodes()
+			.map(|param| {
+				let mut bounds: Vec<Type> = param.nodes().map(|n| self.ty(n)).collect();
+				let default = if param.has_token(&TokenInner::SymEqual) { bounds.pop() } else { None };
+
+				GenericParam { name: self.name(param), bounds, default }
+			})
+			.collect()
+	}
+
+	fn where_clause(&mut self, node: &ConcreteSyntaxNode) -> Vec<WherePredicate> {
+		let Some(clause) = node.node(NodeKind::WhereClause) else { return Vec::new() };
+
+		clause.nodes()
+			.map(|predicate| {
+				let mut types = predicate.nodes().map(|n| self.ty(n));
+				let ty = types.next().unwrap();
+				WherePredicate { ty, bounds: types.collect() }
+			})
+			.collect()
+	}
+
+	fn type_decl(&mut self, node: &ConcreteSyntaxNode) -> TypeDecl {
+		let fields: Vec<Field> = node.nodes()
+			.filter(|n| n.kind == NodeKind::Field)
+			.map(|field| Field {
+				vis: if field.has_token(&TokenInner::WordPub) {
+					Visibility::Public
+				} else if field.has_token(&TokenInner::WordPrt) {
+					Visibility::Protected
+				} else {
+					Visibility::Private
+				},
+				name: self.name(field),
+				ty: self.ty(field.nodes().next().unwrap()),
+			})
+			.collect();
+
+		let variants: Vec<Variant> = node.nodes()
+			.filter(|n| n.kind == NodeKind::Variant)
+			.map(|variant| Variant {
+				name: self.name(variant),
+				fields: variant.nodes().map(|n| self.ty(n)).collect(),
+				def: None,
+			})
+			.collect();
+
+		let tuples: Vec<&ConcreteSyntaxNode> = node.nodes().filter(|n| n.kind == NodeKind::TupleFields).collect();
+
+		let kinds = [!fields.is_empty(), !tuples.is_empty(), !variants.is_empty()].iter().filter(|&&kind| kind).count();
+		let body = if kinds > 1 || tuples.len() > 1 {
+			self.error(node.span(), ParseError::Expected("only one of named fields, a tuple or variants"));
+			TypeBody::Opaque
+		} else if !fields.is_empty() {
+			TypeBody::Record(fields)
+		} else if let [tuple] = tuples[..] {
+			TypeBody::Tuple(tuple.nodes().map(|n| self.ty(n)).collect())
+		} else if !variants.is_empty() {
+			TypeBody::Union(variants)
+		} else if node.has_token(&TokenInner::SymEqual) {
+			TypeBody::Alias(self.ty(node.nodes().find(|n| is_type(n.kind)).unwrap()))
+		} else {
+			TypeBody::Opaque
+		};
+
+		// The name is whatever comes after `type`, since primitive types are named with keywords.
+		// Collection types are named after their brackets, so `[T; N]` is `[;]`.
+		let name = match node.node(NodeKind::CollectionName) {
+			Some(collection) if collection.has_token(&TokenInner::SymSemicolon) => Ident::new("[;]", collection.span()),
+			Some(collection) if collection.has_token(&TokenInner::SymColon) => Ident::new("[:]", collection.span()),
+			Some(collection) => Ident::new("[]", collection.span()),
+			None => node.tokens()
+				.skip_while(|token| token.inner != TokenInner::WordType)
+				.nth(1)
+				.map(|token| self.ident(token))
+				.unwrap(),
+		};
+
+		TypeDecl {
+			name,
+			generics: self.generic_params(node),
+			body,
+			items: self.items(node),
+		}
+	}
+
+	//--> Types <--
+
+	fn ty(&mut self, node: &ConcreteSyntaxNode) -> Type {
+		let mut types = node.nodes().filter(|n| is_type(n.kind));
+
+		let kind = match node.kind {
+			NodeKind::PathType => TypeKind::Path(self.path(node.node(NodeKind::Path).unwrap())),
+			NodeKind::NeverType => TypeKind::Never,
+			NodeKind::DynType => TypeKind::Dyn(Box::new(self.ty(types.next().unwrap()))),
+			NodeKind::ListType => TypeKind::List(Box::new(self.ty(types.next().unwrap()))),
+			NodeKind::ArrayType => {
+				let elem = self.ty(types.next().unwrap());
+				let len = self.expr(node.nodes().find(|n| is_expr(n.kind)).unwrap());
+				TypeKind::Array(Box::new(elem), Box::new(len))
+			},
+			NodeKind::MapType => {
+				let key = self.ty(types.next().unwrap());
+				let value = self.ty(types.next().unwrap());
+				TypeKind::Map(Box::new(key), Box::new(value))
+			},
+			NodeKind::FuncType => TypeKind::Func {
+				params: types.map(|n| self.ty(n)).collect(),
+				ret: node.node(NodeKind::RetType).map(|ret| Box::new(self.ty(ret.nodes().next().unwrap()))),
+				effects: self.effects(node),
+			},
+			// Without a comma, parentheses around a type are just grouping.
+			NodeKind::TupleType if !node.has_token(&TokenInner::SymComma) && node.nodes().count() == 1 => return self.ty(types.next().unwrap()),
+			NodeKind::TupleType => TypeKind::Tuple(types.map(|n| self.ty(n)).collect()),
+			NodeKind::RecordType => TypeKind::Record(node.nodes()
+				.map(|field| (self.name(field), self.ty(field.nodes().next().unwrap())))
+				.collect()),
+			kind => unreachable!("{:?} is not a type", kind),
+		};
+
+		Type { kind, span: node.span() }
+	}
+
+	fn path(&mut self, node: &ConcreteSyntaxNode) -> Path {
+		let mut segments: Vec<PathSegment> = Vec::new();
+
+		for child in node.children.iter() {
+			match child {
+				super::cstgen::ConcreteSyntaxElement::Token(token) if token.inner != TokenInner::SymQuad && !token.inner.is_trivia() => {
+					segments.push(PathSegment { name: self.ident(token), generics: Vec::new() });
+				},
+				super::cstgen::ConcreteSyntaxElement::Node(args) => {
+					let generics = args.nodes().map(|n| self.ty(n)).collect();
+					if let Some(segment) = segments.last_mut() {
+						segment.generics = generics;
+					}
+				},
+				_ => {},
+			}
+		}
+
+		Path { segments, span: node.span(), res: Res::Unresolved, resolved: 0 }
+	}
+
+	//--> Statements <--
+
+	fn block(&mut self, node: &ConcreteSyntaxNode) -> Block {
+		Block {
+			stmts: node.nodes().map(|n| self.stmt(n)).collect(),
+			span: node.span(),
+		}
+	}
+
+	fn stmt(&mut self, node: &ConcreteSyntaxNode) -> Stmt {
+		if node.kind == NodeKind::AttrStmt {
+			let inner = node.nodes().find(|n| n.kind != NodeKind::Attribute).unwrap();
+
+			return Stmt {
+				attrs: node.nodes().filter(|n| n.kind == NodeKind::Attribute).map(|n| self.attribute(n)).collect(),
+				span: node.span(),
+				..self.stmt(inner)
+			};
+		}
+
+		let kind = match node.kind {
+			NodeKind::LetStmt => {
+				let mutability = if node.has_token(&TokenInner::WordMut) {
+					Mutability::Mutable
+				} else if node.has_token(&TokenInner::WordConst) {
+					Mutability::Const
+				} else {
+					Mutability::Immutable
+				};
+
+				let mut children = node.nodes();
+				let pat = self.pattern(children.next().unwrap());
+				let ty = if node.has_token(&TokenInner::SymColon) { children.next().map(|n| self.ty(n)) } else { None };
+				let value = self.expr(children.next().unwrap());
+
+				StmtKind::Let { mutability, pat, ty, value }
+			},
+			NodeKind::AssignStmt => {
+				let mut children = node.nodes();
+				let target = self.expr(children.next().unwrap());
+				let value = self.expr(children.next().unwrap());
+
+				let op = match node.tokens().next().unwrap().inner {
+					TokenInner::SymEqual => AssignOp::Assign,
+					TokenInner::SymDotEqual => {
+						if !matches!(&value.kind, ExprKind::Call { callee, .. } if matches!(&callee.kind, ExprKind::Path(path) if path.segments.len() == 1)) {
+							self.error(value.span.clone(), ParseError::Expected("a method call after `.=`"));
+						}
+						AssignOp::Method
+					},
+					ref op => AssignOp::Compound(compound_assign_op(op)),
+				};
+
+				StmtKind::Assign { op, target, value }
+			},
+			NodeKind::WhenExpr if !node.has_token(&TokenInner::WordIn) => StmtKind::Handler(self.handler(node)),
+			_ => StmtKind::Expr(self.expr(node)),
+		};
+
+		Stmt { attrs: Vec::new(), kind, span: node.span() }
+	}
+
+	fn handler(&mut self, node: &ConcreteSyntaxNode) -> Handler {
+		Handler {
+			operation: self.path(node.node(NodeKind::PathExpr).unwrap().node(NodeKind::Path).unwrap()),
+			params: node.node(NodeKind::PatList).unwrap().nodes().map(|n| self.pattern(n)).collect(),
+			body: self.block(node.node(NodeKind::Block).unwrap()),
+			span: node.span(),
+		}
+	}
+
+	//--> Expressions <--
+
+	fn expr(&mut self, node: &ConcreteSyntaxNode) -> Expr {
+		let span = node.span();
+		let label = node.node(NodeKind::Label).map(|label| self.name(label));
+		let mut children = node.nodes().filter(|child| child.kind != NodeKind::Label);
+
+		let kind = match node.kind {
+			NodeKind::Literal => ExprKind::Lit(literal(&node.tokens().next().unwrap().inner)),
+			NodeKind::InterpStrExpr => ExprKind::Interp(node.children.iter()
+				.filter_map(|child| match child {
+					ConcreteSyntaxElement::Node(node) => Some(self.expr(node)),
+					ConcreteSyntaxElement::Token(token) => match &token.inner {
+						TokenInner::LitStrHead(text) | TokenInner::LitStrMid(text) | TokenInner::LitStrTail(text) if !text.is_empty() => {
+							Some(Expr::new(ExprKind::Lit(Literal::Str(text.clone())), token.span.clone()))
+						},
+						_ => None,
+					},
+				})
+				.collect()),
+			NodeKind::PathExpr => ExprKind::Path(self.path(node.node(NodeKind::Path).unwrap())),
+			NodeKind::ParenExpr => return self.expr(children.next().unwrap()),
+			NodeKind::CallExpr => ExprKind::Call {
+				callee: Box::new(self.expr(children.next().unwrap())),
+				args: self.args(children.next().unwrap()),
+			},
+			NodeKind::MethodCallExpr => ExprKind::MethodCall {
+				receiver: Box::new(self.expr(children.next().unwrap())),
+				method: self.name(node),
+				args: self.args(children.next().unwrap()),
+			},
+			NodeKind::FieldExpr => ExprKind::Field {
+				base: Box::new(self.expr(children.next().unwrap())),
+				field: self.name(node),
+			},
+			NodeKind::TupleFieldExpr => {
+				let base = Box::new(self.expr(children.next().unwrap()));
+				let index = match node.tokens().last().unwrap().inner {
+					TokenInner::LitNum(n) => n as usize,
+					_ => unreachable!(),
+				};
+
+				ExprKind::TupleField { base, index }
+			},
+			NodeKind::IndexExpr => ExprKind::Index {
+				base: Box::new(self.expr(children.next().unwrap())),
+				index: Box::new(self.expr(children.next().unwrap())),
+			},
+			NodeKind::TupleExpr => ExprKind::Tuple(children.map(|n| self.expr(n)).collect()),
+			NodeKind::RecordExpr => ExprKind::Record(children
+				.map(|field| (self.name(field), self.expr(field.nodes().next().unwrap())))
+				.collect()),
+			NodeKind::ListExpr => ExprKind::List(children.map(|n| self.expr(n)).collect()),
+			NodeKind::MapExpr => ExprKind::Map(children
+				.map(|entry| {
+					let mut parts = entry.nodes();
+					(self.expr(parts.next().unwrap()), self.expr(parts.next().unwrap()))
+				})
+				.collect()),
+			NodeKind::ClosureExpr => ExprKind::Closure {
+				params: self.params(node),
+				ret: node.node(NodeKind::RetType).map(|ret| Box::new(self.ty(ret.nodes().next().unwrap()))),
+				effects: self.effects(node),
+				body: self.block(node.node(NodeKind::Block).unwrap()),
+			},
+			NodeKind::UnaryExpr => ExprKind::Unary {
+				op: match node.tokens().next().unwrap().inner {
+					TokenInner::SymDash => UnaryOp::Neg,
+					TokenInner::SymPlus => UnaryOp::Plus,
+					_ => UnaryOp::Not,
+				},
+				expr: Box::new(self.expr(children.next().unwrap())),
+			},
+			NodeKind::BinaryExpr => ExprKind::Binary {
+				op: binary_op(&node.tokens().next().unwrap().inner),
+				lhs: Box::new(self.expr(children.next().unwrap())),
+				rhs: Box::new(self.expr(children.next().unwrap())),
+			},
+			NodeKind::IfExpr => {
+				let mut branches = Vec::new();
+				let mut else_ = None;
+
+				for branch in children {
+					if branch.kind == NodeKind::ElseBranch {
+						else_ = Some(self.block(branch.node(NodeKind::Block).unwrap()));
+						continue;
+					}
+
+					let mut parts = branch.nodes();
+					let cond = self.expr(parts.next().unwrap());
+					let cond = if branch.has_token(&TokenInner::WordMatches) {
+						Condition::Matches(cond, self.pattern(parts.next().unwrap()))
+					} else {
+						Condition::Expr(cond)
+					};
+
+					branches.push(IfBranch { cond, body: self.block(parts.next().unwrap()) });
+				}
+
+				ExprKind::If { branches, else_ }
+			},
+			NodeKind::MatchExpr => ExprKind::Match {
+				scrutinee: Box::new(self.expr(children.next().unwrap())),
+				arms: children
+					.map(|arm| {
+						let mut parts = arm.nodes();
+						let pat = self.pattern(parts.next().unwrap());
+						let mut body = self.block(parts.next().unwrap());
+
+						MatchArm {
+							pat,
+							body: match body.stmts.pop().map(|stmt| stmt.kind) {
+								Some(StmtKind::Expr(expr)) => expr,
+								_ => {
+									self.error(arm.span(), ParseError::Expected("an expression"));
+									Expr::new(ExprKind::Continue(None), arm.span())
+								},
+							},
+							span: arm.span(),
+						}
+					})
+					.collect(),
+			},
+			NodeKind::LoopExpr => ExprKind::Loop { label, body: self.block(children.next().unwrap()) },
+			NodeKind::BlockExpr => ExprKind::Block { label: label.unwrap(), body: self.block(children.next().unwrap()) },
+			NodeKind::WhileExpr => ExprKind::While {
+				label,
+				cond: Box::new(self.expr(children.next().unwrap())),
+				body: self.block(children.next().unwrap()),
+			},
+			NodeKind::UntilExpr => ExprKind::Until {
+				label,
+				cond: Box::new(self.expr(children.next().unwrap())),
+				body: self.block(children.next().unwrap()),
+			},
+			NodeKind::ForExpr => ExprKind::For {
+				label,
+				pat: self.pattern(children.next().unwrap()),
+				iter: Box::new(self.expr(children.next().unwrap())),
+				body: self.block(children.next().unwrap()),
+			},
+			NodeKind::WhenExpr => {
+				if !node.has_token(&TokenInner::WordIn) {
+					self.error(span.clone(), ParseError::Expected("`in` and the code to handle, since this handler is used as a value"));
+				}
+
+				ExprKind::Handle {
+					handler: Box::new(self.handler(node)),
+					body: node.nodes().filter(|n| n.kind == NodeKind::Block).nth(1).map(|n| self.block(n)).unwrap_or_else(|| Block::new(Vec::new(), span.clone())),
+				}
+			},
+			NodeKind::BreakExpr => ExprKind::Break { label, value: children.next().map(|n| Box::new(self.expr(n))) },
+			NodeKind::ContinueExpr => ExprKind::Continue(label),
+			NodeKind::ReturnExpr => ExprKind::Return(children.next().map(|n| Box::new(self.expr(n)))),
+			NodeKind::TryExpr => ExprKind::Try(Box::new(self.expr(children.next().unwrap()))),
+			NodeKind::MacroCallExpr => unreachable!("macros are expanded before parsing"),
+			kind => unreachable!("{:?} is not an expression", kind),
+		};
+
+		Expr { kind, span }
+	}
+
+	/// Named fields in the arguments are all put together into one record.
+	fn args(&mut self, node: &ConcreteSyntaxNode) -> Vec<Expr> {
+		let named = node.nodes().filter(|n| n.kind == NodeKind::RecordField).count();
+
+		if named == 0 {
+			return node.nodes().map(|n| self.expr(n)).collect();
+		}
+
+		if named < node.nodes().count() {
+			self.error(node.span(), ParseError::Expected("either arguments or named fields, but not both"));
+		}
+
+		let fields = node.nodes()
+			.filter(|n| n.kind == NodeKind::RecordField)
+			.map(|field| (self.name(field), self.expr(field.nodes().next().unwrap())))
+			.collect();
+
+		vec![Expr { kind: ExprKind::Record(fields), span: node.span() }]
+	}
+
+	//--> Patterns <--
+
+	fn pattern(&mut self, node: &ConcreteSyntaxNode) -> Pattern {
+		let kind = match node.kind {
+			NodeKind::WildcardPat => PatternKind::Wildcard,
+			NodeKind::BindingPat => PatternKind::Binding {
+				is_mut: node.has_token(&TokenInner::WordMut),
+				name: self.name(node),
+				local: None,
+			},
+			NodeKind::LiteralPat => PatternKind::Lit {
+				negated: node.has_token(&TokenInner::SymDash),
+				lit: literal(&node.tokens().last().unwrap().inner),
+			},
+			NodeKind::RangePat => {
+				// Which end is which depends on what side of the `..` it's on.
+				let mut ends = (None, None);
+				let mut past = false;
+
+				for child in node.children.iter() {
+					match child {
+						ConcreteSyntaxElement::Token(token) if matches!(token.inner, TokenInner::SymRange | TokenInner::SymRangeEqual) => past = true,
+						ConcreteSyntaxElement::Node(end) if past => ends.1 = Some(Box::new(self.pattern(end))),
+						ConcreteSyntaxElement::Node(start) => ends.0 = Some(Box::new(self.pattern(start))),
+						ConcreteSyntaxElement::Token(_) => {},
+					}
+				}
+
+				PatternKind::Range { start: ends.0, end: ends.1, inclusive: node.has_token(&TokenInner::SymRangeEqual) }
+			},
+			NodeKind::PathPat => PatternKind::Path(self.path(node.node(NodeKind::Path).unwrap())),
+			NodeKind::TupleStructPat => PatternKind::TupleStruct {
+				path: self.path(node.node(NodeKind::Path).unwrap()),
+				fields: node.node(NodeKind::PatList).unwrap().nodes().map(|n| self.pattern(n)).collect(),
+			},
+			// Without a comma, parentheses around a pattern are just grouping.
+			NodeKind::TuplePat if !node.has_token(&TokenInner::SymComma) && node.nodes().count() == 1 => return self.pattern(node.nodes().next().unwrap()),
+			NodeKind::TuplePat => PatternKind::Tuple(node.nodes().map(|n| self.pattern(n)).collect()),
+			kind => unreachable!("{:?} is not a pattern", kind),
+		};
+
+		Pattern { kind, span: node.span() }
+	}
+}
+
+impl UseTree {
+	/// Tacks another tree onto the end of this one.
+	fn append(&self, suffix: &UseTree) -> UseTree {
+		match &self.kind {
+			UseKind::List(trees) => UseTree {
+				prefix: self.prefix.clone(),
+				kind: UseKind::List(trees.iter().map(|tree| tree.append(suffix)).collect()),
+				span: self.span.clone(),
+			},
+			_ => UseTree {
+				prefix: self.prefix.iter().chain(suffix.prefix.iter()).cloned().collect(),
+				kind: suffix.kind.clone(),
+				span: self.span.start..suffix.span.end,
+			},
+		}
+	}
+}
+
+fn is_item(kind: NodeKind) -> bool {
+	matches!(kind, NodeKind::Use | NodeKind::Func | NodeKind::TypeDecl | NodeKind::Trait | NodeKind::Effect | NodeKind::Impl | NodeKind::Const)
+}
+
+fn is_type(kind: NodeKind) -> bool {
+	matches!(kind,
+		NodeKind::PathType | NodeKind::NeverType | NodeKind::DynType | NodeKind::ListType | NodeKind::ArrayType | NodeKind::MapType |
+		NodeKind::FuncType | NodeKind::TupleType | NodeKind::RecordType
+	)
+}
+
+fn is_expr(kind: NodeKind) -> bool {
+	matches!(kind,
+		NodeKind::Literal | NodeKind::InterpStrExpr | NodeKind::PathExpr | NodeKind::CallExpr | NodeKind::MethodCallExpr | NodeKind::FieldExpr |
+		NodeKind::TupleFieldExpr | NodeKind::IndexExpr | NodeKind::TupleExpr | NodeKind::RecordExpr | NodeKind::ListExpr |
+		NodeKind::MapExpr | NodeKind::ClosureExpr | NodeKind::BlockExpr | NodeKind::MacroCallExpr |
+		NodeKind::UnaryExpr | NodeKind::BinaryExpr | NodeKind::ParenExpr | NodeKind::IfExpr | NodeKind::MatchExpr |
+		NodeKind::LoopExpr | NodeKind::WhileExpr | NodeKind::UntilExpr | NodeKind::ForExpr | NodeKind::WhenExpr |
+		NodeKind::BreakExpr | NodeKind::ContinueExpr | NodeKind::ReturnExpr | NodeKind::TryExpr
+	)
+}
+
+fn literal(token: &TokenInner) -> Literal {
+	match token {
+		TokenInner::LitNum(n) => Literal::Num(*n),
+		TokenInner::LitFloat(f) => Literal::Float(*f),
+		TokenInner::LitChar(c) => Literal::Char(*c),
+		TokenInner::LitCharStr(s) => Literal::Str(s.clone()),
+		TokenInner::LitByte(b) => Literal::Byte(*b),
+		TokenInner::LitByteStr(b) => Literal::ByteStr(b.clone()),
+		TokenInner::WordTrue => Literal::Bool(true),
+		TokenInner::WordFalse => Literal::Bool(false),
+		token => unreachable!("{:?} is not a literal", token),
+	}
+}
+
+fn binary_op(token: &TokenInner) -> BinaryOp {
+	match token {
+		TokenInner::SymPlus => BinaryOp::Add,
+		TokenInner::SymDash => BinaryOp::Sub,
+		TokenInner::SymStar => BinaryOp::Mul,
+		TokenInner::SymSlash => BinaryOp::Div,
+		TokenInner::SymPercent => BinaryOp::Rem,
+		TokenInner::SymDPercent => BinaryOp::DivisibleBy,
+		TokenInner::SymAmpersand => BinaryOp::BitAnd,
+		TokenInner::SymPipe => BinaryOp::BitOr,
+		TokenInner::SymCaret => BinaryOp::BitXor,
+		TokenInner::SymDOAngle => BinaryOp::Shl,
+		TokenInner::SymDCAngle => BinaryOp::Shr,
+		TokenInner::WordAnd | TokenInner::SymDAmpersand => BinaryOp::And,
+		TokenInner::WordOr | TokenInner::SymDPipe => BinaryOp::Or,
+		TokenInner::WordXor | TokenInner::SymDCaret => BinaryOp::Xor,
+		TokenInner::SymDEqual => BinaryOp::Eq,
+		TokenInner::SymBangEqual => BinaryOp::Ne,
+		TokenInner::SymOAngle => BinaryOp::Lt,
+		TokenInner::SymLessEqual => BinaryOp::Le,
+		TokenInner::SymCAngle => BinaryOp::Gt,
+		TokenInner::SymGreaterEqual => BinaryOp::Ge,
+		TokenInner::SymRange => BinaryOp::Range,
+		TokenInner::SymRangeEqual => BinaryOp::RangeInclusive,
+		TokenInner::SymBind => BinaryOp::Bind,
+		token => unreachable!("{:?} is not a binary operator", token),
+	}
+}
+
+fn compound_assign_op(token: &TokenInner) -> BinaryOp {
+	match token {
+		TokenInner::SymPlusEqual => BinaryOp::Add,
+		TokenInner::SymDashEqual => BinaryOp::Sub,
+		TokenInner::SymStarEqual => BinaryOp::Mul,
+		TokenInner::SymSlashEqual => BinaryOp::Div,
+		TokenInner::SymPercentEqual => BinaryOp::Rem,
+		TokenInner::SymCaretEqual => BinaryOp::BitXor,
+		TokenInner::SymAmpersandEqual => BinaryOp::BitAnd,
+		TokenInner::SymPipeEqual => BinaryOp::BitOr,
+		TokenInner::SymDOAngleEqual => BinaryOp::Shl,
+		TokenInner::SymDCAngleEqual => BinaryOp::Shr,
+		token => unreachable!("{:?} is not a compound assignment operator", token),
+	}
+}
+
+impl fmt::Display for AbstractSyntaxTree {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let mut printer = Printer { f, indent: 0 };
+
+		for (i, item) in self.items.iter().enumerate() {
+			if i > 0 { writeln!(printer.f)?; }
+			printer.item(item)?;
+			writeln!(printer.f)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl fmt::Display for Path {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (i, segment) in self.segments.iter().enumerate() {
+			if i > 0 { write!(f, "::")?; }
+			write!(f, "{}", segment.name.name)?;
+
+			if !segment.generics.is_empty() {
+				write!(f, "<")?;
+				write_list(f, &segment.generics)?;
+				write!(f, ">")?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl fmt::Display for Type {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.kind {
+			TypeKind::Path(path) => write!(f, "{}", path),
+			TypeKind::Never => write!(f, "!"),
+			TypeKind::Dyn(trait_) => write!(f, "dyn {}", trait_),
+			TypeKind::List(elem) => write!(f, "[{}]", elem),
+			TypeKind::Array(elem, len) => write!(f, "[{}; {}]", elem, InlineExpr(len)),
+			TypeKind::Map(key, value) => write!(f, "[{}: {}]", key, value),
+			TypeKind::Func { params, ret, effects } => {
+				write!(f, "Func(")?;
+				write_list(f, params)?;
+				write!(f, ")")?;
+				if let Some(ret) = ret { write!(f, " -> {}", ret)?; }
+				if !effects.is_empty() {
+					write!(f, " -< ")?;
+					write_separated(f, effects, " + ")?;
+				}
+				Ok(())
+			},
+			TypeKind::Tuple(elems) => {
+				write!(f, "(")?;
+				write_list(f, elems)?;
+				write!(f, "{})", if elems.len() == 1 { "," } else { "" })
+			},
+			TypeKind::Record(fields) => {
+				write!(f, "(")?;
+				for (i, (name, ty)) in fields.iter().enumerate() {
+					if i > 0 { write!(f, ", ")?; }
+					write!(f, "{}: {}", name.name, ty)?;
+				}
+				write!(f, ")")
+			},
+		}
+	}
+}
+
+impl fmt::Display for Literal {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Literal::Num(n) => write!(f, "{}", n),
+			Literal::Float(n) => write!(f, "{:?}", n),
+			Literal::Char(c) => write!(f, "{:?}", c),
+			Literal::Str(s) => write!(f, "{:?}", s),
+			Literal::Byte(b) => write!(f, "b'\\x{:02X}'b", b),
+			Literal::ByteStr(bytes) => {
+				write!(f, "b\"")?;
+				for b in bytes { write!(f, "\\x{:02X}", b)?; }
+				write!(f, "\"b")
+			},
+			Literal::Bool(b) => write!(f, "{}", b),
+		}
+	}
+}
+
+impl fmt::Display for Pattern {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.kind {
+			PatternKind::Wildcard => write!(f, "_"),
+			PatternKind::Binding { is_mut, name, .. } => write!(f, "{}{}", if *is_mut { "mut " } else { "" }, name.name),
+			PatternKind::Lit { negated, lit } => write!(f, "{}{}", if *negated { "-" } else { "" }, lit),
+			PatternKind::Range { start, end, inclusive } => {
+				if let Some(start) = start { write!(f, "{}", start)?; }
+				write!(f, "{}", if *inclusive { "..=" } else { ".." })?;
+				if let Some(end) = end { write!(f, "{}", end)?; }
+				Ok(())
+			},
+			PatternKind::Path(path) => write!(f, "{}", path),
+			PatternKind::TupleStruct { path, fields } => {
+				write!(f, "{}(", path)?;
+				write_list(f, fields)?;
+				write!(f, ")")
+			},
+			PatternKind::Tuple(fields) => {
+				write!(f, "(")?;
+				write_list(f, fields)?;
+				write!(f, "{})", if fields.len() == 1 { "," } else { "" })
+			},
+		}
+	}
+}
+
+impl fmt::Display for Attribute {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "@{}", self.name.name)?;
+
+		if !self.args.is_empty() {
+			write!(f, "(")?;
+			write_list(f, &self.args)?;
+			write!(f, ")")?;
+		}
+
+		Ok(())
+	}
+}
+
+impl fmt::Display for Meta {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.name.name)?;
+
+		match &self.kind {
+			MetaKind::Word => Ok(()),
+			MetaKind::Value(lit) => write!(f, " = {}", lit),
+			MetaKind::List(args) => {
+				write!(f, "(")?;
+				write_list(f, args)?;
+				write!(f, ")")
+			},
+		}
+	}
+}
+
+impl UnaryOp {
+	pub(crate) fn as_str(&self) -> &'static str {
+		match self {
+			UnaryOp::Neg => "-",
+			UnaryOp::Plus => "+",
+			UnaryOp::Not => "!",
+		}
+	}
+}
+
+impl BinaryOp {
+	pub(crate) fn as_str(&self) -> &'static str {
+		match self {
+			BinaryOp::Add => "+",
+			BinaryOp::Sub => "-",
+			BinaryOp::Mul => "*",
+			BinaryOp::Div => "/",
+			BinaryOp::Rem => "%",
+			BinaryOp::DivisibleBy => "%%",
+			BinaryOp::BitAnd => "&",
+			BinaryOp::BitOr => "|",
+			BinaryOp::BitXor => "^",
+			BinaryOp::Shl => "<<",
+			BinaryOp::Shr => ">>",
+			BinaryOp::And => "and",
+			BinaryOp::Or => "or",
+			BinaryOp::Xor => "xor",
+			BinaryOp::Eq => "==",
+			BinaryOp::Ne => "!=",
+			BinaryOp::Lt => "<",
+			BinaryOp::Le => "<=",
+			BinaryOp::Gt => ">",
+			BinaryOp::Ge => ">=",
+			BinaryOp::Range => "..",
+			BinaryOp::RangeInclusive => "..=",
+			BinaryOp::Bind => ">=>",
+		}
+	}
+}
+
+impl fmt::Display for InlineExpr<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		Printer { f, indent: 0 }.expr(self.0)
+	}
+}
+
+fn write_list<T: fmt::Display>(f: &mut fmt::Formatter<'_>, list: &[T]) -> fmt::Result {
+	write_separated(f, list, ", ")
+}
+
+fn write_separated<T: fmt::Display>(f: &mut fmt::Formatter<'_>, list: &[T], separator: &str) -> fmt::Result {
+	for (i, elem) in list.iter().enumerate() {
+		if i > 0 { write!(f, "{}", separator)?; }
+		write!(f, "{}", elem)?;
+	}
+
+	Ok(())
+}
+
+impl Printer<'_, '_> {
+	fn newline(&mut self) -> fmt::Result {
+		writeln!(self.f)?;
+		for _ in 0..self.indent { write!(self.f, "\t")?; }
+		Ok(())
+	}
+
+	fn item(&mut self, item: &Item) -> fmt::Result {
+		for attr in item.attrs.iter() {
+			write!(self.f, "{}", attr)?;
+			self.newline()?;
+		}
+
+		match item.vis {
+			Visibility::Public => write!(self.f, "pub ")?,
+			Visibility::Protected => write!(self.f, "prt ")?,
+			Visibility::Private => {},
+		}
+
+		match &item.kind {
+			ItemKind::Use(tree) => {
+				write!(self.f, "use ")?;
+				self.use_tree(tree)
+			},
+			ItemKind::Func(func) => self.func(func),
+			ItemKind::Type(decl) => {
+				match (decl.name.name.as_str(), &decl.generics[..]) {
+					("[]", [elem]) => write!(self.f, "type [{}]", elem.name.name)?,
+					("[;]", [elem, len]) => write!(self.f, "type [{}; {}]", elem.name.name, len.name.name)?,
+					("[:]", [key, value]) => write!(self.f, "type [{}: {}]", key.name.name, value.name.name)?,
+					_ => {
+						write!(self.f, "type {}", decl.name.name)?;
+						self.generic_params(&decl.generics)?;
+					},
+				}
+
+				if let TypeBody::Alias(ty) = &decl.body {
+					return write!(self.f, " = {}", ty);
+				}
+
+				if matches!(decl.body, TypeBody::Opaque) && decl.items.is_empty() {
+					return Ok(());
+				}
+
+				write!(self.f, " is")?;
+				self.indent += 1;
+
+				match &decl.body {
+					TypeBody::Record(fields) => for field in fields {
+						self.newline()?;
+						match field.vis {
+							Visibility::Public => write!(self.f, "pub ")?,
+							Visibility::Protected => write!(self.f, "prt ")?,
+							Visibility::Private => {},
+						}
+						write!(self.f, "{}: {}", field.name.name, field.ty)?;
+					},
+					TypeBody::Tuple(fields) => {
+						self.newline()?;
+						write!(self.f, "(")?;
+						write_list(self.f, fields)?;
+						write!(self.f, ")")?;
+					},
+					TypeBody::Union(variants) => for variant in variants {
+						self.newline()?;
+						write!(self.f, "| {}", variant.name.name)?;
+						if !variant.fields.is_empty() {
+							write!(self.f, "(")?;
+							write_list(self.f, &variant.fields)?;
+							write!(self.f, ")")?;
+						}
+					},
+					_ => {},
+				}
+
+				self.member_items(&decl.items)
+			},
+			ItemKind::Trait(decl) => {
+				write!(self.f, "trait {}", decl.name.name)?;
+				self.generic_params(&decl.generics)?;
+				if !decl.supertraits.is_empty() {
+					write!(self.f, ": ")?;
+					write_separated(self.f, &decl.supertraits, " + ")?;
+				}
+				write!(self.f, " is")?;
+				self.indent += 1;
+				self.member_items(&decl.items)
+			},
+			ItemKind::Effect(decl) => {
+				write!(self.f, "effect {}", decl.name.name)?;
+				self.generic_params(&decl.generics)?;
+				write!(self.f, " is")?;
+				self.indent += 1;
+				self.member_items(&decl.items)
+			},
+			ItemKind::Impl(decl) => {
+				write!(self.f, "impl")?;
+				self.generic_params(&decl.generics)?;
+				if let Some(trait_) = &decl.trait_ { write!(self.f, " {} for", trait_)?; }
+				write!(self.f, " {}", decl.ty)?;
+				self.where_clause(&decl.where_clause)?;
+				write!(self.f, "{}is", if decl.where_clause.is_empty() { " " } else { "" })?;
+				self.indent += 1;
+				self.member_items(&decl.items)
+			},
+			ItemKind::Const(decl) => {
+				write!(self.f, "const {}", decl.name.name)?;
+				match (&decl.ty, &decl.value) {
+					(Some(ty), Some(value)) => {
+						write!(self.f, ": {} = ", ty)?;
+						self.expr(value)
+					},
+					(Some(ty), None) => write!(self.f, ": {}", ty),
+					(None, Some(value)) => {
+						write!(self.f, " := ")?;
+						self.expr(value)
+					},
+					(None, None) => Ok(()),
+				}
+			},
+		}
+	}
+
+	/// Prints the items inside a type, trait, effect, or impl, and closes it off. Expects the indent to have been bumped already.
+	fn member_items(&mut self, items: &[Item]) -> fmt::Result {
+		for item in items {
+			self.newline()?;
+			self.item(item)?;
+		}
+
+		self.indent -= 1;
+		self.newline()?;
+		write!(self.f, "end")
+	}
+
+	fn use_tree(&mut self, tree: &UseTree) -> fmt::Result {
+		let prefix: Vec<&str> = tree.prefix.iter().map(|ident| ident.name.as_str()).collect();
+		write!(self.f, "{}", prefix.join("::"))?;
+
+		match &tree.kind {
+			UseKind::Single(Some(alias)) => write!(self.f, " as {}", alias.name),
+			UseKind::Single(None) => Ok(()),
+			UseKind::Glob => write!(self.f, "{}*", if prefix.is_empty() { "" } else { "::" }),
+			UseKind::List(trees) => {
+				write!(self.f, "{}[", if prefix.is_empty() { "" } else { "::" })?;
+				for (i, tree) in trees.iter().enumerate() {
+					if i > 0 { write!(self.f, ", ")?; }
+					self.use_tree(tree)?;
+				}
+				write!(self.f, "]")
+			},
+		}
+	}
+
+	fn func(&mut self, func: &Func) -> fmt::Result {
+		if func.is_const { write!(self.f, "const ")?; }
+		if func.is_extern { write!(self.f, "extern ")?; }
+
+		write!(self.f, "func {}", func.name.name)?;
+		self.generic_params(&func.generics)?;
+		self.signature(&func.params, func.ret.as_ref(), &func.effects)?;
+
+		self.where_clause(&func.where_clause)?;
+
+		match &func.body {
+			Some(body) => {
+				write!(self.f, "{}do", if func.where_clause.is_empty() { " " } else { "" })?;
+				self.block(body)?;
+				write!(self.f, "end")
+			},
+			None => Ok(()),
+		}
+	}
+
+	/// Prints the parameters, return type and effects of a function or closure.
+	fn signature(&mut self, params: &[Param], ret: Option<&Type>, effects: &[Type]) -> fmt::Result {
+		write!(self.f, "(")?;
+
+		for (i, param) in params.iter().enumerate() {
+			if i > 0 { write!(self.f, ", ")?; }
+			if param.is_mut { write!(self.f, "mut ")?; }
+			write!(self.f, "{}", param.name.name)?;
+			if let Some(ty) = &param.ty { write!(self.f, ": {}", ty)?; }
+		}
+
+		write!(self.f, ")")?;
+
+		if let Some(ret) = ret { write!(self.f, " -> {}", ret)?; }
+
+		if !effects.is_empty() {
+			write!(self.f, " -< ")?;
+			write_separated(self.f, effects, " + ")?;
+		}
+
+		Ok(())
+	}
+
+	fn generic_params(&mut self, generics: &[GenericParam]) -> fmt::Result {
+		if generics.is_empty() { return Ok(()) }
+
+		write!(self.f, "<")?;
+		for (i, param) in generics.iter().enumerate() {
+			if i > 0 { write!(self.f, ", ")?; }
+			write!(self.f, "{}", param.name.name)?;
+			if !param.bounds.is_empty() {
+				write!(self.f, ": ")?;
+				write_separated(self.f, &param.bounds, " + ")?;
+			}
+			if let Some(default) = &param.default { write!(self.f, " = {}", default)?; }
+		}
+		write!(self.f, ">")
+	}
+
+	/// Prints a where clause, leaving the cursor at the start of the line after it.
+	fn where_clause(&mut self, predicates: &[WherePredicate]) -> fmt::Result {
+		if predicates.is_empty() { return Ok(()) }
+
+		write!(self.f, " where")?;
+		self.indent += 1;
+		for predicate in predicates {
+			self.newline()?;
+			write!(self.f, "{}: ", predicate.ty)?;
+			write_separated(self.f, &predicate.bounds, " + ")?;
+		}
+		self.indent -= 1;
+		self.newline()
+	}
+
+	/// Prints the statements of a block on their own lines, leaving the cursor at the start of the line after them.
+	fn block(&mut self, block: &Block) -> fmt::Result {
+		self.indent += 1;
+		for stmt in block.stmts.iter() {
+			self.newline()?;
+			self.stmt(stmt)?;
+		}
+		self.indent -= 1;
+		self.newline()
+	}
+
+	fn label(&mut self, label: Option<&Ident>) -> fmt::Result {
+		match label {
+			Some(label) => write!(self.f, "`{} ", label.name),
+			None => Ok(()),
+		}
+	}
+
+	fn stmt(&mut self, stmt: &Stmt) -> fmt::Result {
+		for attr in stmt.attrs.iter() {
+			write!(self.f, "{}", attr)?;
+			self.newline()?;
+		}
+
+		match &stmt.kind {
+			StmtKind::Let { mutability, pat, ty, value } => {
+				match mutability {
+					Mutability::Immutable => {},
+					Mutability::Mutable => write!(self.f, "mut ")?,
+					Mutability::Const => write!(self.f, "const ")?,
+				}
+				write!(self.f, "{}", pat)?;
+				match ty {
+					Some(ty) => write!(self.f, ": {} = ", ty)?,
+					None => write!(self.f, " := ")?,
+				}
+				self.expr(value)
+			},
+			StmtKind::Assign { op, target, value } => {
+				self.expr(target)?;
+				match op {
+					AssignOp::Assign => write!(self.f, " = ")?,
+					AssignOp::Compound(op) => write!(self.f, " {}= ", op.as_str())?,
+					AssignOp::Method => write!(self.f, " .= ")?,
+				}
+				self.expr(value)
+			},
+			StmtKind::Expr(expr) => self.expr(expr),
+			StmtKind::Handler(handler) => {
+				self.handler(handler)?;
+				write!(self.f, "end")
+			},
+		}
+	}
+
+	fn handler(&mut self, handler: &Handler) -> fmt::Result {
+		write!(self.f, "when {}(", handler.operation)?;
+		write_list(self.f, &handler.params)?;
+		write!(self.f, ") do")?;
+		self.block(&handler.body)
+	}
+
+	/// Prints an operand of an operator, wrapping it in parentheses if it's an operator expression itself.
+	fn operand(&mut self, expr: &Expr) -> fmt::Result {
+		if matches!(expr.kind, ExprKind::Binary { .. } | ExprKind::Unary { .. }) {
+			write!(self.f, "(")?;
+			self.expr(expr)?;
+			write!(self.f, ")")
+		} else {
+			self.expr(expr)
+		}
+	}
+
+	fn args(&mut self, args: &[Expr]) -> fmt::Result {
+		// A record on its own is written as named fields, which parse back into the same thing.
+		if let [arg @ Expr { kind: ExprKind::Record(fields), .. }] = args {
+			if !fields.is_empty() { return self.expr(arg) }
+		}
+
+		write!(self.f, "(")?;
+		for (i, arg) in args.iter().enumerate() {
+			if i > 0 { write!(self.f, ", ")?; }
+			self.expr(arg)?;
+		}
+		write!(self.f, ")")
+	}
+
+	fn expr(&mut self, expr: &Expr) -> fmt::Result {
+		match &expr.kind {
+			ExprKind::Lit(lit) => write!(self.f, "{}", lit),
+			ExprKind::Path(path) => write!(self.f, "{}", path),
+			ExprKind::Call { callee, args } => {
+				self.operand(callee)?;
+				self.args(args)
+			},
+			ExprKind::MethodCall { receiver, method, args } => {
+				self.operand(receiver)?;
+				write!(self.f, ".{}", method.name)?;
+				self.args(args)
+			},
+			ExprKind::Field { base, field } => {
+				self.operand(base)?;
+				write!(self.f, ".{}", field.name)
+			},
+			ExprKind::TupleField { base, index } => {
+				self.operand(base)?;
+				write!(self.f, ".{}", index)
+			},
+			ExprKind::Index { base, index } => {
+				self.operand(base)?;
+				write!(self.f, "[")?;
+				self.expr(index)?;
+				write!(self.f, "]")
+			},
+			ExprKind::Tuple(elems) => {
+				write!(self.f, "(")?;
+				for (i, elem) in elems.iter().enumerate() {
+					if i > 0 { write!(self.f, ", ")?; }
+					self.expr(elem)?;
+				}
+				write!(self.f, "{})", if elems.len() == 1 { "," } else { "" })
+			},
+			ExprKind::Record(fields) => {
+				write!(self.f, "(")?;
+				for (i, (name, value)) in fields.iter().enumerate() {
+					if i > 0 { write!(self.f, ", ")?; }
+					write!(self.f, "{}: ", name.name)?;
+					self.expr(value)?;
+				}
+				write!(self.f, ")")
+			},
+			ExprKind::Interp(parts) => {
+				write!(self.f, "\"")?;
+				for part in parts.iter() {
+					match &part.kind {
+						ExprKind::Lit(Literal::Str(text)) => {
+							let quoted = format!("{:?}", text);
+							write!(self.f, "{}", &quoted[1..quoted.len() - 1])?;
+						},
+						_ => {
+							write!(self.f, "\\{{")?;
+							self.expr(part)?;
+							write!(self.f, "}}")?;
+						},
+					}
+				}
+				write!(self.f, "\"")
+			},
+			ExprKind::List(elems) => {
+				write!(self.f, "[")?;
+				for (i, elem) in elems.iter().enumerate() {
+					if i > 0 { write!(self.f, ", ")?; }
+					self.expr(elem)?;
+				}
+				write!(self.f, "]")
+			},
+			ExprKind::Map(entries) => {
+				write!(self.f, "[")?;
+				if entries.is_empty() { write!(self.f, ":")?; }
+				for (i, (key, value)) in entries.iter().enumerate() {
+					if i > 0 { write!(self.f, ", ")?; }
+					self.expr(key)?;
+					write!(self.f, ": ")?;
+					self.expr(value)?;
+				}
+				write!(self.f, "]")
+			},
+			ExprKind::Closure { params, ret, effects, body } => {
+				self.signature(params, ret.as_deref(), effects)?;
+				write!(self.f, " do")?;
+				self.block(body)?;
+				write!(self.f, "end")
+			},
+			ExprKind::Unary { op, expr } => {
+				write!(self.f, "{}", op.as_str())?;
+				self.operand(expr)
+			},
+			ExprKind::Binary { op: op @ (BinaryOp::Range | BinaryOp::RangeInclusive), lhs, rhs } => {
+				self.operand(lhs)?;
+				write!(self.f, "{}", op.as_str())?;
+				self.operand(rhs)
+			},
+			ExprKind::Binary { op, lhs, rhs } => {
+				self.operand(lhs)?;
+				write!(self.f, " {} ", op.as_str())?;
+				self.operand(rhs)
+			},
+			ExprKind::If { branches, else_ } => {
+				for (i, branch) in branches.iter().enumerate() {
+					write!(self.f, "{} ", if i == 0 { "if" } else { "elif" })?;
+					match &branch.cond {
+						Condition::Expr(cond) => self.expr(cond)?,
+						Condition::Matches(scrutinee, pat) => {
+							self.expr(scrutinee)?;
+							write!(self.f, " matches {}", pat)?;
+						},
+					}
+					write!(self.f, " then")?;
+					self.block(&branch.body)?;
+				}
+				if let Some(else_) = else_ {
+					write!(self.f, "else")?;
+					self.block(else_)?;
+				}
+				write!(self.f, "end")
+			},
+			ExprKind::Match { scrutinee, arms } => {
+				write!(self.f, "if ")?;
+				self.expr(scrutinee)?;
+				write!(self.f, " matches")?;
+				self.indent += 1;
+				for arm in arms {
+					self.newline()?;
+					write!(self.f, "{} then ", arm.pat)?;
+					self.expr(&arm.body)?;
+				}
+				self.indent -= 1;
+				self.newline()?;
+				write!(self.f, "end")
+			},
+			ExprKind::Loop { label, body } => {
+				self.label(label.as_ref())?;
+				write!(self.f, "always do")?;
+				self.block(body)?;
+				write!(self.f, "end")
+			},
+			ExprKind::While { label, cond, body } | ExprKind::Until { label, cond, body } => {
+				self.label(label.as_ref())?;
+				write!(self.f, "{} ", if matches!(expr.kind, ExprKind::While { .. }) { "while" } else { "until" })?;
+				self.expr(cond)?;
+				write!(self.f, " do")?;
+				self.block(body)?;
+				write!(self.f, "end")
+			},
+			ExprKind::For { label, pat, iter, body } => {
+				self.label(label.as_ref())?;
+				write!(self.f, "for {} in ", pat)?;
+				self.expr(iter)?;
+				write!(self.f, " do")?;
+				self.block(body)?;
+				write!(self.f, "end")
+			},
+			ExprKind::Block { label, body } => {
+				self.label(Some(label))?;
+				write!(self.f, "do")?;
+				self.block(body)?;
+				write!(self.f, "end")
+			},
+			ExprKind::Handle { handler, body } => {
+				self.handler(handler)?;
+				write!(self.f, "in")?;
+				self.block(body)?;
+				write!(self.f, "end")
+			},
+			ExprKind::Break { label, value } => {
+				write!(self.f, "break")?;
+				if let Some(label) = label {
+					write!(self.f, " `{}", label.name)?;
+				}
+				if let Some(value) = value {
+					write!(self.f, " ")?;
+					self.expr(value)?;
+				}
+				Ok(())
+			},
+			ExprKind::Return(value) => {
+				write!(self.f, "return")?;
+				if let Some(value) = value {
+					write!(self.f, " ")?;
+					self.expr(value)?;
+				}
+				Ok(())
+			},
+			ExprKind::Continue(label) => match label {
+				Some(label) => write!(self.f, "continue `{}", label.name),
+				None => write!(self.f, "continue"),
+			},
+			ExprKind::Try(inner) => {
+				self.operand(inner)?;
+				write!(self.f, "?")
+			},
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compiler::{
+		lexer::TokenStream,
+		parser::ParseLimits,
+	};
+
+	fn parse(source: &str) -> AbstractSyntaxTree {
+		let tokens = TokenStream::lex(None, source, &ParseLimits::default()).unwrap();
+		let (cst, _) = ConcreteSyntaxTree::new(None, source, tokens, &ParseLimits::default()).unwrap();
+		AbstractSyntaxTree::new(&cst, source).unwrap()
+	}
+
+	/// The expression on the right of the first statement in `main`.
+	fn value(source: &str) -> Expr {
+		let tree = parse(&format!("func main() do\n\tx := {}\nend\n", source));
+		let ItemKind::Func(func) = &tree.items[0].kind else { panic!("expected a function") };
+
+		match &func.body.as_ref().unwrap().stmts[0].kind {
+			StmtKind::Let { value, .. } => value.clone(),
+			kind => panic!("expected a let statement, got {:?}", kind),
+		}
+	}
+
+	#[test]
+	fn operators_bind_by_precedence() {
+		let ExprKind::Binary { op: BinaryOp::And, lhs, .. } = value("1 + 2 * 3 == 7 and ok").kind else { panic!("expected `and` on the outside") };
+		let ExprKind::Binary { op: BinaryOp::Eq, lhs, .. } = lhs.kind else { panic!("expected `==` under `and`") };
+		let ExprKind::Binary { op: BinaryOp::Add, rhs, .. } = lhs.kind else { panic!("expected `+` under `==`") };
+
+		assert!(matches!(rhs.kind, ExprKind::Binary { op: BinaryOp::Mul, .. }));
+	}
+
+	#[test]
+	fn use_lists_share_their_prefix() {
+		let tree = parse("use std::[io, error::*]\n");
+		let ItemKind::Use(tree) = &tree.items[0].kind else { panic!("expected an import") };
+		let UseKind::List(members) = &tree.kind else { panic!("expected a list") };
+
+		assert_eq!(tree.prefix.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(), ["std"]);
+		assert!(matches!(&members[0].kind, UseKind::Single(None)));
+		assert!(matches!(&members[1].kind, UseKind::Glob));
+	}
+
+	#[test]
+	fn if_chains_keep_every_branch() {
+		let ExprKind::If { branches, else_ } = value("if a then 1 elif b matches Some(n) then n else 0").kind else {
+			panic!("expected an if");
+		};
+
+		assert_eq!(branches.len(), 2);
+		assert!(matches!(&branches[0].cond, Condition::Expr(_)));
+		assert!(matches!(&branches[1].cond, Condition::Matches(_, pat) if matches!(pat.kind, PatternKind::TupleStruct { .. })));
+		assert!(else_.is_some());
+	}
+
+	#[test]
+	fn matches_with_arms_is_a_match() {
+		let ExprKind::Match { arms, .. } = value("if n matches\n\t\t1..=9 then 1\n\t\t_ then 0\n\tend").kind else {
+			panic!("expected a match");
+		};
+
+		assert!(matches!(arms[0].pat.kind, PatternKind::Range { inclusive: true, .. }));
+		assert!(matches!(arms[1].pat.kind, PatternKind::Wildcard));
+	}
+
+	#[test]
+	fn assignments_keep_their_operator() {
+		let tree = parse("func main() do\n\tx = 1\n\tx += 1\n\tx .= f()\nend\n");
+		let ItemKind::Func(func) = &tree.items[0].kind else { panic!("expected a function") };
+
+		let ops: Vec<AssignOp> = func.body.as_ref().unwrap().stmts.iter().map(|stmt| match &stmt.kind {
+			StmtKind::Assign { op, .. } => *op,
+			kind => panic!("expected an assignment, got {:?}", kind),
+		}).collect();
+
+		assert_eq!(ops, [AssignOp::Assign, AssignOp::Compound(BinaryOp::Add), AssignOp::Method]);
+	}
+
+	#[test]
+	fn syntax_errors_are_reported() {
+		for source in ["func main( do\nend\n", "func main() do\n\tx := (1 + \nend\n"] {
+			let tokens = TokenStream::lex(None, source, &ParseLimits::default()).unwrap();
+			assert!(ConcreteSyntaxTree::new(None, source, tokens, &ParseLimits::default()).is_err(), "{:?} parsed", source);
+		}
+	}
+
+	#[test]
+	fn parentheses_group_or_make_tuples() {
+		assert!(matches!(value("(1 + 2)").kind, ExprKind::Binary { .. }));
+		assert!(matches!(value("(1,)").kind, ExprKind::Tuple(elems) if elems.len() == 1));
+		assert!(matches!(value("(1, 2)").kind, ExprKind::Tuple(elems) if elems.len() == 2));
+		assert!(matches!(value("()").kind, ExprKind::Tuple(elems) if elems.is_empty()));
+	}
+
+	#[test]
+	fn records_can_span_lines() {
+		let ExprKind::Record(fields) = value("(\n\temployer: \"Banana Incorporated\" # not a real company\n\ttitle: \"Developer\"\n)").kind else {
+			panic!("expected a record");
+		};
+
+		let names: Vec<&str> = fields.iter().map(|(name, _)| name.name.as_str()).collect();
+		assert_eq!(names, ["employer", "title"]);
+	}
+
+	#[test]
+	fn closures_take_untyped_params() {
+		let ExprKind::Closure { params, body, .. } = value("(n, mut c: char) do n + 1").kind else { panic!("expected a closure") };
+
+		assert_eq!(params.len(), 2);
+		assert!(params[0].ty.is_none());
+		assert!(params[1].is_mut && params[1].ty.is_some());
+		assert_eq!(body.stmts.len(), 1);
+	}
+
+	#[test]
+	fn loop_headers_are_not_closures() {
+		let tree = parse("func main() do\n\twhile (x) do\n\t\tx = f(() do 1)\n\tend\nend\n");
+		assert_eq!(tree.to_string(), "func main() do\n\twhile x do\n\t\tx = f(() do\n\t\t\t1\n\t\tend)\n\tend\nend\n");
+	}
+
+	#[test]
+	fn indexing_and_tuple_fields() {
+		assert_eq!(InlineExpr(&value("chain[-1]")).to_string(), "chain[-1]");
+		assert_eq!(InlineExpr(&value("pair.0.1")).to_string(), "pair.0.1");
+		assert!(matches!(value("pair.0.1").kind, ExprKind::TupleField { index: 1, .. }));
+	}
+
+	#[test]
+	fn interpolated_strings() {
+		let expr = value(r#""\{a + 1} of \{f("\{b}")}\n""#);
+		let ExprKind::Interp(parts) = &expr.kind else { panic!("expected an interpolated string, got {:?}", expr.kind) };
+
+		assert_eq!(parts.len(), 4);
+		assert!(matches!(&parts[1].kind, ExprKind::Lit(Literal::Str(text)) if text == " of "));
+		assert_eq!(InlineExpr(&expr).to_string(), r#""\{a + 1} of \{f("\{b}")}\n""#);
+	}
+
+	#[test]
+	fn lists_and_maps() {
+		assert!(matches!(value("[1, 2, 3]").kind, ExprKind::List(elems) if elems.len() == 3));
+		assert!(matches!(value("[]").kind, ExprKind::List(elems) if elems.is_empty()));
+		assert!(matches!(value("[:]").kind, ExprKind::Map(entries) if entries.is_empty()));
+		assert!(matches!(value("[\"one\": 1, \"two\": 2]").kind, ExprKind::Map(entries) if entries.len() == 2));
+	}
+
+	#[test]
+	fn tuple_and_record_types_and_patterns() {
+		let tree = parse("func f(pair: (nat, str), person: (name: str, age: nat)) do\n\t(a, b) := pair\nend\n");
+		assert_eq!(tree.to_string(), "func f(pair: (nat, str), person: (name: str, age: nat)) do\n\t(a, b) := pair\nend\n");
+	}
+
+	#[test]
+	fn ranges_in_patterns_can_leave_an_end_off() {
+		let tree = parse("func f(n: int) do\n\tif n matches\n\t\t..=-1 then 0\n\t\t0..10 then 1\n\t\t10.. then 2\n\tend\nend\n");
+		let ItemKind::Func(func) = &tree.items[0].kind else { panic!("expected a function") };
+		let StmtKind::Expr(expr) = &func.body.as_ref().unwrap().stmts[0].kind else { panic!("expected an expression") };
+		let ExprKind::Match { arms, .. } = &expr.kind else { panic!("expected a match") };
+
+		let ranges: Vec<(bool, bool, String)> = arms.iter().map(|arm| match &arm.pat.kind {
+			PatternKind::Range { start, end, .. } => (start.is_some(), end.is_some(), arm.pat.to_string()),
+			_ => panic!("expected a range"),
+		}).collect();
+
+		assert_eq!(ranges, [(false, true, "..=-1".to_string()), (true, true, "0..10".to_string()), (true, false, "10..".to_string())]);
+	}
+
+	#[test]
+	fn dyn_is_only_a_keyword_before_a_trait() {
+		let tree = parse("func f(widgets: [dyn ui::Widget<nat>], other: dyn) do\nend\n");
+		let ItemKind::Func(func) = &tree.items[0].kind else { panic!("expected a function") };
 
-//--> Functions <--
\ No newline at end of file
+		assert!(matches!(&func.params[0].ty.as_ref().unwrap().kind, TypeKind::List(elem) if matches!(elem.kind, TypeKind::Dyn(_))));
+		assert!(matches!(&func.params[1].ty.as_ref().unwrap().kind, TypeKind::Path(path) if path.to_string() == "dyn"));
+		assert_eq!(tree.to_string(), "func f(widgets: [dyn ui::Widget<nat>], other: dyn) do\nend\n");
+	}
+}
@@ -6,6 +6,7 @@ use crate::{
 	ErrorList,
 	compiler::{
 		InterpretError,
+		line_of,
 		lexer::{
 			Token,
 			TokenInner,
@@ -14,9 +15,17 @@ use crate::{
 	},
 };
 
-use super::ParseError;
+use super::{
+	Limit,
+	ParseError,
+	ParseLimits,
+	check_limits,
+};
+
+use logos::Span;
 
 use std::{
+	mem::discriminant,
 	path::{
 		Path,
 		PathBuf,
@@ -27,56 +36,194 @@ use std::{
 
 pub(crate) type Result = std::result::Result<(ConcreteSyntaxTree, ErrorList), ErrorList>;
 
-type ContextStack = Vec<ConcreteSyntaxNode>;
-
 //--> Structs <--
 
+/// A syntax tree which keeps every token from the source, comments and newlines included.
 #[derive(Clone, Debug)]
 pub(crate) struct ConcreteSyntaxTree {
-	pub file: PathBuf,
+	pub file: Option<PathBuf>,
 	pub root: ConcreteSyntaxNode
 }
 
 #[derive(Clone, Debug)]
 pub(crate) struct ConcreteSyntaxNode {
-	pub token: Token,
-	pub lhs: Option<Box<ConcreteSyntaxNode>>,
-	pub rhs: Option<Box<ConcreteSyntaxNode>>,
+	pub kind: NodeKind,
+	pub children: Vec<ConcreteSyntaxElement>,
+}
+
+/// A hand-written recursive descent parser which builds up a concrete syntax tree as it goes.
+struct Parser<'a> {
+	file: Option<&'a Path>,
+	source: &'a str,
+	tokens: Vec<Token>,
+	pos: usize,
+	/// Nodes which have been started but not yet finished, innermost last.
+	stack: Vec<ConcreteSyntaxNode>,
+	errors: ErrorList,
+	/// Set while parsing the header of a `while`, `until` or `for` loop, where `(...) do` starts the loop body rather than a closure.
+	no_closures: bool,
+	limits: &'a ParseLimits,
+	/// How tall the tallest finished child of each node in `stack` is, first leaving out the links of chains like `a + b + c`
+	/// and then counting them.
+	heights: Vec<(usize, usize)>,
+	/// Set once a limit has been gone past, after which parsing stops.
+	limited: bool,
+}
+
+//--> Enums <--
+
+#[derive(Clone, Debug)]
+pub(crate) enum ConcreteSyntaxElement {
+	Node(ConcreteSyntaxNode),
+	Token(Token),
+}
+
+/// What a concrete syntax node represents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NodeKind {
+	/// The whole file.
+	Root,
+	/// Tokens the parser couldn't make sense of.
+	Error,
+
+	// Items
+	Attribute,
+	AttrArgs,
+	Meta,
+	Use,
+	UseTree,
+	Func,
+	GenericParams,
+	GenericParam,
+	ParamList,
+	Param,
+	RetType,
+	EffectClause,
+	WhereClause,
+	WherePredicate,
+	TypeDecl,
+	/// The name of a built-in collection type, like `[T]`, `[T; N]` or `[K: V]`.
+	CollectionName,
+	Variant,
+	Field,
+	/// The `(A, B)` in the body of a tuple type.
+	TupleFields,
+	Trait,
+	Effect,
+	Impl,
+	Const,
+	/// `macro name is ... end`, which only the formatter ever sees, since macros are expanded before parsing.
+	MacroDef,
+	MacroRule,
+	/// Balanced brackets and whatever is inside of them, which only mean something once a macro is expanded.
+	TokenTree,
+
+	// Types
+	Path,
+	GenericArgs,
+	PathType,
+	NeverType,
+	DynType,
+	ListType,
+	ArrayType,
+	MapType,
+	FuncType,
+	TupleType,
+	RecordType,
+
+	// Statements
+	Block,
+	/// `` `name ``, in front of a loop or block, or after `break` or `continue`.
+	Label,
+	LetStmt,
+	AssignStmt,
+	/// A statement with attributes in front of it, like `@cfg(gpu)`.
+	AttrStmt,
+
+	// Expressions
+	Literal,
+	/// A string with expressions interpolated into it, like `"\{name} is \{age}"`.
+	InterpStrExpr,
+	PathExpr,
+	CallExpr,
+	ArgList,
+	MethodCallExpr,
+	FieldExpr,
+	UnaryExpr,
+	BinaryExpr,
+	ParenExpr,
+	TupleExpr,
+	RecordExpr,
+	RecordField,
+	ListExpr,
+	MapExpr,
+	MapEntry,
+	IndexExpr,
+	TupleFieldExpr,
+	ClosureExpr,
+	BlockExpr,
+	MacroCallExpr,
+	IfExpr,
+	IfBranch,
+	ElseBranch,
+	MatchExpr,
+	MatchArm,
+	LoopExpr,
+	WhileExpr,
+	UntilExpr,
+	ForExpr,
+	WhenExpr,
+	BreakExpr,
+	ContinueExpr,
+	ReturnExpr,
+	TryExpr,
+
+	// Patterns
+	WildcardPat,
+	BindingPat,
+	LiteralPat,
+	RangePat,
+	PathPat,
+	TupleStructPat,
+	TuplePat,
+	PatList,
 }
 
 //--> Functions <--
 
 impl ConcreteSyntaxTree {
-	pub(crate) fn new(path: &Path, mut tokens: TokenStream) -> Result {
+	pub(crate) fn new(path: Option<&Path>, source: &str, tokens: TokenStream, limits: &ParseLimits) -> Result {
+		check_limits(path, source, &tokens, limits)?;
+
 		if tokens.0.is_empty() {
 			return Err(vec![
 				// TODO: I should probably simplify how ErrorKind works. Nesting enums seemed like a good idea at first, but not any more...
-				Error::new(false, Some(path), None, None, None, ErrorKind::Interpret(InterpretError::Parse(ParseError::NoTokens)))
+				Error::new(false, path, None, None, None, ErrorKind::Interpret(InterpretError::Parse(ParseError::NoTokens)))
 			])
 		}
-		
-		let file = path.to_path_buf();
-		let mut errors = ErrorList::new();
-
-		// Going to use the tokens as a stack.
-		tokens.0.reverse();
-
-		let mut context = ContextStack::new();
 
-		loop {
-			let node = ConcreteSyntaxNode::new(tokens.0.pop().unwrap());
-
-			// TODO: How the fuck do I write a parser????
+		let mut parser = Parser {
+			file: path,
+			source,
+			tokens: tokens.0,
+			pos: 0,
+			stack: vec![ConcreteSyntaxNode::new(NodeKind::Root)],
+			errors: ErrorList::new(),
+			no_closures: false,
+			limits,
+			heights: vec![(0, 0)],
+			limited: false,
+		};
 
-			context.push(node);
+		parser.parse_root();
 
-			if tokens.0.is_empty() { break; }
-		}
+		let root = parser.stack.pop().unwrap();
+		let errors = parser.errors;
 
 		if errors.is_empty() || errors.iter().all(|e| e.is_warning()) {
 			Ok((ConcreteSyntaxTree {
-				file,
-				root: context.pop().unwrap()
+				file: path.map(|p| p.to_path_buf()),
+				root
 			}, errors))
 		} else {
 			Err(errors)
@@ -85,33 +232,1968 @@ impl ConcreteSyntaxTree {
 }
 
 impl ConcreteSyntaxNode {
-	pub(crate) fn new(token: Token) -> ConcreteSyntaxNode {
+	pub(crate) fn new(kind: NodeKind) -> ConcreteSyntaxNode {
 		ConcreteSyntaxNode {
-			token,
-			lhs: None,
-			rhs: None,
+			kind,
+			children: Vec::new(),
 		}
 	}
 
-	pub(crate) fn lefthand(self, lhs: ConcreteSyntaxNode) -> ConcreteSyntaxNode {
-		ConcreteSyntaxNode {
-			lhs: Some(Box::new(lhs)),
-			..self
+	/// The child nodes of this node, in order.
+	pub(crate) fn nodes(&self) -> impl Iterator<Item = &ConcreteSyntaxNode> {
+		self.children.iter().filter_map(|child| match child {
+			ConcreteSyntaxElement::Node(node) => Some(node),
+			ConcreteSyntaxElement::Token(_) => None,
+		})
+	}
+
+	/// The tokens directly under this node, leaving out comments and newlines.
+	pub(crate) fn tokens(&self) -> impl Iterator<Item = &Token> {
+		self.children.iter().filter_map(|child| match child {
+			ConcreteSyntaxElement::Token(token) if !token.inner.is_trivia() => Some(token),
+			_ => None,
+		})
+	}
+
+	/// The first child node of the given kind.
+	pub(crate) fn node(&self, kind: NodeKind) -> Option<&ConcreteSyntaxNode> {
+		self.nodes().find(|node| node.kind == kind)
+	}
+
+	/// Whether a token like the given one sits directly under this node.
+	pub(crate) fn has_token(&self, inner: &TokenInner) -> bool {
+		self.tokens().any(|token| discriminant(&token.inner) == discriminant(inner))
+	}
+
+	/// The first and last significant tokens anywhere under this node.
+	fn bounds(&self) -> Option<(&Token, &Token)> {
+		let mut first = None;
+		let mut last = None;
+
+		for child in self.children.iter() {
+			let found = match child {
+				ConcreteSyntaxElement::Node(node) => node.bounds(),
+				ConcreteSyntaxElement::Token(token) if !token.inner.is_trivia() => Some((token, token)),
+				ConcreteSyntaxElement::Token(_) => None,
+			};
+
+			if let Some((start, end)) = found {
+				if first.is_none() { first = Some(start); }
+				last = Some(end);
+			}
 		}
+
+		Some((first?, last?))
 	}
 
-	pub(crate) fn righthand(self, rhs: ConcreteSyntaxNode) -> ConcreteSyntaxNode {
-		ConcreteSyntaxNode {
-			rhs: Some(Box::new(rhs)),
-			..self
+	/// The span of source text covered by this node.
+	pub(crate) fn span(&self) -> Span {
+		match self.bounds() {
+			Some((first, last)) => first.span.start..last.span.end,
+			None => 0..0,
+		}
+	}
+}
+
+impl TokenInner {
+	/// Trivia is anything which doesn't affect the meaning of the code around it.
+	pub(crate) fn is_trivia(&self) -> bool {
+		matches!(self, TokenInner::Comment | TokenInner::SymNewline)
+	}
+
+	pub(crate) fn is_literal(&self) -> bool {
+		matches!(self,
+			TokenInner::LitChar(_) | TokenInner::LitCharStr(_) | TokenInner::LitByte(_) | TokenInner::LitByteStr(_) |
+			TokenInner::LitNum(_) | TokenInner::LitFloat(_)
+		)
+	}
+}
+
+impl<'a> Parser<'a> {
+	//--> Token Handling <--
+
+	/// The position of the `n`th token from here which isn't a comment.
+	fn nth_pos(&self, n: usize) -> Option<usize> {
+		self.tokens.iter()
+			.enumerate()
+			.skip(self.pos)
+			.filter(|(_, token)| token.inner != TokenInner::Comment)
+			.nth(n)
+			.map(|(pos, _)| pos)
+	}
+
+	fn nth(&self, n: usize) -> Option<&TokenInner> {
+		self.nth_pos(n).map(|pos| &self.tokens[pos].inner)
+	}
+
+	fn current(&self) -> Option<&Token> {
+		self.nth_pos(0).map(|pos| &self.tokens[pos])
+	}
+
+	fn nth_at(&self, n: usize, inner: &TokenInner) -> bool {
+		self.nth(n).is_some_and(|token| discriminant(token) == discriminant(inner))
+	}
+
+	fn at(&self, inner: &TokenInner) -> bool {
+		self.nth_at(0, inner)
+	}
+
+	fn at_eof(&self) -> bool {
+		self.current().is_none()
+	}
+
+	/// Whether the current token is an identifier, or a keyword that's allowed to be used as a name.
+	fn at_name(&self) -> bool {
+		matches!(self.nth(0), Some(TokenInner::WordIdentifier(_) | TokenInner::WordAnd | TokenInner::WordOr | TokenInner::WordXor))
+	}
+
+	/// Whether the current token is an identifier with the given text, which is how contextual keywords like `where` are handled.
+	fn at_contextual(&self, word: &str) -> bool {
+		matches!(self.nth(0), Some(TokenInner::WordIdentifier(ident)) if ident == word)
+	}
+
+	/// Whether the current token can start a path.
+	fn at_path_start(&self) -> bool {
+		self.at_name() || self.at_primitive_type() || matches!(self.nth(0), Some(
+			TokenInner::WordSelf | TokenInner::WordSelfType | TokenInner::WordSuper | TokenInner::WordPkg
+		))
+	}
+
+	/// Whether the current token is the name of a primitive type, like `nat`.
+	fn at_primitive_type(&self) -> bool {
+		matches!(self.nth(0), Some(
+			TokenInner::WordBoolType | TokenInner::WordByteType | TokenInner::WordNatType | TokenInner::WordIntType |
+			TokenInner::WordFloType | TokenInner::WordCharType | TokenInner::WordStrType
+		))
+	}
+
+	fn at_literal(&self) -> bool {
+		self.nth(0).is_some_and(|token| token.is_literal() || matches!(token, TokenInner::WordTrue | TokenInner::WordFalse))
+	}
+
+	/// Whether the current token can start an expression.
+	fn at_expr_start(&self) -> bool {
+		self.at_literal() || self.at_path_start() || matches!(self.nth(0), Some(
+			TokenInner::LitStrHead(_) | TokenInner::SymOParen | TokenInner::SymOBracket | TokenInner::SymDash | TokenInner::SymPlus | TokenInner::SymBang |
+			TokenInner::SymBacktick | TokenInner::WordIf | TokenInner::WordLoop | TokenInner::WordWhile | TokenInner::WordUntil | TokenInner::WordFor |
+			TokenInner::WordWhen | TokenInner::WordBreak | TokenInner::WordContinue | TokenInner::WordReturn
+		))
+	}
+
+	/// Whether the current token can start a type.
+	fn at_type_start(&self) -> bool {
+		self.at_path_start() || self.at(&TokenInner::SymBang) || self.at(&TokenInner::SymOBracket) || self.at(&TokenInner::SymOParen)
+	}
+
+	/// If the current token opens a bracket, gives how many tokens ahead the bracket which closes it is.
+	fn closing_bracket(&self) -> Option<usize> {
+		let mut depth = 0usize;
+		let mut n = 0;
+
+		loop {
+			match self.nth(n)? {
+				TokenInner::SymOParen | TokenInner::SymOBracket | TokenInner::SymOBrace => depth += 1,
+				TokenInner::SymCParen | TokenInner::SymCBracket | TokenInner::SymCBrace => {
+					depth = depth.checked_sub(1)?;
+					if depth == 0 { return Some(n) }
+				},
+				_ if depth == 0 => return None,
+				_ => {},
+			}
+
+			n += 1;
+		}
+	}
+
+	/// Whether the current token starts a closure, which is a parenthesised parameter list followed by `do`, `->` or `-<`.
+	fn at_closure_start(&self) -> bool {
+		if !self.at(&TokenInner::SymOParen) { return false }
+		let Some(end) = self.closing_bracket() else { return false };
+
+		match self.nth(end + 1) {
+			Some(TokenInner::WordDo) => !self.no_closures,
+			Some(TokenInner::SymThinArrow | TokenInner::SymWeirdArrow) => true,
+			_ => false,
+		}
+	}
+
+	/// Whether the current token starts an anonymous record, like `(name: "Ashton", age: 22)`.
+	fn at_record_start(&self) -> bool {
+		if !self.at(&TokenInner::SymOParen) { return false }
+
+		let mut n = 1;
+		while self.nth_at(n, &TokenInner::SymNewline) { n += 1; }
+
+		matches!(self.nth(n), Some(TokenInner::WordIdentifier(_))) && self.nth_at(n + 1, &TokenInner::SymColon)
+	}
+
+	/// Moves the current token (and any comments before it) into the node being built.
+	fn bump(&mut self) {
+		while let Some(token) = self.tokens.get(self.pos) {
+			let is_comment = token.inner == TokenInner::Comment;
+			self.push_token(self.pos);
+			self.pos += 1;
+
+			if !is_comment { break }
+		}
+	}
+
+	fn push_token(&mut self, pos: usize) {
+		let token = self.tokens[pos].clone();
+		self.stack.last_mut().unwrap().children.push(ConcreteSyntaxElement::Token(token));
+	}
+
+	fn eat(&mut self, inner: &TokenInner) -> bool {
+		if self.at(inner) {
+			self.bump();
+			true
+		} else {
+			false
+		}
+	}
+
+	/// Eats the given token, or reports an error without consuming anything if it isn't there.
+	fn expect(&mut self, inner: &TokenInner, what: &'static str) -> bool {
+		if self.eat(inner) {
+			true
+		} else {
+			self.error(ParseError::Expected(what));
+			false
+		}
+	}
+
+	fn expect_name(&mut self) -> bool {
+		if self.at_name() {
+			self.bump();
+			true
+		} else {
+			self.error(ParseError::Expected("a name"));
+			false
+		}
+	}
+
+	/// Eats a closing angle bracket, splitting up `>>` and friends if that's what we have.
+	fn expect_closing_angle(&mut self) -> bool {
+		if let Some(pos) = self.nth_pos(0) {
+			let rest = match self.tokens[pos].inner {
+				TokenInner::SymDCAngle => Some(TokenInner::SymCAngle),
+				TokenInner::SymGreaterEqual => Some(TokenInner::SymEqual),
+				TokenInner::SymDCAngleEqual => Some(TokenInner::SymGreaterEqual),
+				_ => None,
+			};
+
+			if let Some(rest) = rest {
+				let token = self.tokens[pos].clone();
+				let split = token.span.start + 1;
+
+				self.tokens[pos] = Token { inner: TokenInner::SymCAngle, span: token.span.start..split, slice: token.slice[..1].into() };
+				self.tokens.insert(pos + 1, Token { inner: rest, span: split..token.span.end, slice: token.slice[1..].into() });
+			}
+		}
+
+		self.expect(&TokenInner::SymCAngle, "`>`")
+	}
+
+	fn skip_newlines(&mut self) {
+		while self.at(&TokenInner::SymNewline) {
+			self.bump();
+		}
+	}
+
+	/// Skips over statement separators: newlines and semicolons.
+	fn skip_separators(&mut self) {
+		while self.at(&TokenInner::SymNewline) || self.at(&TokenInner::SymSemicolon) {
+			self.bump();
+		}
+	}
+
+	/// Whether the next significant token after any newlines is the given one.
+	fn at_after_newlines(&self, inner: &TokenInner) -> bool {
+		let mut n = 0;
+		while self.nth_at(n, &TokenInner::SymNewline) { n += 1; }
+		self.nth_at(n, inner)
+	}
+
+	//--> Tree Building <--
+
+	// Every level of recursion in the parser starts a node, so keeping an eye on how deep the nodes go keeps the parser from overflowing its stack.
+	// Nodes wrapped around earlier ones can make the tree deeper than that though, so how tall each node is gets tracked too,
+	// which keeps everything walking over the tree later on safe as well. Chains like `a + b + c` and `x.f().g()` are wrapped like that
+	// without being nested in the source, so they're counted apart from everything else, with a limit of their own.
+
+	fn start(&mut self, kind: NodeKind) {
+		self.stack.push(ConcreteSyntaxNode::new(kind));
+		self.heights.push((0, 0));
+
+		if self.stack.len() - 1 > self.limits.max_depth {
+			let span = self.current().map_or(self.source.len()..self.source.len(), |token| token.span.clone());
+			self.exceeded(span, Limit::Depth(self.limits.max_depth));
+		}
+	}
+
+	fn finish(&mut self) {
+		let node = self.stack.pop().unwrap();
+		let (nested, total) = self.heights.pop().unwrap();
+		let link = matches!(node.kind, NodeKind::BinaryExpr | NodeKind::CallExpr | NodeKind::IndexExpr | NodeKind::TryExpr | NodeKind::TupleFieldExpr | NodeKind::MethodCallExpr | NodeKind::FieldExpr);
+		let height = (if link { nested } else { nested + 1 }, total + 1);
+
+		if self.stack.len() + height.0 - 1 > self.limits.max_depth {
+			self.exceeded(node.span(), Limit::Depth(self.limits.max_depth));
+		} else if self.stack.len() + height.1 - 1 > self.limits.max_depth + self.limits.max_chain {
+			// In a link, what comes right after the wrapped part is the operator, `.` or `(` which made the chain one longer.
+			let at = match link {
+				true => node.children.iter().skip(1).find_map(|child| match child {
+					ConcreteSyntaxElement::Node(inner) => inner.bounds().map(|(first, _)| first),
+					ConcreteSyntaxElement::Token(token) => (!token.inner.is_trivia()).then_some(token),
+				}),
+				false => node.bounds().map(|(first, _)| first),
+			};
+
+			let span = at.map_or_else(|| node.span(), |token| token.span.clone());
+			self.exceeded(span, Limit::Chain(self.limits.max_chain));
+		}
+
+		let parent = self.heights.last_mut().unwrap();
+		*parent = (parent.0.max(height.0), parent.1.max(height.1));
+
+		self.stack.last_mut().unwrap().children.push(ConcreteSyntaxElement::Node(node));
+	}
+
+	/// Marks a spot in the current node, so that a node can be wrapped around everything after it later on.
+	fn checkpoint(&self) -> usize {
+		self.stack.last().unwrap().children.len()
+	}
+
+	/// Starts a node which takes in everything in the current node from the checkpoint onwards.
+	fn start_at(&mut self, checkpoint: usize, kind: NodeKind) {
+		let children = self.stack.last_mut().unwrap().children.split_off(checkpoint);
+		self.stack.push(ConcreteSyntaxNode { kind, children });
+
+		// We don't know exactly how tall the children we took are, but they can't be any taller than the tallest child of the node they came from.
+		let height = *self.heights.last().unwrap();
+		self.heights.push(height);
+	}
+
+	//--> Error Handling <--
+
+	fn error(&mut self, err: ParseError) {
+		if self.limited { return }
+
+		let (span, slice, err) = match self.current() {
+			Some(token) => (token.span.clone(), Some(token.slice.clone()), err),
+			None => (self.source.len()..self.source.len(), None, ParseError::UnexpectedEnd),
+		};
+
+		let line = line_of(self.source, span.start);
+
+		// One error per line is plenty; anything after the first is usually just fallout from it.
+		if self.errors.last().is_some_and(|e| e.line() == Some(line)) { return }
+
+		self.errors.push(Error::new(false, self.file, Some(line), Some(span), slice.as_deref(), ErrorKind::Interpret(InterpretError::Parse(err))));
+	}
+
+	/// Reports that the code is nested too deeply or chained on for too long, and stops parsing by skipping to the end of the tokens.
+	fn exceeded(&mut self, span: Span, limit: Limit) {
+		if self.limited { return }
+
+		let err = ParseError::LimitExceeded(limit);
+		let slice = self.source.get(span.clone());
+		self.errors.push(Error::new(false, self.file, Some(line_of(self.source, span.start)), Some(span), slice, ErrorKind::Interpret(InterpretError::Parse(err))));
+
+		self.limited = true;
+		self.pos = self.tokens.len();
+	}
+
+	/// Reports an error and moves the offending token into an error node, so that parsing can carry on past it.
+	fn error_and_bump(&mut self, err: ParseError) {
+		self.error(err);
+
+		if !self.at_eof() {
+			self.start(NodeKind::Error);
+			self.bump();
+			self.finish();
+		}
+	}
+
+	/// Skips everything up to the end of the line.
+	fn recover(&mut self) {
+		if self.at_eof() || self.at(&TokenInner::SymNewline) { return }
+
+		self.start(NodeKind::Error);
+		while !self.at_eof() && !self.at(&TokenInner::SymNewline) {
+			self.bump();
+		}
+		self.finish();
+	}
+
+	//--> Items <--
+
+	fn parse_root(&mut self) {
+		loop {
+			self.skip_separators();
+			if self.at_eof() { break }
+
+			self.parse_item();
+
+			if !self.at_eof() && !self.at(&TokenInner::SymNewline) && !self.at(&TokenInner::SymSemicolon) {
+				self.error(ParseError::Expected("a new line"));
+				self.recover();
+			}
+		}
+
+		// Trailing comments still need a home.
+		while self.pos < self.tokens.len() {
+			self.push_token(self.pos);
+			self.pos += 1;
+		}
+	}
+
+	/// Whether the current token can start an item.
+	fn at_item_start(&self) -> bool {
+		matches!(self.nth(0), Some(
+			TokenInner::SymDecorator | TokenInner::WordPub | TokenInner::WordPrt | TokenInner::WordConst | TokenInner::WordExtern |
+			TokenInner::WordFunc | TokenInner::WordType | TokenInner::WordTrait | TokenInner::WordEffect |
+			TokenInner::WordImpl | TokenInner::WordUse
+		)) || self.at_macro_def()
+	}
+
+	/// Whether the current token starts a macro definition, like `macro assert is`.
+	fn at_macro_def(&self) -> bool {
+		self.at_contextual("macro") && matches!(self.nth(1), Some(TokenInner::WordIdentifier(_))) && self.nth_at(2, &TokenInner::WordIs)
+	}
+
+	fn parse_item(&mut self) {
+		let checkpoint = self.checkpoint();
+
+		while self.at(&TokenInner::SymDecorator) {
+			self.parse_attribute();
+			self.skip_newlines();
+		}
+
+		let _ = self.eat(&TokenInner::WordPub) || self.eat(&TokenInner::WordPrt);
+		let is_const = self.eat(&TokenInner::WordConst);
+		self.eat(&TokenInner::WordExtern);
+
+		match self.nth(0) {
+			Some(TokenInner::WordFunc) => self.parse_func(checkpoint),
+			Some(TokenInner::WordType) => self.parse_type_decl(checkpoint),
+			Some(TokenInner::WordTrait) => self.parse_trait(checkpoint),
+			Some(TokenInner::WordEffect) => self.parse_effect(checkpoint),
+			Some(TokenInner::WordImpl) => self.parse_impl(checkpoint),
+			Some(TokenInner::WordUse) => self.parse_use(checkpoint),
+			_ if is_const && self.at_name() => self.parse_const(checkpoint),
+			_ if self.at_macro_def() => self.parse_macro_def(checkpoint),
+			_ => {
+				self.start_at(checkpoint, NodeKind::Error);
+				self.error(ParseError::Expected("an item"));
+				self.finish();
+				self.recover();
+			}
+		}
+	}
+
+	fn parse_macro_def(&mut self, checkpoint: usize) {
+		self.start_at(checkpoint, NodeKind::MacroDef);
+		self.bump();
+		self.expect_name();
+		self.expect(&TokenInner::WordIs, "`is`");
+
+		loop {
+			self.skip_separators();
+			if self.at(&TokenInner::WordEnd) || self.at_eof() { break }
+
+			if self.at(&TokenInner::SymOParen) {
+				self.start(NodeKind::MacroRule);
+				self.parse_token_tree();
+				self.expect(&TokenInner::WordThen, "`then`");
+
+				if self.at(&TokenInner::SymOBrace) {
+					self.parse_token_tree();
+				} else {
+					self.error(ParseError::Expected("a macro body in braces"));
+				}
+
+				self.finish();
+			} else {
+				self.error(ParseError::Expected("a macro rule like `(...) then { ... }`"));
+				self.recover();
+			}
+		}
+
+		self.expect(&TokenInner::WordEnd, "`end`");
+		self.finish();
+	}
+
+	/// Parses a pair of brackets and everything inside of them, without caring what it means.
+	fn parse_token_tree(&mut self) {
+		let close = match self.nth(0) {
+			Some(TokenInner::SymOParen) => TokenInner::SymCParen,
+			Some(TokenInner::SymOBracket) => TokenInner::SymCBracket,
+			_ => TokenInner::SymCBrace,
+		};
+
+		self.start(NodeKind::TokenTree);
+		self.bump();
+
+		loop {
+			match self.nth(0) {
+				None => break,
+				Some(token) if *token == close => break,
+				Some(TokenInner::SymOParen | TokenInner::SymOBracket | TokenInner::SymOBrace) => self.parse_token_tree(),
+				Some(TokenInner::SymCParen | TokenInner::SymCBracket | TokenInner::SymCBrace) => self.error_and_bump(ParseError::UnexpectedToken),
+				_ => self.bump(),
+			}
+		}
+
+		self.expect(&close, match close {
+			TokenInner::SymCParen => "`)`",
+			TokenInner::SymCBracket => "`]`",
+			_ => "`}`",
+		});
+		self.finish();
+	}
+
+	/// Parses an attribute like `@builtin`, `@allow(unused)` or `@cfg(feature = "x")`.
+	fn parse_attribute(&mut self) {
+		self.start(NodeKind::Attribute);
+		self.bump();
+		self.expect_name();
+
+		if self.at(&TokenInner::SymOParen) {
+			self.parse_attr_args();
 		}
+
+		self.finish();
+	}
+
+	fn parse_attr_args(&mut self) {
+		self.start(NodeKind::AttrArgs);
+		self.bump();
+
+		loop {
+			self.skip_newlines();
+			if !self.at_name() { break }
+
+			self.start(NodeKind::Meta);
+			self.bump();
+
+			if self.eat(&TokenInner::SymEqual) {
+				if self.at_literal() {
+					self.start(NodeKind::Literal);
+					self.bump();
+					self.finish();
+				} else {
+					self.error(ParseError::Expected("a literal"));
+				}
+			} else if self.at(&TokenInner::SymOParen) {
+				self.parse_attr_args();
+			}
+
+			self.finish();
+			self.skip_newlines();
+
+			if !self.eat(&TokenInner::SymComma) { break }
+		}
+
+		self.expect(&TokenInner::SymCParen, "`)`");
+		self.finish();
+	}
+
+	fn parse_use(&mut self, checkpoint: usize) {
+		self.start_at(checkpoint, NodeKind::Use);
+		self.bump();
+		self.parse_use_tree();
+		self.finish();
+	}
+
+	fn parse_use_tree(&mut self) {
+		self.start(NodeKind::UseTree);
+
+		loop {
+			if self.eat(&TokenInner::SymStar) { break }
+
+			if self.eat(&TokenInner::SymOBracket) {
+				loop {
+					self.skip_newlines();
+					if self.at(&TokenInner::SymCBracket) || self.at_eof() { break }
+
+					let pos = self.pos;
+					self.parse_use_tree();
+					self.skip_newlines();
+
+					if !self.eat(&TokenInner::SymComma) && !self.at(&TokenInner::SymCBracket) {
+						self.error(ParseError::Expected("`,` or `]`"));
+						if self.pos == pos { self.error_and_bump(ParseError::UnexpectedToken); }
+						break;
+					}
+				}
+				self.expect(&TokenInner::SymCBracket, "`]`");
+
+				// Anything after the list applies to every member of it.
+				if self.eat(&TokenInner::SymQuad) {
+					self.parse_use_tree();
+				}
+				break;
+			}
+
+			if self.at_path_start() {
+				self.bump();
+			} else {
+				self.error(ParseError::Expected("a module or item name"));
+				break;
+			}
+
+			if !self.eat(&TokenInner::SymQuad) { break }
+		}
+
+		if self.eat(&TokenInner::WordAs) {
+			self.expect_name();
+		}
+
+		self.finish();
+	}
+
+	fn parse_func(&mut self, checkpoint: usize) {
+		self.start_at(checkpoint, NodeKind::Func);
+		self.bump();
+		self.expect_name();
+
+		if self.at(&TokenInner::SymOAngle) {
+			self.parse_generic_params();
+		}
+
+		if self.at(&TokenInner::SymOParen) {
+			self.parse_param_list(true);
+		} else {
+			self.error(ParseError::Expected("a parameter list"));
+		}
+
+		if self.at(&TokenInner::SymThinArrow) {
+			self.start(NodeKind::RetType);
+			self.bump();
+			self.parse_type();
+			self.finish();
+		}
+
+		if self.at(&TokenInner::SymWeirdArrow) {
+			self.parse_effect_clause();
+		}
+
+		if self.at_contextual("where") {
+			self.parse_where_clause();
+		}
+
+		if self.at(&TokenInner::WordDo) {
+			self.parse_block(true);
+		}
+
+		self.finish();
+	}
+
+	fn parse_generic_params(&mut self) {
+		self.start(NodeKind::GenericParams);
+		self.bump();
+
+		loop {
+			self.skip_newlines();
+			if !self.at_name() { break }
+
+			self.start(NodeKind::GenericParam);
+			self.bump();
+
+			if self.eat(&TokenInner::SymColon) {
+				self.parse_bounds();
+			}
+
+			if self.eat(&TokenInner::SymEqual) {
+				self.parse_type();
+			}
+
+			self.finish();
+
+			if !self.eat(&TokenInner::SymComma) { break }
+		}
+
+		self.expect_closing_angle();
+		self.finish();
 	}
 
-	pub(crate) fn with_lefthand(&mut self, lhs: ConcreteSyntaxNode) {
-		self.lhs = Some(Box::new(lhs));
+	/// Parses one or more types separated by `+`.
+	fn parse_bounds(&mut self) {
+		self.parse_type();
+
+		while self.eat(&TokenInner::SymPlus) {
+			self.parse_type();
+		}
 	}
 
-	pub(crate) fn with_righthand(&mut self, rhs: ConcreteSyntaxNode) {
-		self.rhs = Some(Box::new(rhs));
+	/// Parses a parameter list. Closures can leave out the types of their parameters, functions can't.
+	fn parse_param_list(&mut self, require_types: bool) {
+		self.start(NodeKind::ParamList);
+		self.bump();
+
+		loop {
+			self.skip_newlines();
+			if self.at(&TokenInner::SymCParen) || self.at_eof() { break }
+
+			self.start(NodeKind::Param);
+			self.eat(&TokenInner::WordMut);
+
+			if !self.eat(&TokenInner::WordSelf) && self.expect_name() {
+				if self.eat(&TokenInner::SymColon) {
+					self.parse_type();
+				} else if require_types {
+					self.error(ParseError::Expected("`:` and the parameter's type"));
+				}
+			}
+
+			self.finish();
+			self.skip_newlines();
+
+			if !self.eat(&TokenInner::SymComma) { break }
+		}
+
+		self.expect(&TokenInner::SymCParen, "`)`");
+		self.finish();
 	}
-}
\ No newline at end of file
+
+	fn parse_effect_clause(&mut self) {
+		self.start(NodeKind::EffectClause);
+		self.bump();
+		self.parse_bounds();
+		self.finish();
+	}
+
+	fn parse_where_clause(&mut self) {
+		self.start(NodeKind::WhereClause);
+		self.bump();
+
+		loop {
+			while self.at(&TokenInner::SymNewline) || self.at(&TokenInner::SymComma) {
+				self.bump();
+			}
+
+			if !self.at_type_start() { break }
+
+			self.start(NodeKind::WherePredicate);
+			self.parse_type();
+			self.expect(&TokenInner::SymColon, "`:` and the bounds");
+			self.parse_bounds();
+			self.finish();
+		}
+
+		self.finish();
+	}
+
+	fn parse_type_decl(&mut self, checkpoint: usize) {
+		self.start_at(checkpoint, NodeKind::TypeDecl);
+		self.bump();
+
+		// The standard library declares the primitive types, whose names are keywords, and the collection types.
+		if self.at_primitive_type() {
+			self.bump();
+		} else if self.at(&TokenInner::SymOBracket) {
+			self.parse_collection_name();
+		} else {
+			self.expect_name();
+		}
+
+		if self.at(&TokenInner::SymOAngle) {
+			self.parse_generic_params();
+		}
+
+		if self.eat(&TokenInner::SymEqual) {
+			self.parse_type();
+		} else if self.eat(&TokenInner::WordIs) {
+			loop {
+				self.skip_separators();
+				if self.at(&TokenInner::WordEnd) || self.at_eof() { break }
+
+				let pos = self.pos;
+
+				if self.at(&TokenInner::SymPipe) {
+					self.parse_variant();
+				} else if self.at(&TokenInner::SymOParen) {
+					self.parse_tuple_fields();
+				} else if self.at_field_start() {
+					self.parse_field();
+				} else if self.at_item_start() {
+					self.parse_item();
+				} else {
+					self.error(ParseError::Expected("a variant, field or item"));
+					self.recover();
+				}
+
+				if self.pos == pos { self.error_and_bump(ParseError::UnexpectedToken); }
+			}
+
+			self.expect(&TokenInner::WordEnd, "`end`");
+		}
+
+		self.finish();
+	}
+
+	/// Parses `[T]`, `[T; N]` or `[K: V]` as the name of a type declaration, where each name is a generic parameter.
+	fn parse_collection_name(&mut self) {
+		self.start(NodeKind::CollectionName);
+		self.bump();
+
+		self.start(NodeKind::GenericParam);
+		self.expect_name();
+		self.finish();
+
+		if self.eat(&TokenInner::SymSemicolon) || self.eat(&TokenInner::SymColon) {
+			self.start(NodeKind::GenericParam);
+			self.expect_name();
+			self.finish();
+		}
+
+		self.expect(&TokenInner::SymCBracket, "`]`");
+		self.finish();
+	}
+
+	fn parse_variant(&mut self) {
+		self.start(NodeKind::Variant);
+		self.bump();
+		self.expect_name();
+
+		if self.eat(&TokenInner::SymOParen) {
+			loop {
+				self.skip_newlines();
+				if !self.at_type_start() { break }
+
+				self.parse_type();
+				self.skip_newlines();
+
+				if !self.eat(&TokenInner::SymComma) { break }
+			}
+
+			self.expect(&TokenInner::SymCParen, "`)`");
+		}
+
+		self.finish();
+	}
+
+	/// Parses the types of a tuple type's fields, like `(nat, nat)`.
+	fn parse_tuple_fields(&mut self) {
+		self.start(NodeKind::TupleFields);
+		self.bump();
+
+		loop {
+			self.skip_newlines();
+			if !self.at_type_start() { break }
+
+			self.parse_type();
+			self.skip_newlines();
+
+			if !self.eat(&TokenInner::SymComma) { break }
+		}
+
+		self.expect(&TokenInner::SymCParen, "`)`");
+		self.finish();
+	}
+
+	fn at_field_start(&self) -> bool {
+		let n = if self.at(&TokenInner::WordPub) || self.at(&TokenInner::WordPrt) { 1 } else { 0 };
+		matches!(self.nth(n), Some(TokenInner::WordIdentifier(_))) && self.nth_at(n + 1, &TokenInner::SymColon)
+	}
+
+	fn parse_field(&mut self) {
+		self.start(NodeKind::Field);
+		let _ = self.eat(&TokenInner::WordPub) || self.eat(&TokenInner::WordPrt);
+		self.bump();
+		self.bump();
+		self.parse_type();
+		self.finish();
+	}
+
+	/// Parses the items between `is` and `end` in a trait, effect, or impl.
+	fn parse_item_body(&mut self) {
+		if !self.expect(&TokenInner::WordIs, "`is`") { return }
+
+		loop {
+			self.skip_separators();
+			if self.at(&TokenInner::WordEnd) || self.at_eof() { break }
+
+			let pos = self.pos;
+			self.parse_item();
+			if self.pos == pos { self.error_and_bump(ParseError::UnexpectedToken); }
+		}
+
+		self.expect(&TokenInner::WordEnd, "`end`");
+	}
+
+	fn parse_trait(&mut self, checkpoint: usize) {
+		self.start_at(checkpoint, NodeKind::Trait);
+		self.bump();
+		self.expect_name();
+
+		if self.at(&TokenInner::SymOAngle) {
+			self.parse_generic_params();
+		}
+
+		if self.eat(&TokenInner::SymColon) {
+			self.parse_bounds();
+		}
+
+		self.parse_item_body();
+		self.finish();
+	}
+
+	fn parse_effect(&mut self, checkpoint: usize) {
+		self.start_at(checkpoint, NodeKind::Effect);
+		self.bump();
+		self.expect_name();
+
+		if self.at(&TokenInner::SymOAngle) {
+			self.parse_generic_params();
+		}
+
+		self.parse_item_body();
+		self.finish();
+	}
+
+	fn parse_impl(&mut self, checkpoint: usize) {
+		self.start_at(checkpoint, NodeKind::Impl);
+		self.bump();
+
+		if self.at(&TokenInner::SymOAngle) {
+			self.parse_generic_params();
+		}
+
+		self.parse_type();
+
+		if self.eat(&TokenInner::WordFor) {
+			self.parse_type();
+		}
+
+		if self.at_contextual("where") {
+			self.parse_where_clause();
+		}
+
+		self.parse_item_body();
+		self.finish();
+	}
+
+	fn parse_const(&mut self, checkpoint: usize) {
+		self.start_at(checkpoint, NodeKind::Const);
+		self.bump();
+
+		// A constant in a trait can leave its value to the impls, like `const MAX: nat`.
+		if self.eat(&TokenInner::SymColon) {
+			self.parse_type();
+			if self.eat(&TokenInner::SymEqual) { self.parse_expr(); }
+		} else {
+			self.expect(&TokenInner::SymWalrus, "`:=`");
+			self.parse_expr();
+		}
+
+		self.finish();
+	}
+
+	//--> Types <--
+
+	fn parse_type(&mut self) {
+		match self.nth(0) {
+			Some(TokenInner::SymBang) => {
+				self.start(NodeKind::NeverType);
+				self.bump();
+				self.finish();
+			},
+			Some(TokenInner::SymOBracket) => {
+				let checkpoint = self.checkpoint();
+				self.bump();
+				self.parse_type();
+
+				let kind = if self.eat(&TokenInner::SymSemicolon) {
+					if self.at_literal() {
+						self.start(NodeKind::Literal);
+						self.bump();
+						self.finish();
+					} else if self.at_name() {
+						self.start(NodeKind::PathExpr);
+						self.parse_path(false);
+						self.finish();
+					} else {
+						self.error(ParseError::Expected("a length"));
+					}
+					NodeKind::ArrayType
+				} else if self.eat(&TokenInner::SymColon) {
+					self.parse_type();
+					NodeKind::MapType
+				} else {
+					NodeKind::ListType
+				};
+
+				self.expect(&TokenInner::SymCBracket, "`]`");
+				self.start_at(checkpoint, kind);
+				self.finish();
+			},
+			Some(TokenInner::SymOParen) => self.parse_paren_type(),
+			_ if self.at_contextual("Func") && self.nth_at(1, &TokenInner::SymOParen) => self.parse_func_type(),
+			// `dyn` is only a keyword when a trait comes after it, so it can still be used as a name.
+			_ if self.at_contextual("dyn") && matches!(self.nth(1), Some(
+				TokenInner::WordIdentifier(_) | TokenInner::WordSelf | TokenInner::WordSuper | TokenInner::WordPkg
+			)) => {
+				self.start(NodeKind::DynType);
+				self.bump();
+				self.start(NodeKind::PathType);
+				self.parse_path(true);
+				self.finish();
+				self.finish();
+			},
+			_ if self.at_path_start() => {
+				self.start(NodeKind::PathType);
+				self.parse_path(true);
+				self.finish();
+			},
+			_ => self.error(ParseError::Expected("a type")),
+		}
+	}
+
+	/// Parses a tuple type like `(nat, str)` or an anonymous record type like `(name: str, age: nat)`.
+	fn parse_paren_type(&mut self) {
+		let is_record = self.at_record_start();
+		self.start(if is_record { NodeKind::RecordType } else { NodeKind::TupleType });
+		self.bump();
+
+		loop {
+			self.skip_newlines();
+			if self.at(&TokenInner::SymCParen) || self.at_eof() { break }
+
+			let pos = self.pos;
+
+			if is_record {
+				self.start(NodeKind::Field);
+				self.expect_name();
+				self.expect(&TokenInner::SymColon, "`:`");
+				self.parse_type();
+				self.finish();
+			} else {
+				self.parse_type();
+			}
+
+			if self.pos == pos { break }
+
+			// Records can put each field on its own line instead of using commas.
+			let separated = self.eat(&TokenInner::SymComma) || (is_record && self.at(&TokenInner::SymNewline));
+			if !separated { break }
+		}
+
+		self.skip_newlines();
+		self.expect(&TokenInner::SymCParen, "`)`");
+		self.finish();
+	}
+
+	fn parse_func_type(&mut self) {
+		self.start(NodeKind::FuncType);
+		self.bump();
+		self.bump();
+
+		loop {
+			self.skip_newlines();
+			if !self.at_type_start() { break }
+
+			self.parse_type();
+			self.skip_newlines();
+
+			if !self.eat(&TokenInner::SymComma) { break }
+		}
+
+		self.expect(&TokenInner::SymCParen, "`)`");
+
+		if self.at(&TokenInner::SymThinArrow) {
+			self.start(NodeKind::RetType);
+			self.bump();
+			self.parse_type();
+			self.finish();
+		}
+
+		if self.at(&TokenInner::SymWeirdArrow) {
+			self.parse_effect_clause();
+		}
+
+		self.finish();
+	}
+
+	/// Parses a path like `std::error::Result`. Generic arguments are only allowed in types, since `<` means something else in expressions.
+	fn parse_path(&mut self, allow_generics: bool) {
+		self.start(NodeKind::Path);
+
+		loop {
+			if self.at_path_start() {
+				self.bump();
+			} else {
+				self.error(ParseError::Expected("a name"));
+				break;
+			}
+
+			if allow_generics && self.at(&TokenInner::SymOAngle) {
+				self.start(NodeKind::GenericArgs);
+				self.bump();
+
+				loop {
+					self.skip_newlines();
+					if !self.at_type_start() { break }
+
+					self.parse_type();
+
+					if !self.eat(&TokenInner::SymComma) { break }
+				}
+
+				self.expect_closing_angle();
+				self.finish();
+			}
+
+			if self.at(&TokenInner::SymQuad) && !self.nth_at(1, &TokenInner::SymStar) && !self.nth_at(1, &TokenInner::SymOBracket) {
+				self.bump();
+			} else {
+				break;
+			}
+		}
+
+		self.finish();
+	}
+
+	//--> Statements <--
+
+	/// Parses a block of code. If the block starts with a new line, it runs until `end`, otherwise it's a single statement.
+	fn parse_block(&mut self, require_do: bool) {
+		self.start(NodeKind::Block);
+
+		if require_do {
+			self.expect(&TokenInner::WordDo, "`do`");
+		} else {
+			self.eat(&TokenInner::WordDo);
+		}
+
+		if self.at(&TokenInner::SymNewline) {
+			self.parse_stmts(&[TokenInner::WordEnd]);
+			self.expect(&TokenInner::WordEnd, "`end`");
+		} else {
+			self.parse_stmt();
+		}
+
+		self.finish();
+	}
+
+	/// Parses statements until one of the terminators (which is not consumed) shows up.
+	fn parse_stmts(&mut self, terminators: &[TokenInner]) {
+		loop {
+			self.skip_separators();
+			if self.at_eof() || terminators.iter().any(|t| self.at(t)) { break }
+
+			let pos = self.pos;
+			self.parse_stmt();
+
+			if self.pos == pos {
+				self.error_and_bump(ParseError::UnexpectedToken);
+			} else if !self.at_eof()
+				&& !self.at(&TokenInner::SymNewline)
+				&& !self.at(&TokenInner::SymSemicolon)
+				&& !terminators.iter().any(|t| self.at(t))
+			{
+				self.error(ParseError::Expected("a new line"));
+				self.recover();
+			}
+		}
+	}
+
+	fn parse_stmt(&mut self) {
+		if self.at(&TokenInner::SymDecorator) {
+			self.start(NodeKind::AttrStmt);
+
+			while self.at(&TokenInner::SymDecorator) {
+				self.parse_attribute();
+				self.skip_newlines();
+			}
+
+			self.parse_stmt();
+			self.finish();
+			return;
+		}
+
+		let is_let = self.at(&TokenInner::WordMut)
+			|| self.at(&TokenInner::WordConst)
+			|| (self.at_name() && (self.nth_at(1, &TokenInner::SymWalrus) || self.nth_at(1, &TokenInner::SymColon)))
+			|| (self.at(&TokenInner::SymOParen) && self.closing_bracket().is_some_and(|end| {
+				self.nth_at(end + 1, &TokenInner::SymWalrus) || self.nth_at(end + 1, &TokenInner::SymColon)
+			}));
+
+		if is_let {
+			self.parse_let();
+			return;
+		}
+
+		let checkpoint = self.checkpoint();
+		self.parse_expr();
+
+		if self.at_assign_op() {
+			self.start_at(checkpoint, NodeKind::AssignStmt);
+			self.bump();
+			self.skip_newlines();
+			self.parse_expr();
+			self.finish();
+		}
+	}
+
+	fn at_assign_op(&self) -> bool {
+		matches!(self.nth(0), Some(
+			TokenInner::SymEqual | TokenInner::SymPlusEqual | TokenInner::SymDashEqual | TokenInner::SymStarEqual |
+			TokenInner::SymSlashEqual | TokenInner::SymPercentEqual | TokenInner::SymCaretEqual | TokenInner::SymAmpersandEqual |
+			TokenInner::SymPipeEqual | TokenInner::SymDOAngleEqual | TokenInner::SymDCAngleEqual | TokenInner::SymDotEqual
+		))
+	}
+
+	fn parse_let(&mut self) {
+		self.start(NodeKind::LetStmt);
+		let _ = self.eat(&TokenInner::WordMut) || self.eat(&TokenInner::WordConst);
+
+		self.parse_pattern();
+
+		if self.eat(&TokenInner::SymColon) {
+			self.parse_type();
+			self.expect(&TokenInner::SymEqual, "`=`");
+		} else {
+			self.expect(&TokenInner::SymWalrus, "`:=`");
+		}
+
+		self.skip_newlines();
+		self.parse_expr();
+		self.finish();
+	}
+
+	//--> Expressions <--
+
+	fn parse_expr(&mut self) {
+		self.parse_expr_bp(0);
+	}
+
+	/// Parses an expression inside some kind of brackets, where closures are always fine.
+	fn parse_delimited_expr(&mut self) {
+		let no_closures = std::mem::replace(&mut self.no_closures, false);
+		self.parse_expr();
+		self.no_closures = no_closures;
+	}
+
+	/// Parses the header of a loop, which is followed by `do`.
+	fn parse_header_expr(&mut self) {
+		let no_closures = std::mem::replace(&mut self.no_closures, true);
+		self.parse_expr();
+		self.no_closures = no_closures;
+	}
+
+	/// Pratt parsing for prefix and infix operators.
+	fn parse_expr_bp(&mut self, min_bp: u8) {
+		let checkpoint = self.checkpoint();
+
+		if matches!(self.nth(0), Some(TokenInner::SymDash | TokenInner::SymPlus | TokenInner::SymBang)) {
+			self.start(NodeKind::UnaryExpr);
+			self.bump();
+			self.parse_expr_bp(PREFIX_BP);
+			self.finish();
+		} else {
+			self.parse_postfix_expr();
+		}
+
+		while let Some((left_bp, right_bp)) = self.nth(0).and_then(infix_binding_power) {
+			if left_bp < min_bp { break }
+
+			self.start_at(checkpoint, NodeKind::BinaryExpr);
+			self.bump();
+			self.skip_newlines();
+			self.parse_expr_bp(right_bp);
+			self.finish();
+		}
+	}
+
+	fn parse_postfix_expr(&mut self) {
+		let checkpoint = self.checkpoint();
+		self.parse_primary_expr();
+
+		loop {
+			match self.nth(0) {
+				Some(TokenInner::SymOParen) => {
+					self.start_at(checkpoint, NodeKind::CallExpr);
+					self.parse_arg_list();
+					self.finish();
+				},
+				Some(TokenInner::SymOBracket) => {
+					self.start_at(checkpoint, NodeKind::IndexExpr);
+					self.bump();
+					self.skip_newlines();
+					self.parse_delimited_expr();
+					self.skip_newlines();
+					self.expect(&TokenInner::SymCBracket, "`]`");
+					self.finish();
+				},
+				Some(TokenInner::SymDot) => {
+					self.bump();
+					self.parse_member(checkpoint);
+				},
+				Some(TokenInner::SymTry) => {
+					self.start_at(checkpoint, NodeKind::TryExpr);
+					self.bump();
+					self.finish();
+				},
+				Some(TokenInner::SymTryChain) => {
+					self.start_at(checkpoint, NodeKind::TryExpr);
+					self.bump();
+					self.finish();
+					self.parse_member(checkpoint);
+				},
+				// Method chains are allowed to carry on over multiple lines.
+				Some(TokenInner::SymNewline) if self.at_after_newlines(&TokenInner::SymDot) || self.at_after_newlines(&TokenInner::SymTryChain) => {
+					self.skip_newlines();
+				},
+				_ => break,
+			}
+		}
+	}
+
+	/// Parses the part of a field access or method call after the `.`, which has already been consumed.
+	fn parse_member(&mut self, checkpoint: usize) {
+		self.split_tuple_fields();
+
+		if matches!(self.nth(0), Some(TokenInner::LitNum(_))) {
+			self.start_at(checkpoint, NodeKind::TupleFieldExpr);
+			self.bump();
+			self.finish();
+			return;
+		}
+
+		if !self.at_name() {
+			self.error(ParseError::Expected("a field or method name"));
+			return;
+		}
+
+		if self.nth_at(1, &TokenInner::SymOParen) {
+			self.start_at(checkpoint, NodeKind::MethodCallExpr);
+			self.bump();
+			self.parse_arg_list();
+		} else {
+			self.start_at(checkpoint, NodeKind::FieldExpr);
+			self.bump();
+		}
+
+		self.finish();
+	}
+
+	/// The lexer sees `pair.0.1` as `pair`, `.` and `0.1`, so that float gets split back up into two tuple fields.
+	fn split_tuple_fields(&mut self) {
+		let Some(pos) = self.nth_pos(0) else { return };
+		let token = self.tokens[pos].clone();
+
+		if !matches!(token.inner, TokenInner::LitFloat(_)) { return }
+		let Some((first, second)) = token.slice.split_once('.') else { return };
+		let (Ok(first_num), Ok(second_num)) = (first.parse(), second.parse()) else { return };
+
+		let dot = token.span.start + first.len();
+
+		self.tokens.splice(pos..=pos, [
+			Token { inner: TokenInner::LitNum(first_num), span: token.span.start..dot, slice: first.into() },
+			Token { inner: TokenInner::SymDot, span: dot..dot + 1, slice: ".".into() },
+			Token { inner: TokenInner::LitNum(second_num), span: dot + 1..token.span.end, slice: second.into() },
+		]);
+	}
+
+	/// Parses the arguments to a call. They can instead be named fields, like `Point(x: 1, y: 2)`, which is how records get made.
+	fn parse_arg_list(&mut self) {
+		self.start(NodeKind::ArgList);
+		self.bump();
+
+		loop {
+			self.skip_newlines();
+			if self.at(&TokenInner::SymCParen) || self.at_eof() { break }
+
+			let pos = self.pos;
+
+			if self.at_name() && self.nth_at(1, &TokenInner::SymColon) {
+				self.start(NodeKind::RecordField);
+				self.bump();
+				self.bump();
+				self.skip_newlines();
+				self.parse_delimited_expr();
+				self.finish();
+			} else {
+				self.parse_delimited_expr();
+			}
+
+			self.skip_newlines();
+
+			if self.pos == pos {
+				self.error_and_bump(ParseError::UnexpectedToken);
+			} else if !self.eat(&TokenInner::SymComma) {
+				break;
+			}
+		}
+
+		self.expect(&TokenInner::SymCParen, "`)`");
+		self.finish();
+	}
+
+	fn parse_primary_expr(&mut self) {
+		match self.nth(0) {
+			_ if self.at_literal() => {
+				self.start(NodeKind::Literal);
+				self.bump();
+				self.finish();
+			},
+			Some(TokenInner::LitStrHead(_)) => self.parse_interp_str(),
+			_ if self.at_path_start() => self.parse_path_expr(),
+			_ if self.at_closure_start() => self.parse_closure(),
+			_ if self.at_record_start() => self.parse_record(),
+			Some(TokenInner::SymOParen) => self.parse_paren_or_tuple(),
+			Some(TokenInner::SymOBracket) => self.parse_list_or_map(),
+			Some(TokenInner::WordIf) => self.parse_if(),
+			Some(TokenInner::WordLoop | TokenInner::WordWhile | TokenInner::WordUntil | TokenInner::WordFor) => {
+				let checkpoint = self.checkpoint();
+				self.parse_loop(checkpoint);
+			},
+			Some(TokenInner::SymBacktick) => self.parse_labelled(),
+			Some(TokenInner::WordWhen) => self.parse_when(),
+			Some(TokenInner::WordBreak) => {
+				self.start(NodeKind::BreakExpr);
+				self.bump();
+				if self.at(&TokenInner::SymBacktick) { self.parse_label(); }
+				if self.at_expr_start() { self.parse_expr(); }
+				self.finish();
+			},
+			Some(TokenInner::WordContinue) => {
+				self.start(NodeKind::ContinueExpr);
+				self.bump();
+				if self.at(&TokenInner::SymBacktick) { self.parse_label(); }
+				self.finish();
+			},
+			Some(TokenInner::WordReturn) => {
+				self.start(NodeKind::ReturnExpr);
+				self.bump();
+				if self.at_expr_start() { self.parse_expr(); }
+				self.finish();
+			},
+			Some(TokenInner::SymNewline | TokenInner::SymSemicolon | TokenInner::WordEnd) | None => self.error(ParseError::Expected("an expression")),
+			_ => self.error_and_bump(ParseError::Expected("an expression")),
+		}
+	}
+
+	/// Parses any kind of loop, wrapping it around everything from the checkpoint onwards (which is where a label would be).
+	fn parse_loop(&mut self, checkpoint: usize) {
+		match self.nth(0) {
+			Some(TokenInner::WordLoop) => {
+				self.start_at(checkpoint, NodeKind::LoopExpr);
+				self.bump();
+				self.parse_block(false);
+			},
+			Some(TokenInner::WordWhile) => {
+				self.start_at(checkpoint, NodeKind::WhileExpr);
+				self.bump();
+				self.parse_header_expr();
+				self.parse_block(true);
+			},
+			Some(TokenInner::WordUntil) => {
+				self.start_at(checkpoint, NodeKind::UntilExpr);
+				self.bump();
+				self.parse_header_expr();
+				self.parse_block(true);
+			},
+			_ => {
+				self.start_at(checkpoint, NodeKind::ForExpr);
+				self.bump();
+				self.parse_pattern();
+				self.expect(&TokenInner::WordIn, "`in`");
+				self.parse_header_expr();
+				self.parse_block(true);
+			},
+		}
+
+		self.finish();
+	}
+
+	/// Parses a loop or block with a label in front of it, like `` `outer for x in xs do ... end ``.
+	fn parse_labelled(&mut self) {
+		let checkpoint = self.checkpoint();
+		self.parse_label();
+
+		match self.nth(0) {
+			Some(TokenInner::WordLoop | TokenInner::WordWhile | TokenInner::WordUntil | TokenInner::WordFor) => self.parse_loop(checkpoint),
+			Some(TokenInner::WordDo) => {
+				self.start_at(checkpoint, NodeKind::BlockExpr);
+				self.parse_block(true);
+				self.finish();
+			},
+			_ => self.error(ParseError::Expected("a loop or `do` block after the label")),
+		}
+	}
+
+	fn parse_label(&mut self) {
+		self.start(NodeKind::Label);
+		self.bump();
+
+		if self.at_name() {
+			self.bump();
+		} else {
+			self.error(ParseError::Expected("a label name"));
+		}
+
+		self.finish();
+	}
+
+	fn parse_closure(&mut self) {
+		self.start(NodeKind::ClosureExpr);
+		self.parse_param_list(false);
+
+		if self.at(&TokenInner::SymThinArrow) {
+			self.start(NodeKind::RetType);
+			self.bump();
+			self.parse_type();
+			self.finish();
+		}
+
+		if self.at(&TokenInner::SymWeirdArrow) {
+			self.parse_effect_clause();
+		}
+
+		let no_closures = std::mem::replace(&mut self.no_closures, false);
+		self.parse_block(true);
+		self.no_closures = no_closures;
+
+		self.finish();
+	}
+
+	/// Parses an anonymous record. Fields are separated by commas, new lines, or both.
+	fn parse_record(&mut self) {
+		self.start(NodeKind::RecordExpr);
+		self.bump();
+
+		loop {
+			self.skip_newlines();
+			if self.at(&TokenInner::SymCParen) || self.at_eof() { break }
+
+			let pos = self.pos;
+
+			self.start(NodeKind::RecordField);
+			self.expect_name();
+			self.expect(&TokenInner::SymColon, "`:`");
+			self.skip_newlines();
+			self.parse_delimited_expr();
+			self.finish();
+
+			if self.pos == pos {
+				self.error_and_bump(ParseError::UnexpectedToken);
+			} else if !self.eat(&TokenInner::SymComma) && !self.at(&TokenInner::SymNewline) {
+				break;
+			}
+		}
+
+		self.skip_newlines();
+		self.expect(&TokenInner::SymCParen, "`)`");
+		self.finish();
+	}
+
+	/// Parses something in parentheses, which is a tuple if there's a comma in there and just grouping otherwise.
+	fn parse_paren_or_tuple(&mut self) {
+		let checkpoint = self.checkpoint();
+		self.bump();
+
+		let mut is_tuple = false;
+		let mut elements = 0;
+
+		loop {
+			self.skip_newlines();
+			if self.at(&TokenInner::SymCParen) || self.at_eof() { break }
+
+			let pos = self.pos;
+			self.parse_delimited_expr();
+			self.skip_newlines();
+			elements += 1;
+
+			if self.pos == pos {
+				self.error_and_bump(ParseError::UnexpectedToken);
+			} else if self.eat(&TokenInner::SymComma) {
+				is_tuple = true;
+			} else {
+				break;
+			}
+		}
+
+		self.expect(&TokenInner::SymCParen, "`)`");
+
+		// `()` is the empty tuple.
+		let kind = if is_tuple || elements == 0 { NodeKind::TupleExpr } else { NodeKind::ParenExpr };
+		self.start_at(checkpoint, kind);
+		self.finish();
+	}
+
+	/// Parses a string with expressions interpolated into it. The lexer has already split it up into the pieces of text between them.
+	fn parse_interp_str(&mut self) {
+		self.start(NodeKind::InterpStrExpr);
+		self.bump();
+
+		loop {
+			self.parse_delimited_expr();
+			if !self.eat(&TokenInner::LitStrMid(String::new())) { break }
+		}
+
+		self.expect(&TokenInner::LitStrTail(String::new()), "`}` and the rest of the string");
+		self.finish();
+	}
+
+	/// Parses a list like `[1, 2, 3]` or a map like `["one": 1, "two": 2]`. `[]` is an empty list or map, and `[:]` is always an empty map.
+	fn parse_list_or_map(&mut self) {
+		let checkpoint = self.checkpoint();
+		self.bump();
+		self.skip_newlines();
+
+		let mut is_map = false;
+
+		if self.at(&TokenInner::SymColon) {
+			self.bump();
+			is_map = true;
+		} else {
+			let mut first = true;
+
+			loop {
+				self.skip_newlines();
+				if self.at(&TokenInner::SymCBracket) || self.at_eof() { break }
+
+				let pos = self.pos;
+				let entry = self.checkpoint();
+				self.parse_delimited_expr();
+
+				if first {
+					is_map = self.at(&TokenInner::SymColon);
+					first = false;
+				}
+
+				if is_map {
+					self.expect(&TokenInner::SymColon, "`:`");
+					self.skip_newlines();
+					self.parse_delimited_expr();
+					self.start_at(entry, NodeKind::MapEntry);
+					self.finish();
+				}
+
+				self.skip_newlines();
+
+				if self.pos == pos {
+					self.error_and_bump(ParseError::UnexpectedToken);
+				} else if !self.eat(&TokenInner::SymComma) {
+					break;
+				}
+			}
+		}
+
+		self.skip_newlines();
+		self.expect(&TokenInner::SymCBracket, "`]`");
+		self.start_at(checkpoint, if is_map { NodeKind::MapExpr } else { NodeKind::ListExpr });
+		self.finish();
+	}
+
+	fn parse_path_expr(&mut self) {
+		let checkpoint = self.checkpoint();
+
+		self.start(NodeKind::PathExpr);
+		self.parse_path(false);
+		self.finish();
+
+		// Macros are expanded before parsing, so only the formatter ever sees calls to them.
+		let bang_adjacent = self.nth_pos(0).is_some_and(|pos| pos > 0 && self.tokens[pos - 1].span.end == self.tokens[pos].span.start);
+
+		if self.at(&TokenInner::SymBang) && bang_adjacent && matches!(self.nth(1), Some(TokenInner::SymOParen | TokenInner::SymOBracket | TokenInner::SymOBrace)) {
+			self.start_at(checkpoint, NodeKind::MacroCallExpr);
+			self.bump();
+			self.parse_token_tree();
+			self.finish();
+			return;
+		}
+
+		// Constructors can be applied to a single simple argument without parentheses, like `Some n`.
+		let is_constructor = self.tokens[..self.pos].iter().rev()
+			.find(|t| t.inner != TokenInner::Comment)
+			.is_some_and(|t| t.slice.starts_with(|c: char| c.is_uppercase()));
+
+		if is_constructor && (self.at_literal() || matches!(self.nth(0), Some(TokenInner::WordIdentifier(_) | TokenInner::WordSelf))) {
+			self.start_at(checkpoint, NodeKind::CallExpr);
+			self.start(NodeKind::ArgList);
+			self.parse_postfix_expr();
+			self.finish();
+			self.finish();
+		}
+	}
+
+	fn parse_if(&mut self) {
+		let checkpoint = self.checkpoint();
+		self.bump();
+		self.parse_expr();
+
+		if self.at(&TokenInner::WordMatches) && self.nth_at(1, &TokenInner::SymNewline) {
+			self.start_at(checkpoint, NodeKind::MatchExpr);
+			self.bump();
+
+			loop {
+				self.skip_separators();
+				if self.at(&TokenInner::WordEnd) || self.at_eof() { break }
+
+				let pos = self.pos;
+
+				self.start(NodeKind::MatchArm);
+				self.parse_pattern();
+				self.expect(&TokenInner::WordThen, "`then`");
+				self.start(NodeKind::Block);
+				self.parse_stmt();
+				self.finish();
+				self.finish();
+
+				if self.pos == pos {
+					self.error_and_bump(ParseError::UnexpectedToken);
+				} else if !self.at(&TokenInner::SymNewline) && !self.at(&TokenInner::WordEnd) {
+					self.error(ParseError::Expected("a new line"));
+					self.recover();
+				}
+			}
+
+			self.expect(&TokenInner::WordEnd, "`end`");
+			self.finish();
+			return;
+		}
+
+		self.start_at(checkpoint, NodeKind::IfExpr);
+		self.start_at(0, NodeKind::IfBranch);
+		let mut multiline = self.parse_if_branch_rest();
+		self.finish();
+
+		loop {
+			// A single-line branch can still be followed by `elif` or `else` on the next line.
+			if multiline || self.at_after_newlines(&TokenInner::WordElif) || self.at_after_newlines(&TokenInner::WordElse) {
+				self.skip_newlines();
+			}
+
+			if self.at(&TokenInner::WordElif) {
+				self.start(NodeKind::IfBranch);
+				self.bump();
+				self.parse_expr();
+				multiline |= self.parse_if_branch_rest();
+				self.finish();
+			} else if self.at(&TokenInner::WordElse) {
+				self.start(NodeKind::ElseBranch);
+				self.bump();
+				multiline |= self.parse_branch_body(&[TokenInner::WordEnd]);
+				self.finish();
+
+				if multiline { self.skip_newlines(); }
+				break;
+			} else {
+				break;
+			}
+		}
+
+		if multiline {
+			self.expect(&TokenInner::WordEnd, "`end`");
+		}
+
+		self.finish();
+	}
+
+	/// Parses the rest of an `if` or `elif` branch after its condition, returning whether the body spanned multiple lines.
+	fn parse_if_branch_rest(&mut self) -> bool {
+		if self.eat(&TokenInner::WordMatches) {
+			self.parse_pattern();
+		}
+
+		self.expect(&TokenInner::WordThen, "`then`");
+		self.parse_branch_body(&[TokenInner::WordElif, TokenInner::WordElse, TokenInner::WordEnd])
+	}
+
+	/// Parses the body of a branch, returning whether it spanned multiple lines.
+	fn parse_branch_body(&mut self, terminators: &[TokenInner]) -> bool {
+		self.start(NodeKind::Block);
+
+		let multiline = self.at(&TokenInner::SymNewline);
+
+		if multiline {
+			self.parse_stmts(terminators);
+		} else {
+			self.parse_stmt();
+		}
+
+		self.finish();
+		multiline
+	}
+
+	fn parse_when(&mut self) {
+		self.start(NodeKind::WhenExpr);
+		self.bump();
+
+		self.start(NodeKind::PathExpr);
+		self.parse_path(false);
+		self.finish();
+
+		self.start(NodeKind::PatList);
+		if self.expect(&TokenInner::SymOParen, "`(`") {
+			loop {
+				self.skip_newlines();
+				if self.at(&TokenInner::SymCParen) || self.at_eof() { break }
+
+				let pos = self.pos;
+				self.parse_pattern();
+				if self.pos == pos || !self.eat(&TokenInner::SymComma) { break }
+			}
+
+			self.expect(&TokenInner::SymCParen, "`)`");
+		}
+		self.finish();
+
+		self.start(NodeKind::Block);
+		self.expect(&TokenInner::WordDo, "`do`");
+		let multiline = self.at(&TokenInner::SymNewline);
+		if multiline {
+			self.parse_stmts(&[TokenInner::WordIn, TokenInner::WordEnd]);
+		} else {
+			self.parse_stmt();
+		}
+		self.finish();
+
+		if self.eat(&TokenInner::WordIn) {
+			self.parse_block(false);
+		} else if multiline {
+			self.expect(&TokenInner::WordEnd, "`end`");
+		}
+
+		self.finish();
+	}
+
+	//--> Patterns <--
+
+	fn parse_pattern(&mut self) {
+		match self.nth(0) {
+			Some(TokenInner::WordIdentifier(name)) if name == "_" => {
+				self.start(NodeKind::WildcardPat);
+				self.bump();
+				self.finish();
+			},
+			Some(TokenInner::WordMut) => {
+				self.start(NodeKind::BindingPat);
+				self.bump();
+				self.expect_name();
+				self.finish();
+			},
+			Some(TokenInner::WordIdentifier(name))
+				if name.starts_with(|c: char| !c.is_uppercase())
+				&& !self.nth_at(1, &TokenInner::SymQuad)
+				&& !self.nth_at(1, &TokenInner::SymOParen) =>
+			{
+				self.start(NodeKind::BindingPat);
+				self.bump();
+				self.finish();
+			},
+			Some(TokenInner::SymDash) => self.parse_literal_pattern(),
+			_ if self.at_literal() => self.parse_literal_pattern(),
+			// A range with no start, like `..=-1`.
+			Some(TokenInner::SymRange | TokenInner::SymRangeEqual) => {
+				self.start(NodeKind::RangePat);
+				self.bump();
+				self.parse_literal_with_sign();
+				self.finish();
+			},
+			Some(TokenInner::SymOParen) => {
+				self.start(NodeKind::TuplePat);
+				self.bump();
+
+				loop {
+					self.skip_newlines();
+					if self.at(&TokenInner::SymCParen) || self.at_eof() { break }
+
+					let pos = self.pos;
+					self.parse_pattern();
+					self.skip_newlines();
+					if self.pos == pos || !self.eat(&TokenInner::SymComma) { break }
+				}
+
+				self.expect(&TokenInner::SymCParen, "`)`");
+				self.finish();
+			},
+			_ if self.at_path_start() => {
+				let checkpoint = self.checkpoint();
+				self.parse_path(false);
+
+				if self.at(&TokenInner::SymOParen) {
+					self.start_at(checkpoint, NodeKind::TupleStructPat);
+					self.start(NodeKind::PatList);
+					self.bump();
+
+					loop {
+						self.skip_newlines();
+						if self.at(&TokenInner::SymCParen) || self.at_eof() { break }
+
+						let pos = self.pos;
+						self.parse_pattern();
+						self.skip_newlines();
+						if self.pos == pos || !self.eat(&TokenInner::SymComma) { break }
+					}
+
+					self.expect(&TokenInner::SymCParen, "`)`");
+					self.finish();
+				} else {
+					self.start_at(checkpoint, NodeKind::PathPat);
+				}
+
+				self.finish();
+			},
+			_ => self.error(ParseError::Expected("a pattern")),
+		}
+	}
+
+	fn parse_literal_pattern(&mut self) {
+		let checkpoint = self.checkpoint();
+		self.parse_literal_with_sign();
+
+		if self.at(&TokenInner::SymRange) || self.at(&TokenInner::SymRangeEqual) {
+			let inclusive = self.at(&TokenInner::SymRangeEqual);
+			self.bump();
+
+			// Without an end, like `1..`, the range goes on as far as the type does. `..=` always needs one.
+			if inclusive || self.at(&TokenInner::SymDash) || self.at_literal() {
+				self.parse_literal_with_sign();
+			}
+
+			self.start_at(checkpoint, NodeKind::RangePat);
+			self.finish();
+		}
+	}
+
+	fn parse_literal_with_sign(&mut self) {
+		self.start(NodeKind::LiteralPat);
+		self.eat(&TokenInner::SymDash);
+
+		if self.at_literal() {
+			self.bump();
+		} else {
+			self.error(ParseError::Expected("a literal"));
+		}
+
+		self.finish();
+	}
+}
+
+/// The binding power of prefix operators, which bind tighter than any infix operator.
+const PREFIX_BP: u8 = 25;
+
+/// Gives the left and right binding powers of an infix operator. Left-associative operators have a higher right binding power.
+fn infix_binding_power(token: &TokenInner) -> Option<(u8, u8)> {
+	Some(match token {
+		TokenInner::SymBind => (1, 2),
+		TokenInner::WordOr | TokenInner::SymDPipe => (3, 4),
+		TokenInner::WordXor | TokenInner::SymDCaret => (5, 6),
+		TokenInner::WordAnd | TokenInner::SymDAmpersand => (7, 8),
+		TokenInner::SymDEqual | TokenInner::SymBangEqual | TokenInner::SymOAngle | TokenInner::SymLessEqual |
+		TokenInner::SymCAngle | TokenInner::SymGreaterEqual | TokenInner::SymDPercent => (9, 10),
+		TokenInner::SymRange | TokenInner::SymRangeEqual => (11, 12),
+		TokenInner::SymPipe => (13, 14),
+		TokenInner::SymCaret => (15, 16),
+		TokenInner::SymAmpersand => (17, 18),
+		TokenInner::SymDOAngle | TokenInner::SymDCAngle => (19, 20),
+		TokenInner::SymPlus | TokenInner::SymDash => (21, 22),
+		TokenInner::SymStar | TokenInner::SymSlash | TokenInner::SymPercent => (23, 24),
+		_ => return None,
+	})
+}
@@ -1,26 +1,55 @@
 //--> Imports <--
 
-mod cstgen; // Concrete Syntax Tree Generator (Tokens -> CST)
-mod astgen; // Abstract Syntax Tree Generator (CST -> AST)
+pub(crate) mod cstgen; // Concrete Syntax Tree Generator (Tokens -> CST)
+pub(crate) mod astgen; // Abstract Syntax Tree Generator (CST -> AST)
 
 use crate::{
 	Error,
+	ErrorKind,
 	ErrorList,
 };
 
-use super::InterpretError;
+use super::{
+	InterpretError,
+	line_of,
+	lexer::{
+		TokenInner,
+		TokenStream,
+	},
+};
 
+use astgen::AbstractSyntaxTree;
 use cstgen::ConcreteSyntaxTree;
 
 use std::{
-	collections::HashMap,
+	fmt,
 	path::Path,
 };
 
-//--> Type Aliases <--
-
 //--> Structs <--
 
+/// Caps on how much the parser will take on, so that untrusted source code can't run it out of stack or memory.
+/// Anything past a limit is reported as a [`ParseError::LimitExceeded`] error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseLimits {
+	/// How deeply things like expressions, types and blocks can be nested inside of each other.
+	/// This counts every level of the syntax tree, so everyday code already goes 20 or so levels deep.
+	pub max_depth: usize,
+
+	/// How much deeper than `max_depth` chains of operators, calls, indexes and field accesses, like `a + b + c` or `x.f().g()`, can make the syntax tree.
+	/// Each one wraps around everything before it, so a long chain makes the tree deep without being nested in the source.
+	pub max_chain: usize,
+
+	/// How many tokens a file can have, not counting comments and new lines.
+	pub max_tokens: usize,
+
+	/// How many bytes long a single literal can be.
+	pub max_literal_len: usize,
+
+	/// How many bytes long a file can be. This gets checked before the file is even read.
+	pub max_source_bytes: usize,
+}
+
 //--> Enums <--
 
 #[derive(Clone, Debug)]
@@ -30,8 +59,179 @@ pub enum ParseError {
 	
 	/// The parser ran across a token it wasn't expecting.
 	UnexpectedToken,
+
+	/// The parser ran out of tokens partway through something.
+	UnexpectedEnd,
+
+	/// The parser needed something in particular, but found something else.
+	Expected(&'static str),
+
+	/// The source code went past one of the parser's limits.
+	LimitExceeded(Limit),
+}
+
+/// Which of the [`ParseLimits`] was gone past, along with what the limit was.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+	Depth(usize),
+	Chain(usize),
+	Tokens(usize),
+	LiteralLen(usize),
+	SourceBytes(usize),
 }
 
 //--> Functions <--
 
-pub fn tokens_to_ast() {}
\ No newline at end of file
+/// Parses a stream of tokens into an abstract syntax tree, by way of a concrete one.
+pub(crate) fn tokens_to_ast(path: Option<&Path>, source: &str, tokens: TokenStream, limits: &ParseLimits) -> Result<(AbstractSyntaxTree, ErrorList), ErrorList> {
+	let (cst, warnings) = ConcreteSyntaxTree::new(path, source, tokens, limits)?;
+	let ast = AbstractSyntaxTree::new(&cst, source)?;
+
+	Ok((ast, warnings))
+}
+
+/// Checks a stream of tokens against the limits which don't need it to be parsed first: how many tokens there are, how long literals are, and how deeply brackets are nested.
+/// The lexer already stops once there are too many tokens, but macro expansion can make more.
+/// The bracket check means nothing which works on raw tokens (like macro expansion) has to worry about running out of stack.
+pub(crate) fn check_limits(path: Option<&Path>, source: &str, tokens: &TokenStream, limits: &ParseLimits) -> Result<(), ErrorList> {
+	let mut count = 0;
+	let mut depth: usize = 0;
+
+	for token in tokens.0.iter().filter(|token| !token.inner.is_trivia()) {
+		count += 1;
+
+		let exceeded = match token.inner {
+			_ if count > limits.max_tokens => Some(Limit::Tokens(limits.max_tokens)),
+			TokenInner::SymOParen | TokenInner::SymOBracket | TokenInner::SymOBrace => {
+				depth += 1;
+				(depth > limits.max_depth).then_some(Limit::Depth(limits.max_depth))
+			},
+			TokenInner::SymCParen | TokenInner::SymCBracket | TokenInner::SymCBrace => {
+				depth = depth.saturating_sub(1);
+				None
+			},
+			_ if token.inner.is_literal() && token.slice.len() > limits.max_literal_len => Some(Limit::LiteralLen(limits.max_literal_len)),
+			_ => None,
+		};
+
+		if let Some(limit) = exceeded {
+			// Whatever's too long isn't worth repeating back.
+			let slice = (!matches!(limit, Limit::LiteralLen(_))).then_some(token.slice.as_str());
+
+			return Err(vec![
+				Error::new(false, path, Some(line_of(source, token.span.start)), Some(token.span.clone()), slice, ErrorKind::Interpret(InterpretError::Parse(ParseError::LimitExceeded(limit))))
+			]);
+		}
+	}
+
+	Ok(())
+}
+
+impl Default for ParseLimits {
+	fn default() -> Self {
+		ParseLimits {
+			max_depth: 128,
+			max_chain: 128,
+			max_tokens: 1_000_000,
+			max_literal_len: 1 << 20,
+			max_source_bytes: 1 << 23,
+		}
+	}
+}
+
+impl fmt::Display for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ParseError::NoTokens => write!(f, "there was nothing to parse"),
+			ParseError::UnexpectedToken => write!(f, "unexpected token"),
+			ParseError::UnexpectedEnd => write!(f, "unexpected end of file"),
+			ParseError::Expected(what) => write!(f, "expected {}", what),
+			ParseError::LimitExceeded(Limit::Depth(max)) => write!(f, "this is nested too deeply; the limit is {} levels", max),
+			ParseError::LimitExceeded(Limit::Chain(max)) => write!(f, "this expression is too long; chains of operators and calls can only go {} levels past the limit on nesting", max),
+			ParseError::LimitExceeded(Limit::Tokens(max)) => write!(f, "there's too much code here; the limit is {} tokens", max),
+			ParseError::LimitExceeded(Limit::LiteralLen(max)) => write!(f, "this literal is too long; the limit is {} bytes", max),
+			ParseError::LimitExceeded(Limit::SourceBytes(max)) => write!(f, "this file is too big; the limit is {} bytes", max),
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compiler::{
+		Options,
+		parse_source,
+	};
+
+	fn limit_hit(source: &str, limits: ParseLimits) -> Option<Limit> {
+		let errors = parse_source(None, source, &Options { limits, ..Default::default() }).err()?;
+
+		errors.iter().find_map(|e| match e.kind() {
+			ErrorKind::Interpret(InterpretError::Parse(ParseError::LimitExceeded(limit))) => Some(limit),
+			_ => None,
+		})
+	}
+
+	#[test]
+	fn deep_nesting_is_an_error_not_a_crash() {
+		let source = format!("func main() do\n\tx := {}1{}\nend\n", "(".repeat(100_000), ")".repeat(100_000));
+		assert_eq!(limit_hit(&source, ParseLimits::default()), Some(Limit::Depth(128)));
+
+		// Nesting without brackets, which only the parser can see.
+		let source = format!("func main() do\n\tx := {}1\nend\n", "-".repeat(100_000));
+		assert_eq!(limit_hit(&source, ParseLimits::default()), Some(Limit::Depth(128)));
+
+		// Left-leaning trees, which get deep without the parser going deep.
+		let source = format!("func main() do\n\tx := 1{}\nend\n", " + 1".repeat(100_000));
+		assert_eq!(limit_hit(&source, ParseLimits::default()), Some(Limit::Chain(128)));
+	}
+
+	#[test]
+	fn long_chains_arent_nesting() {
+		// Tests run on threads with a smaller stack than a program's main thread gets, so this one gets as much as that.
+		let source = format!("func main() do\n\tx := 1{}\n\ty := x{}\nend\n", " + 1".repeat(200), ".f()".repeat(200));
+		let parsed = std::thread::Builder::new().stack_size(8 << 20).spawn(move || limit_hit(&source, ParseLimits::default())).unwrap();
+		assert_eq!(parsed.join().unwrap(), None);
+
+		let source = format!("func main() do\n\tx := 1{}\nend\n", " + 1".repeat(200));
+		let errors = parse_source(None, &source, &Options { limits: ParseLimits { max_chain: 10, ..Default::default() }, ..Default::default() }).err().unwrap();
+
+		assert_eq!(errors.len(), 1);
+		assert!(matches!(errors[0].kind(), ErrorKind::Interpret(InterpretError::Parse(ParseError::LimitExceeded(Limit::Chain(10))))));
+		assert_eq!(errors[0].slice().as_deref(), Some("+"));
+	}
+
+	#[test]
+	fn limits_can_be_tightened() {
+		let source = "func main() do\n\tx := [[[1]]]\n\ty := \"hello there\"\nend\n";
+		assert_eq!(limit_hit(source, ParseLimits::default()), None);
+
+		assert_eq!(limit_hit(source, ParseLimits { max_depth: 4, ..Default::default() }), Some(Limit::Depth(4)));
+		assert_eq!(limit_hit(source, ParseLimits { max_tokens: 10, ..Default::default() }), Some(Limit::Tokens(10)));
+		assert_eq!(limit_hit(source, ParseLimits { max_literal_len: 8, ..Default::default() }), Some(Limit::LiteralLen(8)));
+	}
+
+	#[test]
+	fn lexing_stops_at_the_limits() {
+		// The bad token at the end is never reached, since lexing gives up long before it.
+		let source = format!("func main() do\n{}\nend\n\u{7}", "\tx := 1\n".repeat(10_000));
+		let errors = parse_source(None, &source, &Options { limits: ParseLimits { max_tokens: 100, ..Default::default() }, ..Default::default() }).err().unwrap();
+
+		assert_eq!(errors.len(), 1);
+		assert!(matches!(errors[0].kind(), ErrorKind::Interpret(InterpretError::Parse(ParseError::LimitExceeded(Limit::Tokens(100))))));
+
+		assert_eq!(limit_hit(&source, ParseLimits { max_source_bytes: 1024, ..Default::default() }), Some(Limit::SourceBytes(1024)));
+	}
+
+	#[test]
+	fn limit_errors_point_at_the_problem() {
+		let source = "func main() do\n\tx := 1\n\ty := ((((2))))\nend\n";
+		let errors = parse_source(None, source, &Options { limits: ParseLimits { max_depth: 3, ..Default::default() }, ..Default::default() }).err().unwrap();
+
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].line(), Some(3));
+		assert_eq!(errors[0].slice().as_deref(), Some("("));
+	}
+}
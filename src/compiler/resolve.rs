@@ -0,0 +1,1359 @@
+//! Works out what every name in a program refers to.
+//!
+//! Each module has a scope holding the items it declares, its submodules, and whatever it imports.
+//! Imports get resolved over and over until nothing changes, since glob imports can depend on each other.
+//! That lets modules import from each other both ways, and only imports which lead back to themselves, like two modules
+//! each re-exporting the other's `Thing`, are an error.
+//! Names brought in by glob imports lose out to any other name, and are only an error if they're ambiguous and actually used.
+//!
+//! Inside of items, scopes are built up for generic parameters, parameters, blocks, pattern bindings and handlers.
+//! A name which none of those have gets looked for in the module's scope, and then in the prelude's.
+//!
+//! Every step along a path has to be visible from where the path is. Something without `pub` or `prt` can only be seen
+//! from the module it's in and the modules inside of that one, `prt` opens it up to the rest of its package, and `pub` to everything.
+//! Variants, trait methods and effect operations are as visible as whatever they're part of.
+
+//--> Imports <--
+
+use crate::{
+	Error,
+	ErrorKind,
+	ErrorList,
+	compiler::{
+		InterpretError,
+		line_of,
+		modules::{
+			ModuleId,
+			ModuleTree,
+		},
+		parser::astgen::{
+			AbstractSyntaxTree,
+			Block,
+			Condition,
+			Expr,
+			ExprKind,
+			GenericParam,
+			Handler,
+			Ident,
+			Item,
+			ItemKind,
+			Param,
+			Path,
+			Pattern,
+			PatternKind,
+			Stmt,
+			StmtKind,
+			Type,
+			TypeBody,
+			TypeKind,
+			UseKind,
+			UseTree,
+			Visibility,
+			WherePredicate,
+		},
+	},
+};
+
+use logos::Span;
+
+use std::{
+	collections::{
+		HashMap,
+		HashSet,
+	},
+	fmt,
+};
+
+//--> Type Aliases <--
+
+pub(crate) type DefId = usize;
+pub(crate) type LocalId = usize;
+
+//--> Structs <--
+
+/// Everything name resolution found out, apart from what got filled in on the syntax trees themselves.
+#[derive(Debug, Default)]
+pub(crate) struct Resolutions {
+	pub defs: Vec<Def>,
+	pub locals: Vec<Local>,
+	/// The scope of each module, in the same order as the modules in the tree.
+	pub scopes: Vec<ModuleScope>,
+	/// The full path of everything `pub` which the package declares, since that's all a program embedding it gets to use.
+	pub exports: Vec<(String, ExportKind)>,
+	/// The imports in the package which nothing ever uses, leaving out any which are there for other modules to use.
+	pub unused_imports: Vec<(ModuleId, Span)>,
+}
+
+/// Something declared by an item, or a variant of a type.
+#[derive(Debug)]
+pub(crate) struct Def {
+	pub kind: DefKind,
+	pub name: Ident,
+	pub vis: Visibility,
+	pub module: ModuleId,
+	pub parent: Option<DefId>,
+	/// Variants, methods, trait items and effect operations, by name.
+	pub children: HashMap<String, DefId>,
+}
+
+/// A variable bound by a parameter or a pattern.
+#[derive(Debug)]
+pub(crate) struct Local {
+	pub name: Ident,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ModuleScope {
+	pub names: HashMap<String, Name>,
+	/// Names brought in by glob imports, each of which could have come from more than one place.
+	pub globs: HashMap<String, Vec<Name>>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Name {
+	pub res: Res,
+	pub vis: Visibility,
+	/// Where the name was declared or imported.
+	pub span: Span,
+}
+
+/// A `use` with any lists flattened out of it.
+struct Import {
+	module: ModuleId,
+	path: Vec<Ident>,
+	/// The name a single import brings in, or nothing for a glob import.
+	name: Option<Ident>,
+	vis: Visibility,
+	span: Span,
+}
+
+struct Resolver<'a> {
+	tree: &'a ModuleTree,
+	out: Resolutions,
+	errors: ErrorList,
+	/// The module whose items are being looked at.
+	module: ModuleId,
+	/// Scopes inside of items, innermost last.
+	scopes: Vec<HashMap<String, Res>>,
+	/// Where every name that has been looked up was declared or imported, which is how imports that never get used are found.
+	used: HashSet<(ModuleId, Span)>,
+}
+
+//--> Enums <--
+
+/// What a path refers to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Res {
+	#[default]
+	Unresolved,
+	Module(ModuleId),
+	Def(DefId),
+	Local(LocalId),
+	/// The generic parameter at an index of a function, type, trait, effect or impl.
+	Generic(DefId, usize),
+	/// `Self` inside of a type, trait or impl.
+	SelfType(DefId),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DefKind {
+	Func,
+	Type,
+	Variant,
+	Trait,
+	Effect,
+	Impl,
+	Const,
+}
+
+/// How looking up a path went.
+enum Lookup {
+	/// The path refers to something, through however many of its segments.
+	Found(Res, usize),
+	/// Nothing has the segment at this index.
+	Missing(usize),
+	/// More than one glob import has the segment at this index.
+	Ambiguous(usize, Vec<Name>),
+	/// The segment at this index was found, but it can't be seen from where the path is.
+	/// Along with what it is, this has how visible it is and which module it's in.
+	Private(usize, Res, Visibility, ModuleId),
+}
+
+/// What something a package exports is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportKind {
+	Func,
+	Type,
+	Variant,
+	Trait,
+	Effect,
+	Const,
+}
+
+#[derive(Clone, Debug)]
+pub enum ResolveError {
+	/// Nothing with this name is in scope.
+	Unresolved(String),
+	/// More than one glob import brings in something with this name.
+	Ambiguous(String),
+	/// Something with this name was already declared or imported in the same place.
+	Duplicate(String),
+	/// A binding hides an earlier one with the same name.
+	Shadowed(String),
+	/// Something was used from outside of the module or package it's visible in.
+	Private { name: String, module: String },
+	/// Imports which only lead back to each other, so none of them ever find anything. The first is repeated at the end.
+	Cycle(Vec<String>),
+}
+
+//--> Functions <--
+
+/// Resolves every name in a module tree, filling in what paths refer to on the syntax trees.
+pub(crate) fn resolve(tree: &mut ModuleTree) -> Result<(Resolutions, ErrorList), ErrorList> {
+	// The syntax trees get filled in while the rest of the module tree is looked at, so they're taken out for a bit.
+	let mut asts: Vec<AbstractSyntaxTree> = tree.modules.iter_mut().map(|module| std::mem::take(&mut module.ast)).collect();
+
+	let mut resolver = Resolver {
+		tree,
+		out: Resolutions::default(),
+		errors: ErrorList::new(),
+		module: 0,
+		scopes: Vec::new(),
+		used: HashSet::new(),
+	};
+
+	for (module, ast) in asts.iter_mut().enumerate() {
+		resolver.declare_module(module, ast);
+	}
+
+	resolver.resolve_imports(&asts);
+
+	for (module, ast) in asts.iter_mut().enumerate() {
+		resolver.module = module;
+		for item in ast.items.iter_mut() {
+			resolver.item(item);
+		}
+	}
+
+	resolver.exports();
+	resolver.unused_imports(&asts);
+
+	let Resolver { out, errors, .. } = resolver;
+
+	for (module, ast) in tree.modules.iter_mut().zip(asts) {
+		module.ast = ast;
+	}
+
+	if errors.iter().all(|e| e.is_warning()) {
+		Ok((out, errors))
+	} else {
+		Err(errors)
+	}
+}
+
+/// Whether something with a visibility, which is in `owner`, can be seen from `from`.
+pub(crate) fn accessible(tree: &ModuleTree, from: ModuleId, vis: Visibility, owner: ModuleId) -> bool {
+	match vis {
+		Visibility::Public => true,
+		Visibility::Protected => tree.modules[from].path[0] == tree.modules[owner].path[0],
+		Visibility::Private => {
+			let mut current = Some(from);
+
+			while let Some(module) = current {
+				if module == owner { return true }
+				current = tree.modules[module].parent;
+			}
+
+			false
+		},
+	}
+}
+
+impl Resolver<'_> {
+	fn report(&mut self, module: ModuleId, is_warning: bool, span: Span, err: ResolveError) -> &mut Error {
+		let module = &self.tree.modules[module];
+		let line = line_of(&module.source, span.start);
+		let slice = module.source.get(span.clone());
+
+		self.errors.push(Error::new(is_warning, module.file.as_deref(), Some(line), Some(span), slice, ErrorKind::Interpret(InterpretError::Resolve(err))));
+		self.errors.last_mut().unwrap()
+	}
+
+	fn line(&self, module: ModuleId, span: &Span) -> usize {
+		line_of(&self.tree.modules[module].source, span.start)
+	}
+
+	/// The full name of something, for error messages.
+	fn describe(&self, res: Res) -> String {
+		match res {
+			Res::Module(module) => self.tree.modules[module].path.join("::"),
+			Res::Def(def) => {
+				let mut names = vec![self.out.defs[def].name.name.clone()];
+				let mut parent = self.out.defs[def].parent;
+
+				while let Some(def) = parent {
+					names.push(self.out.defs[def].name.name.clone());
+					parent = self.out.defs[def].parent;
+				}
+
+				names.extend(self.tree.modules[self.out.defs[def].module].path.iter().rev().cloned());
+				names.reverse();
+				names.join("::")
+			},
+			_ => String::from("a local name"),
+		}
+	}
+
+	/// Finds everything in the package (rather than the standard library) which can be seen from anywhere.
+	/// Something inside of a type or trait only counts if what it's inside of does as well.
+	fn exports(&mut self) {
+		let exported = |def: DefId| {
+			let mut current = Some(def);
+
+			while let Some(def) = current {
+				if self.vis_of(def) != Visibility::Public { return false }
+				current = self.out.defs[def].parent;
+			}
+
+			self.tree.modules[self.out.defs[def].module].path[0] == "pkg"
+		};
+
+		let mut exports: Vec<(String, ExportKind)> = (0..self.out.defs.len())
+			.filter(|&def| exported(def))
+			.filter_map(|def| {
+				let kind = match self.out.defs[def].kind {
+					DefKind::Func => ExportKind::Func,
+					DefKind::Type => ExportKind::Type,
+					DefKind::Variant => ExportKind::Variant,
+					DefKind::Trait => ExportKind::Trait,
+					DefKind::Effect => ExportKind::Effect,
+					DefKind::Const => ExportKind::Const,
+					DefKind::Impl => return None,
+				};
+
+				Some((self.describe(Res::Def(def)), kind))
+			})
+			.collect();
+
+		exports.sort_by(|(a, _), (b, _)| a.cmp(b));
+		self.out.exports = exports;
+	}
+
+	//--> Declarations <--
+
+	fn declare_module(&mut self, module: ModuleId, ast: &mut AbstractSyntaxTree) {
+		let mut scope = ModuleScope::default();
+
+		for (name, &child) in self.tree.modules[module].children.iter() {
+			let span = self.tree.modules[child].named_at.clone().unwrap_or(0..0);
+			scope.names.insert(name.clone(), Name { res: Res::Module(child), vis: Visibility::Public, span });
+		}
+
+		self.out.scopes.push(scope);
+
+		for item in ast.items.iter_mut() {
+			let Some(def) = self.declare(module, None, item) else { continue };
+			let name = self.out.defs[def].name.clone();
+
+			if self.out.defs[def].kind == DefKind::Impl { continue }
+
+			self.add_name(module, name, Name { res: Res::Def(def), vis: item.vis, span: self.out.defs[def].name.span.clone() });
+		}
+	}
+
+	/// Adds a name to a module's scope, unless something already has it.
+	fn add_name(&mut self, module: ModuleId, ident: Ident, name: Name) {
+		match self.out.scopes[module].names.get(&ident.name) {
+			// Bringing a submodule in with `use self::inner` names the same module again, which is harmless.
+			// The import takes over the name, so that using the module counts as using the import.
+			Some(first) if matches!(first.res, Res::Module(_)) && first.res == name.res => {
+				let vis = if first.vis == Visibility::Public { first.vis } else { name.vis };
+				self.out.scopes[module].names.insert(ident.name, Name { vis, ..name });
+			},
+			Some(first) => {
+				let note = match first.res {
+					Res::Module(child) if self.tree.modules[child].named_at.is_none() => {
+						let file = self.tree.modules[child].file.as_deref().map(|file| file.display().to_string()).unwrap_or_default();
+						format!("it's also the name of the submodule in `{}`", file)
+					},
+					_ => format!("it was first brought in on line {}", self.line(module, &first.span)),
+				};
+
+				self.report(module, true, ident.span, ResolveError::Duplicate(ident.name)).add_note(note);
+			},
+			None => {
+				self.out.scopes[module].names.insert(ident.name, name);
+			},
+		}
+	}
+
+	fn add_def(&mut self, kind: DefKind, name: Ident, vis: Visibility, module: ModuleId, parent: Option<DefId>) -> DefId {
+		self.out.defs.push(Def { kind, name, vis, module, parent, children: HashMap::new() });
+		self.out.defs.len() - 1
+	}
+
+	/// Adds a variant or an inner item to a def, unless something already has its name.
+	fn add_child(&mut self, parent: DefId, child: DefId) {
+		let name = self.out.defs[child].name.clone();
+		let module = self.out.defs[child].module;
+
+		match self.out.defs[parent].children.get(&name.name) {
+			Some(&first) => {
+				let line = self.line(module, &self.out.defs[first].name.span);
+				let err = self.report(module, true, name.span, ResolveError::Duplicate(name.name));
+				err.add_note(format!("it was first declared on line {}", line));
+			},
+			None => {
+				self.out.defs[parent].children.insert(name.name, child);
+			},
+		}
+	}
+
+	/// Makes a def for an item and everything inside of it.
+	fn declare(&mut self, module: ModuleId, parent: Option<DefId>, item: &mut Item) -> Option<DefId> {
+		let (kind, name) = match &item.kind {
+			ItemKind::Use(_) => return None,
+			ItemKind::Func(func) => (DefKind::Func, func.name.clone()),
+			ItemKind::Type(decl) => (DefKind::Type, decl.name.clone()),
+			ItemKind::Trait(decl) => (DefKind::Trait, decl.name.clone()),
+			ItemKind::Effect(decl) => (DefKind::Effect, decl.name.clone()),
+			ItemKind::Impl(decl) => (DefKind::Impl, Ident::new("impl", decl.ty.span.clone())),
+			ItemKind::Const(decl) => (DefKind::Const, decl.name.clone()),
+		};
+
+		let def = self.add_def(kind, name, item.vis, module, parent);
+		item.def = Some(def);
+
+		let items = match &mut item.kind {
+			ItemKind::Type(decl) => {
+				if let TypeBody::Union(variants) = &mut decl.body {
+					for variant in variants.iter_mut() {
+						let child = self.add_def(DefKind::Variant, variant.name.clone(), item.vis, module, Some(def));
+						variant.def = Some(child);
+						self.add_child(def, child);
+					}
+				}
+
+				&mut decl.items
+			},
+			ItemKind::Trait(decl) => &mut decl.items,
+			ItemKind::Effect(decl) => &mut decl.items,
+			ItemKind::Impl(decl) => &mut decl.items,
+			_ => return Some(def),
+		};
+
+		for inner in items.iter_mut() {
+			if let Some(child) = self.declare(module, Some(def), inner) {
+				self.add_child(def, child);
+			}
+		}
+
+		Some(def)
+	}
+
+	//--> Imports <--
+
+	fn resolve_imports(&mut self, asts: &[AbstractSyntaxTree]) {
+		let mut imports: Vec<Import> = Vec::new();
+
+		for (module, ast) in asts.iter().enumerate() {
+			for item in ast.items.iter() {
+				if let ItemKind::Use(tree) = &item.kind {
+					flatten(module, item.vis, tree, &[], &mut imports);
+				}
+			}
+		}
+
+		let (globs, mut pending): (Vec<Import>, Vec<Import>) = imports.into_iter().partition(|import| import.name.is_none());
+
+		loop {
+			let mut changed = false;
+
+			let mut waiting = Vec::new();
+			for import in pending {
+				match self.lookup(import.module, &import.path, false) {
+					Lookup::Found(res, resolved) if resolved == import.path.len() => {
+						let name = import.name.clone().unwrap();
+						self.add_name(import.module, name, Name { res, vis: import.vis, span: import.span.clone() });
+						changed = true;
+					},
+					// The import still brings the name in, so that using it doesn't lead to more errors.
+					Lookup::Private(index, res, vis, owner) if index + 1 == import.path.len() => {
+						self.private(import.module, &import.path[..=index], import.span.clone(), vis, owner);
+						let name = import.name.clone().unwrap();
+						self.add_name(import.module, name, Name { res, vis: import.vis, span: import.span.clone() });
+						changed = true;
+					},
+					_ => waiting.push(import),
+				}
+			}
+			pending = waiting;
+
+			for import in globs.iter() {
+				changed |= self.glob(import);
+			}
+
+			if !changed { break }
+		}
+
+		// Whatever's left can't ever be resolved. Imports waiting on each other in a cycle get one error for the whole cycle.
+		let cycles = self.import_cycles(&pending, &globs);
+		let in_cycle: HashSet<usize> = cycles.iter().flatten().copied().collect();
+
+		for cycle in cycles.iter() {
+			let names = cycle.iter()
+				.chain(cycle.first())
+				.map(|&i| format!("{}::{}", self.tree.modules[pending[i].module].path.join("::"), pending[i].name.as_ref().unwrap().name))
+				.collect();
+
+			let first = &pending[cycle[0]];
+			self.report(first.module, false, first.span.clone(), ResolveError::Cycle(names));
+		}
+
+		for (_, import) in pending.into_iter().enumerate().filter(|(i, _)| !in_cycle.contains(i)) {
+			match self.lookup(import.module, &import.path, false) {
+				Lookup::Ambiguous(index, candidates) => self.ambiguous(import.module, &import.path[..=index], import.span, candidates),
+				Lookup::Missing(index) => self.unresolved(import.module, &import.path[..=index], import.span),
+				Lookup::Found(_, resolved) => self.unresolved(import.module, &import.path[..=resolved], import.span),
+				Lookup::Private(index, _, vis, owner) => self.private(import.module, &import.path[..=index], import.span, vis, owner),
+			}
+		}
+
+		for import in globs.iter() {
+			match self.lookup(import.module, &import.path, false) {
+				Lookup::Ambiguous(index, candidates) => self.ambiguous(import.module, &import.path[..=index], import.span.clone(), candidates),
+				Lookup::Missing(index) => self.unresolved(import.module, &import.path[..=index], import.span.clone()),
+				Lookup::Private(index, _, vis, owner) => self.private(import.module, &import.path[..=index], import.span.clone(), vis, owner),
+				Lookup::Found(..) => {},
+			}
+		}
+	}
+
+	/// Finds the imports left waiting which are only waiting on each other, giving back each cycle of them once.
+	fn import_cycles(&mut self, pending: &[Import], globs: &[Import]) -> Vec<Vec<usize>> {
+		// Each import waits on the ones which would bring in what it's looking for.
+		let mut waits: Vec<Vec<usize>> = Vec::new();
+
+		for import in pending.iter() {
+			let (prefix, name) = import.path.split_at(import.path.len() - 1);
+
+			waits.push(match self.lookup(import.module, prefix, false) {
+				Lookup::Found(Res::Module(target), resolved) if !prefix.is_empty() && resolved == prefix.len() => {
+					self.bringing_in(target, &name[0].name, pending, globs, &mut HashSet::new())
+				},
+				_ => Vec::new(),
+			});
+		}
+
+		// 0 is unvisited, 1 is being visited, 2 is done.
+		let mut state = vec![0u8; pending.len()];
+		let mut stack = Vec::new();
+		let mut cycles = Vec::new();
+
+		for start in 0..pending.len() {
+			find_cycles(start, &waits, &mut state, &mut stack, &mut cycles);
+		}
+
+		cycles
+	}
+
+	/// The imports left waiting which would bring a name into a module, either themselves or through a glob import.
+	fn bringing_in(&mut self, module: ModuleId, name: &str, pending: &[Import], globs: &[Import], seen: &mut HashSet<ModuleId>) -> Vec<usize> {
+		if !seen.insert(module) { return Vec::new() }
+
+		let mut found: Vec<usize> = pending.iter()
+			.enumerate()
+			.filter(|(_, import)| import.module == module && import.name.as_ref().is_some_and(|ident| ident.name == name))
+			.map(|(i, _)| i)
+			.collect();
+
+		for glob in globs.iter().filter(|glob| glob.module == module) {
+			if let Lookup::Found(Res::Module(from), resolved) = self.lookup(module, &glob.path, false) {
+				if resolved == glob.path.len() { found.extend(self.bringing_in(from, name, pending, globs, seen)); }
+			}
+		}
+
+		found
+	}
+
+	/// Brings in everything a glob import can see so far, giving back whether anything new came in.
+	fn glob(&mut self, import: &Import) -> bool {
+		let Lookup::Found(target, resolved) = self.lookup(import.module, &import.path, false) else { return false };
+		if resolved != import.path.len() { return false }
+
+		let names: Vec<(String, Res)> = match target {
+			Res::Module(module) => {
+				let scope = &self.out.scopes[module];
+				let names = scope.names.iter();
+				let globs = scope.globs.iter().flat_map(|(name, found)| found.iter().map(move |found| (name, found)));
+
+				// A glob import only brings in what's visible from where it is, and something in a module can always see
+				// the module's own names, so a submodule importing everything from its parent gets the private ones as well.
+				names.chain(globs)
+					.filter(|(_, found)| self.accessible(import.module, found.vis, module))
+					.map(|(name, found)| (name.clone(), found.res))
+					.collect()
+			},
+			Res::Def(def) => self.out.defs[def].children.iter()
+				.filter(|(_, &child)| self.accessible(import.module, self.vis_of(child), self.out.defs[child].module))
+				.map(|(name, &child)| (name.clone(), Res::Def(child)))
+				.collect(),
+			_ => Vec::new(),
+		};
+
+		let mut changed = false;
+
+		for (name, res) in names {
+			// A module importing everything from itself doesn't bring in anything new.
+			if res == Res::Module(import.module) { continue }
+
+			let found = self.out.scopes[import.module].globs.entry(name).or_default();
+			if found.iter().any(|found| found.res == res) { continue }
+
+			found.push(Name { res, vis: import.vis, span: import.span.clone() });
+			changed = true;
+		}
+
+		changed
+	}
+
+	/// Finds the imports in the package which never got used. Ones which aren't private are left alone,
+	/// since they're there for other modules to use.
+	fn unused_imports(&mut self, asts: &[AbstractSyntaxTree]) {
+		let mut imports: Vec<Import> = Vec::new();
+
+		for (module, ast) in asts.iter().enumerate() {
+			if self.tree.modules[module].path[0] != "pkg" { continue }
+
+			for item in ast.items.iter() {
+				if let ItemKind::Use(tree) = &item.kind {
+					if item.vis == Visibility::Private { flatten(module, item.vis, tree, &[], &mut imports); }
+				}
+			}
+		}
+
+		self.out.unused_imports = imports.into_iter()
+			.filter(|import| !self.used.contains(&(import.module, import.span.clone())))
+			.map(|import| (import.module, import.span))
+			.collect();
+	}
+
+	//--> Lookup <--
+
+	/// Looks up a name in a module's scope, preferring anything that isn't from a glob import.
+	fn lookup_name(&mut self, module: ModuleId, name: &str) -> Option<Result<Name, Vec<Name>>> {
+		let scope = &self.out.scopes[module];
+
+		let found = match scope.names.get(name) {
+			Some(found) => found.clone(),
+			None => match scope.globs.get(name).map(|found| &found[..]) {
+				None | Some([]) => return None,
+				Some([found]) => found.clone(),
+				Some(candidates) => return Some(Err(candidates.to_vec())),
+			},
+		};
+
+		self.used.insert((module, found.span.clone()));
+		Some(Ok(found))
+	}
+
+	fn accessible(&self, from: ModuleId, vis: Visibility, owner: ModuleId) -> bool {
+		accessible(self.tree, from, vis, owner)
+	}
+
+	/// How visible a def is, which for the parts of a trait or effect is however visible the trait or effect is.
+	fn vis_of(&self, def: DefId) -> Visibility {
+		match self.out.defs[def].parent {
+			Some(parent) if matches!(self.out.defs[parent].kind, DefKind::Trait | DefKind::Effect) => self.out.defs[parent].vis,
+			_ => self.out.defs[def].vis,
+		}
+	}
+
+	/// Looks up a path from a module, and from the scopes inside of an item if there are any.
+	fn lookup(&mut self, module: ModuleId, segments: &[Ident], scoped: bool) -> Lookup {
+		let Some(head) = segments.first() else { return Lookup::Missing(0) };
+
+		let (mut res, start) = match head.name.as_str() {
+			"self" if segments.len() > 1 || !scoped => (Res::Module(module), 1),
+			"super" => {
+				let supers = segments.iter().take_while(|segment| segment.name == "super").count();
+				let mut current = module;
+
+				for i in 0..supers {
+					match self.tree.modules[current].parent {
+						Some(parent) => current = parent,
+						None => return Lookup::Missing(i),
+					}
+				}
+
+				(Res::Module(current), supers)
+			},
+			"pkg" if self.tree.modules[module].path[0] == "std" => (Res::Module(self.tree.std.unwrap()), 1),
+			"pkg" => (Res::Module(self.tree.root), 1),
+			"std" => match self.tree.std {
+				Some(std) => (Res::Module(std), 1),
+				None => return Lookup::Missing(0),
+			},
+			name => {
+				let local = self.scopes.iter().rev().find_map(|scope| scope.get(name)).filter(|_| scoped);
+
+				let found = match local {
+					Some(&res) => Some(Ok(res)),
+					None => match self.lookup_name(module, name) {
+						Some(found) => Some(found.map(|found| found.res)),
+						// Whatever the prelude brings in has to be visible from here, like anything else from another module.
+						None => match self.tree.prelude.and_then(|prelude| Some((prelude, self.lookup_name(prelude, name)?))) {
+							Some((prelude, Ok(found))) if !self.accessible(module, found.vis, prelude) => return Lookup::Private(0, found.res, found.vis, prelude),
+							Some((_, found)) => Some(found.map(|found| found.res)),
+							None => None,
+						},
+					},
+				};
+
+				match found {
+					Some(Ok(res)) => (res, 1),
+					Some(Err(candidates)) => return Lookup::Ambiguous(0, candidates),
+					None => return Lookup::Missing(0),
+				}
+			},
+		};
+
+		for (i, segment) in segments.iter().enumerate().skip(start) {
+			res = match res {
+				Res::Module(owner) => match self.lookup_name(owner, &segment.name) {
+					Some(Ok(found)) if !self.accessible(module, found.vis, owner) => return Lookup::Private(i, found.res, found.vis, owner),
+					Some(Ok(found)) => found.res,
+					Some(Err(candidates)) => return Lookup::Ambiguous(i, candidates),
+					None => return Lookup::Missing(i),
+				},
+				// Anything else inside of a def, like a trait method on a type, is for type checking to find.
+				Res::Def(def) => match self.out.defs[def].children.get(&segment.name) {
+					Some(&child) if !self.accessible(module, self.vis_of(child), self.out.defs[child].module) => {
+						return Lookup::Private(i, Res::Def(child), self.vis_of(child), self.out.defs[child].module);
+					},
+					Some(&child) => Res::Def(child),
+					None => return Lookup::Found(res, i),
+				},
+				// `Self` inside of a type is just that type, so `Self::Variant` is found the same way.
+				Res::SelfType(def) if self.out.defs[def].kind == DefKind::Type => match self.out.defs[def].children.get(&segment.name) {
+					Some(&child) => Res::Def(child),
+					None => return Lookup::Found(res, i),
+				},
+				_ => return Lookup::Found(res, i),
+			};
+		}
+
+		Lookup::Found(res, segments.len())
+	}
+
+	fn unresolved(&mut self, module: ModuleId, segments: &[Ident], span: Span) {
+		let name = segments.iter().map(|segment| segment.name.as_str()).collect::<Vec<_>>().join("::");
+		self.report(module, false, span, ResolveError::Unresolved(name));
+	}
+
+	fn private(&mut self, module: ModuleId, segments: &[Ident], span: Span, vis: Visibility, owner: ModuleId) {
+		let name = segments.last().unwrap().name.clone();
+
+		// Something marked `prt` is private to its whole package, which is its root module.
+		let (owner, note) = match vis {
+			Visibility::Protected => (&self.tree.modules[owner].path[..1], "it's marked `prt`, so only its own package can use it"),
+			_ => (&self.tree.modules[owner].path[..], "it isn't marked `pub` or `prt`, so only its own module and the modules inside of it can use it"),
+		};
+
+		let owner = owner.join("::");
+		self.report(module, false, span, ResolveError::Private { name, module: owner }).add_note(note.to_string());
+	}
+
+	fn ambiguous(&mut self, module: ModuleId, segments: &[Ident], span: Span, candidates: Vec<Name>) {
+		let name = segments.iter().map(|segment| segment.name.as_str()).collect::<Vec<_>>().join("::");
+		let notes: Vec<String> = candidates.iter()
+			.map(|candidate| format!("it could be `{}`, brought in on line {}", self.describe(candidate.res), self.line(module, &candidate.span)))
+			.collect();
+
+		let err = self.report(module, false, span, ResolveError::Ambiguous(name));
+		for note in notes { err.add_note(note); }
+	}
+
+	//--> Scopes <--
+
+	fn push(&mut self) {
+		self.scopes.push(HashMap::new());
+	}
+
+	fn pop(&mut self) {
+		self.scopes.pop();
+	}
+
+	/// Binds a new local in the innermost scope.
+	fn bind(&mut self, name: &Ident) -> LocalId {
+		let earlier = self.scopes.iter().rev().find_map(|scope| match scope.get(&name.name) {
+			Some(Res::Local(local)) => Some(*local),
+			_ => None,
+		});
+
+		// Generated names and ones starting with `_` are allowed to shadow.
+		let quiet = name.name.starts_with('_') || name.name.contains('$') || name.name == "self";
+
+		if let Some(earlier) = earlier.filter(|_| !quiet) {
+			let line = self.line(self.module, &self.out.locals[earlier].name.span);
+			let err = self.report(self.module, true, name.span.clone(), ResolveError::Shadowed(name.name.clone()));
+			err.add_note(format!("the earlier binding is on line {}", line));
+		}
+
+		let local = self.out.locals.len();
+		self.out.locals.push(Local { name: name.clone() });
+		self.scopes.last_mut().unwrap().insert(name.name.clone(), Res::Local(local));
+		local
+	}
+
+	/// Puts generic parameters in scope, and resolves their bounds and defaults.
+	fn generics(&mut self, owner: DefId, params: &mut [GenericParam], where_clause: &mut [WherePredicate]) {
+		for (i, param) in params.iter().enumerate() {
+			self.scopes.last_mut().unwrap().insert(param.name.name.clone(), Res::Generic(owner, i));
+		}
+
+		for param in params.iter_mut() {
+			for bound in param.bounds.iter_mut() { self.ty(bound); }
+			if let Some(default) = &mut param.default { self.ty(default); }
+		}
+
+		for predicate in where_clause.iter_mut() {
+			self.ty(&mut predicate.ty);
+			for bound in predicate.bounds.iter_mut() { self.ty(bound); }
+		}
+	}
+
+	//--> Items <--
+
+	fn item(&mut self, item: &mut Item) {
+		let Some(def) = item.def else { return };
+		self.push();
+
+		match &mut item.kind {
+			ItemKind::Use(_) => {},
+			ItemKind::Func(func) => {
+				self.generics(def, &mut func.generics, &mut func.where_clause);
+
+				for param in func.params.iter_mut() {
+					if let Some(ty) = &mut param.ty { self.ty(ty); }
+				}
+				if let Some(ret) = &mut func.ret { self.ty(ret); }
+				for effect in func.effects.iter_mut() { self.ty(effect); }
+
+				self.push();
+				self.params(&mut func.params);
+				if let Some(body) = &mut func.body { self.block(body); }
+				self.pop();
+			},
+			ItemKind::Type(decl) => {
+				self.scopes.last_mut().unwrap().insert("Self".into(), Res::SelfType(def));
+				self.generics(def, &mut decl.generics, &mut []);
+
+				match &mut decl.body {
+					TypeBody::Opaque => {},
+					TypeBody::Alias(ty) => self.ty(ty),
+					TypeBody::Record(fields) => {
+						for field in fields.iter_mut() { self.ty(&mut field.ty); }
+					},
+					TypeBody::Tuple(fields) => {
+						for field in fields.iter_mut() { self.ty(field); }
+					},
+					TypeBody::Union(variants) => {
+						for variant in variants.iter_mut() {
+							for field in variant.fields.iter_mut() { self.ty(field); }
+						}
+					},
+				}
+
+				for item in decl.items.iter_mut() { self.item(item); }
+			},
+			ItemKind::Trait(decl) => {
+				self.scopes.last_mut().unwrap().insert("Self".into(), Res::SelfType(def));
+				self.generics(def, &mut decl.generics, &mut []);
+				for supertrait in decl.supertraits.iter_mut() { self.ty(supertrait); }
+				for item in decl.items.iter_mut() { self.item(item); }
+			},
+			ItemKind::Effect(decl) => {
+				self.generics(def, &mut decl.generics, &mut []);
+				for item in decl.items.iter_mut() { self.item(item); }
+			},
+			ItemKind::Impl(decl) => {
+				self.generics(def, &mut decl.generics, &mut decl.where_clause);
+				if let Some(trait_) = &mut decl.trait_ { self.ty(trait_); }
+				self.ty(&mut decl.ty);
+
+				self.scopes.last_mut().unwrap().insert("Self".into(), Res::SelfType(def));
+				for item in decl.items.iter_mut() { self.item(item); }
+			},
+			ItemKind::Const(decl) => {
+				if let Some(ty) = &mut decl.ty { self.ty(ty); }
+				if let Some(value) = &mut decl.value { self.expr(value); }
+			},
+		}
+
+		self.pop();
+	}
+
+	fn params(&mut self, params: &mut [Param]) {
+		for param in params.iter_mut() {
+			param.local = Some(self.bind(&param.name));
+		}
+	}
+
+	fn path(&mut self, path: &mut Path) {
+		let segments: Vec<Ident> = path.segments.iter().map(|segment| segment.name.clone()).collect();
+
+		match self.lookup(self.module, &segments, true) {
+			Lookup::Found(res, resolved) => {
+				path.res = res;
+				path.resolved = resolved;
+			},
+			Lookup::Missing(index) => self.unresolved(self.module, &segments[..=index], path.span.clone()),
+			Lookup::Ambiguous(index, candidates) => self.ambiguous(self.module, &segments[..=index], path.span.clone(), candidates),
+			Lookup::Private(index, _, vis, owner) => self.private(self.module, &segments[..=index], path.span.clone(), vis, owner),
+		}
+
+		for segment in path.segments.iter_mut() {
+			for generic in segment.generics.iter_mut() { self.ty(generic); }
+		}
+	}
+
+	fn ty(&mut self, ty: &mut Type) {
+		match &mut ty.kind {
+			TypeKind::Path(path) => self.path(path),
+			TypeKind::Never => {},
+			TypeKind::Dyn(inner) | TypeKind::List(inner) => self.ty(inner),
+			TypeKind::Array(inner, len) => {
+				self.ty(inner);
+				self.expr(len);
+			},
+			TypeKind::Map(key, value) => {
+				self.ty(key);
+				self.ty(value);
+			},
+			TypeKind::Func { params, ret, effects } => {
+				for param in params.iter_mut() { self.ty(param); }
+				if let Some(ret) = ret { self.ty(ret); }
+				for effect in effects.iter_mut() { self.ty(effect); }
+			},
+			TypeKind::Tuple(elems) => {
+				for elem in elems.iter_mut() { self.ty(elem); }
+			},
+			TypeKind::Record(fields) => {
+				for (_, ty) in fields.iter_mut() { self.ty(ty); }
+			},
+		}
+	}
+
+	//--> Statements and Expressions <--
+
+	fn block(&mut self, block: &mut Block) {
+		self.push();
+		for stmt in block.stmts.iter_mut() {
+			self.stmt(stmt);
+		}
+		self.pop();
+	}
+
+	fn stmt(&mut self, stmt: &mut Stmt) {
+		match &mut stmt.kind {
+			// The value can't see what the pattern binds, but everything after it can.
+			StmtKind::Let { pat, ty, value, .. } => {
+				self.expr(value);
+				if let Some(ty) = ty { self.ty(ty); }
+				self.pattern(pat);
+			},
+			StmtKind::Assign { target, value, .. } => {
+				self.expr(target);
+				self.expr(value);
+			},
+			StmtKind::Expr(expr) => self.expr(expr),
+			StmtKind::Handler(handler) => self.handler(handler),
+		}
+	}
+
+	fn handler(&mut self, handler: &mut Handler) {
+		self.path(&mut handler.operation);
+
+		self.push();
+		for param in handler.params.iter_mut() { self.pattern(param); }
+		self.block(&mut handler.body);
+		self.pop();
+	}
+
+	/// Resolves the paths in a pattern, and binds its names in the innermost scope.
+	fn pattern(&mut self, pat: &mut Pattern) {
+		match &mut pat.kind {
+			PatternKind::Wildcard | PatternKind::Lit { .. } => {},
+			PatternKind::Binding { name, local, .. } => *local = Some(self.bind(name)),
+			PatternKind::Range { start, end, .. } => {
+				for end in [start, end].into_iter().flatten() { self.pattern(end); }
+			},
+			PatternKind::Path(path) => self.path(path),
+			PatternKind::TupleStruct { path, fields } => {
+				self.path(path);
+				for field in fields.iter_mut() { self.pattern(field); }
+			},
+			PatternKind::Tuple(elems) => {
+				for elem in elems.iter_mut() { self.pattern(elem); }
+			},
+		}
+	}
+
+	fn expr(&mut self, expr: &mut Expr) {
+		match &mut expr.kind {
+			ExprKind::Lit(_) | ExprKind::Continue(_) => {},
+			ExprKind::Path(path) => self.path(path),
+			ExprKind::Call { callee, args } => {
+				self.expr(callee);
+				for arg in args.iter_mut() { self.expr(arg); }
+			},
+			ExprKind::MethodCall { receiver, args, .. } => {
+				self.expr(receiver);
+				for arg in args.iter_mut() { self.expr(arg); }
+			},
+			ExprKind::Field { base, .. } | ExprKind::TupleField { base, .. } => self.expr(base),
+			ExprKind::Index { base, index } => {
+				self.expr(base);
+				self.expr(index);
+			},
+			ExprKind::Tuple(elems) | ExprKind::List(elems) | ExprKind::Interp(elems) => {
+				for elem in elems.iter_mut() { self.expr(elem); }
+			},
+			ExprKind::Record(fields) => {
+				for (_, value) in fields.iter_mut() { self.expr(value); }
+			},
+			ExprKind::Map(entries) => {
+				for (key, value) in entries.iter_mut() {
+					self.expr(key);
+					self.expr(value);
+				}
+			},
+			ExprKind::Closure { params, ret, effects, body } => {
+				for param in params.iter_mut() {
+					if let Some(ty) = &mut param.ty { self.ty(ty); }
+				}
+				if let Some(ret) = ret { self.ty(ret); }
+				for effect in effects.iter_mut() { self.ty(effect); }
+
+				self.push();
+				self.params(params);
+				self.block(body);
+				self.pop();
+			},
+			ExprKind::Unary { expr, .. } | ExprKind::Try(expr) => self.expr(expr),
+			ExprKind::Binary { lhs, rhs, .. } => {
+				self.expr(lhs);
+				self.expr(rhs);
+			},
+			ExprKind::If { branches, else_ } => {
+				for branch in branches.iter_mut() {
+					self.push();
+
+					match &mut branch.cond {
+						Condition::Expr(cond) => self.expr(cond),
+						Condition::Matches(cond, pat) => {
+							self.expr(cond);
+							self.pattern(pat);
+						},
+					}
+
+					self.block(&mut branch.body);
+					self.pop();
+				}
+
+				if let Some(else_) = else_ { self.block(else_); }
+			},
+			ExprKind::Match { scrutinee, arms } => {
+				self.expr(scrutinee);
+
+				for arm in arms.iter_mut() {
+					self.push();
+					self.pattern(&mut arm.pat);
+					self.expr(&mut arm.body);
+					self.pop();
+				}
+			},
+			ExprKind::Loop { body, .. } | ExprKind::Block { body, .. } => self.block(body),
+			ExprKind::While { cond, body, .. } | ExprKind::Until { cond, body, .. } => {
+				self.expr(cond);
+				self.block(body);
+			},
+			ExprKind::For { pat, iter, body, .. } => {
+				self.expr(iter);
+
+				self.push();
+				self.pattern(pat);
+				self.block(body);
+				self.pop();
+			},
+			ExprKind::Handle { handler, body } => {
+				self.handler(handler);
+				self.block(body);
+			},
+			ExprKind::Break { value, .. } | ExprKind::Return(value) => {
+				if let Some(value) = value { self.expr(value); }
+			},
+		}
+	}
+}
+
+/// Flattens a `use` into separate imports, one for each name or glob.
+fn flatten(module: ModuleId, vis: Visibility, tree: &UseTree, prefix: &[Ident], out: &mut Vec<Import>) {
+	let path: Vec<Ident> = prefix.iter().chain(tree.prefix.iter()).cloned().collect();
+
+	match &tree.kind {
+		UseKind::Single(alias) => {
+			let Some(name) = alias.clone().or_else(|| path.last().cloned()) else { return };
+			out.push(Import { module, path, name: Some(name), vis, span: tree.span.clone() });
+		},
+		UseKind::Glob => out.push(Import { module, path, name: None, vis, span: tree.span.clone() }),
+		UseKind::List(trees) => {
+			for tree in trees.iter() {
+				flatten(module, vis, tree, &path, out);
+			}
+		},
+	}
+}
+
+/// Walks through what each import waits on, noting down each cycle the first time it's come across.
+fn find_cycles(import: usize, waits: &[Vec<usize>], state: &mut [u8], stack: &mut Vec<usize>, cycles: &mut Vec<Vec<usize>>) {
+	if state[import] != 0 { return }
+
+	state[import] = 1;
+	stack.push(import);
+
+	for &next in waits[import].iter() {
+		match state[next] {
+			0 => find_cycles(next, waits, state, stack, cycles),
+			1 => {
+				let start = stack.iter().position(|&i| i == next).unwrap();
+				cycles.push(stack[start..].to_vec());
+			},
+			_ => {},
+		}
+	}
+
+	stack.pop();
+	state[import] = 2;
+}
+
+impl fmt::Display for ResolveError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ResolveError::Unresolved(name) => write!(f, "unresolved name `{}`", name),
+			ResolveError::Ambiguous(name) => write!(f, "ambiguous import: more than one glob import brings in `{}`", name),
+			ResolveError::Duplicate(name) => write!(f, "`{}` is already defined here", name),
+			ResolveError::Shadowed(name) => write!(f, "`{}` shadows an earlier binding with the same name", name),
+			ResolveError::Private { name, module } => write!(f, "`{}` is private to module `{}`", name, module),
+			ResolveError::Cycle(cycle) => {
+				let cycle: Vec<String> = cycle.iter().map(|name| format!("`{}`", name)).collect();
+				write!(f, "these imports only lead back to each other: {}", cycle.join(" -> "))
+			},
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compiler::{
+		Options,
+		TestPackage,
+		modules,
+	};
+
+	/// Each error and warning, as whether it's a warning, its line and what went wrong.
+	type Reported = Vec<(bool, Option<usize>, ResolveError)>;
+
+	/// Resolves a package, giving back the module tree along with every error and warning.
+	fn resolve_package(name: &str, files: &[(&str, &str)]) -> (ModuleTree, Option<Resolutions>, Reported) {
+		let (mut tree, _) = modules::load(TestPackage::new(name, files).root(), &Options::default()).unwrap();
+
+		let (out, errors) = match resolve(&mut tree) {
+			Ok((out, warnings)) => (Some(out), warnings),
+			Err(errors) => (None, errors),
+		};
+
+		let errors = errors.into_iter()
+			.map(|e| match e.kind() {
+				ErrorKind::Interpret(InterpretError::Resolve(err)) => (e.is_warning(), e.line(), err),
+				kind => panic!("expected a resolve error, got {}", kind),
+			})
+			.collect();
+
+		(tree, out, errors)
+	}
+
+	/// What the path in an expression statement resolved to.
+	fn stmt_res(tree: &ModuleTree, func: usize, stmt: usize) -> Res {
+		let ItemKind::Func(func) = &tree.modules[tree.root].ast.items[func].kind else { panic!("expected a function") };
+		let StmtKind::Expr(expr) = &func.body.as_ref().unwrap().stmts[stmt].kind else { panic!("expected an expression") };
+
+		match &expr.kind {
+			ExprKind::Path(path) => path.res,
+			ExprKind::Call { callee, .. } => match &callee.kind {
+				ExprKind::Path(path) => path.res,
+				_ => panic!("expected a path"),
+			},
+			_ => panic!("expected a path or a call"),
+		}
+	}
+
+	#[test]
+	fn names_come_from_scopes_modules_and_the_prelude() {
+		let (tree, out, errors) = resolve_package("scopes", &[
+			("main.ro", "use self::shapes::[Shape, area]\n\nfunc main(n: nat) do\n\tn\n\tarea()\n\tShape::Circle\n\tSome(1)\nend\n"),
+			("shapes.ro", "pub type Shape is\n\t| Circle\n\t| Square\nend\n\npub func area() do\nend\n"),
+		]);
+
+		assert!(errors.is_empty(), "{:?}", errors);
+		let out = out.unwrap();
+
+		assert!(matches!(stmt_res(&tree, 1, 0), Res::Local(local) if out.locals[local].name.name == "n"));
+
+		for (stmt, kind, name) in [(1, DefKind::Func, "area"), (2, DefKind::Variant, "Circle"), (3, DefKind::Variant, "Some")] {
+			let Res::Def(def) = stmt_res(&tree, 1, stmt) else { panic!("`{}` didn't resolve to a def", name) };
+			assert_eq!(out.defs[def].kind, kind);
+			assert_eq!(out.defs[def].name.name, name);
+		}
+	}
+
+	#[test]
+	fn modules_can_import_each_other() {
+		let (_, out, errors) = resolve_package("mutual", &[
+			("main.ro", "use self::shapes::circle\n\npub func unit() do\nend\n\nfunc main() do\n\tcircle()\nend\n"),
+			("shapes.ro", "use super::unit\n\npub func circle() do\n\tunit()\nend\n"),
+		]);
+
+		assert!(out.is_some());
+		assert!(errors.is_empty());
+	}
+
+	#[test]
+	fn imports_which_lead_back_to_themselves_are_errors() {
+		let (_, out, mut errors) = resolve_package("import-cycle", &[
+			("main.ro", "use self::[a::Thing, c::Other]\n\nfunc main() do\nend\n"),
+			("a.ro", "pub use super::b::Thing\n"),
+			("b.ro", "pub use super::a::Thing\n"),
+			("c.ro", "pub use super::d::*\n"),
+			("d.ro", "pub use super::c::Other\n"),
+		]);
+
+		assert!(out.is_none());
+		errors.sort_by_key(|(_, _, err)| err.to_string());
+
+		let cycle = |err: &ResolveError| match err {
+			ResolveError::Cycle(cycle) => cycle.join(" "),
+			_ => String::new(),
+		};
+
+		assert_eq!(cycle(&errors[0].2), "pkg::a::Thing pkg::b::Thing pkg::a::Thing");
+		assert_eq!(cycle(&errors[1].2), "pkg::d::Other pkg::d::Other");
+
+		// Importing something from a cycle doesn't make another one, it just doesn't find anything.
+		assert!(matches!(&errors[2], (false, Some(1), ResolveError::Unresolved(name)) if name == "self::a::Thing"));
+		assert!(matches!(&errors[3], (false, Some(1), ResolveError::Unresolved(name)) if name == "self::c::Other"));
+		assert_eq!(errors.len(), 4);
+	}
+
+	#[test]
+	fn unresolved_names_are_errors() {
+		let (_, out, errors) = resolve_package("unresolved", &[
+			("main.ro", "use std::cmp::Ordering::Sideways\n\nfunc main() do\n\tnope()\n\tutil::helper()\nend\n"),
+			("util.ro", "func helper() do\nend\n"),
+		]);
+
+		assert!(out.is_none());
+		assert!(matches!(&errors[0], (false, Some(1), ResolveError::Unresolved(name)) if name == "std::cmp::Ordering::Sideways"));
+		assert!(matches!(&errors[1], (false, Some(4), ResolveError::Unresolved(name)) if name == "nope"));
+
+		// Submodules only come into scope through paths starting with `self`, `super` or `pkg`.
+		assert!(matches!(&errors[2], (false, Some(5), ResolveError::Unresolved(name)) if name == "util"));
+		assert_eq!(errors.len(), 3);
+	}
+
+	#[test]
+	fn glob_imports_lose_out_and_can_be_ambiguous() {
+		let (tree, out, errors) = resolve_package("globs", &[
+			("main.ro", "use self::a::*\nuse self::b::*\nuse self::b::thing\n\nfunc main() do\n\tthing()\n\tother()\nend\n"),
+			("a.ro", "pub func thing() do\nend\n\npub func other() do\nend\n"),
+			("b.ro", "use super::a::other\n\npub func thing() do\nend\n\nfunc other() do\nend\n"),
+		]);
+
+		// `other` is imported privately by `b`, so only the one in `a` comes through its glob.
+		assert!(errors.iter().all(|(is_warning, ..)| *is_warning), "{:?}", errors);
+		let out = out.unwrap();
+
+		let Res::Def(def) = stmt_res(&tree, 3, 0) else { panic!("`thing` didn't resolve") };
+		assert_eq!(tree.modules[out.defs[def].module].path.join("::"), "pkg::b");
+
+		let (_, _, errors) = resolve_package("ambiguous", &[
+			("main.ro", "use self::a::*\nuse self::b::*\n\nfunc main() do\n\tthing()\nend\n"),
+			("a.ro", "pub func thing() do\nend\n"),
+			("b.ro", "pub func thing() do\nend\n"),
+		]);
+
+		assert!(matches!(&errors[..], [(false, Some(5), ResolveError::Ambiguous(name))] if name == "thing"));
+	}
+
+	#[test]
+	fn only_visible_things_can_be_used() {
+		let (_, out, errors) = resolve_package("visibility", &[
+			("main.ro", "use self::a::hidden\nuse self::a::inner::call\n\nfunc main() do\n\tcall()\n\tself::a::shared()\n\tself::a::open()\n\tself::a::Shape::Circle\nend\n"),
+			("a.ro", "func hidden() do\nend\n\nprt func shared() do\nend\n\npub func open() do\nend\n\npub type Shape is\n\t| Circle\n\t| Square\nend\n"),
+			("a/inner.ro", "use super::*\n\npub func call() do\n\thidden()\n\tsuper::hidden()\nend\n"),
+		]);
+
+		// Modules inside of `a` can see everything in it, even through glob imports, but `main` can only see what isn't private.
+		assert!(out.is_none());
+		assert!(matches!(&errors[..], [(false, Some(1), ResolveError::Private { name, module })] if name == "hidden" && module == "pkg::a"), "{:?}", errors);
+	}
+
+	#[test]
+	fn only_pub_things_are_exported() {
+		let (_, out, _) = resolve_package("exports", &[
+			("main.ro", "use self::a::open\n\npub func main() do\nend\n\nfunc helper() do\nend\n"),
+			("a.ro", "pub func open() do\nend\n\nprt func shared() do\nend\n\npub type Shape is\n\t| Circle\n\t| Square\nend\n\ntype Hidden is\n\t| Thing\nend\n"),
+		]);
+
+		let exports = out.unwrap().exports;
+		let names: Vec<&str> = exports.iter().map(|(name, _)| name.as_str()).collect();
+		assert_eq!(names, ["pkg::a::Shape", "pkg::a::Shape::Circle", "pkg::a::Shape::Square", "pkg::a::open", "pkg::main"]);
+		assert_eq!(exports[1].1, ExportKind::Variant);
+	}
+
+	#[test]
+	fn duplicates_and_shadowing_are_warnings() {
+		let (_, out, errors) = resolve_package("duplicates", &[
+			("main.ro", "func main() do\n\tx := 1\n\tif true then\n\t\tx := 2\n\tend\n\t_y := 1\n\t_y := 2\nend\n\nfunc main() do\nend\n"),
+		]);
+
+		assert!(out.is_some());
+		assert!(matches!(&errors[0], (true, Some(10), ResolveError::Duplicate(name)) if name == "main"));
+		assert!(matches!(&errors[1], (true, Some(4), ResolveError::Shadowed(name)) if name == "x"));
+		assert_eq!(errors.len(), 2);
+	}
+
+	#[test]
+	fn submodules_can_be_imported_into_their_parent() {
+		let (_, out, errors) = resolve_package("submodules", &[
+			("main.ro", "pub use self::shapes\nuse self::util\n\nfunc main() do\n\tshapes::area()\n\tutil::run()\nend\n"),
+			("shapes.ro", "pub func area() do\nend\n"),
+			("util.ro", "pub func run() do\nend\n"),
+		]);
+
+		assert!(errors.is_empty(), "{:?}", errors);
+		assert!(out.unwrap().unused_imports.is_empty());
+
+		let (_, _, errors) = resolve_package("clashing", &[
+			("main.ro", "func main() do\n\tself::shapes::area()\nend\n\nfunc shapes() do\nend\n"),
+			("shapes.ro", "pub func area() do\nend\n"),
+		]);
+
+		assert!(matches!(&errors[0], (true, Some(5), ResolveError::Duplicate(name)) if name == "shapes"));
+		assert_eq!(errors.len(), 1);
+	}
+}
@@ -0,0 +1,703 @@
+//! Works out the types in the signatures of items, before any bodies get checked.
+
+//--> Imports <--
+
+use super::{
+	Checker,
+	Head,
+	Sig,
+	Tail,
+	TraitRef,
+	Ty,
+	TypeError,
+	traits::WellFormed,
+	ty::Prim,
+};
+
+use crate::compiler::{
+	modules::ModuleId,
+	parser::astgen::{
+		Expr,
+		ExprKind,
+		Func,
+		GenericParam,
+		Ident,
+		Item,
+		ItemKind,
+		Literal,
+		Path,
+		Type,
+		TypeBody,
+		TypeDecl,
+		TypeKind,
+		WherePredicate,
+	},
+	resolve::{
+		DefId,
+		DefKind,
+		Res,
+	},
+};
+
+use logos::Span;
+
+use std::collections::HashMap;
+
+//--> Functions <--
+
+impl<'a> Checker<'a> {
+	/// Lowers the signature of every item, so that bodies can be checked in any order.
+	pub(super) fn collect(&mut self) {
+		let tree = self.tree;
+
+		for (module, m) in tree.modules.iter().enumerate() {
+			for item in m.ast.items.iter() {
+				self.collect_item(module, item);
+			}
+		}
+
+		self.range = self.std_def(&["ops", "Range"]);
+
+		let mut defs: Vec<DefId> = self.items.keys().copied().collect();
+		defs.sort_unstable();
+
+		// Methods inside of impls need to know what `Self` is, so impls come first.
+		for &def in defs.iter() {
+			let item = self.items[&def];
+			let ItemKind::Impl(decl) = &item.kind else { continue };
+
+			self.module = self.res.defs[def].module;
+			self.within = Some(def);
+			let ty = self.lower_ty(&decl.ty);
+
+			if let Some(head) = head(&ty) {
+				self.impls.entry(head).or_default().push(def);
+			}
+
+			if let Some(trait_) = decl.trait_.as_ref().and_then(|trait_| self.lower_trait(trait_, &ty)) {
+				self.impl_traits.insert(def, trait_);
+			}
+
+			self.impl_tys.insert(def, ty);
+		}
+
+		for &def in defs.iter() {
+			let item = self.items[&def];
+			self.module = self.res.defs[def].module;
+			self.within = Some(def);
+			self.check_defaults(def);
+
+			match &item.kind {
+				ItemKind::Func(func) => {
+					self.collect_bounds(def, &func.generics, &func.where_clause);
+					let sig = self.lower_sig(def, func);
+					self.sigs.insert(def, sig);
+				},
+				ItemKind::Type(decl) => {
+					self.collect_bounds(def, &decl.generics, &[]);
+					self.check_assoc_type(def, decl);
+
+					// Records and tuple types get made by calling the type itself, like a variant.
+					// A record's constructor takes all of its fields as one record, which named arguments turn into.
+					let params = match &decl.body {
+						TypeBody::Record(fields) => {
+							let mut fields: Vec<(String, Ty)> = fields.iter().map(|field| (field.name.name.clone(), self.lower_ty(&field.ty))).collect();
+							fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+							vec![Ty::Record(fields)]
+						},
+						TypeBody::Tuple(fields) => fields.iter().map(|field| self.lower_ty(field)).collect(),
+						_ => continue,
+					};
+
+					let ret = self.self_adt(def);
+					self.sigs.insert(def, Sig { params, ret, ret_span: None, owner: None, has_self: false, effects: Ty::Row(Vec::new(), None) });
+				},
+				ItemKind::Trait(decl) => {
+					self.collect_bounds(def, &decl.generics, &[]);
+					let supertraits = decl.supertraits.iter().filter_map(|supertrait| self.lower_trait(supertrait, &Ty::SelfOf(def))).collect();
+					self.supertraits.insert(def, supertraits);
+				},
+				ItemKind::Effect(decl) => self.collect_bounds(def, &decl.generics, &[]),
+				ItemKind::Impl(decl) => self.collect_bounds(def, &decl.generics, &decl.where_clause),
+				ItemKind::Const(decl) => {
+					if let Some(ty) = &decl.ty {
+						let ty = self.lower_ty(ty);
+						self.consts.insert(def, ty);
+					}
+
+					if decl.value.is_none() && !self.is_assoc(def) {
+						self.report(decl.name.span.clone(), TypeError::MissingValue(decl.name.name.clone()));
+					}
+				},
+				ItemKind::Use(_) => {},
+			}
+		}
+
+		let mut variants: Vec<DefId> = self.variants.keys().copied().collect();
+		variants.sort_unstable();
+
+		for def in variants {
+			let variant = self.variants[&def];
+			let parent = self.res.defs[def].parent.unwrap();
+			self.module = self.res.defs[def].module;
+			self.within = Some(def);
+
+			let params = variant.fields.iter().map(|field| self.lower_ty(field)).collect();
+			let ret = self.self_adt(parent);
+			self.sigs.insert(def, Sig { params, ret, ret_span: None, owner: None, has_self: false, effects: Ty::Row(Vec::new(), None) });
+		}
+
+		self.within = None;
+	}
+
+	fn collect_item(&mut self, module: ModuleId, item: &'a Item) {
+		let Some(def) = item.def else { return };
+		self.items.insert(def, item);
+
+		let items = match &item.kind {
+			ItemKind::Type(decl) => {
+				// The primitives are declared as builtin types in the standard library.
+				let is_builtin = item.attrs.iter().any(|attr| attr.name.name == "builtin");
+				if let Some(prim) = Prim::from_name(&decl.name.name).filter(|_| is_builtin && self.tree.modules[module].path[0] == "std") {
+					self.prims.insert(def, prim);
+				}
+
+				let collection = match decl.name.name.as_str() {
+					"[]" => Some(Head::List),
+					"[;]" => Some(Head::Array),
+					"[:]" => Some(Head::Map),
+					_ => None,
+				};
+
+				if let Some(head) = collection.filter(|_| is_builtin && self.tree.modules[module].path[0] == "std") {
+					self.collections.insert(def, head);
+				}
+
+				if let TypeBody::Union(variants) = &decl.body {
+					for variant in variants.iter() {
+						if let Some(def) = variant.def { self.variants.insert(def, variant); }
+					}
+				}
+
+				&decl.items
+			},
+			ItemKind::Trait(decl) => &decl.items,
+			ItemKind::Effect(decl) => &decl.items,
+			ItemKind::Impl(decl) => &decl.items,
+			_ => return,
+		};
+
+		for item in items.iter() {
+			self.collect_item(module, item);
+		}
+	}
+
+	/// Finds something in the standard library by its path.
+	pub(super) fn std_def(&self, path: &[&str]) -> Option<DefId> {
+		let mut module = self.tree.std?;
+
+		for (i, name) in path.iter().enumerate() {
+			match self.res.scopes[module].names.get(*name)?.res {
+				Res::Module(inner) if i + 1 < path.len() => module = inner,
+				Res::Def(def) if i + 1 == path.len() => return Some(def),
+				_ => return None,
+			}
+		}
+
+		None
+	}
+
+	/// The generic parameters of an item.
+	pub(super) fn generics(&self, def: DefId) -> &'a [GenericParam] {
+		match self.items.get(&def).map(|item| &item.kind) {
+			Some(ItemKind::Func(func)) => &func.generics,
+			Some(ItemKind::Type(decl)) => &decl.generics,
+			Some(ItemKind::Trait(decl)) => &decl.generics,
+			Some(ItemKind::Effect(decl)) => &decl.generics,
+			Some(ItemKind::Impl(decl)) => &decl.generics,
+			_ => &[],
+		}
+	}
+
+	/// The type a type declaration declares, with its own generic parameters as arguments.
+	pub(super) fn self_adt(&self, def: DefId) -> Ty {
+		let param = |i| Box::new(Ty::Param(def, i));
+
+		match (self.prims.get(&def), self.collections.get(&def)) {
+			(Some(prim), _) => Ty::Prim(*prim),
+			(_, Some(Head::List)) => Ty::List(param(0)),
+			(_, Some(Head::Array)) => Ty::Array(param(0), None),
+			(_, Some(Head::Map)) => Ty::Map(param(0), param(1)),
+			_ => Ty::Adt(def, (0..self.generics(def).len()).map(|i| Ty::Param(def, i)).collect()),
+		}
+	}
+
+	/// What `Self` is for something declared inside of a type, trait or impl.
+	fn owner_ty(&self, def: DefId) -> Option<Ty> {
+		let parent = self.res.defs[def].parent?;
+
+		match self.res.defs[parent].kind {
+			DefKind::Type => Some(self.self_adt(parent)),
+			DefKind::Impl => self.impl_tys.get(&parent).cloned(),
+			DefKind::Trait => Some(Ty::SelfOf(parent)),
+			_ => None,
+		}
+	}
+
+	fn lower_sig(&mut self, def: DefId, func: &Func) -> Sig {
+		let owner = self.owner_ty(def);
+		self.tail = Tail::Of(def);
+
+		let params = func.params.iter()
+			.map(|param| match &param.ty {
+				Some(ty) => self.lower_ty(ty),
+				// Only `self` gets away without a type.
+				None => owner.clone().unwrap_or(Ty::Error),
+			})
+			.collect();
+
+		let ret = func.ret.as_ref().map_or(Ty::unit(), |ty| self.lower_ty(ty));
+		let mut effects: Vec<Ty> = func.effects.iter().filter_map(|effect| self.lower_effect(effect)).collect();
+
+		// Performing an operation performs the effect it belongs to.
+		let parent = self.res.defs[def].parent;
+		if let Some(parent) = parent.filter(|parent| self.res.defs[*parent].kind == DefKind::Effect) {
+			effects.push(self.self_adt(parent));
+		}
+
+		self.tail = Tail::Closed;
+
+		Sig {
+			params,
+			ret,
+			ret_span: func.ret.as_ref().map(|ty| ty.span.clone()),
+			owner,
+			has_self: func.params.first().is_some_and(|param| param.name.name == "self"),
+			effects: Ty::Row(effects, Some(Box::new(Ty::EffectsOf(def)))),
+		}
+	}
+
+	/// Holds on to what generic parameters and the types in a `where` clause are bounded by.
+	/// Function types are kept by parameter, since they're what lets a parameter be called, while trait bounds are kept by the item declaring them.
+	fn collect_bounds(&mut self, owner: DefId, generics: &[GenericParam], where_clause: &[WherePredicate]) {
+		for (i, param) in generics.iter().enumerate() {
+			for bound in param.bounds.iter() {
+				self.collect_bound(owner, Ty::Param(owner, i), bound);
+			}
+		}
+
+		// Each predicate is lowered after the ones before it are in place, so one can use an associated type they give a parameter,
+		// like `I: Iterator, I::Item: Display`.
+		for predicate in where_clause.iter() {
+			let ty = self.lower_ty(&predicate.ty);
+
+			for bound in predicate.bounds.iter() {
+				self.collect_bound(owner, ty.clone(), bound);
+			}
+		}
+	}
+
+	fn collect_bound(&mut self, owner: DefId, ty: Ty, bound: &Type) {
+		// Only a function's own generic parameters pass their effects on to it.
+		if self.res.defs[owner].kind == DefKind::Func { self.tail = Tail::Of(owner) }
+
+		match (&bound.kind, ty) {
+			(TypeKind::Func { .. }, Ty::Param(param, index)) => {
+				let func = self.lower_ty(bound);
+				self.bounds.entry((param, index)).or_default().push(func);
+			},
+			// Something like `(nat, T): Func()` doesn't mean anything.
+			(TypeKind::Func { .. }, _) => {},
+			(_, ty) => if let Some(trait_) = self.lower_trait(bound, &ty) {
+				self.predicates.entry(owner).or_default().push((ty, trait_));
+			},
+		}
+
+		self.tail = Tail::Closed;
+	}
+
+	/// Turns a type as it was written into one the checker can work with.
+	pub(super) fn lower_ty(&mut self, ty: &Type) -> Ty {
+		match &ty.kind {
+			TypeKind::Path(path) => self.lower_path(path),
+			TypeKind::Never => Ty::Never,
+			TypeKind::Dyn(bound) => self.lower_dyn(bound, &ty.span),
+			TypeKind::List(elem) => Ty::List(Box::new(self.lower_ty(elem))),
+			TypeKind::Array(elem, len) => Ty::Array(Box::new(self.lower_ty(elem)), literal_len(len)),
+			TypeKind::Map(key, value) => Ty::Map(Box::new(self.lower_ty(key)), Box::new(self.lower_ty(value))),
+			TypeKind::Func { params, ret, effects } => {
+				let params = params.iter().map(|param| self.lower_ty(param)).collect();
+				let ret = ret.as_ref().map_or(Ty::unit(), |ret| self.lower_ty(ret));
+				let effects = effects.iter().filter_map(|effect| self.lower_effect(effect)).collect();
+
+				let tail = match self.tail {
+					Tail::Closed => None,
+					Tail::Of(def) => Some(Ty::EffectsOf(def)),
+					Tail::Fresh => Some(self.fresh()),
+				};
+
+				Ty::Func(params, Box::new(ret), Box::new(Ty::Row(effects, tail.map(Box::new))))
+			},
+			TypeKind::Tuple(elems) => Ty::Tuple(elems.iter().map(|elem| self.lower_ty(elem)).collect()),
+			TypeKind::Record(fields) => {
+				let mut fields: Vec<(String, Ty)> = fields.iter().map(|(name, ty)| (name.name.clone(), self.lower_ty(ty))).collect();
+				fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+				Ty::Record(fields)
+			},
+		}
+	}
+
+	fn lower_path(&mut self, path: &Path) -> Ty {
+		let mut ty = self.lower_res(path);
+
+		// Whatever goes on past what name resolution found is associated types, like the `Item` in `T::Item`.
+		for segment in path.segments[path.resolved..].iter() {
+			ty = self.project(&ty, path, &segment.name);
+		}
+
+		ty
+	}
+
+	/// Lowers what name resolution found a path to be, leaving out anything after that.
+	fn lower_res(&mut self, path: &Path) -> Ty {
+		match path.res {
+			Res::Def(def) if self.res.defs[def].kind == DefKind::Type && self.is_assoc(def) => {
+				self.ambiguous_assoc(def, path);
+				Ty::Error
+			},
+			Res::Def(def) if self.res.defs[def].kind == DefKind::Type => {
+				let Some(args) = self.generic_args(def, path) else { return Ty::Error };
+				self.well_formed(def, &args, &path.span);
+
+				match &self.items[&def].kind {
+					ItemKind::Type(decl) if !self.prims.contains_key(&def) => match &decl.body {
+						TypeBody::Alias(target) => self.lower_alias(def, target, args),
+						_ => Ty::Adt(def, args),
+					},
+					_ => self.self_adt(def),
+				}
+			},
+			Res::Generic(owner, index) => Ty::Param(owner, index),
+			Res::SelfType(def) => match self.res.defs[def].kind {
+				DefKind::Type => self.self_adt(def),
+				DefKind::Trait => Ty::SelfOf(def),
+				// `Self` inside of an impl's own type doesn't mean anything.
+				_ => self.impl_tys.get(&def).cloned().unwrap_or(Ty::Error),
+			},
+			Res::Unresolved => Ty::Error,
+			_ => {
+				self.report(path.span.clone(), TypeError::NotAType(path.to_string()));
+				Ty::Error
+			},
+		}
+	}
+
+	/// Finds an associated type on a type. A generic parameter or `Self` in a trait only has what its bounds give it,
+	/// which stays as it is, while anything else gets whatever its impl of the trait declaring it says.
+	fn project(&mut self, ty: &Ty, path: &Path, name: &Ident) -> Ty {
+		// Inside of an impl, `Self::Item` is the impl's own.
+		if let Res::SelfType(imp) = path.res {
+			let is_own = self.res.defs[imp].kind == DefKind::Impl && path.resolved + 1 == path.segments.len()
+				&& self.res.defs[imp].children.get(&name.name).is_some_and(|item| self.res.defs[*item].kind == DefKind::Type);
+			if is_own { return self.impl_assoc(imp, ty, &name.name) }
+		}
+
+		let found = match ty {
+			Ty::Error => return Ty::Error,
+			Ty::Param(..) | Ty::SelfOf(_) | Ty::Proj(..) => {
+				let scope = scope_of(ty);
+
+				self.assumptions(scope).into_iter()
+					.filter(|(bounded, _)| bounded == ty)
+					.flat_map(|(_, bound)| self.with_supertraits(&bound))
+					.find_map(|bound| {
+						let item = self.res.defs[bound.def].children.get(&name.name).copied()?;
+						(self.res.defs[item].kind == DefKind::Type).then(|| Ty::Proj(Box::new(ty.clone()), item, bound.args))
+					})
+			},
+			_ => head(ty).and_then(|head| {
+				let imp = self.impls.get(&head).into_iter().flatten().copied().find(|imp| {
+					self.impl_traits.get(imp).is_some_and(|trait_| {
+						self.res.defs[trait_.def].children.get(&name.name).is_some_and(|item| self.res.defs[*item].kind == DefKind::Type)
+					})
+				})?;
+
+				Some(self.impl_assoc(imp, ty, &name.name))
+			}),
+		};
+
+		found.unwrap_or_else(|| {
+			let shown = self.show(ty);
+			self.report(name.span.clone(), TypeError::NoAssocType { ty: shown, name: name.name.clone() });
+			Ty::Error
+		})
+	}
+
+	/// An associated type from an impl, for a type the impl covers.
+	pub(super) fn impl_assoc(&mut self, imp: DefId, ty: &Ty, name: &str) -> Ty {
+		// The impl's generic parameters are whatever matching its type against this one makes them.
+		let fresh: HashMap<Ty, Ty> = (0..self.generics(imp).len()).map(|i| (Ty::Param(imp, i), self.fresh())).collect();
+		let _ = self.table.unify(&self.impl_tys[&imp].subst(&fresh), ty);
+
+		// Leaving it out, or leaving out its type, was already reported.
+		let Some(item) = self.res.defs[imp].children.get(name).copied() else { return Ty::Error };
+		let Some(ItemKind::Type(decl)) = self.items.get(&item).map(|item| &item.kind) else { return Ty::Error };
+		let TypeBody::Alias(target) = &decl.body else { return Ty::Error };
+
+		let assoc = self.lower_alias(item, target, Vec::new());
+		self.table.zonk(&assoc.subst(&fresh))
+	}
+
+	/// Reports an associated type or constant used straight from its trait, like `Iterator::Item`.
+	pub(super) fn ambiguous_assoc(&mut self, def: DefId, path: &Path) {
+		let note = format!("it could be for any type which implements its trait, so one has to be in front of it, like `T::{}`", self.res.defs[def].name.name);
+		self.report(path.span.clone(), TypeError::AmbiguousAssoc(path.to_string())).add_note(note);
+	}
+
+	/// Whether a def is an associated type or constant of a trait.
+	pub(super) fn is_assoc(&self, def: DefId) -> bool {
+		self.res.defs[def].parent.is_some_and(|parent| self.res.defs[parent].kind == DefKind::Trait)
+	}
+
+	/// Checks that an associated type is only given a type in impls, and always is there.
+	fn check_assoc_type(&mut self, def: DefId, decl: &TypeDecl) {
+		let Some(parent) = self.res.defs[def].parent else { return };
+
+		match (self.res.defs[parent].kind, &decl.body) {
+			(DefKind::Trait, TypeBody::Opaque) => {},
+			(DefKind::Trait, _) => {
+				self.report(decl.name.span.clone(), TypeError::AssocTypeBody(decl.name.name.clone()));
+			},
+			(DefKind::Impl, TypeBody::Alias(_)) => {},
+			(DefKind::Impl, _) if self.impl_traits.contains_key(&parent) => {
+				self.report(decl.name.span.clone(), TypeError::AssocTypeMissing(decl.name.name.clone()));
+			},
+			_ => {},
+		}
+	}
+
+	/// Lowers something written after `-<`, which has to be an effect.
+	pub(super) fn lower_effect(&mut self, ty: &Type) -> Option<Ty> {
+		let TypeKind::Path(path) = &ty.kind else {
+			self.report(ty.span.clone(), TypeError::NotAnEffect(ty.to_string()));
+			return None;
+		};
+
+		let def = match path.res {
+			Res::Def(def) if self.res.defs[def].kind == DefKind::Effect && path.resolved == path.segments.len() => def,
+			Res::Unresolved => return None,
+			_ => {
+				self.report(path.span.clone(), TypeError::NotAnEffect(path.to_string()));
+				return None;
+			},
+		};
+
+		let args = self.generic_args(def, path)?;
+		self.well_formed(def, &args, &path.span);
+		Some(Ty::Adt(def, args))
+	}
+
+	/// Leaves the bounds on the generic parameters of a type or effect to be checked against the arguments it was given,
+	/// since the bounds of everything declared after it aren't collected yet.
+	fn well_formed(&mut self, def: DefId, args: &[Ty], span: &Span) {
+		if args.is_empty() { return }
+
+		self.well_formed.push(WellFormed { module: self.module, span: span.clone(), def, args: args.to_vec(), within: self.within });
+	}
+
+	/// Lowers a bound, or the trait of an impl, which has to be a trait.
+	/// A default of one of the trait's parameters can be `Self`, which is whatever type it's bounding or being implemented for.
+	pub(super) fn lower_trait(&mut self, ty: &Type, self_ty: &Ty) -> Option<TraitRef> {
+		let TypeKind::Path(path) = &ty.kind else {
+			self.report(ty.span.clone(), TypeError::NotATrait(ty.to_string()));
+			return None;
+		};
+
+		let def = match path.res {
+			Res::Def(def) if self.res.defs[def].kind == DefKind::Trait && path.resolved == path.segments.len() => def,
+			Res::Unresolved => return None,
+			_ => {
+				self.report(path.span.clone(), TypeError::NotATrait(path.to_string()));
+				return None;
+			},
+		};
+
+		let args = self.generic_args(def, path)?;
+		let self_ty = HashMap::from([(Ty::SelfOf(def), self_ty.clone())]);
+		Some(TraitRef { def, args: args.iter().map(|arg| arg.subst(&self_ty)).collect() })
+	}
+
+	/// Lowers `dyn Trait`. Whether the trait can be made into a trait object is checked once every signature is known.
+	fn lower_dyn(&mut self, bound: &Type, span: &Span) -> Ty {
+		// There's no one type for `Self` to be, so it's left as it is to catch defaults which would use it.
+		let self_ty = match &bound.kind {
+			TypeKind::Path(path) => match path.res {
+				Res::Def(def) => Ty::SelfOf(def),
+				_ => Ty::Error,
+			},
+			_ => Ty::Error,
+		};
+
+		let Some(trait_) = self.lower_trait(bound, &self_ty) else { return Ty::Error };
+
+		let mut uses_self = false;
+		for arg in trait_.args.iter() { arg.walk(&mut |inner| if matches!(inner, Ty::SelfOf(_)) { uses_self = true }) }
+
+		if uses_self {
+			let name = self.res.defs[trait_.def].name.name.clone();
+			self.report(span.clone(), TypeError::NotObjectSafe(name))
+				.add_note(String::from("some of its generic arguments default to `Self`, which isn't one type for every value, so they have to be written out"));
+			return Ty::Error;
+		}
+
+		self.objects.push((self.module, span.clone(), trait_.def));
+		Ty::Dyn(trait_.def, trait_.args)
+	}
+
+	/// Lowers the generic arguments a path gives to a type, effect or trait, filling in the defaults of any left off the end.
+	/// They're on the last segment name resolution found, since anything after that is associated types.
+	fn generic_args(&mut self, def: DefId, path: &Path) -> Option<Vec<Ty>> {
+		let mut args: Vec<Ty> = path.segments[path.resolved - 1].generics.iter().map(|arg| self.lower_ty(arg)).collect();
+		let generics = self.generics(def);
+		let required = generics.iter().take_while(|param| param.default.is_none()).count();
+
+		if args.len() < required || args.len() > generics.len() {
+			let err = self.report(path.span.clone(), TypeError::GenericCount { name: path.to_string(), expected: generics.len(), found: args.len() });
+			if required < generics.len() {
+				err.add_note(format!("the last {} of them have defaults, so they can be left off", generics.len() - required));
+			}
+
+			return None;
+		}
+
+		// A default can use the parameters before it, like `B` in `Pair<A, B = A>`, so those get filled in with what they were given.
+		for index in args.len()..generics.len() {
+			let earlier = args.iter().enumerate().map(|(i, arg)| (Ty::Param(def, i), arg.clone())).collect();
+			let default = self.default(def, index);
+			args.push(default.subst(&earlier));
+		}
+
+		Some(args)
+	}
+
+	/// Lowers the default of a generic parameter, once, where it was declared.
+	fn default(&mut self, def: DefId, index: usize) -> Ty {
+		if let Some(ty) = self.defaults.get(&(def, index)) { return ty.clone() }
+
+		let Some(default) = &self.generics(def)[index].default else { return Ty::Error };
+
+		// Anything which refers back to itself gets nowhere, so it's an error until it's done.
+		self.defaults.insert((def, index), Ty::Error);
+		let module = std::mem::replace(&mut self.module, self.res.defs[def].module);
+		let within = self.within.replace(def);
+
+		// A trait standing in for a type means any type which implements it, which is what a trait object is.
+		let is_trait = matches!(&default.kind, TypeKind::Path(path) if matches!(path.res, Res::Def(other) if self.res.defs[other].kind == DefKind::Trait));
+		let ty = if is_trait { self.lower_dyn(default, &default.span) } else { self.lower_ty(default) };
+		self.module = module;
+		self.within = within;
+
+		self.defaults.insert((def, index), ty.clone());
+		ty
+	}
+
+	/// Checks that generic parameters with defaults come last, and only on things a default could ever be used for.
+	fn check_defaults(&mut self, def: DefId) {
+		let is_func = self.res.defs[def].kind == DefKind::Func;
+		let mut defaulted = false;
+
+		for param in self.generics(def).iter() {
+			match &param.default {
+				Some(default) if is_func => {
+					self.report(default.span.clone(), TypeError::FuncDefault(param.name.name.clone()));
+				},
+				Some(_) => defaulted = true,
+				None if defaulted => {
+					self.report(param.name.span.clone(), TypeError::DefaultOrder(param.name.name.clone()));
+				},
+				None => {},
+			}
+		}
+	}
+
+	fn lower_alias(&mut self, def: DefId, target: &Type, args: Vec<Ty>) -> Ty {
+		if !self.lowering.insert(def) { return Ty::Error }
+
+		// The alias gets lowered where it was declared, so that any errors in it are reported there, and its bounds are what it can rely on.
+		let module = std::mem::replace(&mut self.module, self.res.defs[def].module);
+		let within = self.within.replace(def);
+		let ty = self.lower_ty(target);
+		self.module = module;
+		self.within = within;
+		self.lowering.remove(&def);
+
+		let args = args.into_iter().enumerate().map(|(i, arg)| (Ty::Param(def, i), arg)).collect();
+		ty.subst(&args)
+	}
+
+	/// Finds a method or associated function on a type. What's declared inside of the type or in its impls comes first,
+	/// and then the defaults of the traits it implements. Generic parameters only have what their bounds give them.
+	pub(super) fn lookup_method(&self, ty: &Ty, name: &str) -> Option<DefId> {
+		self.lookup_assoc(ty, name, DefKind::Func)
+	}
+
+	/// Finds something of a particular kind inside of a type or its impls, the same way as a method.
+	pub(super) fn lookup_assoc(&self, ty: &Ty, name: &str, kind: DefKind) -> Option<DefId> {
+		let Some(head) = head(ty) else { return self.lookup_bounded(ty, name, kind) };
+
+		let inherent = match head {
+			Head::Def(def) => Some(def),
+			Head::Prim(prim) => self.prims.iter().find(|(_, p)| **p == prim).map(|(def, _)| *def),
+			Head::List | Head::Array | Head::Map => self.collections.iter().find(|(_, h)| **h == head).map(|(def, _)| *def),
+			Head::Tuple(_) | Head::Record(_) => None,
+		};
+
+		let impls = self.impls.get(&head).into_iter().flatten().copied();
+		let defaults = impls.clone().filter_map(|def| self.impl_traits.get(&def)).map(|trait_| trait_.def);
+
+		inherent.into_iter()
+			.chain(impls)
+			.chain(defaults)
+			.filter_map(|def| self.res.defs[def].children.get(name).copied())
+			.find(|child| self.res.defs[*child].kind == kind)
+	}
+
+	/// Whether everything a type has is known, which is the case for types declared in Rouge code, rather than built into the runtime,
+	/// and for generic parameters and trait objects, which only have what their traits give them.
+	pub(super) fn is_declared(&self, ty: &Ty) -> bool {
+		match ty {
+			Ty::Adt(def, _) => !matches!(&self.items[def].kind, ItemKind::Type(decl) if matches!(decl.body, TypeBody::Opaque)),
+			Ty::Param(..) | Ty::SelfOf(_) | Ty::Dyn(..) | Ty::Proj(..) => true,
+			_ => false,
+		}
+	}
+}
+
+/// What's at the head of a type, if it's something impls can be written for.
+pub(super) fn head(ty: &Ty) -> Option<Head> {
+	match ty {
+		Ty::Adt(def, _) => Some(Head::Def(*def)),
+		Ty::Prim(prim) => Some(Head::Prim(*prim)),
+		Ty::List(_) => Some(Head::List),
+		Ty::Array(..) => Some(Head::Array),
+		Ty::Map(..) => Some(Head::Map),
+		Ty::Tuple(elems) => Some(Head::Tuple(elems.len())),
+		Ty::Record(fields) => Some(Head::Record(fields.len())),
+		_ => None,
+	}
+}
+
+/// The item whose bounds say what a generic parameter or `Self` has, from the first one inside of a type.
+fn scope_of(ty: &Ty) -> Option<DefId> {
+	let mut scope = None;
+	ty.walk(&mut |inner| if let (None, Ty::Param(def, _) | Ty::SelfOf(def)) = (scope, inner) { scope = Some(*def) });
+	scope
+}
+
+/// The length of an array type, if it was written as a literal.
+fn literal_len(len: &Expr) -> Option<u64> {
+	match len.kind {
+		ExprKind::Lit(Literal::Num(n)) => Some(n),
+		_ => None,
+	}
+}
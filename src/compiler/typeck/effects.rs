@@ -0,0 +1,121 @@
+//! Checks that function bodies only perform the effects they're allowed to.
+//!
+//! Each body gets a row of effects it can perform. For a function that's what it declares after `-<`,
+//! along with what the functions passed to it perform. For a closure which doesn't declare anything it's a variable,
+//! so whatever it performs ends up in its type. Performing an effect which a handler around it deals with doesn't count.
+
+//--> Imports <--
+
+use super::{
+	Checker,
+	Sig,
+	Ty,
+	TypeError,
+};
+
+use crate::compiler::{
+	modules::ModuleId,
+	parser::astgen::Func,
+	resolve::DefId,
+};
+
+use logos::Span;
+
+//--> Structs <--
+
+/// An effect performed where nothing handles or allows it, reported once every type is known so it's named in full.
+pub(super) struct Unhandled {
+	module: ModuleId,
+	span: Span,
+	effect: Ty,
+	/// The function it's performed in, or `None` for a closure.
+	func: Option<DefId>,
+}
+
+//--> Functions <--
+
+impl Checker<'_> {
+	/// The effects a function's body is allowed to perform.
+	pub(super) fn body_row(&mut self, def: DefId, func: &Func, sig: &Sig) -> Ty {
+		if !self.is_main(def) { return sig.effects.clone() }
+
+		// There's nothing outside of `main` to handle effects for it, so it can't pass any on.
+		for effect in func.effects.iter() {
+			self.report(effect.span.clone(), TypeError::ReachesMain(effect.to_string()))
+				.add_note(String::from("`main` can't declare effects, since nothing outside of it can handle them"));
+		}
+
+		Ty::Row(Vec::new(), None)
+	}
+
+	fn is_main(&self, def: DefId) -> bool {
+		let def = &self.res.defs[def];
+		def.name.name == "main" && def.parent.is_none() && self.tree.modules[def.module].path == ["pkg"]
+	}
+
+	/// Performs every effect in a row. Any which aren't handled have to be allowed by the body they're performed in.
+	pub(super) fn perform(&mut self, effects: &Ty, span: &Span) {
+		let Some(scope) = self.scopes.last() else { return };
+		let row = scope.row.clone();
+		let (effects, tail) = self.table.row(effects);
+
+		for effect in effects.iter() {
+			self.perform_one(effect, span);
+		}
+
+		// What the tail stands for isn't known yet, like the effects of a function passed in, so it's passed on as it is.
+		if let Some(tail) = tail {
+			let _ = self.table.unify(&row, &Ty::Row(Vec::new(), Some(Box::new(tail))));
+		}
+	}
+
+	fn perform_one(&mut self, effect: &Ty, span: &Span) {
+		let Ty::Adt(def, _) = self.table.resolve(effect) else { return };
+		let scope = self.scopes.last().unwrap();
+
+		let handled = scope.handlers.iter().rev().find(|handled| matches!(handled, Ty::Adt(other, _) if *other == def)).cloned();
+		if let Some(handled) = handled {
+			return self.expect(effect, &handled, span.clone(), None);
+		}
+
+		let (row, func) = (scope.row.clone(), scope.func);
+		let rest = self.fresh();
+		if self.table.unify(&row, &Ty::Row(vec![effect.clone()], Some(Box::new(rest)))).is_ok() { return }
+
+		self.unhandled.push(Unhandled { module: self.module, span: span.clone(), effect: effect.clone(), func });
+	}
+
+	/// Reports every effect which wasn't handled or allowed where it was performed.
+	pub(super) fn check_unhandled(&mut self) {
+		let module = self.module;
+
+		for unhandled in std::mem::take(&mut self.unhandled) {
+			let Ty::Adt(def, _) = self.table.resolve(&unhandled.effect) else { continue };
+			let (span, name) = (unhandled.span, self.show(&unhandled.effect));
+			self.module = unhandled.module;
+
+			match unhandled.func {
+				Some(func) if self.is_main(func) => {
+					let effect = &self.res.defs[def];
+					let note = match effect.children.iter().min_by_key(|(_, op)| **op) {
+						Some((op, _)) => format!("handle it with `when {}::{}(...) do ... end` somewhere before then", effect.name.name, op),
+						None => String::from("handle it somewhere before then"),
+					};
+
+					self.report(span, TypeError::ReachesMain(name)).add_note(note);
+				},
+				Some(func) => {
+					let func = self.res.defs[func].name.name.clone();
+					let note = format!("either handle it, or add `-< {}` to what `{}` performs", name, func);
+					self.report(span, TypeError::Undeclared { effect: name, func: Some(func) }).add_note(note);
+				},
+				None => {
+					let note = format!("either handle it, or add `-< {}` to what the closure performs", name);
+					self.report(span, TypeError::Undeclared { effect: name, func: None }).add_note(note);
+				},
+			}
+		}
+
+		self.module = module;
+	}
+}
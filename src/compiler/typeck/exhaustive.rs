@@ -0,0 +1,383 @@
+//! Checks that `if x matches` blocks cover every value they could be given, and that each of their arms can be reached.
+//!
+//! Both come down to the same question, answered with a matrix of patterns: is there a value which a pattern matches,
+//! but none of the patterns above it do? For the arms that's asked of each one in turn, and a missing case is a value
+//! that a wildcard after the last arm would still match. Whole numbers and characters are ranges, which get split up
+//! wherever the arms' ranges start and stop, so only as many cases as there are arms ever need to be looked at.
+//!
+//! A match which doesn't produce a value can leave cases out, which then just do nothing.
+
+//--> Imports <--
+
+use super::{
+	Checker,
+	Ty,
+	TypeError,
+	ty::Prim,
+};
+
+use crate::compiler::{
+	modules::ModuleId,
+	parser::astgen::{
+		Literal,
+		Pattern,
+		PatternKind,
+	},
+	resolve::{
+		DefId,
+		DefKind,
+		Res,
+	},
+};
+
+use logos::Span;
+
+use std::collections::HashMap;
+
+//--> Structs <--
+
+/// A match, to be checked once every type is known.
+pub(super) struct MatchCheck {
+	pub module: ModuleId,
+	/// Where `matches` is, which is what missing cases get reported at.
+	pub span: Span,
+	pub scrutinee: Ty,
+	/// What the match produces, which decides whether it has to cover everything.
+	pub value: Ty,
+	pub arms: Vec<Pattern>,
+}
+
+//--> Enums <--
+
+/// What's at the head of a pattern.
+#[derive(Clone, Debug, PartialEq)]
+enum Ctor {
+	Variant(DefId),
+	Tuple(usize),
+	Bool(bool),
+	/// Whole numbers or characters from one value to another, including both. Characters are stored as their code points.
+	Range { lo: i128, hi: i128, is_char: bool },
+	/// One value out of more than could ever be listed, like a string. Only a wildcard covers the rest of them.
+	Opaque(String),
+}
+
+#[derive(Clone, Debug)]
+enum Pat {
+	Wild,
+	Ctor(Ctor, Vec<Pat>),
+}
+
+/// The values a column of patterns can be matched against, which decides what it takes to cover all of them.
+enum Domain {
+	/// The variants of a union, along with the types of their fields.
+	Variants(Vec<(DefId, Vec<Ty>)>),
+	Tuple(Vec<Ty>),
+	Bool,
+	Range { lo: i128, hi: i128, is_char: bool },
+	/// Too many values to cover without a wildcard, or a type which isn't known.
+	Open,
+}
+
+//--> Functions <--
+
+impl Checker<'_> {
+	/// Checks every match left behind by inference.
+	pub(super) fn check_matches(&mut self) {
+		let module = self.module;
+
+		for check in std::mem::take(&mut self.matches) {
+			self.module = check.module;
+			self.check_match(check);
+		}
+
+		self.module = module;
+	}
+
+	fn check_match(&mut self, check: MatchCheck) {
+		let ty = self.table.zonk(&check.scrutinee);
+
+		// Anything that already had an error reported about it would only lead to more confusing ones.
+		let mut error = false;
+		ty.walk(&mut |inner| if *inner == Ty::Error { error = true });
+		if error { return }
+
+		let mut rows: Vec<Vec<Pat>> = Vec::new();
+
+		for pat in check.arms.iter() {
+			let row = vec![self.lower_pat(pat)];
+
+			if self.useful(&rows, &row, std::slice::from_ref(&ty)).is_none() {
+				self.warn(pat.span.clone(), TypeError::Unreachable);
+			}
+
+			rows.push(row);
+		}
+
+		// Without knowing the type there's no way of telling what's missing.
+		if matches!(ty, Ty::Var(_)) || self.table.zonk(&check.value) == Ty::unit() { return }
+
+		let mut missing = Vec::new();
+
+		// Each case found gets added as an arm of its own, so the next one is something else. A few is enough to get the idea across.
+		while missing.len() < 3 {
+			let Some(witness) = self.useful(&rows, &[Pat::Wild], std::slice::from_ref(&ty)) else { break };
+			missing.push(self.show_pat(&witness[0]));
+			rows.push(witness);
+		}
+
+		if !missing.is_empty() {
+			let more = self.useful(&rows, &[Pat::Wild], std::slice::from_ref(&ty)).is_some();
+			let err = self.report(check.span, TypeError::NonExhaustive(missing));
+			if more { err.add_note(String::from("there are other cases missing too, which an arm with `_` would cover")); }
+		}
+	}
+
+	/// Turns a pattern into what the matrix works with.
+	fn lower_pat(&self, pat: &Pattern) -> Pat {
+		match &pat.kind {
+			PatternKind::Wildcard | PatternKind::Binding { .. } => Pat::Wild,
+			PatternKind::Lit { negated, lit } => Pat::Ctor(lit_ctor(lit, *negated), Vec::new()),
+			PatternKind::Range { start, end, inclusive } => {
+				let value = |end: &Option<Box<Pattern>>| match end.as_deref().map(|end| &end.kind) {
+					None => Some(None),
+					Some(PatternKind::Lit { negated, lit }) => match lit_ctor(lit, *negated) {
+						Ctor::Range { lo, is_char, .. } => Some(Some((lo, is_char))),
+						_ => None,
+					},
+					Some(_) => None,
+				};
+
+				let (Some(lo), Some(hi)) = (value(start), value(end)) else {
+					return Pat::Ctor(Ctor::Opaque(pat.to_string()), Vec::new());
+				};
+
+				// An end which is left off goes as far as it can, which gets cut down to what the type holds once that's known.
+				let is_char = lo.or(hi).is_some_and(|(_, is_char)| is_char);
+				let lo = lo.map_or(i128::MIN, |(lo, _)| lo);
+				let hi = match hi {
+					Some((hi, _)) if *inclusive => hi,
+					Some((hi, _)) => hi - 1,
+					None => i128::MAX,
+				};
+
+				Pat::Ctor(Ctor::Range { lo, hi, is_char }, Vec::new())
+			},
+			PatternKind::Path(path) => match path.res {
+				Res::Def(def) if self.res.defs[def].kind == DefKind::Variant => Pat::Ctor(Ctor::Variant(def), Vec::new()),
+				// A constant's value isn't known here, so it can't be counted on to cover anything.
+				_ => Pat::Ctor(Ctor::Opaque(path.to_string()), Vec::new()),
+			},
+			PatternKind::TupleStruct { path, fields } => match path.res {
+				Res::Def(def) | Res::SelfType(def) if self.is_ctor(def) => Pat::Ctor(Ctor::Variant(def), fields.iter().map(|field| self.lower_pat(field)).collect()),
+				_ => Pat::Ctor(Ctor::Opaque(path.to_string()), Vec::new()),
+			},
+			PatternKind::Tuple(elems) => Pat::Ctor(Ctor::Tuple(elems.len()), elems.iter().map(|elem| self.lower_pat(elem)).collect()),
+		}
+	}
+
+	/// Whether a def can be used to take apart a value in a pattern, which variants and tuple types can be.
+	fn is_ctor(&self, def: DefId) -> bool {
+		match self.res.defs[def].kind {
+			DefKind::Variant => true,
+			DefKind::Type => self.sigs.contains_key(&def),
+			_ => false,
+		}
+	}
+
+	/// The values a type has, as far as patterns go. A tuple type is treated like a union with a single variant.
+	fn domain(&self, ty: &Ty) -> Domain {
+		match self.table.resolve(ty) {
+			Ty::Adt(def, args) => {
+				let mut variants: Vec<DefId> = match self.sigs.contains_key(&def) {
+					true => vec![def],
+					false => self.res.defs[def].children.values().copied()
+						.filter(|child| self.res.defs[*child].kind == DefKind::Variant)
+						.collect(),
+				};
+				if variants.is_empty() { return Domain::Open }
+				variants.sort_unstable();
+
+				let args: HashMap<Ty, Ty> = args.iter().enumerate().map(|(i, arg)| (Ty::Param(def, i), arg.clone())).collect();
+				Domain::Variants(variants.into_iter()
+					.map(|variant| (variant, self.sigs[&variant].params.iter().map(|param| param.subst(&args)).collect()))
+					.collect())
+			},
+			Ty::Tuple(elems) => Domain::Tuple(elems),
+			Ty::Prim(Prim::Bool) => Domain::Bool,
+			Ty::Prim(Prim::Byte) => Domain::Range { lo: 0, hi: u8::MAX as i128, is_char: false },
+			Ty::Prim(Prim::Nat) => Domain::Range { lo: 0, hi: u64::MAX as i128, is_char: false },
+			Ty::Prim(Prim::Int) => Domain::Range { lo: i64::MIN as i128, hi: i64::MAX as i128, is_char: false },
+			Ty::Prim(Prim::Char) => Domain::Range { lo: 0, hi: char::MAX as i128, is_char: true },
+			_ => Domain::Open,
+		}
+	}
+
+	/// The types of a constructor's fields.
+	fn fields(&self, ctor: &Ctor, domain: &Domain) -> Vec<Ty> {
+		match (ctor, domain) {
+			(Ctor::Variant(def), Domain::Variants(variants)) => match variants.iter().find(|(variant, _)| variant == def) {
+				Some((_, fields)) => fields.clone(),
+				None => vec![Ty::Error; self.sigs.get(def).map_or(0, |sig| sig.params.len())],
+			},
+			(Ctor::Variant(def), _) => vec![Ty::Error; self.sigs.get(def).map_or(0, |sig| sig.params.len())],
+			(Ctor::Tuple(_), Domain::Tuple(elems)) => elems.clone(),
+			(Ctor::Tuple(len), _) => vec![Ty::Error; *len],
+			_ => Vec::new(),
+		}
+	}
+
+	/// Finds a value that `row` matches and none of `rows` do, given the types of each column.
+	/// What comes back is a pattern for each column which matches only values like that.
+	fn useful(&self, rows: &[Vec<Pat>], row: &[Pat], tys: &[Ty]) -> Option<Vec<Pat>> {
+		let Some((first, rest)) = row.split_first() else {
+			return rows.is_empty().then(Vec::new);
+		};
+
+		let domain = tys.first().map_or(Domain::Open, |ty| self.domain(ty));
+		let heads: Vec<&Ctor> = rows.iter().filter_map(|row| match row.first() {
+			Some(Pat::Ctor(ctor, _)) => Some(ctor),
+			_ => None,
+		}).collect();
+
+		let ctors = match first {
+			// Only the part of a range that the type holds matters, so one which doesn't hold any of it is never useful.
+			Pat::Ctor(Ctor::Range { lo, hi, is_char }, _) => match &domain {
+				Domain::Range { lo: min, hi: max, .. } if lo.max(min) > hi.min(max) => Vec::new(),
+				Domain::Range { lo: min, hi: max, .. } => split(*lo.max(min), *hi.min(max), *is_char, &heads),
+				_ => split(*lo, *hi, *is_char, &heads),
+			},
+			Pat::Ctor(ctor, _) => vec![ctor.clone()],
+			Pat::Wild => {
+				let all = match &domain {
+					Domain::Variants(variants) => Some(variants.iter().map(|(def, _)| Ctor::Variant(*def)).collect()),
+					Domain::Tuple(elems) => Some(vec![Ctor::Tuple(elems.len())]),
+					Domain::Bool => Some(vec![Ctor::Bool(false), Ctor::Bool(true)]),
+					Domain::Range { lo, hi, is_char } => Some(split(*lo, *hi, *is_char, &heads)),
+					Domain::Open => None,
+				};
+
+				let missing: Vec<Ctor> = all.clone().unwrap_or_default().into_iter()
+					.filter(|ctor| !heads.iter().any(|head| covers(head, ctor)))
+					.collect();
+
+				match all {
+					// Everything is covered by something, so each possibility gets looked into.
+					Some(all) if missing.is_empty() => all,
+					// Anything a row starts with a constructor for can't match what's missing, so only the wildcard rows are left.
+					_ => {
+						let rows: Vec<Vec<Pat>> = rows.iter()
+							.filter(|row| matches!(row.first(), Some(Pat::Wild)))
+							.map(|row| row[1..].to_vec())
+							.collect();
+
+						let mut witness = self.useful(&rows, rest, tys.get(1..).unwrap_or(&[]))?;
+						let head = match missing.into_iter().next() {
+							Some(ctor) if !heads.is_empty() => {
+								let arity = self.fields(&ctor, &domain).len();
+								Pat::Ctor(ctor, vec![Pat::Wild; arity])
+							},
+							_ => Pat::Wild,
+						};
+
+						witness.insert(0, head);
+						return Some(witness);
+					},
+				}
+			},
+		};
+
+		for ctor in ctors {
+			let fields = self.fields(&ctor, &domain);
+			let arity = fields.len();
+			let tys: Vec<Ty> = fields.into_iter().chain(tys.get(1..).unwrap_or(&[]).iter().cloned()).collect();
+
+			let rows: Vec<Vec<Pat>> = rows.iter().filter_map(|row| specialize(row, &ctor, arity)).collect();
+			let Some(row) = specialize(row, &ctor, arity) else { continue };
+
+			if let Some(mut witness) = self.useful(&rows, &row, &tys) {
+				let rest = witness.split_off(arity.min(witness.len()));
+				let mut result = vec![Pat::Ctor(ctor, witness)];
+				result.extend(rest);
+				return Some(result);
+			}
+		}
+
+		None
+	}
+
+	/// Writes out a pattern the way it would be written in source code.
+	fn show_pat(&self, pat: &Pat) -> String {
+		let list = |pats: &[Pat]| pats.iter().map(|pat| self.show_pat(pat)).collect::<Vec<_>>().join(", ");
+
+		match pat {
+			Pat::Wild => String::from("_"),
+			Pat::Ctor(Ctor::Variant(def), fields) if fields.is_empty() => self.res.defs[*def].name.name.clone(),
+			Pat::Ctor(Ctor::Variant(def), fields) => format!("{}({})", self.res.defs[*def].name.name, list(fields)),
+			Pat::Ctor(Ctor::Tuple(_), elems) => format!("({})", list(elems)),
+			Pat::Ctor(Ctor::Bool(value), _) => value.to_string(),
+			Pat::Ctor(Ctor::Range { lo, hi, is_char }, _) => {
+				let show = |value: i128| match is_char {
+					true => format!("{:?}", char::from_u32(value as u32).unwrap_or(char::REPLACEMENT_CHARACTER)),
+					false => value.to_string(),
+				};
+
+				if lo == hi { show(*lo) } else { format!("{}..={}", show(*lo), show(*hi)) }
+			},
+			Pat::Ctor(Ctor::Opaque(text), _) => text.clone(),
+		}
+	}
+}
+
+/// What a literal in a pattern matches.
+fn lit_ctor(lit: &Literal, negated: bool) -> Ctor {
+	let sign = if negated { -1 } else { 1 };
+
+	match lit {
+		Literal::Num(n) => Ctor::Range { lo: sign * *n as i128, hi: sign * *n as i128, is_char: false },
+		Literal::Byte(b) => Ctor::Range { lo: *b as i128, hi: *b as i128, is_char: false },
+		Literal::Char(c) => Ctor::Range { lo: *c as i128, hi: *c as i128, is_char: true },
+		Literal::Bool(b) => Ctor::Bool(*b),
+		_ => Ctor::Opaque(format!("{}{}", if negated { "-" } else { "" }, lit)),
+	}
+}
+
+/// Whether everything a constructor at the head of a row matches includes everything another does.
+fn covers(head: &Ctor, ctor: &Ctor) -> bool {
+	match (head, ctor) {
+		(Ctor::Range { lo, hi, .. }, Ctor::Range { lo: other_lo, hi: other_hi, .. }) => lo <= other_lo && other_hi <= hi,
+		_ => head == ctor,
+	}
+}
+
+/// Splits a range up wherever one of the ranges at the heads of the rows starts or stops,
+/// so that each piece is either entirely inside of each of them or entirely outside.
+fn split(lo: i128, hi: i128, is_char: bool, heads: &[&Ctor]) -> Vec<Ctor> {
+	let mut bounds = vec![lo, hi.saturating_add(1)];
+
+	for head in heads.iter() {
+		if let Ctor::Range { lo: start, hi: end, .. } = head {
+			bounds.extend([*start, end.saturating_add(1)].into_iter().filter(|bound| (lo..=hi).contains(bound)));
+		}
+	}
+
+	bounds.sort_unstable();
+	bounds.dedup();
+
+	bounds.windows(2).map(|pair| Ctor::Range { lo: pair[0], hi: pair[1] - 1, is_char }).collect()
+}
+
+/// The rest of a row once its head has been matched against a constructor, or nothing if its head can't match it.
+fn specialize(row: &[Pat], ctor: &Ctor, arity: usize) -> Option<Vec<Pat>> {
+	let (first, rest) = row.split_first()?;
+
+	let mut fields = match first {
+		Pat::Wild => vec![Pat::Wild; arity],
+		Pat::Ctor(head, fields) if covers(head, ctor) => fields.clone(),
+		Pat::Ctor(..) => return None,
+	};
+
+	// Patterns with the wrong number of fields already had an error reported about them.
+	fields.resize(arity, Pat::Wild);
+	fields.extend(rest.iter().cloned());
+	Some(fields)
+}
@@ -0,0 +1,1230 @@
+//! Infers the types of everything inside of function bodies and constants.
+
+//--> Imports <--
+
+use super::{
+	Checker,
+	EffectScope,
+	MatchCheck,
+	Sig,
+	Tail,
+	Target,
+	Ty,
+	TypeError,
+	collect::head,
+	ty::{
+		Prim,
+		Scheme,
+		VarKind,
+	},
+};
+
+use crate::compiler::{
+	parser::astgen::{
+		AssignOp,
+		BinaryOp,
+		Block,
+		Condition,
+		Expr,
+		ExprKind,
+		Func,
+		Handler,
+		Item,
+		ItemKind,
+		Literal,
+		Param,
+		Path,
+		Pattern,
+		PatternKind,
+		Stmt,
+		StmtKind,
+		Type,
+		TypeBody,
+		UnaryOp,
+		Visibility,
+	},
+	resolve::{
+		DefId,
+		DefKind,
+		Res,
+		accessible,
+	},
+};
+
+use logos::Span;
+
+use std::collections::HashMap;
+
+//--> Functions <--
+
+impl Checker<'_> {
+	/// Checks every constant, and then every function body.
+	pub(super) fn check_bodies(&mut self) {
+		let tree = self.tree;
+		self.tail = Tail::Fresh;
+
+		// Constants without a type written down get theirs from their value, which functions might need.
+		for only_consts in [true, false] {
+			for (module, m) in tree.modules.iter().enumerate() {
+				self.module = module;
+
+				for item in m.ast.items.iter() {
+					self.check_item(item, only_consts);
+				}
+			}
+		}
+	}
+
+	fn check_item(&mut self, item: &Item, only_consts: bool) {
+		let Some(def) = item.def else { return };
+
+		let items = match &item.kind {
+			ItemKind::Func(func) if !only_consts => return self.check_func(def, func),
+			ItemKind::Const(decl) if only_consts => {
+				self.within = self.res.defs[def].parent;
+
+				let ty = match (self.consts.get(&def).cloned(), &decl.value) {
+					(Some(ty), Some(value)) => {
+						let span = decl.ty.as_ref().map(|ty| ty.span.clone());
+						self.check(value, &ty, span);
+						ty
+					},
+					(Some(ty), None) => ty,
+					(None, Some(value)) => self.infer(value),
+					(None, None) => Ty::Error,
+				};
+
+				self.within = None;
+
+				self.consts.insert(def, ty);
+				return;
+			},
+			ItemKind::Type(decl) => &decl.items,
+			ItemKind::Trait(decl) => &decl.items,
+			ItemKind::Effect(decl) => &decl.items,
+			ItemKind::Impl(decl) => &decl.items,
+			_ => return,
+		};
+
+		for item in items.iter() {
+			self.check_item(item, only_consts);
+		}
+	}
+
+	fn check_func(&mut self, def: DefId, func: &Func) {
+		let Some(body) = &func.body else { return };
+		let sig = self.sigs[&def].clone();
+		self.within = Some(def);
+
+		self.bind_params(&func.params, &sig.params);
+		self.rets.push((sig.ret.clone(), sig.ret_span.clone()));
+		let row = self.body_row(def, func, &sig);
+		self.scopes.push(EffectScope { row, func: Some(def), handlers: Vec::new() });
+
+		// Functions which don't say what they return just throw away whatever their body ends with.
+		let found = self.block(body);
+		if func.ret.is_some() { self.expect(&found, &sig.ret, result_span(body), sig.ret_span); }
+
+		self.scopes.pop();
+		self.rets.pop();
+		self.within = None;
+	}
+
+	fn bind_params(&mut self, params: &[Param], tys: &[Ty]) {
+		for (param, ty) in params.iter().zip(tys.iter()) {
+			if let Some(local) = param.local {
+				self.bind(local, Scheme::mono(ty.clone()));
+			}
+		}
+	}
+
+	fn bind(&mut self, local: usize, scheme: Scheme) {
+		self.locals.insert(local, scheme);
+		self.bound.push((self.module, local));
+	}
+
+	/// Infers an expression's type, and makes it fit an expected one. Gives back the type it was found to have.
+	fn check(&mut self, expr: &Expr, expected: &Ty, expected_at: Option<Span>) -> Ty {
+		let found = match (&expr.kind, self.table.resolve(expected)) {
+			(ExprKind::Closure { params, ret, effects, body }, _) => self.closure(params, ret.as_deref(), effects, body, Some(expected)),
+			// Each element gets checked against what's expected of it, so that they can each be turned into a trait object.
+			(ExprKind::List(elems), Ty::List(elem)) if !elems.is_empty() => {
+				for value in elems.iter() { self.check(value, &elem, expected_at.clone()); }
+				Ty::List(elem)
+			},
+			(ExprKind::Map(entries), Ty::Map(key, value)) if !entries.is_empty() => {
+				for (k, v) in entries.iter() {
+					self.check(k, &key, expected_at.clone());
+					self.check(v, &value, expected_at.clone());
+				}
+				Ty::Map(key, value)
+			},
+			_ => self.infer(expr),
+		};
+
+		self.expect(&found, expected, expr.span.clone(), expected_at);
+		found
+	}
+
+	fn diverges(&self, ty: &Ty) -> bool {
+		self.table.resolve(ty) == Ty::Never
+	}
+
+	//--> Statements <--
+
+	fn block(&mut self, block: &Block) -> Ty {
+		let mut ty = Ty::unit();
+		let mut diverged: Option<Span> = None;
+		let mut warned = false;
+
+		for (i, stmt) in block.stmts.iter().enumerate() {
+			// Only the first statement that can't be reached gets pointed out, since the rest follow from it.
+			if let (Some(at), false) = (&diverged, warned) {
+				let line = self.line(at);
+				self.warn(stmt.span.clone(), TypeError::UnreachableCode)
+					.add_note(format!("the statement on line {} never finishes, so nothing after it runs", line));
+				warned = true;
+			}
+
+			let found = self.stmt(stmt);
+
+			if diverged.is_none() && self.diverges(&found) { diverged = Some(stmt.span.clone()) }
+			if i + 1 == block.stmts.len() { ty = found }
+		}
+
+		// Anything after something that never finishes is never reached, so the block never produces a value either.
+		if diverged.is_some() { Ty::Never } else { ty }
+	}
+
+	/// Checks a statement, giving back its type if it's an expression, or `()` otherwise.
+	fn stmt(&mut self, stmt: &Stmt) -> Ty {
+		match &stmt.kind {
+			StmtKind::Let { pat, ty, value, .. } => {
+				// Only closures get generalized, so that something like `x := []` still has one type everywhere it's used.
+				let generalize = ty.is_none() && matches!(value.kind, ExprKind::Closure { .. });
+
+				self.level += 1;
+				let (found, diverges) = match ty {
+					Some(ty) => {
+						let expected = self.lower_ty(ty);
+						let found = self.check(value, &expected, Some(ty.span.clone()));
+						(expected, self.diverges(&found))
+					},
+					None => {
+						let found = self.infer(value);
+						let diverges = self.diverges(&found);
+						(found, diverges)
+					},
+				};
+				self.level -= 1;
+
+				match &pat.kind {
+					PatternKind::Binding { local: Some(local), .. } if generalize => {
+						let scheme = self.table.generalize(&found, self.level);
+						self.bind(*local, scheme);
+					},
+					_ => self.pattern(pat, &found),
+				}
+
+				// A binding whose value never gets worked out never gets made either.
+				if diverges { Ty::Never } else { Ty::unit() }
+			},
+			StmtKind::Assign { op, target, value } => {
+				let target_ty = self.infer(target);
+
+				let value_ty = match op {
+					AssignOp::Compound(op) => {
+						let value_ty = self.infer(value);
+
+						match assign_trait(*op) {
+							// Types declared in Rouge code change themselves through a trait, rather than being given a new value.
+							Some((name, overload)) if self.is_declared(&self.table.resolve(&target_ty)) => {
+								self.overloaded(name, overload, &[(&target_ty, &target.span), (&value_ty, &value.span)], &target.span);
+							},
+							_ => {
+								let found = self.binary_op(*op, &target_ty, &value_ty, &stmt.span, &target.span, &value.span);
+								self.expect(&found, &target_ty, value.span.clone(), None);
+							},
+						}
+
+						value_ty
+					},
+					// `.=` has been desugared into a plain assignment by now.
+					AssignOp::Assign | AssignOp::Method => self.check(value, &target_ty, None),
+				};
+
+				if self.diverges(&value_ty) { Ty::Never } else { Ty::unit() }
+			},
+			StmtKind::Expr(expr) => self.infer(expr),
+			StmtKind::Handler(handler) => {
+				self.handler(handler);
+				self.block(&handler.body);
+				Ty::unit()
+			},
+		}
+	}
+
+	/// Binds the parameters of a handler clause to what its operation takes, and gives back the effect it handles, if it's for one.
+	/// The clause's body is left to be checked after what it handles, which is what pins down the effect's generic arguments.
+	fn handler(&mut self, handler: &Handler) -> Option<Ty> {
+		let op = match handler.operation.res {
+			Res::Def(def) if self.is_operation(def) => Some(def),
+			Res::Unresolved => None,
+			_ => {
+				self.report(handler.operation.span.clone(), TypeError::NotAnOperation(handler.operation.to_string()));
+				None
+			},
+		};
+
+		let (params, effects) = match op {
+			Some(op) => {
+				let Sig { params, effects, .. } = self.instantiate(op, &handler.operation.span);
+
+				if params.len() != handler.params.len() {
+					self.report(handler.span.clone(), TypeError::ArgCount { expected: params.len(), found: handler.params.len() });
+				}
+
+				(params, effects)
+			},
+			None => (Vec::new(), Ty::Error),
+		};
+
+		for (i, pat) in handler.params.iter().enumerate() {
+			let ty = params.get(i).cloned().unwrap_or(Ty::Error);
+			self.pattern(pat, &ty);
+		}
+
+		// An operation's row only has the effect it belongs to in it.
+		let (effects, _) = self.table.row(&effects);
+		effects.into_iter().next()
+	}
+
+	fn is_operation(&self, def: DefId) -> bool {
+		let def = &self.res.defs[def];
+		def.kind == DefKind::Func && def.parent.is_some_and(|parent| self.res.defs[parent].kind == DefKind::Effect)
+	}
+
+	//--> Patterns <--
+
+	/// Checks a pattern against the type of whatever it's matched against, binding its names.
+	fn pattern(&mut self, pat: &Pattern, expected: &Ty) {
+		match &pat.kind {
+			PatternKind::Wildcard => {},
+			PatternKind::Binding { local, .. } => if let Some(local) = local {
+				self.bind(*local, Scheme::mono(expected.clone()));
+			},
+			PatternKind::Lit { negated, lit } => {
+				let found = self.literal(lit, *negated, &pat.span);
+				self.expect(&found, expected, pat.span.clone(), None);
+			},
+			PatternKind::Range { start, end, .. } => {
+				for end in [start, end].into_iter().flatten() { self.pattern(end, expected); }
+			},
+			PatternKind::Path(path) => {
+				let found = self.value_path(path);
+				self.expect(&found, expected, pat.span.clone(), None);
+			},
+			PatternKind::TupleStruct { path, fields } => {
+				let ctor = self.value_path(path);
+				let (params, found) = match self.table.resolve(&ctor) {
+					Ty::Func(params, ret, _) => (params, *ret),
+					Ty::Error => (Vec::new(), Ty::Error),
+					other => {
+						let ty = self.show(&other);
+						self.report(path.span.clone(), TypeError::NotCallable(ty));
+						(Vec::new(), Ty::Error)
+					},
+				};
+
+				self.expect(&found, expected, pat.span.clone(), None);
+
+				if found != Ty::Error && params.len() != fields.len() {
+					self.report(pat.span.clone(), TypeError::ArgCount { expected: params.len(), found: fields.len() });
+				}
+
+				for (i, field) in fields.iter().enumerate() {
+					let ty = params.get(i).cloned().unwrap_or(Ty::Error);
+					self.pattern(field, &ty);
+				}
+			},
+			PatternKind::Tuple(elems) => {
+				let tys: Vec<Ty> = match self.table.resolve(expected) {
+					Ty::Tuple(tys) if tys.len() == elems.len() => tys,
+					_ => {
+						let tys: Vec<Ty> = elems.iter().map(|_| self.fresh()).collect();
+						self.expect(&Ty::Tuple(tys.clone()), expected, pat.span.clone(), None);
+						tys
+					},
+				};
+
+				for (elem, ty) in elems.iter().zip(tys.iter()) {
+					self.pattern(elem, ty);
+				}
+			},
+		}
+	}
+
+	//--> Expressions <--
+
+	fn literal(&mut self, lit: &Literal, negated: bool, span: &Span) -> Ty {
+		match lit {
+			Literal::Num(n) => self.number(*n, negated, span),
+			Literal::Float(_) => Ty::Prim(Prim::Flo),
+			Literal::Char(_) => Ty::Prim(Prim::Char),
+			Literal::Str(_) => Ty::Prim(Prim::Str),
+			Literal::Byte(_) => Ty::Prim(Prim::Byte),
+			Literal::ByteStr(_) => Ty::List(Box::new(Ty::Prim(Prim::Byte))),
+			Literal::Bool(_) => Ty::Prim(Prim::Bool),
+		}
+	}
+
+	fn infer(&mut self, expr: &Expr) -> Ty {
+		match &expr.kind {
+			ExprKind::Lit(lit) => self.literal(lit, false, &expr.span),
+			ExprKind::Path(path) => self.value_path(path),
+			ExprKind::Call { callee, args } => {
+				let callee_ty = self.infer(callee);
+				self.call(&callee_ty, &callee.span, args)
+			},
+			ExprKind::MethodCall { receiver, method, args } => {
+				let receiver_ty = self.infer(receiver);
+				let receiver_ty = match self.table.resolve(&receiver_ty) {
+					// Which methods a number has depends on what kind of number it is,
+					// so one which isn't known yet gets the type it would have ended up with anyway.
+					Ty::Var(var) if self.table.kind(var) != VarKind::General => {
+						let prim = if self.table.kind(var) == VarKind::Signed { Prim::Int } else { Prim::Nat };
+						self.expect(&receiver_ty, &Ty::Prim(prim), receiver.span.clone(), None);
+						Ty::Prim(prim)
+					},
+					ty => ty,
+				};
+
+				// A trait object's methods come from its trait, with nothing generic about them to instantiate.
+				let found = match &receiver_ty {
+					Ty::Dyn(..) => self.dyn_method(&receiver_ty, &method.name).filter(|(_, sig)| sig.has_self),
+					_ => self.lookup_method(&receiver_ty, &method.name)
+						.filter(|def| self.sigs[def].has_self)
+						.map(|def| (def, self.instantiate(def, &method.span))),
+				};
+
+				let Some((def, sig)) = found else {
+					// Which method it is can't be known until the receiver's type is.
+					if let Ty::Var(_) = receiver_ty {
+						self.unknown_receivers.insert((self.module, receiver.span.clone()));
+						for arg in args.iter() { self.infer(arg); }
+						return self.unknown();
+					}
+
+					if !matches!(receiver_ty, Ty::Error | Ty::Never) {
+						let ty = self.show(&receiver_ty);
+						self.report(method.span.clone(), TypeError::NoMethod { ty, method: method.name.clone() });
+					}
+
+					for arg in args.iter() { self.infer(arg); }
+					return Ty::Error;
+				};
+
+				if let Some(owner) = &sig.owner {
+					self.expect(&receiver_ty, owner, receiver.span.clone(), None);
+				}
+
+				if let ItemKind::Func(func) = &self.items[&def].kind {
+					if func.params.first().is_some_and(|param| param.is_mut) { self.mutating.insert((self.module, receiver.span.clone())); }
+				}
+
+				self.args(&sig.params[1..], args, &method.span);
+				self.normalize_projections();
+				self.perform(&sig.effects, &method.span);
+				sig.ret
+			},
+			ExprKind::Field { base, field } => {
+				let base_ty = self.infer(base);
+
+				if let Ty::Adt(def, args) = self.table.resolve(&base_ty) {
+					if let Some((vis, ty)) = self.record_field(def, &args, &field.name) {
+						self.field_access(def, &field.name, vis, &field.span);
+						return ty;
+					}
+				}
+
+				match self.table.resolve(&base_ty) {
+					Ty::Record(fields) => match fields.iter().find(|(name, _)| *name == field.name) {
+						Some((_, ty)) => ty.clone(),
+						None => self.no_field(&base_ty, &field.name, field.span.clone()),
+					},
+					Ty::Var(_) | Ty::Error | Ty::Never => self.unknown(),
+					_ => self.no_field(&base_ty, &field.name, field.span.clone()),
+				}
+			},
+			ExprKind::TupleField { base, index } => {
+				let base_ty = self.infer(base);
+
+				match self.table.resolve(&base_ty) {
+					Ty::Tuple(elems) if *index < elems.len() => elems[*index].clone(),
+					Ty::Adt(def, args) if self.tuple_fields(def, &args).is_some_and(|fields| *index < fields.len()) => {
+						self.tuple_fields(def, &args).unwrap().swap_remove(*index)
+					},
+					Ty::Var(_) | Ty::Error | Ty::Never => self.unknown(),
+					_ => self.no_field(&base_ty, &index.to_string(), expr.span.clone()),
+				}
+			},
+			ExprKind::Index { base, index } => {
+				let base_ty = self.infer(base);
+				let index_ty = self.infer(index);
+
+				match self.table.resolve(&base_ty) {
+					// Negative indices count back from the end, so any whole number will do.
+					Ty::List(elem) | Ty::Array(elem, _) => {
+						self.integral(&index_ty, "[]", &index.span);
+						*elem
+					},
+					Ty::Map(key, value) => {
+						self.expect(&index_ty, &key, index.span.clone(), None);
+						*value
+					},
+					Ty::Prim(Prim::Str) => Ty::Prim(Prim::Char),
+					Ty::Var(_) | Ty::Error | Ty::Never => self.unknown(),
+					other => {
+						let ty = self.show(&other);
+						self.report(expr.span.clone(), TypeError::CantApply { op: "[]", ty });
+						Ty::Error
+					},
+				}
+			},
+			ExprKind::Tuple(elems) => Ty::Tuple(elems.iter().map(|elem| self.infer(elem)).collect()),
+			ExprKind::Record(fields) => {
+				let mut fields: Vec<(String, Ty)> = fields.iter().map(|(name, value)| (name.name.clone(), self.infer(value))).collect();
+				fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+				Ty::Record(fields)
+			},
+			// Anything can be interpolated into a string.
+			ExprKind::Interp(parts) => {
+				for part in parts.iter() { self.infer(part); }
+				Ty::Prim(Prim::Str)
+			},
+			// An empty list could just as well be an empty map.
+			ExprKind::List(elems) if elems.is_empty() => self.fresh(),
+			ExprKind::List(elems) => {
+				let elem = self.fresh();
+				for value in elems.iter() { self.check(value, &elem, None); }
+				Ty::List(Box::new(elem))
+			},
+			ExprKind::Map(entries) => {
+				let (key, value) = (self.fresh(), self.fresh());
+
+				for (k, v) in entries.iter() {
+					self.check(k, &key, None);
+					self.check(v, &value, None);
+				}
+
+				Ty::Map(Box::new(key), Box::new(value))
+			},
+			ExprKind::Closure { params, ret, effects, body } => self.closure(params, ret.as_deref(), effects, body, None),
+			// A negated number literal is checked as a whole, since `-9223372036854775808` fits in an `int` even though its digits alone don't.
+			ExprKind::Unary { op: UnaryOp::Neg, expr: operand } if matches!(operand.kind, ExprKind::Lit(Literal::Num(_))) => {
+				let ExprKind::Lit(lit) = &operand.kind else { unreachable!() };
+				self.literal(lit, true, &expr.span)
+			},
+			ExprKind::Unary { op, expr: operand } => {
+				let ty = self.infer(operand);
+
+				if let Some(overload) = unary_trait(*op).filter(|_| self.is_declared(&self.table.resolve(&ty))) {
+					return self.overloaded(op.as_str(), overload, &[(&ty, &operand.span)], &expr.span);
+				}
+
+				match op {
+					UnaryOp::Neg => self.negate(&ty, &operand.span),
+					UnaryOp::Plus => self.numeric(&ty, op.as_str(), &operand.span),
+					UnaryOp::Not => match self.table.resolve(&ty) {
+						Ty::Prim(prim) if prim == Prim::Bool || prim.is_integral() => {},
+						Ty::Var(_) | Ty::Error | Ty::Never => {},
+						other => {
+							let ty = self.show(&other);
+							self.report(operand.span.clone(), TypeError::CantApply { op: "!", ty });
+						},
+					},
+				}
+
+				ty
+			},
+			ExprKind::Binary { op, lhs, rhs } => {
+				let lhs_ty = self.infer(lhs);
+				let rhs_ty = self.infer(rhs);
+				self.binary_op(*op, &lhs_ty, &rhs_ty, &expr.span, &lhs.span, &rhs.span)
+			},
+			ExprKind::If { branches, else_ } => {
+				// Without an `else`, nothing comes out of the branches.
+				let ty = if else_.is_some() { self.fresh() } else { Ty::unit() };
+				let mut diverges = else_.is_some();
+
+				for branch in branches.iter() {
+					match &branch.cond {
+						Condition::Expr(cond) => { self.check(cond, &Ty::Prim(Prim::Bool), None); },
+						Condition::Matches(value, pat) => {
+							let found = self.infer(value);
+							self.pattern(pat, &found);
+						},
+					}
+
+					let found = self.block(&branch.body);
+					diverges &= self.diverges(&found);
+					if else_.is_some() { self.expect(&found, &ty, result_span(&branch.body), None); }
+				}
+
+				if let Some(else_) = else_ {
+					let found = self.block(else_);
+					diverges &= self.diverges(&found);
+					self.expect(&found, &ty, result_span(else_), None);
+				}
+
+				// If every way through never finishes, neither does the whole thing.
+				if diverges { Ty::Never } else { ty }
+			},
+			ExprKind::Match { scrutinee, arms } => {
+				let scrutinee_ty = self.infer(scrutinee);
+				let ty = self.fresh();
+				let mut diverges = !arms.is_empty();
+
+				for arm in arms.iter() {
+					self.pattern(&arm.pat, &scrutinee_ty);
+					let found = self.check(&arm.body, &ty, None);
+					diverges &= self.diverges(&found);
+				}
+
+				// Missing cases get pointed out at `matches`, which is somewhere between the scrutinee and the first arm.
+				let source = &self.tree.modules[self.module].source;
+				let span = source.get(scrutinee.span.end..expr.span.end)
+					.and_then(|rest| rest.find("matches"))
+					.map_or(expr.span.clone(), |offset| {
+						let start = scrutinee.span.end + offset;
+						start..start + "matches".len()
+					});
+
+				self.matches.push(MatchCheck {
+					module: self.module,
+					span,
+					scrutinee: scrutinee_ty,
+					value: ty.clone(),
+					arms: arms.iter().map(|arm| arm.pat.clone()).collect(),
+				});
+
+				if diverges { Ty::Never } else { ty }
+			},
+			ExprKind::Loop { label, body } => self.loop_body(label.as_ref().map(|label| label.name.clone()), body),
+			ExprKind::While { label, cond, body } | ExprKind::Until { label, cond, body } => {
+				self.check(cond, &Ty::Prim(Prim::Bool), None);
+				self.loop_body(label.as_ref().map(|label| label.name.clone()), body);
+				Ty::unit()
+			},
+			ExprKind::For { label, pat, iter, body } => {
+				self.infer(iter);
+				let elem = self.unknown();
+				self.pattern(pat, &elem);
+				self.loop_body(label.as_ref().map(|label| label.name.clone()), body);
+				Ty::unit()
+			},
+			ExprKind::Block { label, body } => {
+				let ty = self.fresh();
+				self.targets.push(Target { label: Some(label.name.clone()), is_loop: false, ty: ty.clone(), broke: false });
+
+				let found = self.block(body);
+				self.expect(&found, &ty, result_span(body), None);
+
+				self.targets.pop();
+				ty
+			},
+			ExprKind::Handle { handler, body } => {
+				let handled = self.handler(handler);
+				let depth = self.scopes.last().map_or(0, |scope| scope.handlers.len());
+
+				if let (Some(scope), Some(effect)) = (self.scopes.last_mut(), handled) { scope.handlers.push(effect) }
+				let ty = self.block(body);
+				if let Some(scope) = self.scopes.last_mut() { scope.handlers.truncate(depth) }
+
+				self.block(&handler.body);
+				ty
+			},
+			ExprKind::Break { label, value } => {
+				let target = match label {
+					Some(label) => self.targets.iter().rposition(|target| target.label.as_ref() == Some(&label.name)),
+					None => self.targets.iter().rposition(|target| target.is_loop),
+				};
+
+				let found = match value {
+					Some(value) => self.infer(value),
+					None => Ty::unit(),
+				};
+
+				// Breaks which don't go anywhere were already reported when labels were checked.
+				if let Some(target) = target {
+					self.targets[target].broke = true;
+					let ty = self.targets[target].ty.clone();
+					let span = value.as_ref().map_or(expr.span.clone(), |value| value.span.clone());
+					self.expect(&found, &ty, span, None);
+				}
+
+				Ty::Never
+			},
+			ExprKind::Continue(_) => Ty::Never,
+			ExprKind::Return(value) => {
+				let (ret, ret_span) = self.rets.last().cloned().unwrap_or((Ty::Error, None));
+
+				match value {
+					Some(value) => { self.check(value, &ret, ret_span); },
+					None => self.expect(&Ty::unit(), &ret, expr.span.clone(), ret_span),
+				}
+
+				Ty::Never
+			},
+			// `?` has been desugared into a match by now.
+			ExprKind::Try(value) => {
+				self.infer(value);
+				self.unknown()
+			},
+		}
+	}
+
+	/// Checks a closure. If it's expected to be a function type, its parameters get their types from that before its body is checked,
+	/// so that something like `inner.map(...)` inside of it knows what `inner` is.
+	fn closure(&mut self, params: &[Param], ret: Option<&Type>, effects: &[Type], body: &Block, expected: Option<&Ty>) -> Ty {
+		let param_tys: Vec<Ty> = params.iter()
+			.map(|param| match &param.ty {
+				Some(ty) => self.lower_ty(ty),
+				None => self.fresh(),
+			})
+			.collect();
+
+		if let Some(Ty::Func(expected, ..)) = expected.map(|ty| self.table.resolve(ty)) {
+			if expected.len() == param_tys.len() {
+				for (param, ty) in param_tys.iter().zip(expected.iter()) { let _ = self.table.unify(param, ty); }
+			}
+		}
+
+		let ret_span = ret.as_ref().map(|ret| ret.span.clone());
+		let ret = match ret {
+			Some(ret) => self.lower_ty(ret),
+			None => self.fresh(),
+		};
+
+		// A closure which declares its effects can only perform those.
+		let row = match effects.is_empty() {
+			true => self.fresh(),
+			false => Ty::Row(effects.iter().filter_map(|effect| self.lower_effect(effect)).collect(), None),
+		};
+
+		self.bind_params(params, &param_tys);
+
+		// Loops outside of a closure can't be broken out of from inside of it.
+		let targets = std::mem::take(&mut self.targets);
+		self.rets.push((ret.clone(), ret_span.clone()));
+		self.scopes.push(EffectScope { row: row.clone(), func: None, handlers: Vec::new() });
+
+		let found = self.block(body);
+		self.expect(&found, &ret, result_span(body), ret_span);
+
+		self.scopes.pop();
+		self.rets.pop();
+		self.targets = targets;
+
+		Ty::Func(param_tys, Box::new(ret), Box::new(row))
+	}
+
+	fn no_field(&mut self, ty: &Ty, field: &str, span: Span) -> Ty {
+		let ty = self.show(ty);
+		self.report(span, TypeError::NoField { ty, field: field.to_string() });
+		Ty::Error
+	}
+
+	/// Checks the body of a loop, giving back the type of the values it's broken out of with.
+	fn loop_body(&mut self, label: Option<String>, body: &Block) -> Ty {
+		let ty = self.fresh();
+		self.targets.push(Target { label, is_loop: true, ty: ty.clone(), broke: false });
+
+		self.block(body);
+
+		let target = self.targets.pop().unwrap();
+		// A loop which is never broken out of never finishes.
+		if target.broke { ty } else { Ty::Never }
+	}
+
+	fn call(&mut self, callee: &Ty, callee_span: &Span, args: &[Expr]) -> Ty {
+		let callee = self.table.resolve(callee);
+
+		let (params, ret, effects) = match &callee {
+			Ty::Func(params, ret, effects) => (params.clone(), (**ret).clone(), (**effects).clone()),
+			// Generic parameters can be called if they're bounded by a function type.
+			Ty::Param(owner, index) if self.bounds.contains_key(&(*owner, *index)) => match &self.bounds[&(*owner, *index)][0] {
+				Ty::Func(params, ret, effects) => (params.clone(), (**ret).clone(), (**effects).clone()),
+				_ => unreachable!(),
+			},
+			Ty::Var(_) => {
+				let params: Vec<Ty> = args.iter().map(|_| self.fresh()).collect();
+				let (ret, effects) = (self.fresh(), self.fresh());
+				self.expect(&Ty::Func(params.clone(), Box::new(ret.clone()), Box::new(effects.clone())), &callee, callee_span.clone(), None);
+				(params, ret, effects)
+			},
+			Ty::Error | Ty::Never => {
+				for arg in args.iter() { self.infer(arg); }
+				return Ty::Error;
+			},
+			_ => {
+				let ty = self.show(&callee);
+				self.report(callee_span.clone(), TypeError::NotCallable(ty));
+				for arg in args.iter() { self.infer(arg); }
+				return Ty::Error;
+			},
+		};
+
+		// The effects which are already known go first, so that an operation like `Exception::throw` takes its arguments
+		// at the type the effect was declared or handled with, which might be a trait object.
+		// The rest can come from the arguments, like the effects of a closure passed in, so they have to wait for them.
+		let (known, tail) = self.table.row(&effects);
+		self.perform(&Ty::Row(known, None), callee_span);
+		self.args(&params, args, callee_span);
+		self.normalize_projections();
+		if let Some(tail) = tail { self.perform(&Ty::Row(Vec::new(), Some(Box::new(tail))), callee_span); }
+		ret
+	}
+
+	/// Checks the arguments to a call against the parameters they're passed to.
+	fn args(&mut self, params: &[Ty], args: &[Expr], span: &Span) {
+		if params.len() != args.len() {
+			self.report(span.clone(), TypeError::ArgCount { expected: params.len(), found: args.len() });
+		}
+
+		for (i, arg) in args.iter().enumerate() {
+			match params.get(i) {
+				Some(param) => { self.check(arg, param, None); },
+				None => { self.infer(arg); },
+			}
+		}
+	}
+
+	/// Gives the signature of a function or variant fresh variables for its generic parameters, and those of whatever it's declared inside of.
+	/// Any trait bounds on those are left behind to be checked at `span`.
+	fn instantiate(&mut self, def: DefId, span: &Span) -> Sig {
+		// A function which only calls itself still never gets used.
+		if self.within != Some(def) { self.used.insert(def); }
+
+		let sig = self.sigs[&def].clone();
+		let mut fresh: HashMap<Ty, Ty> = HashMap::new();
+
+		let mut collect = |ty: &Ty| ty.walk(&mut |inner| if matches!(inner, Ty::Param(..) | Ty::SelfOf(_) | Ty::EffectsOf(_)) && !fresh.contains_key(inner) {
+			fresh.insert(inner.clone(), self.table.fresh(self.level, VarKind::General));
+		});
+
+		sig.params.iter().for_each(&mut collect);
+		collect(&sig.ret);
+		collect(&sig.effects);
+		if let Some(owner) = &sig.owner { collect(owner); }
+
+		// Only the parameters of the item itself and what it's inside of get replaced, not those of the function being checked.
+		let parents: Vec<DefId> = std::iter::successors(Some(def), |def| self.res.defs[*def].parent).collect();
+		fresh.retain(|param, _| match param {
+			Ty::Param(owner, _) | Ty::SelfOf(owner) | Ty::EffectsOf(owner) => parents.contains(owner),
+			_ => false,
+		});
+
+		// A parameter bounded by a function type can only be that function type.
+		for (param, var) in fresh.iter() {
+			let Ty::Param(owner, index) = param else { continue };
+
+			for bound in self.bounds.get(&(*owner, *index)).into_iter().flatten() {
+				let _ = self.table.unify(var, &bound.subst(&fresh));
+			}
+		}
+
+		self.oblige(def, &fresh, span);
+
+		Sig {
+			params: sig.params.iter().map(|param| self.defer(&param.subst(&fresh), span)).collect(),
+			ret: self.defer(&sig.ret.subst(&fresh), span),
+			owner: sig.owner.map(|owner| owner.subst(&fresh)),
+			effects: self.defer(&sig.effects.subst(&fresh), span),
+			..sig
+		}
+	}
+
+	/// Works out the type of a path used as a value.
+	fn value_path(&mut self, path: &Path) -> Ty {
+		if path.resolved < path.segments.len() {
+			return self.associated(path);
+		}
+
+		match path.res {
+			Res::Local(local) => match self.locals.get(&local).cloned() {
+				Some(scheme) => self.table.instantiate(&scheme, self.level),
+				None => Ty::Error,
+			},
+			Res::Def(def) => match self.res.defs[def].kind {
+				DefKind::Func => {
+					let sig = self.instantiate(def, &path.span);
+					Ty::Func(sig.params, Box::new(sig.ret), Box::new(sig.effects))
+				},
+				DefKind::Variant => match self.instantiate(def, &path.span) {
+					sig if sig.params.is_empty() => sig.ret,
+					sig => Ty::Func(sig.params, Box::new(sig.ret), Box::new(sig.effects)),
+				},
+				DefKind::Const if self.is_assoc(def) => {
+					self.ambiguous_assoc(def, path);
+					Ty::Error
+				},
+				DefKind::Const => self.consts.get(&def).cloned().unwrap_or(Ty::Error),
+				DefKind::Type if self.sigs.contains_key(&def) => self.constructor(def, path),
+				_ => self.not_a_value(path),
+			},
+			Res::SelfType(def) if self.sigs.contains_key(&def) && self.res.defs[def].kind == DefKind::Type => self.constructor(def, path),
+			Res::Unresolved => Ty::Error,
+			_ => self.not_a_value(path),
+		}
+	}
+
+	/// The function which makes a record or tuple type. Every one of a record's fields has to be visible to make it.
+	fn constructor(&mut self, def: DefId, path: &Path) -> Ty {
+		if let ItemKind::Type(decl) = &self.items[&def].kind {
+			if let TypeBody::Record(fields) = &decl.body {
+				for field in fields.iter() {
+					if !self.field_access(def, &field.name.name, field.vis, &path.span) { break }
+				}
+			}
+		}
+
+		let sig = self.instantiate(def, &path.span);
+		Ty::Func(sig.params, Box::new(sig.ret), Box::new(sig.effects))
+	}
+
+	/// Finds a field of a record type, along with how visible it is, with the type's generic arguments filled in.
+	fn record_field(&self, def: DefId, args: &[Ty], name: &str) -> Option<(Visibility, Ty)> {
+		let ItemKind::Type(decl) = &self.items.get(&def)?.kind else { return None };
+		let TypeBody::Record(fields) = &decl.body else { return None };
+		let field = fields.iter().find(|field| field.name.name == name)?;
+
+		let Some(Ty::Record(tys)) = self.sigs.get(&def).and_then(|sig| sig.params.first()) else { return None };
+		let (_, ty) = tys.iter().find(|(field, _)| field == name)?;
+
+		let args: HashMap<Ty, Ty> = args.iter().enumerate().map(|(i, arg)| (Ty::Param(def, i), arg.clone())).collect();
+		Some((field.vis, ty.subst(&args)))
+	}
+
+	/// The types of a tuple type's fields, with its generic arguments filled in.
+	fn tuple_fields(&self, def: DefId, args: &[Ty]) -> Option<Vec<Ty>> {
+		let ItemKind::Type(decl) = &self.items.get(&def)?.kind else { return None };
+		if !matches!(decl.body, TypeBody::Tuple(_)) { return None }
+
+		let args: HashMap<Ty, Ty> = args.iter().enumerate().map(|(i, arg)| (Ty::Param(def, i), arg.clone())).collect();
+		Some(self.sigs.get(&def)?.params.iter().map(|param| param.subst(&args)).collect())
+	}
+
+	/// Checks that a field of a record type can be seen from the module being checked, reporting it if not.
+	fn field_access(&mut self, def: DefId, field: &str, vis: Visibility, span: &Span) -> bool {
+		let owner = self.res.defs[def].module;
+		if accessible(self.tree, self.module, vis, owner) { return true }
+
+		// Something marked `prt` is private to its whole package, which is its root module.
+		let path = &self.tree.modules[owner].path;
+		let (module, note) = match vis {
+			Visibility::Protected => (path[0].clone(), "it's marked `prt`, so only its own package can use it"),
+			_ => (path.join("::"), "it isn't marked `pub` or `prt`, so only its own module and the modules inside of it can use it"),
+		};
+
+		let ty = self.res.defs[def].name.name.clone();
+		self.report(span.clone(), TypeError::PrivateField { field: field.to_string(), ty, module }).add_note(note.to_string());
+		false
+	}
+
+	fn not_a_value(&mut self, path: &Path) -> Ty {
+		self.report(path.span.clone(), TypeError::NotAValue(path.to_string()));
+		Ty::Error
+	}
+
+	/// Works out the type of a path which goes on past what name resolution found, like `nat::from_str`.
+	fn associated(&mut self, path: &Path) -> Ty {
+		let base = match path.res {
+			Res::Def(def) if self.res.defs[def].kind == DefKind::Type => {
+				let args = (0..self.generics(def).len()).map(|_| self.fresh()).collect();
+				match self.prims.get(&def) {
+					Some(prim) => Ty::Prim(*prim),
+					None => Ty::Adt(def, args),
+				}
+			},
+			Res::SelfType(def) if self.res.defs[def].kind == DefKind::Type => self.self_adt(def),
+			Res::SelfType(def) if self.res.defs[def].kind == DefKind::Impl => self.impl_tys.get(&def).cloned().unwrap_or(Ty::Error),
+			// Anything found through a generic parameter, like `T::default`, comes from its bounds.
+			Res::SelfType(def) if self.res.defs[def].kind == DefKind::Trait => Ty::SelfOf(def),
+			Res::Generic(owner, index) => Ty::Param(owner, index),
+			_ => return self.unknown(),
+		};
+
+		let name = &path.segments[path.resolved].name;
+
+		if path.resolved + 1 == path.segments.len() {
+			if let Some(def) = self.lookup_assoc(&base, &name.name, DefKind::Const) {
+				return self.assoc_const(def, &base, &path.span);
+			}
+		}
+
+		match self.lookup_method(&base, &name.name) {
+			Some(def) if path.resolved + 1 == path.segments.len() => {
+				let sig = self.instantiate(def, &path.span);
+				if let Some(owner) = &sig.owner {
+					self.expect(&base, owner, path.span.clone(), None);
+				}
+
+				Ty::Func(sig.params, Box::new(sig.ret), Box::new(sig.effects))
+			},
+			None => {
+				let ty = self.show(&base);
+				self.report(name.span.clone(), TypeError::NoMethod { ty, method: name.name.clone() });
+				Ty::Error
+			},
+			_ => self.unknown(),
+		}
+	}
+
+	/// The type of one of a trait's constants, for the type it was found through.
+	fn assoc_const(&mut self, def: DefId, base: &Ty, span: &Span) -> Ty {
+		let Some(ty) = self.consts.get(&def).cloned() else { return Ty::Error };
+		let parent = self.res.defs[def].parent.unwrap();
+		let mut fresh: HashMap<Ty, Ty> = (0..self.generics(parent).len()).map(|i| (Ty::Param(parent, i), self.fresh())).collect();
+
+		match self.res.defs[parent].kind {
+			DefKind::Impl => {
+				let impl_ty = self.impl_tys[&parent].subst(&fresh);
+				self.expect(base, &impl_ty, span.clone(), None);
+			},
+			_ => { fresh.insert(Ty::SelfOf(parent), base.clone()); },
+		}
+
+		// A trait's own declaration is only one particular constant if the type it's for is known, and it's the default.
+		if self.res.defs[parent].kind == DefKind::Impl || head(base).is_some() {
+			self.assoc_consts.insert((self.module, span.clone()), def);
+		}
+
+		self.defer(&ty.subst(&fresh), span)
+	}
+
+	/// Requires a type to be a number.
+	pub(super) fn numeric(&mut self, ty: &Ty, op: &'static str, span: &Span) {
+		match self.table.resolve(ty) {
+			Ty::Prim(prim) if prim.is_numeric() => {},
+			Ty::Var(_) => {
+				let number = self.table.fresh(self.level, VarKind::Integral);
+				self.expect(ty, &number, span.clone(), None);
+			},
+			Ty::Error | Ty::Never => {},
+			other => {
+				let ty = self.show(&other);
+				self.report(span.clone(), TypeError::CantApply { op, ty });
+			},
+		}
+	}
+
+	/// Requires two values of a type to be comparable, either for equality or for which comes first.
+	/// Functions never are, and types declared in Rouge code inside of it have to implement `Eq` or `Ord` from `std::ops`.
+	fn comparable(&mut self, ty: &Ty, op: &'static str, ordered: bool, span: &Span) {
+		match self.table.resolve(ty) {
+			Ty::List(elem) | Ty::Array(elem, _) => self.comparable(&elem, op, ordered, span),
+			Ty::Tuple(elems) => for elem in elems.iter() {
+				self.comparable(elem, op, ordered, span);
+			},
+			Ty::Map(key, value) if !ordered => {
+				self.comparable(&key, op, ordered, span);
+				self.comparable(&value, op, ordered, span);
+			},
+			Ty::Record(fields) if !ordered => for (_, field) in fields.iter() {
+				self.comparable(field, op, ordered, span);
+			},
+			Ty::Prim(_) | Ty::Var(_) | Ty::Error | Ty::Never => {},
+			other if self.is_declared(&other) => {
+				if let Some(trait_) = self.std_def(&["ops", if ordered { "Ord" } else { "Eq" }]) {
+					self.require(other, trait_, span);
+				}
+			},
+			other => {
+				let ty = self.show(&other);
+				let note = match other {
+					Ty::Func(..) => "functions can't be compared",
+					_ => "only lists, arrays and tuples have an order",
+				};
+
+				self.report(span.clone(), TypeError::CantApply { op, ty }).add_note(note.to_string());
+			},
+		}
+	}
+
+	/// Requires a type to be a whole number.
+	fn integral(&mut self, ty: &Ty, op: &'static str, span: &Span) {
+		match self.table.resolve(ty) {
+			Ty::Prim(prim) if prim.is_integral() => {},
+			Ty::Prim(Prim::Flo) => self.report(span.clone(), TypeError::CantApply { op, ty: String::from("flo") }).add_note(String::from("this only works on whole numbers")),
+			_ => self.numeric(ty, op, span),
+		}
+	}
+
+	fn binary_op(&mut self, op: BinaryOp, lhs: &Ty, rhs: &Ty, span: &Span, lhs_span: &Span, rhs_span: &Span) -> Ty {
+		let name = op.as_str();
+
+		if let Some(overload) = binary_trait(op).filter(|_| self.is_declared(&self.table.resolve(lhs))) {
+			return self.overloaded(name, overload, &[(lhs, lhs_span), (rhs, rhs_span)], span);
+		}
+
+		match op {
+			BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => match self.table.resolve(lhs) {
+				// Adding to a list either appends another list, or pushes a single value.
+				Ty::List(elem) if op == BinaryOp::Add => {
+					match self.table.resolve(rhs) {
+						Ty::List(_) => self.expect(rhs, lhs, rhs_span.clone(), None),
+						_ => self.expect(rhs, &elem, rhs_span.clone(), None),
+					}
+					lhs.clone()
+				},
+				Ty::Prim(Prim::Str) if op == BinaryOp::Add => {
+					self.expect(rhs, lhs, rhs_span.clone(), None);
+					lhs.clone()
+				},
+				_ => {
+					self.numeric(lhs, name, lhs_span);
+					self.expect(rhs, lhs, rhs_span.clone(), None);
+					lhs.clone()
+				},
+			},
+			BinaryOp::DivisibleBy => {
+				self.integral(lhs, name, lhs_span);
+				self.expect(rhs, lhs, rhs_span.clone(), None);
+				Ty::Prim(Prim::Bool)
+			},
+			BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor => {
+				if self.table.resolve(lhs) != Ty::Prim(Prim::Bool) { self.integral(lhs, name, lhs_span) }
+				self.expect(rhs, lhs, rhs_span.clone(), None);
+				lhs.clone()
+			},
+			BinaryOp::Shl | BinaryOp::Shr => {
+				self.integral(lhs, name, lhs_span);
+				self.integral(rhs, name, rhs_span);
+				lhs.clone()
+			},
+			BinaryOp::And | BinaryOp::Or | BinaryOp::Xor => {
+				self.expect(lhs, &Ty::Prim(Prim::Bool), lhs_span.clone(), None);
+				self.expect(rhs, &Ty::Prim(Prim::Bool), rhs_span.clone(), None);
+				Ty::Prim(Prim::Bool)
+			},
+			BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+				self.expect(rhs, lhs, rhs_span.clone(), None);
+				let ordered = !matches!(op, BinaryOp::Eq | BinaryOp::Ne);
+				self.comparable(lhs, name, ordered, lhs_span);
+				Ty::Prim(Prim::Bool)
+			},
+			BinaryOp::Range | BinaryOp::RangeInclusive => {
+				self.expect(rhs, lhs, rhs_span.clone(), None);
+				match self.range {
+					Some(range) => Ty::Adt(range, vec![lhs.clone()]),
+					None => Ty::Error,
+				}
+			},
+			// `f >=> g` runs `f` and then passes what it gives back on to `g`. The two have to perform the same effects, which the result does too.
+			BinaryOp::Bind => {
+				let (param, middle, ret, effects) = (self.fresh(), self.fresh(), self.fresh(), self.fresh());
+				self.expect(lhs, &Ty::Func(vec![param.clone()], Box::new(middle.clone()), Box::new(effects.clone())), lhs_span.clone(), None);
+				self.expect(rhs, &Ty::Func(vec![middle], Box::new(ret.clone()), Box::new(effects.clone())), rhs_span.clone(), None);
+				Ty::Func(vec![param], Box::new(ret), Box::new(effects))
+			},
+		}
+	}
+
+	/// Works out an operator used on a type declared in Rouge code, which goes through the method of its trait in `std::ops`.
+	/// The operands are given in the order the method takes them, starting with `self`.
+	fn overloaded(&mut self, op: &'static str, (name, method): (&str, &str), operands: &[(&Ty, &Span)], span: &Span) -> Ty {
+		let ty = self.table.resolve(operands[0].0);
+		let rhs = operands.get(1).map(|(rhs, _)| self.table.zonk(rhs));
+
+		let found = match (self.std_def(&["ops", name]), &ty) {
+			(Some(trait_), Ty::Param(..) | Ty::SelfOf(_)) => self.bounded_method(trait_, &ty, method),
+			(Some(trait_), _) => match self.select_impl(trait_, &ty, rhs.as_ref()) {
+				// Anything the impl leaves out is the trait's default, or else has already been reported.
+				Some(imp) => match self.res.defs[imp].children.get(method).or_else(|| self.res.defs[trait_].children.get(method)).copied() {
+					Some(def) => Some((def, self.instantiate(def, span))),
+					None => return Ty::Error,
+				},
+				None => None,
+			},
+			(None, _) => None,
+		};
+
+		let Some((def, sig)) = found else {
+			let shown = self.show(&ty);
+			let trait_ = match &rhs {
+				Some(rhs) => format!("{}<{}>", name, self.show(rhs)),
+				None => name.to_string(),
+			};
+
+			self.report(operands[0].1.clone(), TypeError::CantApply { op, ty: shown })
+				.add_note(format!("it would need to implement `std::ops::{}`", trait_));
+			return Ty::Error;
+		};
+
+		for ((operand, at), param) in operands.iter().zip(sig.params.iter()) {
+			self.expect(operand, param, (*at).clone(), None);
+		}
+
+		self.perform(&sig.effects, span);
+		self.operators.insert((self.module, span.clone()), def);
+		sig.ret
+	}
+}
+
+/// The trait in `std::ops` a binary operator goes through, and its method.
+fn binary_trait(op: BinaryOp) -> Option<(&'static str, &'static str)> {
+	Some(match op {
+		BinaryOp::Add => ("Add", "add"),
+		BinaryOp::Sub => ("Sub", "sub"),
+		BinaryOp::Mul => ("Mul", "mul"),
+		BinaryOp::Div => ("Div", "div"),
+		BinaryOp::Rem => ("Rem", "rem"),
+		BinaryOp::DivisibleBy => ("DivisibleBy", "divisible_by"),
+		BinaryOp::BitAnd => ("BitAnd", "bit_and"),
+		BinaryOp::BitOr => ("BitOr", "bit_or"),
+		BinaryOp::BitXor => ("BitXor", "bit_xor"),
+		BinaryOp::Shl => ("Shl", "shl"),
+		BinaryOp::Shr => ("Shr", "shr"),
+		BinaryOp::Bind => ("Bind", "bind"),
+		BinaryOp::Eq => ("Eq", "eq"),
+		BinaryOp::Ne => ("Eq", "ne"),
+		BinaryOp::Lt => ("Ord", "lt"),
+		BinaryOp::Le => ("Ord", "le"),
+		BinaryOp::Gt => ("Ord", "gt"),
+		BinaryOp::Ge => ("Ord", "ge"),
+		// Logic only works on `bool`, and ranges work on anything.
+		_ => return None,
+	})
+}
+
+/// The trait in `std::ops` a unary operator goes through, and its method.
+fn unary_trait(op: UnaryOp) -> Option<(&'static str, &'static str)> {
+	match op {
+		UnaryOp::Neg => Some(("Neg", "neg")),
+		UnaryOp::Not => Some(("Not", "not")),
+		UnaryOp::Plus => None,
+	}
+}
+
+/// What a compound assignment looks like, along with the trait in `std::ops` it goes through and its method.
+fn assign_trait(op: BinaryOp) -> Option<(&'static str, (&'static str, &'static str))> {
+	Some(match op {
+		BinaryOp::Add => ("+=", ("AddAssign", "add_assign")),
+		BinaryOp::Sub => ("-=", ("SubAssign", "sub_assign")),
+		BinaryOp::Mul => ("*=", ("MulAssign", "mul_assign")),
+		BinaryOp::Div => ("/=", ("DivAssign", "div_assign")),
+		BinaryOp::Rem => ("%=", ("RemAssign", "rem_assign")),
+		BinaryOp::BitAnd => ("&=", ("BitAndAssign", "bit_and_assign")),
+		BinaryOp::BitOr => ("|=", ("BitOrAssign", "bit_or_assign")),
+		BinaryOp::BitXor => ("^=", ("BitXorAssign", "bit_xor_assign")),
+		BinaryOp::Shl => ("<<=", ("ShlAssign", "shl_assign")),
+		BinaryOp::Shr => (">>=", ("ShrAssign", "shr_assign")),
+		_ => return None,
+	})
+}
+
+/// Where the value of a block comes from, for pointing at when it's the wrong type.
+fn result_span(block: &Block) -> Span {
+	block.stmts.last().map_or(block.span.clone(), |stmt| stmt.span.clone())
+}
@@ -0,0 +1,1118 @@
+//! Works out the type of everything in a program, and checks that they all fit together.
+//!
+//! Inference is Hindley-Milner with a few extensions: number literals get a variable which can only become a number,
+//! and `:=` declarations of closures get generalized, so one closure can be used at more than one type.
+//! Items are never inferred, since their signatures always spell out their types.
+//!
+//! Function types carry a row of the effects calling them performs. A function may only perform the effects it declares,
+//! along with whatever the functions passed to it perform, which its callers then perform in turn.
+//! Closures don't declare anything, so their effects are inferred from their bodies.
+//!
+//! Trait bounds are checked once every type is known: each call to something with bounds leaves behind an obligation,
+//! which holds if an impl covers the type it ended up with, or if the body it's in assumes it through a bound of its own.
+//!
+//! `!` is the type of anything which never finishes, like `return`, `break`, a call to `Exception::throw` or an `always` loop
+//! which is never broken out of. It fits in wherever any other type is expected, so one arm of a match can throw while the others
+//! give back a value, but only something else which never finishes fits where `!` is expected. Statements after one of those
+//! get a warning, since they can never run.
+//!
+//! Operators work on the builtin types by themselves. On a type declared in Rouge code, each one calls the method of its trait
+//! in `std::ops`, like `a + b` calling `Add::add`, from whichever impl of it the right-hand side fits. Compound assignments
+//! go through the `*Assign` traits instead, which change what they're assigning to in place. Comparisons go through `Eq` and `Ord`,
+//! and the builtin types compare by what's in them, so a list of some declared type can only be compared if that type can be.
+//! Functions can't be compared at all.
+//!
+//! Generic code is only ever compiled once, rather than once for each type it gets used with. Every value is a `RougeData`
+//! when the program runs, which already knows what kind of value it is, so a generic function works the same whatever
+//! its parameters turn out to be. Where a bound is needed to find a trait's method, like `T::default()`,
+//! the impl which satisfies it gets passed along as a hidden argument.
+//!
+//! `dyn Trait` is a trait object, which can hold a value of any type that implements the trait, so a list like `[dyn Widget]`
+//! can hold buttons and labels alike. A value turns into one wherever a trait object is expected, and gets a vtable then,
+//! with the function its type has for each of the trait's methods. Calling a method on a trait object goes through that,
+//! so only traits whose methods all take `self`, aren't generic, and don't use `Self` anywhere else can be made into them.
+//!
+//! A trait can also declare types and constants for its impls to fill in, like an iterator's `type Item`. Written on a generic parameter,
+//! like `I::Item`, one stays as it is, since only the parameter's bounds are known. Anywhere the type is known, it's whatever that
+//! type's impl says. A signature using one for a parameter which gets inferred, like `func first<I: Iterator>(it: I) -> I::Item`,
+//! gets a variable for it instead, which is filled in once the arguments have pinned down what the parameter is.
+//!
+//! The builtin types get their methods from the standard library, which declares them as `@builtin` functions inside of
+//! the types' own declarations, like `len` inside of `[T]` in `std::collections`. A method or field none of them have is an error,
+//! the same as on any other type. Calling a method on something whose type isn't known yet gives back a type to be inferred,
+//! since which method it is can't be known until then.
+
+//--> Imports <--
+
+mod collect; // Signatures and types of items
+mod effects;
+mod exhaustive; // Whether matches cover everything
+mod infer; // Function bodies
+mod num;
+mod traits; // Impls, bounds and coherence
+mod ty;
+
+use crate::{
+	Error,
+	ErrorKind,
+	ErrorList,
+	compiler::{
+		InterpretError,
+		line_of,
+		modules::{
+			ModuleId,
+			ModuleTree,
+		},
+		parser::astgen::{
+			Item,
+			Variant,
+		},
+		resolve::{
+			DefId,
+			LocalId,
+			Resolutions,
+		},
+	},
+};
+
+use effects::Unhandled;
+use exhaustive::MatchCheck;
+use num::NumLit;
+use traits::{
+	Coercion,
+	Obligation,
+	WellFormed,
+};
+use ty::{
+	Scheme,
+	Table,
+	Ty,
+	TyVar,
+	VarKind,
+};
+
+pub(crate) use ty::Prim;
+
+use logos::Span;
+
+use std::{
+	collections::{
+		HashMap,
+		HashSet,
+	},
+	fmt,
+	path::PathBuf,
+};
+
+//--> Structs <--
+
+/// The type inferred for a variable, for showing in editors when it gets hovered over.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeHint {
+	pub file: Option<PathBuf>,
+	pub line: usize,
+	/// Where the variable's name is.
+	pub span: Span,
+	pub name: String,
+	pub ty: String,
+}
+
+/// Everything type checking found out.
+#[derive(Debug, Default)]
+pub(crate) struct Types {
+	/// The type of every local, with any variables which were generalized left in.
+	pub locals: HashMap<LocalId, Ty>,
+	/// Hints for every variable in the package being compiled, leaving out the standard library.
+	pub hints: Vec<TypeHint>,
+	/// Where the receivers of methods which take `mut self` are, since those have to be something which can be changed.
+	pub mutating: HashSet<(ModuleId, Span)>,
+	/// Where the receivers of methods called before their types were known are, since those might change them.
+	pub unknown_receivers: HashSet<(ModuleId, Span)>,
+	/// The type each number literal ended up with, by where it is. A negated literal is where its `-` starts.
+	pub numbers: HashMap<(ModuleId, Span), Prim>,
+	/// The method each operator used on a type declared in Rouge code calls, by where the operator's expression is.
+	/// Compound assignments are by where what they assign to is.
+	pub operators: HashMap<(ModuleId, Span), DefId>,
+	/// Every function and constructor used from somewhere other than its own body, which is how ones that never get used are found.
+	pub used: HashSet<DefId>,
+	/// The vtable of each value turned into a trait object, by where the value is. It has the function for each of the trait's methods,
+	/// in the order they're declared, followed by those of its supertraits.
+	pub vtables: HashMap<(ModuleId, Span), Vec<DefId>>,
+	/// The constant each path through a type to one of its trait's constants ended up at, like `Counter::MAX`, by where the path is.
+	pub assoc_consts: HashMap<(ModuleId, Span), DefId>,
+}
+
+/// The type of a function or variant constructor, with its generic parameters left as they are.
+#[derive(Clone, Debug)]
+struct Sig {
+	params: Vec<Ty>,
+	ret: Ty,
+	/// Where the return type was written, if it was.
+	ret_span: Option<Span>,
+	/// For anything declared inside of a type, trait or impl, the type `Self` stands for.
+	owner: Option<Ty>,
+	/// Whether the first parameter is `self`, so the function can be called as a method.
+	has_self: bool,
+	/// The row of effects calling it performs.
+	effects: Ty,
+}
+
+/// A trait along with its generic arguments, like the `Error` in `E: Error`.
+#[derive(Clone, Debug, PartialEq)]
+struct TraitRef {
+	def: DefId,
+	args: Vec<Ty>,
+}
+
+/// Something `break` can jump out of.
+struct Target {
+	label: Option<String>,
+	is_loop: bool,
+	/// The type of the values it's broken out of with.
+	ty: Ty,
+	broke: bool,
+}
+
+/// The body of a function or closure, as far as effects go.
+struct EffectScope {
+	/// The effects the body is allowed to perform.
+	row: Ty,
+	/// The function the body belongs to, or nothing for a closure.
+	func: Option<DefId>,
+	/// The effects handled around what's being checked, innermost last.
+	handlers: Vec<Ty>,
+}
+
+struct Checker<'a> {
+	tree: &'a ModuleTree,
+	res: &'a Resolutions,
+	table: Table,
+	errors: ErrorList,
+	/// The module whose items are being looked at.
+	module: ModuleId,
+	/// How many `:=` declarations deep inference is, which decides what gets generalized.
+	level: usize,
+
+	items: HashMap<DefId, &'a Item>,
+	variants: HashMap<DefId, &'a Variant>,
+	/// The builtin types that are primitives, like `nat` and `str`.
+	prims: HashMap<DefId, Prim>,
+	/// The builtin collection types, like `[T]`, along with the head of the types they declare.
+	collections: HashMap<DefId, Head>,
+	/// `std::ops::Range`, which is the type of `a..b`.
+	range: Option<DefId>,
+	/// The type each impl is for.
+	impl_tys: HashMap<DefId, Ty>,
+	/// The impls for each type, by what's at the head of the type.
+	impls: HashMap<Head, Vec<DefId>>,
+	sigs: HashMap<DefId, Sig>,
+	/// Function types that generic parameters are bounded by, which is what lets them be called.
+	bounds: HashMap<(DefId, usize), Vec<Ty>>,
+	/// The trait each impl of one is for.
+	impl_traits: HashMap<DefId, TraitRef>,
+	supertraits: HashMap<DefId, Vec<TraitRef>>,
+	/// The trait bounds each item declares, from its generic parameters and `where` clause.
+	predicates: HashMap<DefId, Vec<(Ty, TraitRef)>>,
+	consts: HashMap<DefId, Ty>,
+	/// The defaults of generic parameters, lowered when they're first needed.
+	defaults: HashMap<(DefId, usize), Ty>,
+	/// Aliases being lowered, so that ones which refer back to themselves don't go on forever.
+	lowering: HashSet<DefId>,
+	/// What the rows of function types being lowered are left open with.
+	tail: Tail,
+
+	locals: HashMap<LocalId, Scheme>,
+	/// Every local bound so far, along with the module it's in.
+	bound: Vec<(ModuleId, LocalId)>,
+	/// The return types of the functions and closures being checked, innermost last.
+	rets: Vec<(Ty, Option<Span>)>,
+	targets: Vec<Target>,
+	literals: Vec<NumLit>,
+	numbers: HashMap<(ModuleId, Span), Prim>,
+	scopes: Vec<EffectScope>,
+	unhandled: Vec<Unhandled>,
+	mutating: HashSet<(ModuleId, Span)>,
+	unknown_receivers: HashSet<(ModuleId, Span)>,
+	operators: HashMap<(ModuleId, Span), DefId>,
+	used: HashSet<DefId>,
+	/// Every `dyn Trait` written, to check that the trait can be made into a trait object.
+	objects: Vec<(ModuleId, Span, DefId)>,
+	/// Values turned into trait objects, which get their vtables once every type is known.
+	coercions: Vec<Coercion>,
+	vtables: HashMap<(ModuleId, Span), Vec<DefId>>,
+	/// Associated types from signatures which were instantiated before what they're on was known,
+	/// each with where the signature was used and the variable standing in for it.
+	projections: Vec<(ModuleId, Span, Ty, Ty)>,
+	assoc_consts: HashMap<(ModuleId, Span), DefId>,
+	/// The function whose body is being checked, whose bounds can be relied on.
+	within: Option<DefId>,
+	obligations: Vec<Obligation>,
+	/// Every type and effect given generic arguments, whose bounds get checked along with the obligations.
+	well_formed: Vec<WellFormed>,
+	matches: Vec<MatchCheck>,
+}
+
+//--> Enums <--
+
+/// How the effects of a function type which was written down are left open.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tail {
+	/// They're exactly what was written, like for a function stored in a type.
+	Closed,
+	/// They can be more than what was written, if the function they're passed to passes those effects on.
+	Of(DefId),
+	/// They can be more than what was written, to be inferred from how they're used.
+	Fresh,
+}
+
+/// What's at the head of a type, which is what impls are found by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Head {
+	Def(DefId),
+	Prim(Prim),
+	List,
+	Array,
+	Map,
+	/// Tuples and records have nothing declaring them, so they go by how many elements or fields they have.
+	Tuple(usize),
+	Record(usize),
+}
+
+#[derive(Clone, Debug)]
+pub enum TypeError {
+	/// Two types which needed to be the same weren't. If the expected type was written down somewhere, that's where.
+	Mismatch { expected: String, found: String, expected_at: Option<Span> },
+	/// Something was called which isn't a function.
+	NotCallable(String),
+	/// A function was called with the wrong number of arguments.
+	ArgCount { expected: usize, found: usize },
+	NoField { ty: String, field: String },
+	NoMethod { ty: String, method: String },
+	/// A path to a module, type or trait was used where a value was needed.
+	NotAValue(String),
+	/// A path to a value, trait or module was used where a type was needed.
+	NotAType(String),
+	/// A type was given the wrong number of generic arguments.
+	GenericCount { name: String, expected: usize, found: usize },
+	/// A handler was written for something which isn't an operation of an effect.
+	NotAnOperation(String),
+	/// An operator was used on a type it doesn't work with.
+	CantApply { op: &'static str, ty: String },
+	/// A signed number was used where an unsigned one was expected, or the other way around.
+	SignMismatch { expected: String, found: String },
+	/// A number literal is too big for its type.
+	Overflow { lit: String, ty: String },
+	/// Something other than an effect was written after `-<`.
+	NotAnEffect(String),
+	/// A function or closure performs an effect that it doesn't declare, and that isn't handled.
+	Undeclared { effect: String, func: Option<String> },
+	/// An effect isn't handled anywhere before it gets to `main`, which nothing can handle effects for.
+	ReachesMain(String),
+	/// Something other than a trait was implemented or used as a bound.
+	NotATrait(String),
+	/// A trait was used as `dyn Trait`, but it has something a trait object couldn't call, which the notes explain.
+	NotObjectSafe(String),
+	/// A type was used where a bound needs it to implement a trait, and it doesn't.
+	Unsatisfied { ty: String, trait_: String },
+	/// An impl leaves out something from its trait which doesn't have a default.
+	MissingItem { trait_: String, item: String },
+	/// A type was used as though it had an associated type, which none of the traits it implements or is bounded by declare.
+	NoAssocType { ty: String, name: String },
+	/// An associated type or constant was used straight from its trait, rather than through a type implementing it.
+	AmbiguousAssoc(String),
+	/// An associated type was given a type in its trait, rather than in the impls of it.
+	AssocTypeBody(String),
+	/// An associated type in an impl wasn't given a type.
+	AssocTypeMissing(String),
+	/// A constant outside of a trait doesn't have a value.
+	MissingValue(String),
+	/// An impl has something in it which its trait doesn't declare.
+	NotInTrait { trait_: String, item: String },
+	/// A method in an impl has a different type to the one its trait declares.
+	ImplMismatch { item: String, expected: String, found: String },
+	/// Two impls of the same trait could both be for the same type.
+	Overlap { trait_: String, ty: String },
+	/// A generic parameter without a default comes after one with a default.
+	DefaultOrder(String),
+	/// A function's generic parameter has a default, which would never be used.
+	FuncDefault(String),
+	/// A match used as a value doesn't have an arm for every case, which are listed.
+	NonExhaustive(Vec<String>),
+	/// An arm of a match which the arms before it already cover.
+	Unreachable,
+	/// A statement after one which never finishes, like a `return`.
+	UnreachableCode,
+	/// A field of a record type was used from outside of where it's visible.
+	PrivateField { field: String, ty: String, module: String },
+}
+
+//--> Functions <--
+
+/// Checks the types of everything in a module tree which has had its names resolved.
+pub(crate) fn check(tree: &ModuleTree, res: &Resolutions) -> Result<(Types, ErrorList), ErrorList> {
+	let mut checker = Checker {
+		tree,
+		res,
+		table: Table::default(),
+		errors: ErrorList::new(),
+		module: 0,
+		level: 0,
+		items: HashMap::new(),
+		variants: HashMap::new(),
+		prims: HashMap::new(),
+		collections: HashMap::new(),
+		range: None,
+		impl_tys: HashMap::new(),
+		impls: HashMap::new(),
+		sigs: HashMap::new(),
+		bounds: HashMap::new(),
+		impl_traits: HashMap::new(),
+		supertraits: HashMap::new(),
+		predicates: HashMap::new(),
+		consts: HashMap::new(),
+		defaults: HashMap::new(),
+		lowering: HashSet::new(),
+		tail: Tail::Closed,
+		locals: HashMap::new(),
+		bound: Vec::new(),
+		rets: Vec::new(),
+		targets: Vec::new(),
+		literals: Vec::new(),
+		numbers: HashMap::new(),
+		scopes: Vec::new(),
+		unhandled: Vec::new(),
+		mutating: HashSet::new(),
+		unknown_receivers: HashSet::new(),
+		operators: HashMap::new(),
+		used: HashSet::new(),
+		objects: Vec::new(),
+		coercions: Vec::new(),
+		vtables: HashMap::new(),
+		projections: Vec::new(),
+		assoc_consts: HashMap::new(),
+		within: None,
+		obligations: Vec::new(),
+		well_formed: Vec::new(),
+		matches: Vec::new(),
+	};
+
+	checker.collect();
+	checker.check_impls();
+	checker.check_coherence();
+	checker.check_bodies();
+	checker.check_objects();
+	checker.table.default_integrals();
+	checker.normalize_projections();
+	checker.check_literals();
+	checker.check_unhandled();
+	checker.check_obligations();
+	checker.check_coercions();
+	checker.check_matches();
+
+	let types = checker.results();
+
+	if checker.errors.iter().all(|e| e.is_warning()) {
+		Ok((types, checker.errors))
+	} else {
+		Err(checker.errors)
+	}
+}
+
+impl Checker<'_> {
+	fn report(&mut self, span: Span, err: TypeError) -> &mut Error {
+		let module = &self.tree.modules[self.module];
+		let line = line_of(&module.source, span.start);
+		let slice = module.source.get(span.clone());
+
+		self.errors.push(Error::new(false, module.file.as_deref(), Some(line), Some(span), slice, ErrorKind::Interpret(InterpretError::Type(err))));
+		self.errors.last_mut().unwrap()
+	}
+
+	fn warn(&mut self, span: Span, err: TypeError) -> &mut Error {
+		let module = &self.tree.modules[self.module];
+		let line = line_of(&module.source, span.start);
+		let slice = module.source.get(span.clone());
+
+		self.errors.push(Error::new(true, module.file.as_deref(), Some(line), Some(span), slice, ErrorKind::Interpret(InterpretError::Type(err))));
+		self.errors.last_mut().unwrap()
+	}
+
+	fn line(&self, span: &Span) -> usize {
+		line_of(&self.tree.modules[self.module].source, span.start)
+	}
+
+	fn fresh(&mut self) -> Ty {
+		self.table.fresh(self.level, VarKind::General)
+	}
+
+	/// Stands in for the type of something the checker can't work out yet, like a method called on something whose type isn't known.
+	fn unknown(&mut self) -> Ty {
+		self.fresh()
+	}
+
+	/// Makes a found type fit an expected one, reporting a mismatch at `span` if it doesn't.
+	fn expect(&mut self, found: &Ty, expected: &Ty, span: Span, expected_at: Option<Span>) {
+		// `!` fits in anywhere, but only something else which never finishes fits in where `!` is expected.
+		let never_expected = self.table.resolve(expected) == Ty::Never && !matches!(self.table.resolve(found), Ty::Never | Ty::Error | Ty::Var(_));
+		if !never_expected && self.table.unify(expected, found).is_ok() { return }
+		if self.coerce(found, expected, &span) { return }
+
+		match (self.table.resolve(found), self.table.resolve(expected)) {
+			(Ty::Prim(found), Ty::Prim(expected)) if found.is_numeric() && expected.is_numeric() => {
+				return self.number_mismatch(found, expected, span, expected_at);
+			},
+			(Ty::Var(var), Ty::Prim(prim)) | (Ty::Prim(prim), Ty::Var(var)) if self.table.kind(var) == VarKind::Signed && prim.is_numeric() => {
+				return self.negative_mismatch(prim, span);
+			},
+			_ => {},
+		}
+
+		let (expected, found) = (self.show(expected), self.show(found));
+		let line = expected_at.as_ref().map(|at| self.line(at));
+
+		let err = self.report(span, TypeError::Mismatch { expected, found, expected_at });
+		if let Some(line) = line {
+			err.add_note(format!("the expected type comes from line {}", line));
+		}
+	}
+
+	/// Writes out a type the way it would be written in source code.
+	fn show(&self, ty: &Ty) -> String {
+		self.show_with(&self.table.zonk(ty), &HashMap::new())
+	}
+
+	/// Writes out a scheme, naming its generic variables `A`, `B` and so on.
+	fn show_scheme(&self, scheme: &Scheme) -> String {
+		let names: HashMap<TyVar, String> = scheme.vars.iter().enumerate()
+			.map(|(i, var)| (*var, ((b'A' + (i % 26) as u8) as char).to_string()))
+			.collect();
+
+		self.show_with(&self.table.zonk(&scheme.ty), &names)
+	}
+
+	fn show_with(&self, ty: &Ty, names: &HashMap<TyVar, String>) -> String {
+		let list = |tys: &[Ty]| tys.iter().map(|ty| self.show_with(ty, names)).collect::<Vec<_>>().join(", ");
+		let row = |tys: &[Ty]| tys.iter().map(|ty| self.show_with(ty, names)).collect::<Vec<_>>().join(" + ");
+
+		match ty {
+			Ty::Var(var) => match names.get(var) {
+				Some(name) => name.clone(),
+				None if self.table.kind(*var) == VarKind::Integral => String::from("{number}"),
+				None if self.table.kind(*var) == VarKind::Signed => String::from("{signed number}"),
+				None => String::from("_"),
+			},
+			Ty::Prim(prim) => prim.as_str().to_string(),
+			Ty::Adt(def, args) if args.is_empty() => self.res.defs[*def].name.name.clone(),
+			Ty::Adt(def, args) => format!("{}<{}>", self.res.defs[*def].name.name, list(args)),
+			Ty::Param(owner, index) => match self.generics(*owner).get(*index) {
+				Some(param) => param.name.name.clone(),
+				None => String::from("_"),
+			},
+			Ty::SelfOf(_) => String::from("Self"),
+			Ty::Dyn(def, args) if args.is_empty() => format!("dyn {}", self.res.defs[*def].name.name),
+			Ty::Dyn(def, args) => format!("dyn {}<{}>", self.res.defs[*def].name.name, list(args)),
+			Ty::Proj(base, def, _) => format!("{}::{}", self.show_with(base, names), self.res.defs[*def].name.name),
+			Ty::List(elem) => format!("[{}]", self.show_with(elem, names)),
+			Ty::Array(elem, Some(len)) => format!("[{}; {}]", self.show_with(elem, names), len),
+			Ty::Array(elem, None) => format!("[{}; _]", self.show_with(elem, names)),
+			Ty::Map(key, value) => format!("[{}: {}]", self.show_with(key, names), self.show_with(value, names)),
+			Ty::Tuple(elems) => format!("({})", list(elems)),
+			Ty::Record(fields) => format!("({})", fields.iter().map(|(name, ty)| format!("{}: {}", name, self.show_with(ty, names))).collect::<Vec<_>>().join(", ")),
+			Ty::Func(params, ret, effects) => {
+				let mut func = format!("Func({})", list(params));
+				if **ret != Ty::unit() { func += &format!(" -> {}", self.show_with(ret, names)) }
+				if let Ty::Row(effects, _) = &**effects {
+					if !effects.is_empty() { func += &format!(" -< {}", row(effects)) }
+				}
+				func
+			},
+			// Only the effects a row is known to have mean anything to someone reading it.
+			Ty::Row(effects, _) => row(effects),
+			Ty::EffectsOf(def) => format!("the effects of `{}`", self.res.defs[*def].name.name),
+			Ty::Never => String::from("!"),
+			Ty::Error => String::from("{error}"),
+		}
+	}
+
+	/// Gathers up the types of every local, and the hints for the ones in the package being compiled.
+	fn results(&mut self) -> Types {
+		let mut types = Types {
+			mutating: std::mem::take(&mut self.mutating),
+			unknown_receivers: std::mem::take(&mut self.unknown_receivers),
+			numbers: std::mem::take(&mut self.numbers),
+			operators: std::mem::take(&mut self.operators),
+			used: std::mem::take(&mut self.used),
+			vtables: std::mem::take(&mut self.vtables),
+			assoc_consts: std::mem::take(&mut self.assoc_consts),
+			..Types::default()
+		};
+
+		for (module, local) in self.bound.iter() {
+			let Some(scheme) = self.locals.get(local) else { continue };
+			let name = &self.res.locals[*local].name;
+			types.locals.insert(*local, self.table.zonk(&scheme.ty));
+
+			// Generated names aren't anything someone would hover over.
+			let module = &self.tree.modules[*module];
+			if module.path[0] != "pkg" || name.name.contains('$') { continue }
+
+			types.hints.push(TypeHint {
+				file: module.file.clone(),
+				line: line_of(&module.source, name.span.start),
+				span: name.span.clone(),
+				name: name.name.clone(),
+				ty: self.show_scheme(scheme),
+			});
+		}
+
+		types
+	}
+}
+
+impl fmt::Display for TypeError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			TypeError::Mismatch { expected, found, .. } => write!(f, "mismatched types: expected `{}`, found `{}`", expected, found),
+			TypeError::NotCallable(ty) => write!(f, "`{}` isn't a function, so it can't be called", ty),
+			TypeError::ArgCount { expected, found } => write!(f, "expected {} argument{}, found {}", expected, if *expected == 1 { "" } else { "s" }, found),
+			TypeError::NoField { ty, field } => write!(f, "`{}` has no field `{}`", ty, field),
+			TypeError::NoMethod { ty, method } => write!(f, "`{}` has no method `{}`", ty, method),
+			TypeError::NotAValue(name) => write!(f, "`{}` isn't a value", name),
+			TypeError::NotAType(name) => write!(f, "`{}` isn't a type", name),
+			TypeError::GenericCount { name, expected, found } => write!(f, "`{}` takes {} generic argument{}, but was given {}", name, expected, if *expected == 1 { "" } else { "s" }, found),
+			TypeError::NotAnOperation(name) => write!(f, "`{}` isn't an operation of an effect", name),
+			TypeError::CantApply { op, ty } => write!(f, "`{}` can't be used on `{}`", op, ty),
+			TypeError::SignMismatch { expected, found } => write!(f, "signed and unsigned numbers can't be mixed: expected `{}`, found `{}`", expected, found),
+			TypeError::Overflow { lit, ty } => write!(f, "the literal `{}` doesn't fit in `{}`", lit, ty),
+			TypeError::NotAnEffect(name) => write!(f, "`{}` isn't an effect", name),
+			TypeError::Undeclared { effect, func: Some(func) } => write!(f, "`{}` performs `{}`, which it neither declares nor handles", func, effect),
+			TypeError::Undeclared { effect, func: None } => write!(f, "this closure performs `{}`, which it neither declares nor handles", effect),
+			TypeError::ReachesMain(effect) => write!(f, "`{}` is never handled, so it reaches `main`", effect),
+			TypeError::NotATrait(name) => write!(f, "`{}` isn't a trait", name),
+			TypeError::NotObjectSafe(name) => write!(f, "`{}` can't be made into a trait object", name),
+			TypeError::Unsatisfied { ty, trait_ } => write!(f, "`{}` doesn't implement `{}`", ty, trait_),
+			TypeError::MissingItem { trait_, item } => write!(f, "`{}` from `{}` isn't implemented", item, trait_),
+			TypeError::NoAssocType { ty, name } => write!(f, "`{}` has no associated type `{}`", ty, name),
+			TypeError::AmbiguousAssoc(name) => write!(f, "`{}` has to be used through a type which implements its trait", name),
+			TypeError::AssocTypeBody(name) => write!(f, "the associated type `{}` can only be given a type in impls of its trait", name),
+			TypeError::AssocTypeMissing(name) => write!(f, "the associated type `{}` needs to be given a type, like `type {} = nat`", name, name),
+			TypeError::MissingValue(name) => write!(f, "the constant `{}` needs a value, since only constants in traits can leave theirs to impls", name),
+			TypeError::NotInTrait { trait_, item } => write!(f, "`{}` isn't declared by `{}`, so it can't be in an impl of it", item, trait_),
+			TypeError::ImplMismatch { item, expected, found } => write!(f, "`{}` doesn't match its declaration in the trait: expected `{}`, found `{}`", item, expected, found),
+			TypeError::Overlap { trait_, ty } => write!(f, "conflicting impls of `{}` for `{}`", trait_, ty),
+			TypeError::DefaultOrder(name) => write!(f, "`{}` comes after a generic parameter with a default, so it needs one too", name),
+			TypeError::FuncDefault(name) => write!(f, "`{}` can't have a default, since a function's generic parameters are always inferred from how it's called", name),
+			TypeError::NonExhaustive(missing) => {
+				let missing: Vec<String> = missing.iter().map(|pat| format!("`{}`", pat)).collect();
+				write!(f, "this match doesn't cover every case, missing {}", missing.join(", "))
+			},
+			TypeError::Unreachable => write!(f, "this arm can never be reached, since the arms before it already cover everything it matches"),
+			TypeError::UnreachableCode => write!(f, "this code can never be reached"),
+			TypeError::PrivateField { field, ty, module } => write!(f, "the field `{}` of `{}` is private to module `{}`", field, ty, module),
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compiler::{
+		Options,
+		TestPackage,
+	};
+
+	/// Checks a package with a single file.
+	fn check_source(name: &str, source: &str) -> Result<(Types, ErrorList), ErrorList> {
+		check_files(name, &[("main.ro", source)])
+	}
+
+	fn check_files(name: &str, files: &[(&str, &str)]) -> Result<(Types, ErrorList), ErrorList> {
+		TestPackage::new(name, files).check(&Options::default()).map(|(package, warnings)| (package.types, warnings))
+	}
+
+	fn hints(name: &str, source: &str) -> Vec<(String, String)> {
+		let (types, _) = check_source(name, source).unwrap();
+		types.hints.into_iter().map(|hint| (hint.name, hint.ty)).collect()
+	}
+
+	fn type_errors(name: &str, source: &str) -> Vec<(Option<usize>, TypeError)> {
+		check_source(name, source).err().unwrap().into_iter()
+			.map(|e| match e.kind() {
+				ErrorKind::Interpret(InterpretError::Type(err)) => (e.line(), err),
+				kind => panic!("expected a type error, got {}", kind),
+			})
+			.collect()
+	}
+
+	#[test]
+	fn declarations_are_inferred() {
+		let hints = hints("inferred", "pub func main() do\n\tname := \"Ashton\"\n\tage: nat = 22\n\tjob := (title: \"Developer\", salary: 86_215)\n\tnext := age + 1\n\tnames := [name]\nend\n");
+
+		assert_eq!(hints, [
+			("name".to_string(), "str".to_string()),
+			("age".to_string(), "nat".to_string()),
+			("job".to_string(), "(salary: nat, title: str)".to_string()),
+			("next".to_string(), "nat".to_string()),
+			("names".to_string(), "[str]".to_string()),
+		]);
+	}
+
+	#[test]
+	fn types_come_from_later_uses() {
+		let hints = hints("later", "func half(n: int) -> int do\n\tn / 2\nend\n\npub func main() do\n\tmut x := 4\n\tx = half(x)\n\tfound := Some(x)\n\tnothing := None\n\tnothing == found\nend\n");
+
+		assert_eq!(hints[1..], [
+			("x".to_string(), "int".to_string()),
+			("found".to_string(), "Option<int>".to_string()),
+			("nothing".to_string(), "Option<int>".to_string()),
+		]);
+	}
+
+	#[test]
+	fn closures_are_generalized() {
+		let hints = hints("generalized", "pub func main() do\n\tsame := (x) do x\n\ta: nat = same(1)\n\tb: str = same(\"b\")\n\tplus_one := (x) do x + 1\nend\n");
+
+		let hint = |name: &str| hints.iter().find(|(n, _)| n == name).map(|(_, ty)| ty.as_str());
+
+		assert_eq!(hint("same"), Some("Func(A) -> A"));
+		assert_eq!(hint("a"), Some("nat"));
+		assert_eq!(hint("b"), Some("str"));
+		// Number literals aren't generalized, so they still end up as `nat`.
+		assert_eq!(hint("plus_one"), Some("Func(nat) -> nat"));
+	}
+
+	#[test]
+	fn mismatches_point_at_both_types() {
+		let source = "pub func main() do\n\tage: nat = \"twenty\"\nend\n";
+		let errors = check_source("mismatch", source).err().unwrap();
+
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].slice().as_deref(), Some("\"twenty\""));
+
+		let ErrorKind::Interpret(InterpretError::Type(TypeError::Mismatch { expected, found, expected_at: Some(at) })) = errors[0].kind() else { panic!("expected a mismatch") };
+		assert_eq!((expected.as_str(), found.as_str(), &source[at]), ("nat", "str", "nat"));
+	}
+
+	#[test]
+	fn calls_and_methods_are_checked() {
+		let errors = type_errors("calls", "type Point is\n\t| Point(int, int)\n\n\tpub func sum(self) -> int do\n\t\tif self matches Self::Point(x, y) then x + y else 0\n\tend\nend\n\npub func main() do\n\tp := Point::Point(1, 2)\n\ts: str = p.sum()\n\tp.nope()\n\tp.sum(1)\n\tp()\n\tm: Option<str> = Some(1).map((v) do v + 1)\nend\n");
+
+		assert!(matches!(&errors[0], (Some(11), TypeError::Mismatch { expected, found, .. }) if expected == "str" && found == "int"));
+		assert!(matches!(&errors[1], (Some(12), TypeError::NoMethod { method, .. }) if method == "nope"));
+		assert!(matches!(&errors[2], (Some(13), TypeError::ArgCount { expected: 0, found: 1 })));
+		assert!(matches!(&errors[3], (Some(14), TypeError::NotCallable(ty)) if ty == "Point"));
+		assert!(matches!(&errors[4], (Some(15), TypeError::Mismatch { expected, .. }) if expected == "Option<str>"));
+		assert_eq!(errors.len(), 5);
+	}
+
+	#[test]
+	fn builtin_types_only_have_what_std_declares() {
+		let hints = hints("builtin", "pub func main() do\n\tmut list := [1, 2]\n\tlist.push_back(3)\n\tsize := list.len()\n\tchars := \"abc\".iter()\n\ttotal := list.iter().map((n) do n * 2).sum()\n\tparsed := int::from_str(\"-4\")\nend\n");
+		let hint = |name: &str| hints.iter().find(|(n, _)| n == name).map(|(_, ty)| ty.as_str());
+
+		assert_eq!(hint("size"), Some("nat"));
+		assert_eq!(hint("chars"), Some("Iter<char>"));
+		assert_eq!(hint("total"), Some("nat"));
+		assert_eq!(hint("parsed"), Some("Result<int, ParseNumError>"));
+
+		let errors = type_errors("not-builtin", "pub func main() do\n\tx: str = 5.nope()\n\t\"s\".bogus(1, 2).more()\n\ty := true.whatever\n\tz := [1].first\nend\n");
+
+		assert!(matches!(&errors[0], (Some(2), TypeError::NoMethod { ty, method }) if ty == "nat" && method == "nope"));
+		assert!(matches!(&errors[1], (Some(3), TypeError::NoMethod { ty, method }) if ty == "str" && method == "bogus"));
+		assert!(matches!(&errors[2], (Some(4), TypeError::NoField { ty, field }) if ty == "bool" && field == "whatever"));
+		assert!(matches!(&errors[3], (Some(5), TypeError::NoField { ty, field }) if ty == "[{number}]" && field == "first"));
+		assert_eq!(errors.len(), 4);
+	}
+
+	#[test]
+	fn for_loops_go_through_each_value() {
+		let hints = hints("for", "pub func main() do\n\tfor n in [1, 2] do first := n\n\tfor c in \"abc\" do second := c\n\tfor i in 0..10 do third := i\n\tfor (k, v) in [\"one\": 1.0] do fourth := v\n\tfor x in [true].iter() do fifth := x\nend\n");
+		let hint = |name: &str| hints.iter().find(|(n, _)| n == name).map(|(_, ty)| ty.as_str());
+
+		assert_eq!(hint("first"), Some("nat"));
+		assert_eq!(hint("second"), Some("char"));
+		assert_eq!(hint("third"), Some("nat"));
+		assert_eq!(hint("fourth"), Some("flo"));
+		assert_eq!(hint("fifth"), Some("bool"));
+
+		let errors = type_errors("bad-for", "pub func main() do\n\tfor n in [1, 2, 3] do\n\t\ts: str = n\n\tend\n\tfor m in 5 do\n\t\toutl(\"\\{m}\")\n\tend\nend\n");
+
+		assert!(matches!(&errors[0], (Some(3), TypeError::Mismatch { expected, found, .. }) if expected == "str" && found == "{number}"));
+		assert!(matches!(&errors[1], (Some(5), TypeError::NoMethod { ty, method }) if ty == "nat" && method == "each"));
+		assert_eq!(errors.len(), 2);
+	}
+
+	#[test]
+	fn literals_must_fit_their_type() {
+		let hints = hints("literals", "pub func main() do\n\tlow: int = -9223372036854775808\n\tratio: flo = 3\n\tneg := -5\nend\n");
+		assert_eq!(hints[1..], [("ratio".to_string(), "flo".to_string()), ("neg".to_string(), "int".to_string())]);
+
+		let errors = type_errors("overflow", "pub func main() do\n\tsmall: byte = 300\n\tbig: int = 9223372036854775808\n\tcount: nat = -1\nend\n");
+		assert!(matches!(&errors[0], (Some(4), TypeError::SignMismatch { expected, .. }) if expected == "nat"));
+		assert!(matches!(&errors[1], (Some(2), TypeError::Overflow { lit, ty }) if lit == "300" && ty == "byte"));
+		assert!(matches!(&errors[2], (Some(3), TypeError::Overflow { ty, .. }) if ty == "int"));
+		assert_eq!(errors.len(), 3);
+	}
+
+	#[test]
+	fn signed_and_unsigned_dont_mix() {
+		let source = "func half(n: int) -> int do\n\tn / 2\nend\n\npub func main() do\n\tcount: nat = 10\n\thalf(count)\n\thalf(count.to_int().unwrap_or(0))\n\t-count\n\tcount.to_flo() + 0.5\nend\n";
+		let errors = check_source("signs", source).err().unwrap();
+
+		assert_eq!(errors.len(), 2);
+		assert!(matches!(errors[0].kind(), ErrorKind::Interpret(InterpretError::Type(TypeError::SignMismatch { expected, found })) if expected == "int" && found == "nat"));
+		assert!(errors[0].notes().iter().any(|note| note.contains("`count.to_int()`")));
+		assert!(matches!(errors[1].kind(), ErrorKind::Interpret(InterpretError::Type(TypeError::CantApply { op: "-", .. }))));
+	}
+
+	/// An effect with one operation, a function which performs it, and a higher-order function which passes on whatever it's given.
+	const EFFECTS: &str = "effect Log is\n\tfunc log(message: str)\nend\n\nfunc noisy(n: nat) -> nat -< Log do\n\tLog::log(\"noisy\")\n\tn\nend\n\nfunc twice<F>(f: F, n: nat) -> nat where\n\tF: Func(nat) -> nat\ndo\n\tf(f(n))\nend\n\n";
+
+	#[test]
+	fn effects_are_checked_against_declarations() {
+		let source = format!("{}func quiet(n: nat) -> nat do\n\tnoisy(n)\nend\n\nfunc passed(n: nat) -> nat do\n\ttwice((x) do noisy(x), n)\nend\n\nfunc declared(n: nat) -> nat -< Log do\n\ttwice((x) do noisy(x), n)\nend\n\nfunc handled(n: nat) -> nat do\n\twhen Log::log(_) do\n\t\terrl(\"logged\")\n\tin\n\t\tnoisy(n)\n\tend\nend\n\npub func main() do\n\tnoisy(1)\nend\n", EFFECTS);
+		let errors = type_errors("effects", &source);
+
+		assert!(matches!(&errors[0], (Some(17), TypeError::Undeclared { effect, func: Some(func) }) if effect == "Log" && func == "quiet"));
+		assert!(matches!(&errors[1], (Some(21), TypeError::Undeclared { func: Some(func), .. }) if func == "passed"));
+		assert!(matches!(&errors[2], (Some(37), TypeError::ReachesMain(effect)) if effect == "Log"));
+		assert_eq!(errors.len(), 3);
+	}
+
+	#[test]
+	fn unhandled_effects_name_their_arguments() {
+		let source = "type Oops is\n\t| Bad\nend\n\nimpl Error for Oops is\nend\n\nfunc fail() -> nat -< Exception<Oops> do\n\tException::throw(Oops::Bad)\nend\n\nfunc thrown() -> nat do\n\tException::throw(Oops::Bad)\nend\n\nfunc parse(text: str) -> Result<nat, Oops> do\n\tOk(text.len())\nend\n\nfunc passed(text: str) -> nat do\n\tparse(text)?\nend\n\npub func main() do\n\tfail()\nend\n";
+		let errors = type_errors("unhandled", source);
+
+		assert!(matches!(&errors[0], (Some(13), TypeError::Undeclared { effect, func: Some(func) }) if effect == "Exception<Oops>" && func == "thrown"));
+		assert!(matches!(&errors[1], (Some(21), TypeError::Undeclared { effect, func: Some(func) }) if effect == "Exception<Oops>" && func == "passed"));
+		assert!(matches!(&errors[2], (Some(25), TypeError::ReachesMain(effect)) if effect == "Exception<Oops>"));
+		assert_eq!(errors.len(), 3);
+	}
+
+	#[test]
+	fn closures_infer_their_effects() {
+		let hints = hints("closure-effects", &format!("{}pub func main() do\n\tloud := (n: nat) do noisy(n)\n\tsilent := (n: nat) do n + 1\n\twhen Log::log(_) do\n\t\terrl(\"logged\")\n\tin\n\t\ttwice(loud, 2)\n\t\ttwice(silent, 2)\n\tend\nend\n", EFFECTS));
+		let hint = |name: &str| hints.iter().find(|(n, _)| n == name).map(|(_, ty)| ty.as_str());
+
+		assert_eq!(hint("loud"), Some("Func(nat) -> nat -< Log"));
+		assert_eq!(hint("silent"), Some("Func(nat) -> nat"));
+	}
+
+	/// A trait with a default method, a trait building on it, and a type which implements the first.
+	const SHAPES: &str = "trait Shape is\n\tfunc area(self) -> nat\n\n\tfunc double(self) -> nat do\n\t\tself.area() * 2\n\tend\nend\n\ntrait Solid: Shape is\n\tfunc volume(self) -> nat\nend\n\ntype Square is\n\t| Square(nat)\nend\n\nimpl Shape for Square is\n\tfunc area(self) -> nat do\n\t\tif self matches Square::Square(side) then side * side else 0\n\tend\nend\n\n";
+
+	#[test]
+	fn impls_match_their_traits() {
+		let source = format!("{}impl Shape for Square is\n\tfunc area(self) -> str do\n\t\t\"big\"\n\tend\n\n\tfunc extra(self) do\n\tend\nend\n\nimpl Solid for Square is\nend\n\npub func main() do\nend\n", SHAPES);
+		let errors = type_errors("impls", &source);
+
+		assert!(matches!(&errors[0], (Some(24), TypeError::ImplMismatch { item, expected, found }) if item == "area" && expected == "Func(Square) -> nat" && found == "Func(Square) -> str"));
+		assert!(matches!(&errors[1], (Some(28), TypeError::NotInTrait { item, .. }) if item == "extra"));
+		assert!(matches!(&errors[2], (Some(32), TypeError::MissingItem { trait_, item }) if trait_ == "Solid" && item == "volume"));
+		assert!(matches!(&errors[3], (Some(23), TypeError::Overlap { trait_, ty }) if trait_ == "Shape" && ty == "Square"));
+		assert_eq!(errors.len(), 4);
+	}
+
+	#[test]
+	fn bounds_give_methods_and_are_checked() {
+		let source = format!("{}func make<T: Default>() -> T do\n\tT::default()\nend\n\nfunc both<S>(s: S) -> nat where\n\tS: Solid\ndo\n\ts.volume() + s.double()\nend\n\nfunc unbounded<T>(t: T) -> nat do\n\tt.area()\nend\n\npub func main() do\n\tsquare := Square::Square(3)\n\tdoubled := square.double()\n\tnumber: nat = make()\n\tlist: [nat] = make()\n\tboth(square)\nend\n", SHAPES);
+		let errors = type_errors("bounds", &source);
+
+		assert!(matches!(&errors[0], (Some(34), TypeError::NoMethod { ty, method }) if ty == "T" && method == "area"));
+		assert!(matches!(&errors[1], (Some(41), TypeError::Unsatisfied { ty, trait_ }) if ty == "[nat]" && trait_ == "Default"));
+		assert!(matches!(&errors[2], (Some(42), TypeError::Unsatisfied { ty, trait_ }) if ty == "Square" && trait_ == "Solid"));
+		assert_eq!(errors.len(), 3);
+	}
+
+	#[test]
+	fn type_arguments_are_checked_against_bounds() {
+		let source = "type Failure is\n\t| Failure\nend\n\nimpl Error for Failure is\nend\n\nfunc fine<E: Error>(r: Result<nat, E>) -> nat do\n\t0\nend\n\nfunc loose<E>(r: Result<nat, E>) -> nat do\n\t0\nend\n\nfunc throws() -< Exception<str> do\nend\n\npub func main() do\n\tgood: Result<nat, Failure> = Ok(1)\n\tbad: Result<nat, nat> = Ok(2)\n\tfine(good)\nend\n";
+		let mut errors = type_errors("well-formed", source);
+		errors.sort_by_key(|(line, _)| *line);
+
+		assert!(matches!(&errors[0], (Some(12), TypeError::Unsatisfied { ty, trait_ }) if ty == "E" && trait_ == "Error"));
+		assert!(matches!(&errors[1], (Some(16), TypeError::Unsatisfied { ty, trait_ }) if ty == "str" && trait_ == "Error"));
+		assert!(matches!(&errors[2], (Some(21), TypeError::Unsatisfied { ty, trait_ }) if ty == "nat" && trait_ == "Error"));
+		assert_eq!(errors.len(), 3);
+	}
+
+	#[test]
+	fn generic_methods_work_at_any_type() {
+		let hints = hints("map", "pub func main() do\n\tnumber := Some(1).map((n) do n + 1)\n\ttext := Some(1).map((n) do \"one\")\n\tlist := Some(\"a\").map((s) do [s, s])\n\tflag := Some(-1).map((n) do n < 0)\n\tnested := Some(Some(2.5)).map((inner) do inner.map((x) do x * 2.0))\nend\n");
+		let hint = |name: &str| hints.iter().find(|(n, _)| n == name).map(|(_, ty)| ty.as_str());
+
+		assert_eq!(hint("number"), Some("Option<nat>"));
+		assert_eq!(hint("text"), Some("Option<str>"));
+		assert_eq!(hint("list"), Some("Option<[str]>"));
+		assert_eq!(hint("flag"), Some("Option<bool>"));
+		assert_eq!(hint("nested"), Some("Option<Option<flo>>"));
+	}
+
+	#[test]
+	fn defaults_fill_in_generic_arguments() {
+		let hints = hints("defaults", "type Pair<A, B = A> is\n\t| Pair(A, B)\nend\n\npub func main() do\n\tsame: Pair<nat> = Pair::Pair(1, 2)\n\tmixed: Pair<nat, str> = Pair::Pair(1, \"two\")\nend\n");
+		assert_eq!(hints, [("same".to_string(), "Pair<nat, nat>".to_string()), ("mixed".to_string(), "Pair<nat, str>".to_string())]);
+
+		let errors = type_errors("bad-defaults", "type Pair<A = nat, B> is\n\t| Pair(A, B)\nend\n\nfunc pick<T = nat>(t: T) -> T do\n\tt\nend\n\ntype Triple<A, B = A, C = B> is\n\t| Triple(A, B, C)\nend\n\npub func main() do\n\tt: Triple = Triple::Triple(1, 2, 3)\nend\n");
+		assert!(matches!(&errors[0], (Some(1), TypeError::DefaultOrder(name)) if name == "B"));
+		assert!(matches!(&errors[1], (Some(5), TypeError::FuncDefault(name)) if name == "T"));
+		assert!(matches!(&errors[2], (Some(14), TypeError::GenericCount { expected: 3, found: 0, .. })));
+		assert_eq!(errors.len(), 3);
+	}
+
+	#[test]
+	fn trait_defaults_are_trait_objects() {
+		let source = "use std::error::[AssertionFailed, Exception]\n\nfunc fails() -< Exception do\n\tException::throw(AssertionFailed::new(\"no\"))\nend\n\nfunc throws_a_number() -< Exception do\n\tException::throw(5)\nend\n\nfunc shout(s: str) do\nend\n\npub func main() do\n\twhen Exception::throw(e) do\n\t\tshout(e)\n\tin\n\t\tfails()\n\tend\nend\n";
+		let mut errors = type_errors("trait-defaults", source);
+		errors.sort_by_key(|(line, _)| *line);
+
+		// Throwing anything which implements `Error` is fine, but a number isn't an error, and what's caught isn't a `str`.
+		assert!(matches!(&errors[0], (Some(8), TypeError::Unsatisfied { ty, trait_ }) if ty == "nat" && trait_ == "Error"));
+		assert!(matches!(&errors[1], (Some(16), TypeError::Mismatch { expected, found, .. }) if expected == "str" && found == "dyn Error"));
+		assert_eq!(errors.len(), 2);
+	}
+
+	#[test]
+	fn never_fits_anywhere_but_is_only_made_by_diverging() {
+		let hints = hints("never", "use std::error::[Exception, AssertionFailed, OptionError]\n\nfunc first(o: Option<str>) -> str -< Exception<AssertionFailed> do\n\tif o matches\n\t\tSome(s) then s\n\t\tNone then Exception::throw(AssertionFailed::new(\"empty\"))\n\tend\nend\n\nfunc unwrapped() -< Exception<OptionError> do\n\tn := Some(1).unwrap()\nend\n\nfunc thrown() -< Exception<AssertionFailed> do\n\ts := first(None)\nend\n\npub func main() do\nend\n");
+		assert_eq!(hints[2..], [("n".to_string(), "nat".to_string()), ("s".to_string(), "str".to_string())]);
+
+		let source = "func early(flag: bool) -> nat do\n\tif flag then return 1 else return 2\n\tx := 3\n\tx\nend\n\nfunc spin() -> nat do\n\talways\n\t\tcontinue\n\tend\n\terrl(\"never\")\nend\n\nfunc fails() -> ! do\n\t\"oops\"\nend\n\npub func main() do\n\tn := 1\n\tx := return\n\tn + 1\nend\n";
+		let errors = type_errors("diverging", source);
+
+		assert!(matches!(&errors[0], (Some(3), TypeError::UnreachableCode)), "{:?}", errors);
+		assert!(matches!(&errors[1], (Some(11), TypeError::UnreachableCode)));
+		assert!(matches!(&errors[2], (Some(15), TypeError::Mismatch { expected, found, .. }) if expected == "!" && found == "str"));
+		assert!(matches!(&errors[3], (Some(21), TypeError::UnreachableCode)));
+		assert_eq!(errors.len(), 4);
+	}
+
+	/// A record type and a generic tuple type, each with methods.
+	const SHAPES_AND_PAIRS: &str = "type Point is\n\tpub x: int\n\tpub y: int\n\n\tpub func origin() -> Point do\n\t\tPoint(x: 0, y: 0)\n\tend\n\n\tpub func sum(self) -> int do\n\t\tself.x + self.y\n\tend\nend\n\ntype Pair<A, B> is\n\t(A, B)\n\n\tpub func swap(self) -> Pair<B, A> do\n\t\tPair(self.1, self.0)\n\tend\nend\n\n";
+
+	#[test]
+	fn records_and_tuple_types_have_fields() {
+		let source = format!("{}pub func main() do\n\tpoint := Point(x: 1, y: -2)\n\ttotal := point.sum()\n\torigin := Point::origin()\n\tswapped := Pair(1, \"one\").swap()\n\tfirst := swapped.0\n\tsecond := if swapped matches Pair(_, n) then n else 0\nend\n", SHAPES_AND_PAIRS);
+		let hints = hints("records", &source);
+		let hint = |name: &str| hints.iter().find(|(n, _)| n == name).map(|(_, ty)| ty.as_str());
+
+		assert_eq!(hint("point"), Some("Point"));
+		assert_eq!(hint("total"), Some("int"));
+		assert_eq!(hint("origin"), Some("Point"));
+		assert_eq!(hint("swapped"), Some("Pair<str, nat>"));
+		assert_eq!(hint("first"), Some("str"));
+		assert_eq!(hint("second"), Some("nat"));
+
+		let source = format!("{}pub func main() do\n\tpoint := Point(x: 1)\n\tPoint(x: 1, y: 2).z\n\tPair(1, 2).2\nend\n", SHAPES_AND_PAIRS);
+		let errors = type_errors("bad-records", &source);
+
+		assert!(matches!(&errors[0], (Some(23), TypeError::Mismatch { expected, .. }) if expected == "(x: int, y: int)"));
+		assert!(matches!(&errors[1], (Some(24), TypeError::NoField { ty, field }) if ty == "Point" && field == "z"));
+		assert!(matches!(&errors[2], (Some(25), TypeError::NoField { ty, field }) if ty.starts_with("Pair<") && field == "2"));
+		assert_eq!(errors.len(), 3);
+	}
+
+	#[test]
+	fn private_fields_stay_in_their_module() {
+		let errors = check_files("private-fields", &[
+			("main.ro", "use self::secret::Secret\n\nfunc peek(s: Secret) -> nat do\n\ts.hidden + s.shown\nend\n\npub func main() do\n\ts := Secret(hidden: 1, shown: 2)\nend\n"),
+			("secret.ro", "pub type Secret is\n\thidden: nat\n\tpub shown: nat\n\n\tpub func new() -> Secret do\n\t\tSecret(hidden: 1, shown: 2)\n\tend\nend\n"),
+		]).err().unwrap();
+
+		let private: Vec<(Option<usize>, String)> = errors.iter()
+			.map(|e| match e.kind() {
+				ErrorKind::Interpret(InterpretError::Type(TypeError::PrivateField { field, module, .. })) if module == "pkg::secret" => (e.line(), field),
+				kind => panic!("expected a private field, got {}", kind),
+			})
+			.collect();
+
+		assert_eq!(private, [(Some(4), "hidden".to_string()), (Some(8), "hidden".to_string())]);
+	}
+
+	const MONEY: &str = "use std::ops::*\n\ntype Money is\n\t(nat)\nend\n\nimpl Add for Money is\n\tfunc add(self, rhs: Money) -> Money do\n\t\tMoney(self.0 + rhs.0)\n\tend\nend\n\nimpl Mul<nat> for Money is\n\tfunc mul(self, rhs: nat) -> Money do\n\t\tMoney(self.0 * rhs)\n\tend\nend\n\nimpl DivisibleBy<nat> for Money is\n\tfunc divisible_by(self, rhs: nat) -> bool do\n\t\tself.0 %% rhs\n\tend\nend\n\nimpl AddAssign for Money is\n\tfunc add_assign(mut self, rhs: Money) do\n\t\tself.0 += rhs.0\n\tend\nend\n\nfunc total<T: Add>(a: T, b: T) -> T do\n\ta + b\nend\n\n";
+
+	#[test]
+	fn operators_go_through_std_ops_traits() {
+		let source = format!("{}pub func main() do\n\tsum := Money(1) + Money(2)\n\ttripled := sum * 3\n\teven := sum %% 2\n\tboth := total(sum, tripled)\n\tmut wallet := sum\n\twallet += tripled\nend\n", MONEY);
+		let hints = hints("operators", &source);
+		let hint = |name: &str| hints.iter().find(|(n, _)| n == name).map(|(_, ty)| ty.as_str());
+
+		assert_eq!(hint("sum"), Some("Money"));
+		assert_eq!(hint("tripled"), Some("Money"));
+		assert_eq!(hint("even"), Some("bool"));
+		assert_eq!(hint("both"), Some("Money"));
+
+		let source = format!("{}pub func main() do\n\tMoney(1) - Money(2)\n\tMoney(1) * Money(2)\n\tmut wallet := Money(1)\n\twallet -= Money(1)\n\tflag: nat = Money(1) %% 2\nend\n", MONEY);
+		let errors = type_errors("bad-operators", &source);
+
+		assert!(matches!(&errors[0], (Some(36), TypeError::CantApply { op: "-", ty }) if ty == "Money"));
+		assert!(matches!(&errors[1], (Some(37), TypeError::CantApply { op: "*", ty }) if ty == "Money"));
+		assert!(matches!(&errors[2], (Some(39), TypeError::CantApply { op: "-=", ty }) if ty == "Money"));
+		assert!(matches!(&errors[3], (Some(40), TypeError::Mismatch { expected, found, .. }) if expected == "nat" && found == "bool"));
+		assert_eq!(errors.len(), 4);
+	}
+
+	const COMPARED: &str = "use std::ops::*\n\ntype Plain is\n\t| Plain\nend\n\ntype Money is\n\t(nat)\nend\n\nimpl Eq for Money is\n\tfunc eq(self, rhs: Money) -> bool do\n\t\tself.0 == rhs.0\n\tend\nend\n\nfunc double(n: nat) -> nat do\n\tn * 2\nend\n\nfunc show(n: nat) -> str do\n\t\"\\{n}\"\nend\n\n";
+
+	#[test]
+	fn comparisons_need_comparable_types() {
+		let source = format!("{}pub func main() do\n\tsame := Money(1) == Money(2)\n\tdiffer := Money(1) != Money(2)\n\tlists := [Some(1)] == [None]\n\tordered := [(1, \"a\")] < [(2, \"b\")]\n\tcomposed := double >=> show\nend\n", COMPARED);
+		let hints = hints("compared", &source);
+		let hint = |name: &str| hints.iter().find(|(n, _)| n == name).map(|(_, ty)| ty.as_str());
+
+		assert_eq!(hint("same"), Some("bool"));
+		assert_eq!(hint("differ"), Some("bool"));
+		assert_eq!(hint("lists"), Some("bool"));
+		assert_eq!(hint("ordered"), Some("bool"));
+		assert_eq!(hint("composed"), Some("Func(nat) -> str"));
+
+		let source = format!("{}pub func main() do\n\tPlain::Plain == Plain::Plain\n\tMoney(1) < Money(2)\n\tdouble == double\n\t[Plain::Plain] == []\n\t[1: 2] < [3: 4]\n\tshow >=> double\nend\n", COMPARED);
+		let errors = type_errors("incomparable", &source);
+
+		assert!(matches!(&errors[0], (Some(26), TypeError::CantApply { op: "==", ty }) if ty == "Plain"));
+		assert!(matches!(&errors[1], (Some(27), TypeError::CantApply { op: "<", ty }) if ty == "Money"));
+		assert!(matches!(&errors[2], (Some(28), TypeError::CantApply { op: "==", ty }) if ty == "Func(nat) -> nat"));
+		assert!(matches!(&errors[3], (Some(30), TypeError::CantApply { op: "<", .. })));
+		assert!(matches!(&errors[4], (Some(31), TypeError::Mismatch { .. })));
+		assert!(matches!(&errors[5], (Some(29), TypeError::Unsatisfied { ty, trait_ }) if ty == "Plain" && trait_ == "Eq<Plain>"));
+		assert_eq!(errors.len(), 6);
+	}
+
+	const WIDGETS: &str = "trait Named is\n\tfunc name(self) -> str do\n\t\t\"unnamed\"\n\tend\nend\n\ntrait Widget: Named is\n\tfunc width(self) -> nat\nend\n\ntype Button is\n\t(nat)\nend\n\ntype Label is\n\t(str)\nend\n\nimpl Named for Button is\nend\n\nimpl Widget for Button is\n\tfunc width(self) -> nat do\n\t\tself.0\n\tend\nend\n\nimpl Named for Label is\n\tfunc name(self) -> str do\n\t\tself.0\n\tend\nend\n\nimpl Widget for Label is\n\tfunc width(self) -> nat do\n\t\t4\n\tend\nend\n\n";
+
+	#[test]
+	fn impls_cover_lists_and_tuples() {
+		let source = "trait Show is\n\tfunc show(self) -> str\nend\n\nimpl Show for nat is\n\tfunc show(self) -> str do\n\t\t\"\\{self}\"\n\tend\nend\n\nimpl<T: Show> Show for [T] is\n\tfunc show(self) -> str do\n\t\t\"a list\"\n\tend\nend\n\nimpl<A: Show, B: Show> Show for (A, B) is\n\tfunc show(self) -> str do\n\t\t\"\\{self.0.show()}, \\{self.1.show()}\"\n\tend\nend\n\nfunc print<T: Show>(t: T) -> str do\n\tt.show()\nend\n\npub func main() do\n\tprint([1, 2])\n\tprint((1, [2]))\n\tshown := [3].show()\n\tprint([\"no\"])\nend\n";
+		let errors = type_errors("structural", source);
+
+		assert!(matches!(&errors[..], [(Some(31), TypeError::Unsatisfied { ty, trait_ })] if ty == "[str]" && trait_ == "Show"));
+	}
+
+	#[test]
+	fn trait_objects_hold_anything_implementing_their_trait() {
+		let source = format!("{}func first<T: Widget>(w: T) -> dyn Widget do\n\tw\nend\n\npub func main() do\n\twidgets: [dyn Widget] = [Button(2), Label(\"ok\"), Button(3)]\n\twidth := widgets[0].width()\n\tname := widgets[1].name()\n\tone := first(Label(\"one\"))\nend\n", WIDGETS);
+		let (types, _) = check_source("objects", &source).unwrap();
+		let hint = |name: &str| types.hints.iter().find(|hint| hint.name == name).map(|hint| hint.ty.as_str());
+
+		assert_eq!(hint("widgets"), Some("[dyn Widget]"));
+		assert_eq!(hint("width"), Some("nat"));
+		assert_eq!(hint("name"), Some("str"));
+		assert_eq!(hint("one"), Some("dyn Widget"));
+
+		// Each vtable has `width` and then `name`, from the supertrait. `Button` leaves `name` to the trait's default, and `Label` doesn't.
+		let mut vtables: Vec<(&Span, &Vec<DefId>)> = types.vtables.iter().map(|((_, span), vtable)| (span, vtable)).collect();
+		vtables.sort_by_key(|(span, _)| span.start);
+		let vtables: Vec<&Vec<DefId>> = vtables.into_iter().map(|(_, vtable)| vtable).collect();
+
+		assert_eq!(vtables.len(), 4);
+		assert!(vtables.iter().all(|vtable| vtable.len() == 2));
+		assert_eq!(vtables[1], vtables[3]);
+		assert!(vtables[1][0] != vtables[2][0] && vtables[1][1] != vtables[2][1]);
+		// Which impl a generic parameter has isn't known until the program runs, so its vtable is the trait's declarations,
+		// which is also what `Button` has for `name`.
+		assert_eq!(vtables[0][1], vtables[1][1]);
+		assert!(vtables[0][0] != vtables[1][0] && vtables[0][0] != vtables[2][0]);
+	}
+
+	#[test]
+	fn only_some_traits_make_trait_objects() {
+		let source = format!("{}use std::ops::Add\n\ntrait Shape is\n\tfunc new() -> Self\n\tfunc scaled(self, by: nat) -> Self\n\tfunc visit<T>(self, with: T)\nend\n\ntrait Summable: Add is\nend\n\npub func main() do\n\tshape: dyn Shape = Button(1)\n\tsum: dyn Summable = Button(1)\n\tplus: dyn Add = Button(1)\n\tnumber: dyn Widget = 1\nend\n", WIDGETS);
+		let errors = check_source("unsafe-objects", &source).err().unwrap();
+		let mut errors: Vec<(Option<usize>, String, &[String])> = errors.iter().map(|e| (e.line(), e.kind().to_string(), e.notes())).collect();
+		errors.sort_by_key(|(line, _, _)| *line);
+
+		assert!(matches!(&errors[..], [
+			(Some(52), a, [new, scaled, visit]),
+			(Some(52), _, _),
+			(Some(53), b, [summable]),
+			(Some(53), _, _),
+			(Some(54), c, [_]),
+			(Some(55), d, [_]),
+		] if a == "`Shape` can't be made into a trait object" && new.contains("`new` doesn't take `self`") && scaled.contains("`scaled` uses `Self`") && visit.contains("`visit` is generic")
+			&& b == "`Summable` can't be made into a trait object" && summable.contains("`Add<Self, Self>`")
+			&& c == "`Add` can't be made into a trait object" && d == "`nat` doesn't implement `Widget`"), "{:?}", errors);
+	}
+
+	const SOURCES: &str = "trait Show is\n\tfunc show(self) -> str\nend\n\nimpl Show for nat is\n\tfunc show(self) -> str do\n\t\t\"n\"\n\tend\nend\n\ntrait Source is\n\ttype Item\n\tconst LIMIT: nat\n\tconst STEP: nat = 1\n\n\tfunc next(mut self) -> Option<Self::Item>\nend\n\ntype Counter is\n\t(nat)\nend\n\nimpl Source for Counter is\n\ttype Item = nat\n\tconst LIMIT := 10\n\n\tfunc next(mut self) -> Option<nat> do\n\t\tNone\n\tend\nend\n\ntype Words is\n\t(str)\nend\n\nimpl Source for Words is\n\ttype Item = str\n\tconst LIMIT: nat = 3\n\n\tfunc next(mut self) -> Option<Self::Item> do\n\t\tSome(self.0)\n\tend\nend\n\nfunc first<S: Source>(mut source: S) -> Option<S::Item> do\n\tsource.next()\nend\n\nfunc describe<S: Source>(mut source: S) -> str where S::Item: Show do\n\tif source.next() matches\n\t\tSome(item) then item.show()\n\t\tNone then \"\"\n\tend\nend\n\n";
+
+	#[test]
+	fn associated_types_come_from_impls() {
+		let source = format!("{}func limit<S: Source>(source: S) -> nat do\n\tS::LIMIT + S::STEP\nend\n\nfunc peek<S: Source>(mut source: S) do\n\tnext := source.next()\nend\n\npub func main() do\n\tcounted := first(Counter(1))\n\tworded := first(Words(\"a\"))\n\tgiven: Counter::Item = 3\n\tsum := Counter::LIMIT + Words::STEP + limit(Counter(0))\n\tshown := describe(Counter(2))\nend\n", SOURCES);
+		let (types, _) = check_source("assoc", &source).unwrap();
+		let hint = |name: &str| types.hints.iter().find(|hint| hint.name == name).map(|hint| hint.ty.as_str());
+
+		assert_eq!(hint("next"), Some("Option<S::Item>"));
+		assert_eq!(hint("counted"), Some("Option<nat>"));
+		assert_eq!(hint("worded"), Some("Option<str>"));
+		assert_eq!(hint("given"), Some("nat"));
+		assert_eq!(hint("sum"), Some("nat"));
+		assert_eq!(hint("shown"), Some("str"));
+	}
+
+	#[test]
+	fn associated_items_have_to_line_up() {
+		let source = format!("{}trait Broken is\n\ttype Given = nat\nend\n\nimpl Source for bool is\n\ttype Item\nend\n\nimpl Source for str is\n\ttype Item = str\n\tconst LIMIT: str = \"none\"\n\n\tfunc next(mut self) -> Option<str> do\n\t\tNone\n\tend\nend\n\nconst ORPHAN: nat\n\nfunc nope<S: Source>(s: S) -> S::Nope do\nend\n\npub func main() do\n\tloose: Source::Item = 1\n\twrong: Option<str> = first(Counter(1))\n\tdescribe(Words(\"a\"))\nend\n", SOURCES);
+		let errors = check_source("assoc-errors", &source).err().unwrap();
+		let mut errors: Vec<(Option<usize>, String)> = errors.iter().filter(|e| !e.is_warning()).map(|e| (e.line(), e.kind().to_string())).collect();
+		errors.sort();
+
+		assert_eq!(errors, [
+			(Some(57), "the associated type `Given` can only be given a type in impls of its trait".to_string()),
+			(Some(60), "`LIMIT` from `Source` isn't implemented".to_string()),
+			(Some(60), "`next` from `Source` isn't implemented".to_string()),
+			(Some(61), "the associated type `Item` needs to be given a type, like `type Item = nat`".to_string()),
+			(Some(66), "`LIMIT` doesn't match its declaration in the trait: expected `nat`, found `str`".to_string()),
+			(Some(73), "the constant `ORPHAN` needs a value, since only constants in traits can leave theirs to impls".to_string()),
+			(Some(75), "`S` has no associated type `Nope`".to_string()),
+			(Some(79), "`Source::Item` has to be used through a type which implements its trait".to_string()),
+			(Some(80), "mismatched types: expected `Option<str>`, found `Option<nat>`".to_string()),
+			(Some(81), "`str` doesn't implement `Show`".to_string()),
+		]);
+	}
+
+	#[test]
+	fn matches_have_to_cover_everything() {
+		let source = "func nested(r: Option<Result<nat, dyn Error>>) -> nat do\n\tif r matches\n\t\tSome(Ok(n)) then n\n\t\tNone then 0\n\tend\nend\n\nfunc pairs(p: (bool, bool)) -> nat do\n\tif p matches\n\t\t(true, _) then 1\n\t\t(_, false) then 2\n\tend\nend\n\nfunc digits(n: byte) -> str do\n\tif n matches\n\t\t0 then \"zero\"\n\t\t1..=9 then \"one\"\n\t\t20..=255 then \"lots\"\n\tend\nend\n\nfunc fine(r: Result<nat, dyn Error>) -> nat do\n\tif r matches\n\t\tOk(n) then n\n\t\tErr(_) then 0\n\tend\nend\n\npub func main() do\n\tr: Result<nat, dyn Error> = Ok(1)\n\tn := if r matches\n\t\tOk(n) then n\n\tend\nend\n";
+		let errors = check_source("exhaustive", source).err().unwrap();
+
+		let missing: Vec<(Option<usize>, Option<String>, Vec<String>)> = errors.iter()
+			.filter(|e| !e.is_warning())
+			.map(|e| match e.kind() {
+				ErrorKind::Interpret(InterpretError::Type(TypeError::NonExhaustive(missing))) => (e.line(), e.slice(), missing),
+				kind => panic!("expected a missing case, got {}", kind),
+			})
+			.collect();
+
+		assert_eq!(missing, [
+			(Some(2), Some("matches".to_string()), vec!["Some(Err(_))".to_string()]),
+			(Some(9), Some("matches".to_string()), vec!["(false, true)".to_string()]),
+			(Some(16), Some("matches".to_string()), vec!["10..=19".to_string()]),
+			(Some(32), Some("matches".to_string()), vec!["Err(_)".to_string()]),
+		]);
+	}
+
+	#[test]
+	fn half_open_ranges_go_to_the_ends_of_their_type() {
+		let source = "func sign(n: int) -> str do\n\tif n matches\n\t\t..=-1 then \"negative\"\n\t\t0 then \"zero\"\n\t\t1.. then \"positive\"\n\tend\nend\n\nfunc size(n: nat) -> str do\n\tif n matches\n\t\t..10 then \"small\"\n\t\t100.. then \"big\"\n\tend\nend\n\nfunc letter(c: char) -> bool do\n\tif c matches\n\t\t'a'..='z' then true\n\t\t..'a' then false\n\t\t'{'.. then false\n\tend\nend\n\npub func main() do\n\tsign(1)\n\tsize(1)\n\tletter('q')\nend\n";
+		let errors = check_source("half-open", source).err().unwrap();
+
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].line(), Some(10));
+		assert!(matches!(errors[0].kind(), ErrorKind::Interpret(InterpretError::Type(TypeError::NonExhaustive(missing))) if missing == ["10..=99"]));
+	}
+
+	#[test]
+	fn covered_arms_are_unreachable() {
+		let source = "func sign(n: int) -> str do\n\tif n matches\n\t\t0 then \"zero\"\n\t\t_ then \"some\"\n\t\t-1 then \"negative\"\n\tend\nend\n\nfunc pick(o: Option<bool>) -> bool do\n\tif o matches\n\t\tSome(true) then true\n\t\tSome(false) then false\n\t\tSome(_) then false\n\t\tNone then false\n\tend\nend\n\npub func main() do\n\tsign(1)\n\tpick(None)\nend\n";
+		let (_, warnings) = check_source("unreachable", source).unwrap();
+
+		let lines: Vec<Option<usize>> = warnings.iter()
+			.filter(|e| matches!(e.kind(), ErrorKind::Interpret(InterpretError::Type(TypeError::Unreachable))))
+			.map(|e| e.line())
+			.collect();
+		assert_eq!(lines, [Some(5), Some(13)]);
+	}
+}
@@ -0,0 +1,141 @@
+//! The rules for numbers.
+//!
+//! `byte`, `nat`, `int` and `flo` are separate types, and one is never turned into another without saying so,
+//! which is what the `to_*` methods in `std::num` are for.
+//! A number literal takes whichever of them its context needs, and is an error if its value doesn't fit in it.
+//!
+//! Arithmetic on `nat` and `byte` never wraps around: `n - 1` when `n` is 0 is an error when it runs,
+//! the same as going over the top. Code which could go below zero either works in `int`,
+//! or uses `checked_sub` or `saturating_sub` to say what should happen instead.
+
+//--> Imports <--
+
+use super::{
+	Checker,
+	Ty,
+	TypeError,
+	ty::{
+		Prim,
+		VarKind,
+	},
+};
+
+use crate::compiler::modules::ModuleId;
+
+use logos::Span;
+
+//--> Structs <--
+
+/// A number literal, which is checked once every type is known.
+pub(super) struct NumLit {
+	module: ModuleId,
+	span: Span,
+	value: u64,
+	negated: bool,
+	ty: Ty,
+}
+
+//--> Functions <--
+
+impl Checker<'_> {
+	/// Gives a number literal a type which is only decided by how it's used.
+	pub(super) fn number(&mut self, value: u64, negated: bool, span: &Span) -> Ty {
+		let kind = if negated { VarKind::Signed } else { VarKind::Integral };
+		let ty = self.table.fresh(self.level, kind);
+
+		self.literals.push(NumLit { module: self.module, span: span.clone(), value, negated, ty: ty.clone() });
+		ty
+	}
+
+	/// Checks that something being negated can be negative.
+	pub(super) fn negate(&mut self, ty: &Ty, span: &Span) {
+		match self.table.resolve(ty) {
+			Ty::Prim(prim @ (Prim::Nat | Prim::Byte)) => {
+				let note = format!("`{}` can't be negative, so convert it with `{}` first", prim.as_str(), self.conversion(span, Prim::Int));
+				self.report(span.clone(), TypeError::CantApply { op: "-", ty: prim.as_str().to_string() }).add_note(note);
+			},
+			Ty::Var(_) => {
+				let signed = self.table.fresh(self.level, VarKind::Signed);
+				self.expect(ty, &signed, span.clone(), None);
+			},
+			_ => self.numeric(ty, "-", span),
+		}
+	}
+
+	/// Reports a number which has to be negative being used where an unsigned one was expected.
+	pub(super) fn negative_mismatch(&mut self, expected: Prim, span: Span) {
+		self.report(span, TypeError::SignMismatch { expected: expected.as_str().to_string(), found: String::from("{signed number}") });
+	}
+
+	/// Reports two different kinds of number being used together, with how to convert one into the other.
+	pub(super) fn number_mismatch(&mut self, found: Prim, expected: Prim, span: Span, expected_at: Option<Span>) {
+		let conversion = self.conversion(&span, expected);
+		let fallible = !matches!((found, expected), (Prim::Byte, _) | (_, Prim::Flo));
+		let mixes_signs = found.is_signed() != expected.is_signed();
+		let line = expected_at.as_ref().map(|at| self.line(at));
+
+		let (expected, found) = (expected.as_str().to_string(), found.as_str().to_string());
+		let err = if mixes_signs {
+			self.report(span, TypeError::SignMismatch { expected: expected.clone(), found })
+		} else {
+			self.report(span, TypeError::Mismatch { expected: expected.clone(), found, expected_at })
+		};
+
+		if let Some(line) = line {
+			err.add_note(format!("the expected type comes from line {}", line));
+		}
+
+		err.add_note(match fallible {
+			true => format!("convert it with `{}`, which gives back `None` when it doesn't fit in `{}`", conversion, expected),
+			false => format!("convert it with `{}`", conversion),
+		});
+	}
+
+	/// Writes out the call which converts the expression at `span` to another kind of number.
+	fn conversion(&self, span: &Span, to: Prim) -> String {
+		let source = self.tree.modules[self.module].source.get(span.clone()).unwrap_or("x");
+		let simple = source.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '(' | ')' | ':'));
+
+		match simple {
+			true => format!("{}.to_{}()", source, to.as_str()),
+			false => format!("({}).to_{}()", source, to.as_str()),
+		}
+	}
+
+	/// Checks that every number literal fits in the type it ended up with.
+	pub(super) fn check_literals(&mut self) {
+		let module = self.module;
+
+		for lit in std::mem::take(&mut self.literals) {
+			let Ty::Prim(prim) = self.table.zonk(&lit.ty) else { continue };
+			self.numbers.insert((lit.module, lit.span.clone()), prim);
+			if fits(lit.value, lit.negated, prim) { continue }
+
+			self.module = lit.module;
+			let text = format!("{}{}", if lit.negated { "-" } else { "" }, lit.value);
+			let note = match prim {
+				Prim::Byte => "`byte` holds values from 0 to 255",
+				Prim::Nat => "`nat` holds values from 0 to 18446744073709551615",
+				Prim::Int => "`int` holds values from -9223372036854775808 to 9223372036854775807",
+				_ => "`flo` only holds whole numbers exactly up to 9007199254740992",
+			};
+
+			self.report(lit.span, TypeError::Overflow { lit: text, ty: prim.as_str().to_string() }).add_note(note.to_string());
+		}
+
+		self.module = module;
+	}
+}
+
+/// Whether a literal's value can be held by a type.
+fn fits(value: u64, negated: bool, prim: Prim) -> bool {
+	match prim {
+		Prim::Byte => !negated && value <= u8::MAX as u64,
+		Prim::Nat => !negated,
+		Prim::Int if negated => value <= i64::MIN.unsigned_abs(),
+		Prim::Int => value <= i64::MAX as u64,
+		// Past 2^53 not every whole number has a `flo`, so the literal has to be one of the ones that does.
+		Prim::Flo => value as f64 as u128 == value as u128,
+		_ => true,
+	}
+}
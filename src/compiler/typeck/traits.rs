@@ -0,0 +1,660 @@
+//! Checks impls against their traits, and trait bounds against the types they end up applying to.
+//!
+//! An impl has to provide every item of its trait which doesn't have a default, with the same type the trait gives it.
+//! No two impls of a trait can cover the same type, so which one gets used is never in doubt.
+//! A bound holds for a type if an impl covers it, and that impl's own bounds hold in turn.
+//! Inside of a body, the bounds on the generic parameters in scope are assumed to hold instead.
+//! A trait object implements its trait and the trait's supertraits, since its vtable has all of their methods.
+
+//--> Imports <--
+
+use super::{
+	Checker,
+	Head,
+	Sig,
+	TraitRef,
+	Ty,
+	TypeError,
+	collect::head,
+	ty::{
+		Table,
+		VarKind,
+	},
+};
+
+use crate::compiler::{
+	line_of,
+	modules::ModuleId,
+	parser::astgen::ItemKind,
+	resolve::{
+		DefId,
+		DefKind,
+	},
+};
+
+use logos::Span;
+
+use std::collections::HashMap;
+
+//--> Structs <--
+
+/// A bound which has to hold where something was used, to be checked once every type is known.
+pub(super) struct Obligation {
+	module: ModuleId,
+	span: Span,
+	ty: Ty,
+	trait_: TraitRef,
+	/// The item which declared the bound.
+	from: DefId,
+	/// The function whose body it's in, whose own bounds can be relied on.
+	within: Option<DefId>,
+}
+
+/// A type or effect given generic arguments, which have to satisfy the bounds on its parameters.
+pub(super) struct WellFormed {
+	pub(super) module: ModuleId,
+	pub(super) span: Span,
+	pub(super) def: DefId,
+	pub(super) args: Vec<Ty>,
+	pub(super) within: Option<DefId>,
+}
+
+/// A value being turned into a trait object, which needs its type to implement the trait.
+pub(super) struct Coercion {
+	module: ModuleId,
+	span: Span,
+	ty: Ty,
+	trait_: TraitRef,
+	within: Option<DefId>,
+}
+
+//--> Functions <--
+
+impl Checker<'_> {
+	/// Writes out a trait along with its generic arguments.
+	fn show_trait(&self, trait_: &TraitRef) -> String {
+		let name = &self.res.defs[trait_.def].name.name;
+
+		match trait_.args.is_empty() {
+			true => name.clone(),
+			false => format!("{}<{}>", name, trait_.args.iter().map(|arg| self.show(arg)).collect::<Vec<_>>().join(", ")),
+		}
+	}
+
+	/// A trait along with every trait it has as a supertrait, however far up.
+	pub(super) fn with_supertraits(&self, trait_: &TraitRef) -> Vec<TraitRef> {
+		let mut traits = vec![trait_.clone()];
+		let mut i = 0;
+
+		while let Some(current) = traits.get(i) {
+			let args = current.args.iter().enumerate().map(|(j, arg)| (Ty::Param(current.def, j), arg.clone())).collect();
+			let more: Vec<TraitRef> = self.supertraits.get(&current.def).into_iter().flatten()
+				.map(|supertrait| TraitRef { def: supertrait.def, args: supertrait.args.iter().map(|arg| arg.subst(&args)).collect() })
+				.filter(|supertrait| !traits.iter().any(|other| other.def == supertrait.def))
+				.collect();
+
+			traits.extend(more);
+			i += 1;
+		}
+
+		traits
+	}
+
+	/// The bounds which hold inside of an item's body: its own, those of whatever it's declared inside of,
+	/// and `Self` implementing the trait it's in.
+	pub(super) fn assumptions(&self, within: Option<DefId>) -> Vec<(Ty, TraitRef)> {
+		let mut assumed = Vec::new();
+
+		for def in std::iter::successors(within, |def| self.res.defs[*def].parent) {
+			if self.res.defs[def].kind == DefKind::Trait {
+				let args = (0..self.generics(def).len()).map(|i| Ty::Param(def, i)).collect();
+				assumed.push((Ty::SelfOf(def), TraitRef { def, args }));
+			}
+
+			assumed.extend(self.predicates.get(&def).into_iter().flatten().cloned());
+		}
+
+		assumed
+	}
+
+	/// Finds a method or constant on a generic parameter or `Self` inside of a trait, which only has what its bounds give it.
+	pub(super) fn lookup_bounded(&self, ty: &Ty, name: &str, kind: DefKind) -> Option<DefId> {
+		if !matches!(ty, Ty::Param(..) | Ty::SelfOf(_) | Ty::Proj(..)) { return None }
+
+		self.assumptions(self.within).iter()
+			.filter(|(bounded, _)| bounded == ty)
+			.flat_map(|(_, trait_)| self.with_supertraits(trait_))
+			.filter_map(|trait_| self.res.defs[trait_.def].children.get(name).copied())
+			.find(|child| self.res.defs[*child].kind == kind)
+	}
+
+	/// Finds a method of one particular trait on a generic parameter or `Self`, with the trait's parameters filled in by the bound that gives it.
+	pub(super) fn bounded_method(&self, trait_: DefId, ty: &Ty, name: &str) -> Option<(DefId, Sig)> {
+		let bound = self.assumptions(self.within).into_iter()
+			.filter(|(bounded, _)| bounded == ty)
+			.flat_map(|(_, bound)| self.with_supertraits(&bound))
+			.find(|bound| bound.def == trait_)?;
+
+		self.trait_method(&bound, ty, name)
+	}
+
+	/// Finds a method on a trait object, from its trait or one of the trait's supertraits.
+	pub(super) fn dyn_method(&self, ty: &Ty, name: &str) -> Option<(DefId, Sig)> {
+		let Ty::Dyn(def, args) = ty else { return None };
+
+		self.with_supertraits(&TraitRef { def: *def, args: args.clone() }).iter()
+			.find_map(|bound| self.trait_method(bound, ty, name))
+	}
+
+	/// One of a trait's methods, with `Self` and the trait's parameters filled in.
+	fn trait_method(&self, trait_: &TraitRef, ty: &Ty, name: &str) -> Option<(DefId, Sig)> {
+		let def = self.res.defs[trait_.def].children.get(name).copied()?;
+		let sig = self.sigs.get(&def)?.clone();
+
+		let mut args: HashMap<Ty, Ty> = trait_.args.iter().enumerate().map(|(i, arg)| (Ty::Param(trait_.def, i), arg.clone())).collect();
+		args.insert(Ty::SelfOf(trait_.def), ty.clone());
+
+		Some((def, Sig {
+			params: sig.params.iter().map(|param| param.subst(&args)).collect(),
+			ret: sig.ret.subst(&args),
+			owner: sig.owner.map(|owner| owner.subst(&args)),
+			effects: sig.effects.subst(&args),
+			..sig
+		}))
+	}
+
+	/// Leaves behind a bound on something a builtin operator is used on, like the elements of lists compared with `==`.
+	/// The trait's one parameter is the type itself.
+	pub(super) fn require(&mut self, ty: Ty, trait_: DefId, span: &Span) {
+		self.obligations.push(Obligation {
+			module: self.module,
+			span: span.clone(),
+			ty: ty.clone(),
+			trait_: TraitRef { def: trait_, args: vec![ty] },
+			from: trait_,
+			within: self.within,
+		});
+	}
+
+	/// Leaves behind the bounds that using something brings with it, with its generic parameters replaced by what they're used as.
+	pub(super) fn oblige(&mut self, def: DefId, fresh: &HashMap<Ty, Ty>, span: &Span) {
+		let parents: Vec<DefId> = std::iter::successors(Some(def), |def| self.res.defs[*def].parent).collect();
+
+		for from in parents {
+			let mut bounds = match self.res.defs[from].kind {
+				// Using anything in a trait needs whatever `Self` turns out to be to implement it.
+				DefKind::Trait if fresh.contains_key(&Ty::SelfOf(from)) => {
+					let args = (0..self.generics(from).len()).map(|i| Ty::Param(from, i)).collect();
+					vec![(Ty::SelfOf(from), TraitRef { def: from, args })]
+				},
+				DefKind::Func | DefKind::Impl => Vec::new(),
+				// Bounds on the parameters of types and effects are left to wherever those get used with them.
+				_ => continue,
+			};
+
+			bounds.extend(self.predicates.get(&from).into_iter().flatten().cloned());
+
+			for (ty, trait_) in bounds {
+				self.obligations.push(Obligation {
+					module: self.module,
+					span: span.clone(),
+					ty: ty.subst(fresh),
+					trait_: TraitRef { def: trait_.def, args: trait_.args.iter().map(|arg| arg.subst(fresh)).collect() },
+					from,
+					within: self.within,
+				});
+			}
+		}
+	}
+
+	/// Checks every obligation left behind, now that the types they're about are known.
+	pub(super) fn check_obligations(&mut self) {
+		let module = self.module;
+
+		for wf in std::mem::take(&mut self.well_formed) {
+			let args = wf.args.into_iter().enumerate().map(|(i, arg)| (Ty::Param(wf.def, i), arg)).collect();
+
+			for (ty, trait_) in self.predicates.get(&wf.def).into_iter().flatten() {
+				self.obligations.push(Obligation {
+					module: wf.module,
+					span: wf.span.clone(),
+					ty: ty.subst(&args),
+					trait_: TraitRef { def: trait_.def, args: trait_.args.iter().map(|arg| arg.subst(&args)).collect() },
+					from: wf.def,
+					within: wf.within,
+				});
+			}
+		}
+
+		for obligation in std::mem::take(&mut self.obligations) {
+			let ty = self.normalize(&obligation.ty);
+			let trait_ = TraitRef { def: obligation.trait_.def, args: obligation.trait_.args.iter().map(|arg| self.normalize(arg)).collect() };
+
+			if self.implements(&ty, &trait_, obligation.within, 0) { continue }
+
+			self.module = obligation.module;
+			let (ty, shown) = (self.show(&ty), self.show_trait(&trait_));
+			let from = &self.res.defs[obligation.from];
+			let note = match from.kind {
+				DefKind::Trait => format!("`{}` is only there for types which implement `{}`", self.tree.modules[self.module].source.get(obligation.span.clone()).unwrap_or("it"), shown),
+				_ => format!("it's required by a bound on `{}`", from.name.name),
+			};
+
+			self.report(obligation.span, TypeError::Unsatisfied { ty, trait_: shown }).add_note(note);
+		}
+
+		self.module = module;
+	}
+
+	/// Whether a type implements a trait, either through an impl or because the body it's used in assumes it does.
+	fn implements(&self, ty: &Ty, trait_: &TraitRef, within: Option<DefId>, depth: usize) -> bool {
+		// Impls whose bounds lead back to themselves could go on forever, so after a while they're given the benefit of the doubt.
+		if depth > 16 { return true }
+
+		let mut unknown = false;
+		ty.walk(&mut |inner| if matches!(inner, Ty::Var(_) | Ty::Error) { unknown = true });
+		if unknown || *ty == Ty::Never { return true }
+
+		if matches!(ty, Ty::Param(..) | Ty::SelfOf(_) | Ty::Proj(..)) {
+			return self.assumptions(within).iter()
+				.filter(|(bounded, _)| bounded == ty)
+				.any(|(_, assumed)| self.with_supertraits(assumed).iter().any(|assumed| assumed.def == trait_.def));
+		}
+
+		if let Ty::Dyn(def, args) = ty {
+			return self.with_supertraits(&TraitRef { def: *def, args: args.clone() }).iter().any(|bound| bound.def == trait_.def);
+		}
+
+		// The builtin types are compared by what's in them, rather than through an impl.
+		if let Some(ordered) = self.comparison(trait_.def).filter(|_| !self.is_declared(ty)) {
+			let inner = |inner: &Ty| self.implements(inner, &TraitRef { def: trait_.def, args: vec![inner.clone()] }, within, depth + 1);
+
+			return match ty {
+				Ty::Prim(_) => true,
+				Ty::List(elem) | Ty::Array(elem, _) => inner(elem),
+				Ty::Tuple(elems) => elems.iter().all(inner),
+				Ty::Map(key, value) if !ordered => inner(key) && inner(value),
+				Ty::Record(fields) if !ordered => fields.iter().all(|(_, field)| inner(field)),
+				_ => false,
+			};
+		}
+
+		let Some(head) = head(ty) else { return false };
+
+		self.impls_of(trait_.def, head).into_iter().any(|def| {
+			// The impl's generic parameters get worked out by matching its type against this one, in a table of its own.
+			let mut table = Table::default();
+			let fresh = self.freshen(def, &mut table);
+			let imp = &self.impl_traits[&def];
+
+			if table.unify(&self.impl_tys[&def].subst(&fresh), ty).is_err() { return false }
+			for (arg, other) in imp.args.iter().zip(trait_.args.iter()) {
+				// Variables from the main table don't mean anything in this one, and anything could go in them anyway.
+				let other = other.map(&mut |inner| matches!(inner, Ty::Var(_)).then_some(Ty::Error));
+				if table.unify(&arg.subst(&fresh), &other).is_err() { return false }
+			}
+
+			self.predicates.get(&def).into_iter().flatten().all(|(bounded, bound)| {
+				let bound = TraitRef { def: bound.def, args: bound.args.iter().map(|arg| table.zonk(&arg.subst(&fresh))).collect() };
+				let bounded = table.zonk(&bounded.subst(&fresh));
+				// Anything left as a variable wasn't pinned down by the impl's type, so it can't be checked.
+				let mut free = false;
+				bounded.walk(&mut |inner| if matches!(inner, Ty::Var(_)) { free = true });
+
+				free || self.implements(&bounded, &bound, within, depth + 1)
+			})
+		})
+	}
+
+	/// Whether a trait is `Eq` or `Ord` from `std::ops`, and if it is, whether it's the one for ordering.
+	fn comparison(&self, trait_: DefId) -> Option<bool> {
+		if self.std_def(&["ops", "Eq"]) == Some(trait_) { return Some(false) }
+		(self.std_def(&["ops", "Ord"]) == Some(trait_)).then_some(true)
+	}
+
+	/// The impls of a trait for types with a given head.
+	fn impls_of(&self, trait_: DefId, head: Head) -> Vec<DefId> {
+		self.impls.get(&head).into_iter().flatten().copied()
+			.filter(|def| self.impl_traits.get(def).is_some_and(|imp| imp.def == trait_))
+			.collect()
+	}
+
+	/// Picks the impl of a trait which a type and the trait's first argument both fit, which is how an operator finds what it does.
+	/// Each impl gets tried against a copy of the table, so number literals still only fit impls for numbers.
+	/// If more than one fits, because the argument isn't known yet, the first one gets picked.
+	pub(super) fn select_impl(&self, trait_: DefId, ty: &Ty, arg: Option<&Ty>) -> Option<DefId> {
+		let head = head(ty)?;
+
+		self.impls_of(trait_, head).into_iter().find(|def| {
+			let mut table = self.table.clone();
+			let fresh = self.freshen(*def, &mut table);
+			if table.unify(&self.impl_tys[def].subst(&fresh), ty).is_err() { return false }
+
+			match (arg, self.impl_traits[def].args.first()) {
+				(Some(arg), Some(imp)) => table.unify(&imp.subst(&fresh), arg).is_ok(),
+				_ => true,
+			}
+		})
+	}
+
+	/// Works out the associated types inside of a type wherever what they're on is known well enough to pick its impl.
+	/// Ones on generic parameters, or on types still being inferred, are left as they are.
+	pub(super) fn normalize(&mut self, ty: &Ty) -> Ty {
+		self.table.zonk(ty).map(&mut |inner| {
+			let Ty::Proj(base, def, args) = inner else { return None };
+			let base = self.normalize(base);
+			let args: Vec<Ty> = args.iter().map(|arg| self.normalize(arg)).collect();
+
+			match self.project_impl(&base, *def, &args) {
+				Some(ty) => Some(self.normalize(&ty)),
+				None => Some(Ty::Proj(Box::new(base), *def, args)),
+			}
+		})
+	}
+
+	/// An associated type from the impl of its trait which covers a type, if there is one.
+	fn project_impl(&mut self, ty: &Ty, def: DefId, args: &[Ty]) -> Option<Ty> {
+		if *ty == Ty::Error { return Some(Ty::Error) }
+
+		let trait_ = self.res.defs[def].parent?;
+		let imp = self.select_impl(trait_, ty, args.first())?;
+		let name = self.res.defs[def].name.name.clone();
+		Some(self.impl_assoc(imp, ty, &name))
+	}
+
+	/// Replaces the associated types in an instantiated signature which are on something still being inferred with variables,
+	/// to be filled in once it's known. `span` is where the signature is being used.
+	pub(super) fn defer(&mut self, ty: &Ty, span: &Span) -> Ty {
+		ty.map(&mut |inner| {
+			if !matches!(inner, Ty::Proj(..)) { return None }
+			if !has_vars(&self.table.zonk(inner)) { return Some(self.normalize(inner)) }
+
+			let var = self.fresh();
+			self.projections.push((self.module, span.clone(), var.clone(), inner.clone()));
+			Some(var)
+		})
+	}
+
+	/// Fills in the variables standing in for associated types whose types have become known since.
+	/// Something else might have been expected of one in the meantime, which is a mismatch if it's not what the impl says.
+	pub(super) fn normalize_projections(&mut self) {
+		let module = self.module;
+
+		for (at, span, var, proj) in std::mem::take(&mut self.projections) {
+			if has_vars(&self.table.zonk(&proj)) {
+				self.projections.push((at, span, var, proj));
+				continue;
+			}
+
+			let ty = self.normalize(&proj);
+			if self.table.unify(&var, &ty).is_ok() { continue }
+
+			self.module = at;
+			let (expected, found) = (self.show(&var), self.show(&ty));
+			self.report(span, TypeError::Mismatch { expected, found, expected_at: None });
+		}
+
+		self.module = module;
+	}
+
+	/// Turns a value into a trait object, if that's what's expected of it, and gives back whether it did.
+	/// Whether the value's type implements the trait gets checked once every type is known.
+	pub(super) fn coerce(&mut self, found: &Ty, expected: &Ty, span: &Span) -> bool {
+		let Ty::Dyn(def, args) = self.table.resolve(expected) else { return false };
+		// One trait object can't be turned into another, even of a supertrait, since their vtables are laid out differently.
+		if matches!(self.table.resolve(found), Ty::Dyn(..)) { return false }
+
+		self.coercions.push(Coercion { module: self.module, span: span.clone(), ty: found.clone(), trait_: TraitRef { def, args }, within: self.within });
+		true
+	}
+
+	/// Checks that the values turned into trait objects implement their traits, and works out their vtables.
+	pub(super) fn check_coercions(&mut self) {
+		let module = self.module;
+
+		for coercion in std::mem::take(&mut self.coercions) {
+			let ty = self.table.zonk(&coercion.ty);
+			let trait_ = TraitRef { def: coercion.trait_.def, args: coercion.trait_.args.iter().map(|arg| self.table.zonk(arg)).collect() };
+			self.module = coercion.module;
+
+			if !self.implements(&ty, &trait_, coercion.within, 0) {
+				let (shown, object) = (self.show(&ty), self.show(&Ty::Dyn(trait_.def, trait_.args.clone())));
+				let trait_ = self.show_trait(&trait_);
+
+				self.report(coercion.span, TypeError::Unsatisfied { ty: shown, trait_ })
+					.add_note(format!("it's turned into `{}` here, which only types implementing the trait can be", object));
+				continue;
+			}
+
+			let vtable = self.slots(&trait_).into_iter().map(|(bound, method)| self.vtable_entry(&ty, &bound, method)).collect();
+			self.vtables.insert((coercion.module, coercion.span), vtable);
+		}
+
+		self.module = module;
+	}
+
+	/// The methods a trait object's vtable has a slot for: the trait's own, and then those of its supertraits.
+	fn slots(&self, trait_: &TraitRef) -> Vec<(TraitRef, DefId)> {
+		self.with_supertraits(trait_).into_iter()
+			.flat_map(|bound| self.methods(bound.def).into_iter().map(move |method| (bound.clone(), method)))
+			.collect()
+	}
+
+	/// What goes in a vtable for one of a trait's methods: the impl's version of it, or the trait's default if the impl leaves it out.
+	/// For a generic parameter, which impl it is isn't known until the program runs, so the trait's declaration stands in for it,
+	/// to be looked up in the impl that gets passed along with the parameter.
+	fn vtable_entry(&self, ty: &Ty, trait_: &TraitRef, method: DefId) -> DefId {
+		let name = &self.res.defs[method].name.name;
+
+		self.select_impl(trait_.def, ty, trait_.args.first())
+			.and_then(|imp| self.res.defs[imp].children.get(name).copied())
+			.unwrap_or(method)
+	}
+
+	/// A trait's methods, in the order they're declared.
+	fn methods(&self, trait_: DefId) -> Vec<DefId> {
+		let mut methods: Vec<DefId> = self.res.defs[trait_].children.values().copied()
+			.filter(|def| self.res.defs[*def].kind == DefKind::Func)
+			.collect();
+
+		methods.sort_unstable();
+		methods
+	}
+
+	/// Checks that every trait written as `dyn Trait` can be made into a trait object.
+	pub(super) fn check_objects(&mut self) {
+		let module = self.module;
+
+		for (at, span, def) in std::mem::take(&mut self.objects) {
+			let reasons = self.object_safety(def);
+			if reasons.is_empty() { continue }
+
+			self.module = at;
+			let err = self.report(span, TypeError::NotObjectSafe(self.res.defs[def].name.name.clone()));
+			for reason in reasons { err.add_note(reason); }
+		}
+
+		self.module = module;
+	}
+
+	/// Why a trait can't be made into a trait object, which is nothing if it can be.
+	fn object_safety(&self, def: DefId) -> Vec<String> {
+		let uses_self = |ty: &Ty| {
+			let mut found = false;
+			ty.walk(&mut |inner| if matches!(inner, Ty::SelfOf(_)) { found = true });
+			found
+		};
+
+		let args = (0..self.generics(def).len()).map(|i| Ty::Param(def, i)).collect();
+		let mut reasons = Vec::new();
+
+		for bound in self.with_supertraits(&TraitRef { def, args }) {
+			let mut items: Vec<DefId> = self.res.defs[bound.def].children.values().copied().collect();
+			items.sort_unstable();
+
+			for item in items {
+				let name = &self.res.defs[item].name.name;
+
+				match self.res.defs[item].kind {
+					DefKind::Type => reasons.push(format!("it has the associated type `{}`, which is different for every type implementing it", name)),
+					DefKind::Const => reasons.push(format!("it has the constant `{}`, which is different for every type implementing it", name)),
+					_ => {},
+				}
+			}
+
+			for supertrait in self.supertraits.get(&bound.def).into_iter().flatten() {
+				if supertrait.args.iter().any(uses_self) {
+					reasons.push(format!("it needs `Self` to implement `{}`, and a trait object doesn't know what type that is", self.show_trait(supertrait)));
+				}
+			}
+
+			for method in self.methods(bound.def) {
+				let Some(sig) = self.sigs.get(&method) else { continue };
+				let name = &self.res.defs[method].name.name;
+
+				if !sig.has_self {
+					reasons.push(format!("`{}` doesn't take `self`, so a trait object would have nothing to call it on", name));
+				} else if !self.generics(method).is_empty() {
+					reasons.push(format!("`{}` is generic, so there's no one function a vtable could have for it", name));
+				} else if sig.params[1..].iter().any(uses_self) || uses_self(&sig.ret) {
+					reasons.push(format!("`{}` uses `Self` for more than `self`, and a trait object doesn't know what type that is", name));
+				}
+			}
+		}
+
+		reasons
+	}
+
+	/// Gives each of an impl's generic parameters a variable in a table of its own.
+	fn freshen(&self, def: DefId, table: &mut Table) -> HashMap<Ty, Ty> {
+		(0..self.generics(def).len()).map(|i| (Ty::Param(def, i), table.fresh(0, VarKind::General))).collect()
+	}
+
+	/// Checks that every impl of a trait has what the trait declares, and nothing else.
+	pub(super) fn check_impls(&mut self) {
+		let mut impls: Vec<(DefId, TraitRef)> = self.impl_traits.iter().map(|(def, trait_)| (*def, trait_.clone())).collect();
+		impls.sort_unstable_by_key(|(def, _)| *def);
+
+		for (def, trait_) in impls {
+			self.module = self.res.defs[def].module;
+			let name = self.res.defs[trait_.def].name.name.clone();
+
+			let mut required: Vec<(&String, &DefId)> = self.res.defs[trait_.def].children.iter()
+				.filter(|(item, _)| !self.res.defs[def].children.contains_key(*item))
+				.filter(|(_, item)| match &self.items[item].kind {
+					ItemKind::Func(func) => func.body.is_none(),
+					ItemKind::Const(decl) => decl.value.is_none(),
+					_ => true,
+				})
+				.collect();
+			required.sort_unstable_by_key(|(_, item)| **item);
+
+			let missing: Vec<String> = required.into_iter().map(|(item, _)| item.clone()).collect();
+			let span = self.res.defs[def].name.span.clone();
+
+			for item in missing {
+				self.report(span.clone(), TypeError::MissingItem { trait_: name.clone(), item });
+			}
+
+			let mut items: Vec<DefId> = self.res.defs[def].children.values().copied().collect();
+			items.sort_unstable();
+
+			for item in items {
+				let item_name = self.res.defs[item].name.clone();
+
+				match self.res.defs[trait_.def].children.get(&item_name.name).copied() {
+					Some(declared) => self.check_impl_item(def, &trait_, item, declared),
+					None => {
+						self.report(item_name.span.clone(), TypeError::NotInTrait { trait_: name.clone(), item: item_name.name.clone() });
+					},
+				}
+			}
+		}
+	}
+
+	/// Checks that a method or constant in an impl has the type its trait declares, with `Self`, the trait's parameters
+	/// and its associated types filled in. A constant whose type was left off gets the one its trait declares.
+	fn check_impl_item(&mut self, def: DefId, trait_: &TraitRef, item: DefId, declared: DefId) {
+		let mut args: HashMap<Ty, Ty> = trait_.args.iter().enumerate().map(|(i, arg)| (Ty::Param(trait_.def, i), arg.clone())).collect();
+		args.insert(Ty::SelfOf(trait_.def), self.impl_tys[&def].clone());
+		args.extend((0..self.generics(declared).len()).map(|i| (Ty::Param(declared, i), Ty::Param(item, i))));
+
+		let (expected, found) = match (self.sigs.get(&item), self.sigs.get(&declared)) {
+			(Some(found), Some(expected)) => {
+				// Effects are left out, since an impl is free to perform fewer of them than its trait allows.
+				let unit = || Box::new(Ty::Row(Vec::new(), None));
+				let expected = Ty::Func(expected.params.iter().map(|param| param.subst(&args)).collect(), Box::new(expected.ret.subst(&args)), unit());
+				(expected, Ty::Func(found.params.clone(), Box::new(found.ret.clone()), unit()))
+			},
+			_ => match (self.consts.get(&item).cloned(), self.consts.get(&declared)) {
+				(Some(found), Some(expected)) => (expected.subst(&args), found),
+				(None, Some(expected)) => {
+					let expected = expected.subst(&args);
+					let expected = self.normalize(&expected);
+					self.consts.insert(item, expected);
+					return;
+				},
+				_ => return,
+			},
+		};
+
+		let expected = self.normalize(&expected);
+		let same_generics = self.generics(item).len() == self.generics(declared).len();
+		if same_generics && Table::default().unify(&expected, &found).is_ok() { return }
+
+		let (expected, found) = (self.show(&expected), self.show(&found));
+		let name = self.res.defs[item].name.clone();
+		let declared = &self.res.defs[declared];
+		let line = line_of(&self.tree.modules[declared.module].source, declared.name.span.start);
+
+		self.report(name.span, TypeError::ImplMismatch { item: name.name, expected, found })
+			.add_note(format!("the trait declares it on line {}", line));
+	}
+
+	/// Checks that no two impls of a trait could be for the same type.
+	pub(super) fn check_coherence(&mut self) {
+		let mut impls: Vec<DefId> = self.impl_traits.keys().copied().collect();
+		impls.sort_unstable();
+
+		for (i, &second) in impls.iter().enumerate() {
+			let Some(first) = impls[..i].iter().copied().find(|first| self.overlap(*first, second)) else { continue };
+
+			self.module = self.res.defs[second].module;
+			let trait_ = self.show_trait(&self.impl_traits[&second]);
+			let ty = self.show(&self.impl_tys[&second]);
+
+			let other = &self.res.defs[first];
+			let note = match other.module == self.module {
+				true => format!("it overlaps with the impl on line {}", self.line(&other.name.span)),
+				false => format!("it overlaps with an impl in `{}`", self.tree.modules[other.module].path.join("::")),
+			};
+
+			let span = self.res.defs[second].name.span.clone();
+			self.report(span, TypeError::Overlap { trait_, ty }).add_note(note);
+		}
+	}
+
+	/// Whether two impls could apply to the same type, which is when their types and trait arguments can be made the same.
+	fn overlap(&self, a: DefId, b: DefId) -> bool {
+		if self.impl_traits[&a].def != self.impl_traits[&b].def { return false }
+
+		let mut table = Table::default();
+		let (fresh_a, fresh_b) = (self.freshen(a, &mut table), self.freshen(b, &mut table));
+		let (ty_a, ty_b) = (self.impl_tys[&a].subst(&fresh_a), self.impl_tys[&b].subst(&fresh_b));
+
+		// A type which already had an error reported about it would overlap with everything.
+		let mut error = false;
+		for ty in [&ty_a, &ty_b] { ty.walk(&mut |inner| if *inner == Ty::Error { error = true }) }
+		if error || table.unify(&ty_a, &ty_b).is_err() { return false }
+
+		let (args_a, args_b) = (&self.impl_traits[&a].args, &self.impl_traits[&b].args);
+		args_a.iter().zip(args_b.iter()).all(|(x, y)| table.unify(&x.subst(&fresh_a), &y.subst(&fresh_b)).is_ok())
+	}
+}
+
+/// Whether anything in a type is still to be inferred.
+fn has_vars(ty: &Ty) -> bool {
+	let mut found = false;
+	ty.walk(&mut |inner| if matches!(inner, Ty::Var(_)) { found = true });
+	found
+}
@@ -0,0 +1,496 @@
+//! Types as the type checker sees them, along with the table of inference variables they get unified through.
+//!
+//! Inference variables carry a level, which is how deeply nested in `:=` declarations they were made.
+//! When a declaration's type gets generalized, only the variables made inside of it (the ones with a deeper level) become generic.
+
+//--> Imports <--
+
+use crate::compiler::resolve::DefId;
+
+use std::collections::HashMap;
+
+//--> Type Aliases <--
+
+pub(crate) type TyVar = usize;
+
+//--> Structs <--
+
+/// A type with some of its variables made generic, like the type of a closure declared with `:=`.
+#[derive(Clone, Debug)]
+pub(crate) struct Scheme {
+	pub vars: Vec<TyVar>,
+	pub ty: Ty,
+}
+
+#[derive(Clone, Debug)]
+struct VarData {
+	value: Option<Ty>,
+	level: usize,
+	kind: VarKind,
+}
+
+/// Every inference variable made so far, and what they've been unified with.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Table {
+	vars: Vec<VarData>,
+}
+
+//--> Enums <--
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Ty {
+	/// An inference variable.
+	Var(TyVar),
+	Prim(Prim),
+	/// A type declared with `type`, along with its generic arguments.
+	Adt(DefId, Vec<Ty>),
+	/// The generic parameter at an index of a function, type, trait, effect or impl.
+	Param(DefId, usize),
+	/// `Self` inside of a trait.
+	SelfOf(DefId),
+	/// `dyn Trait`, which is any type implementing the trait, with the trait's generic arguments.
+	Dyn(DefId, Vec<Ty>),
+	/// `T::Item`, one of a trait's associated types for a type which implements it, along with the trait's generic arguments.
+	/// It only stays like this while the type is a generic parameter, since otherwise its impl says what it is.
+	Proj(Box<Ty>, DefId, Vec<Ty>),
+	/// `[T]`
+	List(Box<Ty>),
+	/// `[T; N]`, where the length is only known if it was written as a literal.
+	Array(Box<Ty>, Option<u64>),
+	/// `[K: V]`
+	Map(Box<Ty>, Box<Ty>),
+	/// `(A, B)`, where `()` is the unit type.
+	Tuple(Vec<Ty>),
+	/// `(name: A, other: B)`, with the fields sorted by name.
+	Record(Vec<(String, Ty)>),
+	/// A function's parameters, return type and the effects it performs.
+	Func(Vec<Ty>, Box<Ty>, Box<Ty>),
+	/// The effects something performs. If there's a tail, whatever it stands for gets performed too;
+	/// without one, those are the only effects it performs.
+	Row(Vec<Ty>, Option<Box<Ty>>),
+	/// Whatever effects the functions passed to a function perform, which it passes on to whoever calls it.
+	EffectsOf(DefId),
+	/// `!`, the type of anything which never produces a value.
+	Never,
+	/// Stands in for something which already had an error reported about it, and goes along with anything.
+	Error,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Prim {
+	Bool,
+	Char,
+	Byte,
+	Nat,
+	Int,
+	Flo,
+	Str,
+}
+
+/// What an inference variable is allowed to become, from least to most picky.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum VarKind {
+	General,
+	/// Made for a number literal, so it can only become a number.
+	Integral,
+	/// Made for a negated number, so it can only become a number which can be negative.
+	Signed,
+}
+
+//--> Functions <--
+
+impl Ty {
+	pub(crate) fn unit() -> Ty {
+		Ty::Tuple(Vec::new())
+	}
+
+	/// Rebuilds a type, replacing whatever `f` has a replacement for.
+	pub(crate) fn map(&self, f: &mut impl FnMut(&Ty) -> Option<Ty>) -> Ty {
+		if let Some(ty) = f(self) { return ty }
+
+		match self {
+			Ty::Var(_) | Ty::Prim(_) | Ty::Param(..) | Ty::SelfOf(_) | Ty::EffectsOf(_) | Ty::Never | Ty::Error => self.clone(),
+			Ty::Adt(def, args) => Ty::Adt(*def, args.iter().map(|arg| arg.map(f)).collect()),
+			Ty::Dyn(def, args) => Ty::Dyn(*def, args.iter().map(|arg| arg.map(f)).collect()),
+			Ty::Proj(base, def, args) => Ty::Proj(Box::new(base.map(f)), *def, args.iter().map(|arg| arg.map(f)).collect()),
+			Ty::List(elem) => Ty::List(Box::new(elem.map(f))),
+			Ty::Array(elem, len) => Ty::Array(Box::new(elem.map(f)), *len),
+			Ty::Map(key, value) => Ty::Map(Box::new(key.map(f)), Box::new(value.map(f))),
+			Ty::Tuple(elems) => Ty::Tuple(elems.iter().map(|elem| elem.map(f)).collect()),
+			Ty::Record(fields) => Ty::Record(fields.iter().map(|(name, ty)| (name.clone(), ty.map(f))).collect()),
+			Ty::Func(params, ret, effects) => Ty::Func(params.iter().map(|param| param.map(f)).collect(), Box::new(ret.map(f)), Box::new(effects.map(f))),
+			// A tail which turns out to be another row gets merged into this one, so rows never nest.
+			Ty::Row(effects, tail) => {
+				let mut effects: Vec<Ty> = effects.iter().map(|effect| effect.map(f)).collect();
+
+				match tail.as_ref().map(|tail| tail.map(f)) {
+					Some(Ty::Row(more, tail)) => {
+						effects.extend(more);
+						Ty::Row(effects, tail)
+					},
+					tail => Ty::Row(effects, tail.map(Box::new)),
+				}
+			},
+		}
+	}
+
+	/// Calls `f` on every type inside of this one, including itself.
+	pub(crate) fn walk(&self, f: &mut impl FnMut(&Ty)) {
+		f(self);
+
+		match self {
+			Ty::Var(_) | Ty::Prim(_) | Ty::Param(..) | Ty::SelfOf(_) | Ty::EffectsOf(_) | Ty::Never | Ty::Error => {},
+			Ty::Adt(_, args) | Ty::Dyn(_, args) | Ty::Tuple(args) => args.iter().for_each(|arg| arg.walk(f)),
+			Ty::Proj(base, _, args) => {
+				base.walk(f);
+				args.iter().for_each(|arg| arg.walk(f));
+			},
+			Ty::List(elem) | Ty::Array(elem, _) => elem.walk(f),
+			Ty::Map(key, value) => {
+				key.walk(f);
+				value.walk(f);
+			},
+			Ty::Record(fields) => fields.iter().for_each(|(_, ty)| ty.walk(f)),
+			Ty::Func(params, ret, effects) => {
+				params.iter().for_each(|param| param.walk(f));
+				ret.walk(f);
+				effects.walk(f);
+			},
+			Ty::Row(effects, tail) => {
+				effects.iter().for_each(|effect| effect.walk(f));
+				if let Some(tail) = tail { tail.walk(f) }
+			},
+		}
+	}
+
+	/// Replaces generic parameters with whatever they've been mapped to.
+	pub(crate) fn subst(&self, args: &HashMap<Ty, Ty>) -> Ty {
+		self.map(&mut |ty| args.get(ty).cloned())
+	}
+}
+
+// Generic parameters get used as keys when they're substituted, which is all hashing is needed for.
+impl Eq for Ty {}
+
+impl std::hash::Hash for Ty {
+	fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+		std::mem::discriminant(self).hash(state);
+
+		match self {
+			Ty::Var(var) => var.hash(state),
+			Ty::Prim(prim) => prim.hash(state),
+			Ty::Adt(def, _) | Ty::Dyn(def, _) | Ty::SelfOf(def) | Ty::EffectsOf(def) => def.hash(state),
+			Ty::Param(def, index) => (def, index).hash(state),
+			Ty::Proj(base, def, _) => (base, def).hash(state),
+			_ => {},
+		}
+	}
+}
+
+impl Prim {
+	pub(crate) fn is_numeric(&self) -> bool {
+		matches!(self, Prim::Byte | Prim::Nat | Prim::Int | Prim::Flo)
+	}
+
+	pub(crate) fn is_integral(&self) -> bool {
+		matches!(self, Prim::Byte | Prim::Nat | Prim::Int)
+	}
+
+	pub(crate) fn is_signed(&self) -> bool {
+		matches!(self, Prim::Int | Prim::Flo)
+	}
+
+	pub(crate) fn from_name(name: &str) -> Option<Prim> {
+		Some(match name {
+			"bool" => Prim::Bool,
+			"char" => Prim::Char,
+			"byte" => Prim::Byte,
+			"nat" => Prim::Nat,
+			"int" => Prim::Int,
+			"flo" => Prim::Flo,
+			"str" => Prim::Str,
+			_ => return None,
+		})
+	}
+
+	pub(crate) fn as_str(&self) -> &'static str {
+		match self {
+			Prim::Bool => "bool",
+			Prim::Char => "char",
+			Prim::Byte => "byte",
+			Prim::Nat => "nat",
+			Prim::Int => "int",
+			Prim::Flo => "flo",
+			Prim::Str => "str",
+		}
+	}
+}
+
+impl Table {
+	pub(crate) fn fresh(&mut self, level: usize, kind: VarKind) -> Ty {
+		self.vars.push(VarData { value: None, level, kind });
+		Ty::Var(self.vars.len() - 1)
+	}
+
+	pub(crate) fn kind(&self, var: TyVar) -> VarKind {
+		self.vars[var].kind
+	}
+
+	/// Follows variables until reaching something which isn't a variable that's been unified with something.
+	pub(crate) fn resolve(&self, ty: &Ty) -> Ty {
+		let mut ty = ty.clone();
+
+		while let Ty::Var(var) = ty {
+			match &self.vars[var].value {
+				Some(value) => ty = value.clone(),
+				None => break,
+			}
+		}
+
+		ty
+	}
+
+	/// Resolves every variable inside of a type, as far as they can be.
+	pub(crate) fn zonk(&self, ty: &Ty) -> Ty {
+		ty.map(&mut |ty| match ty {
+			Ty::Var(_) => {
+				let resolved = self.resolve(ty);
+				Some(if resolved == *ty { resolved } else { self.zonk(&resolved) })
+			},
+			_ => None,
+		})
+	}
+
+	/// Makes two types the same, if they can be. Nothing is undone if they can't.
+	pub(crate) fn unify(&mut self, a: &Ty, b: &Ty) -> Result<(), ()> {
+		let (a, b) = (self.resolve(a), self.resolve(b));
+
+		match (&a, &b) {
+			(Ty::Var(x), Ty::Var(y)) if x == y => Ok(()),
+			// Something which never produces a value can stand in for anything.
+			(Ty::Never, _) | (_, Ty::Never) | (Ty::Error, _) | (_, Ty::Error) => Ok(()),
+			(Ty::Var(var), other) | (other, Ty::Var(var)) => self.bind(*var, other),
+			(Ty::Prim(x), Ty::Prim(y)) if x == y => Ok(()),
+			(Ty::Param(x, i), Ty::Param(y, j)) if x == y && i == j => Ok(()),
+			(Ty::SelfOf(x), Ty::SelfOf(y)) if x == y => Ok(()),
+			(Ty::Adt(x, xs), Ty::Adt(y, ys)) | (Ty::Dyn(x, xs), Ty::Dyn(y, ys)) if x == y && xs.len() == ys.len() => self.unify_all(xs, ys),
+			(Ty::Proj(a, x, xs), Ty::Proj(b, y, ys)) if x == y && xs.len() == ys.len() => {
+				self.unify(a, b)?;
+				self.unify_all(xs, ys)
+			},
+			(Ty::List(x), Ty::List(y)) => self.unify(x, y),
+			(Ty::Array(x, n), Ty::Array(y, m)) if n.is_none() || m.is_none() || n == m => self.unify(x, y),
+			(Ty::Map(k, v), Ty::Map(l, w)) => {
+				self.unify(k, l)?;
+				self.unify(v, w)
+			},
+			(Ty::Tuple(xs), Ty::Tuple(ys)) if xs.len() == ys.len() => self.unify_all(xs, ys),
+			(Ty::Record(xs), Ty::Record(ys)) if xs.len() == ys.len() && xs.iter().zip(ys.iter()).all(|((x, _), (y, _))| x == y) => {
+				for ((_, x), (_, y)) in xs.iter().zip(ys.iter()) {
+					self.unify(x, y)?;
+				}
+				Ok(())
+			},
+			(Ty::Func(xs, r, e), Ty::Func(ys, s, f)) if xs.len() == ys.len() => {
+				self.unify_all(xs, ys)?;
+				self.unify(r, s)?;
+				self.unify(e, f)
+			},
+			(Ty::Row(..), _) | (_, Ty::Row(..)) | (Ty::EffectsOf(_), Ty::EffectsOf(_)) => self.unify_rows(&a, &b),
+			_ => Err(()),
+		}
+	}
+
+	/// Splits a row up into the effects it's known to have, and the tail it's left with.
+	pub(crate) fn row(&self, ty: &Ty) -> (Vec<Ty>, Option<Ty>) {
+		match self.zonk(ty) {
+			Ty::Row(effects, tail) => (effects, tail.map(|tail| *tail)),
+			tail => (Vec::new(), Some(tail)),
+		}
+	}
+
+	/// Makes two rows have the same effects, with each tail taking on whatever only the other row has.
+	fn unify_rows(&mut self, a: &Ty, b: &Ty) -> Result<(), ()> {
+		let ((xs, x_tail), (ys, y_tail)) = (self.row(a), self.row(b));
+		let same = |x: &Ty, y: &Ty| matches!((x, y), (Ty::Adt(x, _), Ty::Adt(y, _)) if x == y);
+
+		let only_x: Vec<Ty> = xs.iter().filter(|x| !ys.iter().any(|y| same(x, y))).cloned().collect();
+		let only_y: Vec<Ty> = ys.iter().filter(|y| !xs.iter().any(|x| same(x, y))).cloned().collect();
+
+		for x in xs.iter() {
+			if let Some(y) = ys.iter().find(|y| same(x, y)) { self.unify(x, y)?; }
+		}
+
+		match (x_tail, y_tail) {
+			(Some(Ty::Error), _) | (_, Some(Ty::Error)) => Ok(()),
+			(Some(Ty::Var(x)), Some(Ty::Var(y))) if x == y => if only_x.is_empty() && only_y.is_empty() { Ok(()) } else { Err(()) },
+			(Some(Ty::Var(x)), Some(Ty::Var(y))) => {
+				let rest = Some(Box::new(self.fresh(self.vars[x].level.min(self.vars[y].level), VarKind::General)));
+				self.bind(x, &Ty::Row(only_y, rest.clone()))?;
+				self.bind(y, &Ty::Row(only_x, rest))
+			},
+			(Some(Ty::Var(x)), tail) if only_x.is_empty() => self.bind(x, &Ty::Row(only_y, tail.map(Box::new))),
+			(tail, Some(Ty::Var(y))) if only_y.is_empty() => self.bind(y, &Ty::Row(only_x, tail.map(Box::new))),
+			(x_tail, y_tail) if only_x.is_empty() && only_y.is_empty() && x_tail == y_tail => Ok(()),
+			_ => Err(()),
+		}
+	}
+
+	fn unify_all(&mut self, xs: &[Ty], ys: &[Ty]) -> Result<(), ()> {
+		for (x, y) in xs.iter().zip(ys.iter()) {
+			self.unify(x, y)?;
+		}
+		Ok(())
+	}
+
+	fn bind(&mut self, var: TyVar, ty: &Ty) -> Result<(), ()> {
+		let level = self.vars[var].level;
+		let kind = self.vars[var].kind;
+
+		match (kind, ty) {
+			(_, Ty::Var(other)) => self.vars[*other].kind = self.vars[*other].kind.max(kind),
+			(VarKind::General, _) => {},
+			(VarKind::Integral, Ty::Prim(prim)) if prim.is_numeric() => {},
+			(VarKind::Signed, Ty::Prim(prim)) if prim.is_signed() => {},
+			_ => return Err(()),
+		}
+
+		// The variable can't be part of what it's being unified with, or the type would be infinitely big.
+		let mut occurs = false;
+		let zonked = self.zonk(ty);
+
+		zonked.walk(&mut |inner| if let Ty::Var(other) = inner {
+			if *other == var { occurs = true }
+			// Whatever the variable ends up in needs to stay as shallow as it is, so it doesn't get generalized too early.
+			if self.vars[*other].level > level { self.vars[*other].level = level }
+		});
+
+		if occurs { return Err(()) }
+
+		self.vars[var].value = Some(zonked);
+		Ok(())
+	}
+
+	/// Turns the variables in a type which were made deeper than `level` into generic ones.
+	pub(crate) fn generalize(&self, ty: &Ty, level: usize) -> Scheme {
+		let ty = self.zonk(ty);
+		let mut vars = Vec::new();
+
+		ty.walk(&mut |inner| if let Ty::Var(var) = inner {
+			let data = &self.vars[*var];
+			if data.level > level && data.kind == VarKind::General && !vars.contains(var) { vars.push(*var) }
+		});
+
+		Scheme { vars, ty }
+	}
+
+	/// Gives a scheme fresh variables for each of its generic ones.
+	pub(crate) fn instantiate(&mut self, scheme: &Scheme, level: usize) -> Ty {
+		if scheme.vars.is_empty() { return scheme.ty.clone() }
+
+		let fresh: HashMap<Ty, Ty> = scheme.vars.iter()
+			.map(|var| (Ty::Var(*var), self.fresh(level, VarKind::General)))
+			.collect();
+
+		scheme.ty.subst(&fresh)
+	}
+
+	/// Makes any number literal whose type was never pinned down a `nat`, or an `int` if it was negated.
+	pub(crate) fn default_integrals(&mut self) {
+		for data in self.vars.iter_mut() {
+			if data.value.is_some() { continue }
+
+			match data.kind {
+				VarKind::Integral => data.value = Some(Ty::Prim(Prim::Nat)),
+				VarKind::Signed => data.value = Some(Ty::Prim(Prim::Int)),
+				VarKind::General => {},
+			}
+		}
+	}
+}
+
+impl Scheme {
+	/// A scheme with nothing generic about it.
+	pub(crate) fn mono(ty: Ty) -> Scheme {
+		Scheme { vars: Vec::new(), ty }
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn unification_follows_variables() {
+		let mut table = Table::default();
+		let a = table.fresh(0, VarKind::General);
+		let b = table.fresh(0, VarKind::General);
+
+		assert!(table.unify(&Ty::List(Box::new(a.clone())), &b).is_ok());
+		assert!(table.unify(&b, &Ty::List(Box::new(Ty::Prim(Prim::Str)))).is_ok());
+		assert_eq!(table.zonk(&a), Ty::Prim(Prim::Str));
+
+		// Infinite types aren't allowed.
+		let c = table.fresh(0, VarKind::General);
+		assert!(table.unify(&c, &Ty::List(Box::new(c.clone()))).is_err());
+	}
+
+	#[test]
+	fn number_literals_only_become_numbers() {
+		let mut table = Table::default();
+		let n = table.fresh(0, VarKind::Integral);
+		let v = table.fresh(0, VarKind::General);
+
+		assert!(table.unify(&v, &n).is_ok());
+		assert!(table.unify(&v, &Ty::Prim(Prim::Str)).is_err());
+		assert!(table.unify(&v, &Ty::Prim(Prim::Int)).is_ok());
+		assert_eq!(table.zonk(&n), Ty::Prim(Prim::Int));
+	}
+
+	#[test]
+	fn negated_numbers_stay_signed() {
+		let mut table = Table::default();
+		let n = table.fresh(0, VarKind::Integral);
+		let m = table.fresh(0, VarKind::Signed);
+
+		assert!(table.unify(&n, &m).is_ok());
+		assert!(table.unify(&n, &Ty::Prim(Prim::Nat)).is_err());
+
+		table.default_integrals();
+		assert_eq!(table.zonk(&n), Ty::Prim(Prim::Int));
+	}
+
+	#[test]
+	fn rows_take_on_each_others_effects() {
+		let mut table = Table::default();
+		let (log, fail) = (Ty::Adt(0, Vec::new()), Ty::Adt(1, Vec::new()));
+		let (r, s) = (table.fresh(0, VarKind::General), table.fresh(0, VarKind::General));
+
+		let a = Ty::Row(vec![log.clone()], Some(Box::new(r.clone())));
+		let b = Ty::Row(vec![fail.clone()], Some(Box::new(s.clone())));
+		assert!(table.unify(&a, &b).is_ok());
+		assert_eq!(table.row(&a).0, [log.clone(), fail.clone()]);
+		assert_eq!(table.row(&b).0, [fail.clone(), log.clone()]);
+
+		// A closed row can't pick up effects it doesn't have.
+		let t = table.fresh(0, VarKind::General);
+		assert!(table.unify(&Ty::Row(vec![log], None), &Ty::Row(vec![fail], Some(Box::new(t)))).is_err());
+	}
+
+	#[test]
+	fn only_deeper_variables_are_generalized() {
+		let mut table = Table::default();
+		let outer = table.fresh(0, VarKind::General);
+		let inner = table.fresh(1, VarKind::General);
+		let func = Ty::Func(vec![inner.clone()], Box::new(outer.clone()), Box::new(Ty::Row(Vec::new(), None)));
+
+		let scheme = table.generalize(&func, 0);
+		assert_eq!(scheme.vars, vec![1]);
+
+		let Ty::Func(params, ret, _) = table.instantiate(&scheme, 0) else { panic!("expected a function") };
+		assert_ne!(params[0], inner);
+		assert_eq!(*ret, outer);
+	}
+}
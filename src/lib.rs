@@ -6,8 +6,32 @@
 
 mod compiler;
 
-use compiler::{
+pub use compiler::{
+	AttributeError,
+	ConstError,
+	ConstHint,
+	ExportKind,
 	InterpretError,
+	LabelError,
+	LexError,
+	Limit,
+	LintError,
+	LintLevel,
+	MacroError,
+	ModuleError,
+	MutabilityError,
+	Options,
+	ParseError,
+	ParseLimits,
+	ResolveError,
+	TypeError,
+	TypeHint,
+	compile,
+	const_values,
+	dump_core,
+	exports,
+	format,
+	type_hints,
 };
 
 use std::{
@@ -17,6 +41,7 @@ use std::{
 		HashMap,
 		VecDeque,
 	},
+	fmt,
 	io::ErrorKind as IOError,
 	path::{
 		Path,
@@ -34,7 +59,11 @@ pub type ErrorList = Vec<Error>;
 //--> Structs <--
 
 /// The Rouge runtime itself.
-pub struct Runtime {}
+pub struct Runtime {
+	options: Options,
+	/// Everything marked `pub` in the packages loaded so far, by its full path. Nothing else can be looked up.
+	exports: HashMap<String, ExportKind>,
+}
 
 /// Contains information about some kind of error that occurred while trying to run a program.
 /// 
@@ -47,7 +76,8 @@ pub struct Error {
 	line: Option<usize>,
 	span: Option<Span>,
 	slice: Option<String>,
-	kind: ErrorKind
+	kind: ErrorKind,
+	notes: Vec<String>,
 }
 
 //--> Enums <--
@@ -79,7 +109,12 @@ pub enum RougeData {
 	Map(BTreeMap<RougeData, RougeData>),
 	Str(String),
 	Struct(HashMap<Member, RougeData>),
-	Union{ tag: u8, data: HashMap<Member, RougeData> },
+	/// A value of a union type. The tag is which variant it is, counting from zero in the order they're declared.
+	Union{ tag: u32, data: HashMap<Member, RougeData> },
+	/// A trait object, like a `dyn Widget`. `vtable` is which of the program's vtables has the functions for `data`'s type.
+	Object { vtable: u32, data: Box<RougeData> },
+	/// A trait object implemented by the program embedding the runtime, rather than in Rouge code.
+	Host(Box<dyn HostObject>),
 	ConstRef(),
 	HeapRef(),
 	ExternData(Box<dyn Any>)
@@ -91,6 +126,20 @@ pub enum Member {
 	Named(String),
 }
 
+//--> Traits <--
+
+/// Lets a program embedding the runtime implement a Rouge trait in Rust, so that Rouge code can use its values as trait objects.
+/// A plugin API might have Rouge code draw a `[dyn Widget]`, some of which are widgets written in Rust.
+pub trait HostObject {
+	/// The full path of the trait it implements, like `pkg::ui::Widget`.
+	fn trait_path(&self) -> &str;
+
+	/// Calls one of the trait's methods by name, with the arguments that come after `self`.
+	/// It's free to change the object, but Rouge code will only call methods which take `mut self` on one it's allowed to change.
+	/// If it fails, it gives back what went wrong, which gets reported as a runtime error where the method was called.
+	fn call(&mut self, method: &str, args: Vec<RougeData>) -> Result<RougeData, String>;
+}
+
 //--> Functions <--
 
 impl Runtime {
@@ -100,7 +149,47 @@ impl Runtime {
 	pub const ROUGE_VERSION_STRING: &'static str = "0.1.0";
 	
 	pub fn new() -> Runtime {
-		Runtime {}
+		Runtime::with_options(Options::default())
+	}
+
+	/// Creates a runtime which interprets source code with the options given.
+	pub fn with_options(options: Options) -> Runtime {
+		Runtime { options, exports: HashMap::new() }
+	}
+
+	/// Loads and checks a package from its root file, giving back any warnings.
+	/// Every package's paths start with `pkg`, so loading another one replaces anything the last one had at the same path.
+	pub fn load(&mut self, path: &Path) -> Result<ErrorList, ErrorList> {
+		let (package, warnings) = compiler::check_package(path, &self.options)?;
+		self.exports.extend(package.exports);
+
+		Ok(warnings)
+	}
+
+	/// Looks up something a loaded package declares by its full path, like `pkg::ui::Widget`, giving back what it is.
+	/// Only things marked `pub` can be found. Anything else is private to the package, even with `prt`.
+	pub fn lookup(&self, path: &str) -> Option<ExportKind> {
+		self.exports.get(path).copied()
+	}
+
+	/// Turns an object implemented in Rust into a value Rouge code can use as a trait object.
+	/// The trait it says it implements has to be one a loaded package exports.
+	pub fn host(&self, object: Box<dyn HostObject>) -> Result<RougeData, ErrorList> {
+		let path = object.trait_path();
+
+		if self.lookup(path) != Some(ExportKind::Trait) {
+			let mut err = Error::new(false, None, None, None, Some(path), ErrorKind::Runtime);
+			err.add_note(format!("`{}` isn't a trait exported by any package loaded into the runtime", path));
+			return Err(vec![err]);
+		}
+
+		Ok(RougeData::Host(object))
+	}
+}
+
+impl Default for Runtime {
+	fn default() -> Runtime {
+		Runtime::new()
 	}
 }
 
@@ -113,10 +202,16 @@ impl Error {
 			line,
 			span,
 			slice: slice.map(|source| source.to_string()),
-			kind
+			kind,
+			notes: Vec::new(),
 		}
 	}
 
+	/// Adds a note with some extra context to the error.
+	pub(crate) fn add_note(&mut self, note: String) {
+		self.notes.push(note);
+	}
+
 	/// Indicates whether this is a full-on error, or a simple warning.
 	pub fn is_warning(&self) -> bool {
 		self.is_warning
@@ -150,4 +245,112 @@ impl Error {
 	pub fn kind(&self) -> ErrorKind {
 		self.kind.clone()
 	}
-}
\ No newline at end of file
+
+	/// Any extra context about the error, such as which macro expansions it happened inside of.
+	pub fn notes(&self) -> &[String] {
+		&self.notes
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match (&self.file, self.line) {
+			(Some(file), Some(line)) => write!(f, "{}:{}: ", file.display(), line)?,
+			(Some(file), None) => write!(f, "{}: ", file.display())?,
+			(None, Some(line)) => write!(f, "line {}: ", line)?,
+			(None, None) => {},
+		}
+
+		write!(f, "{}: {}", if self.is_warning { "warning" } else { "error" }, self.kind)?;
+
+		if let Some(slice) = &self.slice {
+			if !slice.trim().is_empty() && !slice.contains('\n') {
+				write!(f, " (at `{}`)", slice)?;
+			}
+		}
+
+		for note in self.notes.iter() {
+			write!(f, "\n\tnote: {}", note)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl fmt::Display for ErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ErrorKind::Interpret(e) => write!(f, "{}", e),
+			ErrorKind::Compile => write!(f, "compilation failed"),
+			ErrorKind::Load => write!(f, "couldn't load bytecode"),
+			ErrorKind::IO(e) => write!(f, "{}", e),
+			ErrorKind::Runtime => write!(f, "runtime error"),
+		}
+	}
+}
+
+//--> Unit Testing <--
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::compiler::TestPackage;
+
+	#[test]
+	fn only_pub_things_can_be_looked_up() {
+		let package = TestPackage::new("runtime-lookup", &[
+			("main.ro", "use self::ui::Widget\n\npub func main() do\nend\n\nfunc helper() do\nend\n"),
+			("ui.ro", "pub trait Widget is\n\tfunc draw(self)\nend\n\nprt func shared() do\nend\n"),
+		]);
+
+		let mut runtime = Runtime::new();
+		assert!(runtime.lookup("pkg::main").is_none());
+		assert!(runtime.load(package.root()).is_ok());
+
+		assert_eq!(runtime.lookup("pkg::main"), Some(ExportKind::Func));
+		assert_eq!(runtime.lookup("pkg::ui::Widget"), Some(ExportKind::Trait));
+		assert_eq!(runtime.lookup("pkg::ui::Widget::draw"), Some(ExportKind::Func));
+		assert_eq!(runtime.lookup("pkg::helper"), None);
+		assert_eq!(runtime.lookup("pkg::ui::shared"), None);
+	}
+
+	struct Label(String);
+
+	impl HostObject for Label {
+		fn trait_path(&self) -> &str {
+			&self.0
+		}
+
+		fn call(&mut self, method: &str, _: Vec<RougeData>) -> Result<RougeData, String> {
+			match method {
+				"draw" => Ok(RougeData::Str(self.0.clone())),
+				_ => Err(format!("labels don't have a `{}` method", method)),
+			}
+		}
+	}
+
+	#[test]
+	fn host_objects_need_an_exported_trait() {
+		let package = TestPackage::new("runtime-host", &[
+			("main.ro", "pub trait Widget is
+	func draw(self) -> str
+end
+
+pub func main() do
+end
+"),
+		]);
+
+		let mut runtime = Runtime::new();
+		assert!(runtime.load(package.root()).is_ok());
+
+		let Ok(RougeData::Host(mut label)) = runtime.host(Box::new(Label("pkg::Widget".into()))) else { panic!("a label is a widget") };
+		assert!(matches!(label.call("draw", Vec::new()), Ok(RougeData::Str(s)) if s == "pkg::Widget"));
+		assert!(label.call("resize", Vec::new()).is_err());
+
+		for path in ["pkg::main", "pkg::Gadget"] {
+			let err = runtime.host(Box::new(Label(path.into()))).err().unwrap();
+			assert_eq!(err[0].slice().as_deref(), Some(path));
+		}
+	}
+}
@@ -1,13 +1,20 @@
 //--> Imports <--
 
 use std::{
+	fs,
 	path::PathBuf,
+	process::ExitCode,
+};
+
+use rouge::{
+	LintLevel,
+	Options,
 };
 
 use clap::{
 	Arg,
+	ArgAction,
 	Command,
-	ValueHint,
 };
 
 //--> Type Aliases <--
@@ -20,7 +27,7 @@ use clap::{
 
 //--> Functions <--
 
-fn main() {
+fn main() -> ExitCode {
 	let args = Command::new("rouge")
 		.version(clap::crate_version!())
 		.about("A rusty programming and scripting language for applications.")
@@ -64,6 +71,79 @@ fn main() {
 				""
 			)
 		)
+		.arg(
+			Arg::new("dump-core")
+			.long("dump-core")
+			.action(ArgAction::SetTrue)
+			.conflicts_with("compile")
+			.help("Print the desugared core syntax of each file instead of running it.")
+			.long_help(
+				"Parses each file and rewrites it into the core language that the rest of the compiler works with, \
+				where `while`, `until` and `for` loops have become `always` loops and effect handlers, and `?` has become a pattern match. \
+				The result is printed as Rouge source code."
+			)
+		)
+		.arg(
+			Arg::new("warn")
+			.short('W')
+			.long("warn")
+			.value_name("LINT")
+			.action(ArgAction::Append)
+			.help("Report a lint as a warning.")
+			.long_help(
+				"Makes the lint a warning, even where it would otherwise be left alone. \
+				`warnings` means every lint, and `unused` means unused_variables, unused_imports and dead_code. \
+				When -W and -D both name a lint, whichever comes last wins."
+			)
+		)
+		.arg(
+			Arg::new("deny")
+			.short('D')
+			.long("deny")
+			.value_name("LINT")
+			.action(ArgAction::Append)
+			.help("Report a lint as an error.")
+			.long_help(
+				"Turns the lint's warnings into errors, so the code doesn't compile while they're there. \
+				`@allow(...)` on an item still silences it there. \
+				`warnings` means every lint, and `unused` means unused_variables, unused_imports and dead_code."
+			)
+		)
+		.arg(
+			Arg::new("cfg")
+			.long("cfg")
+			.value_name("KEY[=VALUE]")
+			.action(ArgAction::Append)
+			.help("Set a flag for @cfg(...) conditions.")
+			.long_help(
+				"Sets a flag which `@cfg(...)` conditions on items and statements are checked against, like `--cfg gpu` or `--cfg backend=vulkan`. \
+				`@cfg(gpu)` holds if the flag was set at all, and `@cfg(backend = \"vulkan\")` if it was set with that value. \
+				`os` and `family` are always set for the host, like `os=linux` and `family=unix`."
+			)
+		)
+		.subcommand_negates_reqs(true)
+		.args_conflicts_with_subcommands(true)
+		.subcommand(
+			Command::new("fmt")
+			.about("Formats Rouge source code in the canonical style.")
+			.long_about(
+				"Rewrites each file in the canonical style, keeping comments and line breaks where they are. \
+				With --check, nothing is rewritten, and the command fails if any file isn't already formatted."
+			)
+			.arg(
+				Arg::new("check")
+				.long("check")
+				.action(ArgAction::SetTrue)
+				.help("List the files which aren't formatted instead of rewriting them.")
+			)
+			.arg(
+				Arg::new("files")
+				.required(true)
+				.value_name("FILES")
+				.value_parser(clap::value_parser!(PathBuf))
+				.num_args(1..)
+			)
+		)
 		.arg(
 			Arg::new("files")
 			.required(true)
@@ -76,4 +156,89 @@ fn main() {
 			)
 		)
 		.get_matches();
-}
\ No newline at end of file
+
+	if let Some(args) = args.subcommand_matches("fmt") {
+		return format(args.get_many::<PathBuf>("files").unwrap().collect(), args.get_flag("check"));
+	}
+
+	let files: Vec<&PathBuf> = args.get_many::<PathBuf>("files").unwrap().collect();
+	let mut options = Options { lints: lints(&args), ..Options::default() };
+	options.cfg.extend(args.get_many::<String>("cfg").into_iter().flatten().map(|flag| cfg_flag(flag)));
+
+	if args.get_flag("dump-core") {
+		let mut failed = false;
+
+		for file in files {
+			match rouge::dump_core(file, &options) {
+				Ok(core) => print!("{}", core),
+				Err(errs) => {
+					failed = true;
+					for err in errs { eprintln!("{}", err); }
+				}
+			}
+		}
+
+		return if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS };
+	}
+
+	match rouge::compile(files.iter().map(|p| p.as_path()).collect(), &options) {
+		Ok(warnings) => {
+			for warning in warnings { eprintln!("{}", warning); }
+			ExitCode::SUCCESS
+		},
+		Err(errs) => {
+			for err in errs { eprintln!("{}", err); }
+			ExitCode::FAILURE
+		}
+	}
+}
+
+/// Gathers the -W and -D flags in the order they were given, since a later one overrides an earlier one.
+fn lints(args: &clap::ArgMatches) -> Vec<(String, LintLevel)> {
+	let mut lints = Vec::new();
+
+	for (id, level) in [("warn", LintLevel::Warn), ("deny", LintLevel::Deny)] {
+		let (Some(indices), Some(names)) = (args.indices_of(id), args.get_many::<String>(id)) else { continue };
+		lints.extend(indices.zip(names).map(|(index, name)| (index, name.clone(), level)));
+	}
+
+	lints.sort_by_key(|(index, _, _)| *index);
+	lints.into_iter().map(|(_, name, level)| (name, level)).collect()
+}
+
+/// Splits a --cfg flag into its name and value, if it has one, like `backend=vulkan`.
+fn cfg_flag(flag: &str) -> (String, Option<String>) {
+	match flag.split_once('=') {
+		Some((name, value)) => (name.trim().to_string(), Some(value.trim().trim_matches('"').to_string())),
+		None => (flag.trim().to_string(), None),
+	}
+}
+
+/// Formats each file, either rewriting it or (with `check`) just reporting whether it needs to be.
+fn format(files: Vec<&PathBuf>, check: bool) -> ExitCode {
+	let mut failed = false;
+
+	for file in files {
+		let formatted = match rouge::format(file, &Options::default()) {
+			Ok(formatted) => formatted,
+			Err(errs) => {
+				failed = true;
+				for err in errs { eprintln!("{}", err); }
+				continue;
+			}
+		};
+
+		// The file was just read successfully, so reading it again shouldn't fail.
+		if fs::read_to_string(file).is_ok_and(|source| source == formatted) { continue }
+
+		if check {
+			failed = true;
+			println!("{} isn't formatted", file.display());
+		} else if let Err(e) = fs::write(file, formatted) {
+			failed = true;
+			eprintln!("{}: error: {}", file.display(), e);
+		}
+	}
+
+	if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}